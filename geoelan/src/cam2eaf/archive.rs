@@ -0,0 +1,385 @@
+//! Minimal IMDI and CMDI metadata records for a recording session,
+//! e.g. as a starting point for depositing in The Language Archive.
+//!
+//! Only values that can be derived from the recording are filled in:
+//! date, location centroid, duration, equipment, session identifier,
+//! and linked files. Remaining fields, such as project, actors,
+//! and access, are left as `Unspecified` to be completed in a metadata editor.
+
+use std::path::{Path, PathBuf};
+
+use time::{Date, Duration};
+
+use crate::{
+    elan::xml::escape,
+    geo::{point_cluster_average, EafPoint},
+    VERSION,
+};
+
+/// Metadata record format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetadataFormat {
+    /// IMDI 3.0 session.
+    Imdi,
+    /// CMDI 1.2 envelope with a session component.
+    Cmdi,
+}
+
+impl std::str::FromStr for MetadataFormat {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "imdi" => Ok(Self::Imdi),
+            "cmdi" => Ok(Self::Cmdi),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("(!) Unknown metadata format '{value}'"),
+            )),
+        }
+    }
+}
+
+impl MetadataFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Imdi => "imdi",
+            Self::Cmdi => "cmdi",
+        }
+    }
+}
+
+/// Session metadata derived from telemetry and output files.
+#[derive(Debug, Clone, Default)]
+pub struct SessionRecord {
+    /// Session name, e.g. the ELAN-file stem.
+    pub name: String,
+    /// Recording date.
+    pub date: Option<Date>,
+    /// Location centroid as `(latitude, longitude, altitude)`.
+    pub centroid: Option<(f64, f64, f64)>,
+    /// Recording duration.
+    pub duration: Option<Duration>,
+    /// Camera model, e.g. `GoPro HERO11 Black`.
+    pub equipment: Option<String>,
+    /// Recording session identifier, UUID (VIRB) or MUID (GoPro).
+    pub session_id: Option<String>,
+    /// Hardware independent session identifier.
+    pub stable_session_id: Option<String>,
+    /// Media and written resources for the session.
+    pub files: Vec<PathBuf>,
+    /// CMDI profile ID, e.g. `clarin.eu:cr1:p_1271859438204`.
+    /// Defaults to [`CMDI_PROFILE`].
+    pub cmdi_profile: Option<String>,
+}
+
+/// Default CMDI profile, the IMDI-derived session profile
+/// in the CLARIN Component Registry.
+pub const CMDI_PROFILE: &str = "clarin.eu:cr1:p_1271859438204";
+
+/// Returns MIME type for file extension, and whether the file is media.
+fn mime_type(path: &Path) -> (&'static str, bool) {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "mp4" | "lrv" | "glv" => ("video/mp4", true),
+        "wav" => ("audio/x-wav", true),
+        "eaf" => ("text/x-eaf+xml", false),
+        "pfsx" | "xml" => ("text/xml", false),
+        "kml" => ("application/vnd.google-earth.kml+xml", false),
+        "json" => ("application/json", false),
+        "csv" => ("text/csv", false),
+        "fit" => ("application/vnd.ant.fit", false),
+        _ => ("application/octet-stream", false),
+    }
+}
+
+/// Formats duration as `HH:MM:SS`.
+fn timestamp(duration: &Duration) -> String {
+    let s = duration.whole_seconds().max(0);
+    format!("{:02}:{:02}:{:02}", s / 3600, (s % 3600) / 60, s % 60)
+}
+
+impl SessionRecord {
+    /// Sets date and location centroid from GPS points.
+    pub fn with_points(self, points: &[EafPoint]) -> Self {
+        if points.is_empty() {
+            return self;
+        }
+        let centroid = point_cluster_average(points);
+        Self {
+            date: points.iter().find_map(|p| p.datetime).map(|dt| dt.date()),
+            centroid: Some((centroid.latitude, centroid.longitude, centroid.altitude)),
+            ..self
+        }
+    }
+
+    /// Resource link relative to `dir` if the file is located there.
+    fn link(path: &Path, dir: &Path) -> String {
+        match path.parent() == Some(dir) {
+            true => path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            false => path.display().to_string(),
+        }
+    }
+
+    /// Location, equipment, and session ID as IMDI keys.
+    fn keys(&self) -> Vec<(&'static str, String)> {
+        let mut keys = Vec::new();
+        if let Some((lat, lon, alt)) = self.centroid {
+            keys.push(("Latitude", format!("{lat:.6}")));
+            keys.push(("Longitude", format!("{lon:.6}")));
+            keys.push(("Altitude", format!("{alt:.1}")));
+        }
+        if let Some(equipment) = &self.equipment {
+            keys.push(("Equipment", equipment.to_owned()));
+        }
+        if let Some(id) = &self.session_id {
+            keys.push(("SessionId", id.to_owned()));
+        }
+        if let Some(id) = &self.stable_session_id {
+            keys.push(("StableSessionId", id.to_owned()));
+        }
+        keys
+    }
+
+    fn date_string(&self) -> String {
+        self.date
+            .map(|d| d.to_string())
+            .unwrap_or(String::from("Unspecified"))
+    }
+
+    /// Returns record as IMDI 3.0 session.
+    /// `dir` is the directory the record is written to.
+    pub fn to_imdi(&self, dir: &Path) -> String {
+        let today = time::OffsetDateTime::now_utc().date();
+        let date = self.date_string();
+        let duration = self
+            .duration
+            .map(|d| timestamp(&d))
+            .unwrap_or(String::from("Unspecified"));
+
+        let keys: String = self
+            .keys()
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "                <Key Name=\"{name}\">{}</Key>\n",
+                    escape(value)
+                )
+            })
+            .collect();
+
+        let mut media = String::new();
+        let mut written = String::new();
+        for path in self.files.iter() {
+            let (mime, is_media) = mime_type(path);
+            let link = escape(&Self::link(path, dir));
+            let size = path
+                .metadata()
+                .map(|m| format!("{}", m.len()))
+                .unwrap_or_default();
+            match is_media {
+                true => media.push_str(&format!(
+                    r#"            <MediaFile>
+                <ResourceLink>{link}</ResourceLink>
+                <Type>{}</Type>
+                <Format>{mime}</Format>
+                <Size>{size}</Size>
+                <Quality>Unspecified</Quality>
+                <RecordingConditions></RecordingConditions>
+                <TimePosition>
+                    <Start>00:00:00</Start>
+                    <End>{duration}</End>
+                </TimePosition>
+                <Access>
+                    <Availability></Availability>
+                    <Date>Unspecified</Date>
+                    <Owner></Owner>
+                    <Publisher></Publisher>
+                    <Contact></Contact>
+                </Access>
+                <Description LanguageId="" Link=""></Description>
+                <Keys></Keys>
+            </MediaFile>
+"#,
+                    mime.split('/').next().unwrap_or_default(),
+                )),
+                false => written.push_str(&format!(
+                    r#"            <WrittenResource>
+                <ResourceLink>{link}</ResourceLink>
+                <MediaResourceLink></MediaResourceLink>
+                <Date>{today}</Date>
+                <Type>{}</Type>
+                <SubType></SubType>
+                <Format>{mime}</Format>
+                <Size>{size}</Size>
+                <Validation>
+                    <Type>Unspecified</Type>
+                    <Methodology>Unspecified</Methodology>
+                    <Level>Unspecified</Level>
+                    <Description LanguageId="" Link=""></Description>
+                </Validation>
+                <Derivation>Unspecified</Derivation>
+                <CharacterEncoding>UTF-8</CharacterEncoding>
+                <ContentEncoding></ContentEncoding>
+                <LanguageId></LanguageId>
+                <Anonymized>Unspecified</Anonymized>
+                <Access>
+                    <Availability></Availability>
+                    <Date>Unspecified</Date>
+                    <Owner></Owner>
+                    <Publisher></Publisher>
+                    <Contact></Contact>
+                </Access>
+                <Description LanguageId="" Link=""></Description>
+                <Keys></Keys>
+            </WrittenResource>
+"#,
+                    match mime {
+                        "text/x-eaf+xml" => "Annotation",
+                        _ => "Unspecified",
+                    },
+                )),
+            }
+        }
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<METATRANSCRIPT xmlns="http://www.mpi.nl/IMDI/Schema/IMDI" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" Date="{today}" FormatId="IMDI 3.03" Originator="GeoELAN {VERSION}" Type="SESSION" Version="0" xsi:schemaLocation="http://www.mpi.nl/IMDI/Schema/IMDI http://www.mpi.nl/IMDI/Schema/IMDI_3.0.xsd">
+    <Session>
+        <Name>{name}</Name>
+        <Title></Title>
+        <Date>{date}</Date>
+        <Description LanguageId="" Link=""></Description>
+        <MDGroup>
+            <Location>
+                <Continent Link="http://www.mpi.nl/IMDI/Schema/Continents.xml" Type="ClosedVocabulary">Unspecified</Continent>
+                <Country Link="http://www.mpi.nl/IMDI/Schema/Countries.xml" Type="OpenVocabulary">Unspecified</Country>
+            </Location>
+            <Project>
+                <Name></Name>
+                <Title></Title>
+                <Id></Id>
+                <Contact></Contact>
+                <Description LanguageId="" Link=""></Description>
+            </Project>
+            <Keys>
+{keys}            </Keys>
+            <Content>
+                <Genre Link="http://www.mpi.nl/IMDI/Schema/Content-Genre.xml" Type="OpenVocabulary">Unspecified</Genre>
+                <SubGenre Link="http://www.mpi.nl/IMDI/Schema/Content-SubGenre.xml" Type="OpenVocabularyList"></SubGenre>
+                <Task Link="http://www.mpi.nl/IMDI/Schema/Content-Task.xml" Type="OpenVocabulary"></Task>
+                <Modalities Link="http://www.mpi.nl/IMDI/Schema/Content-Modalities.xml" Type="OpenVocabularyList"></Modalities>
+                <Subject Link="http://www.mpi.nl/IMDI/Schema/Content-Subject.xml" Type="OpenVocabularyList"></Subject>
+                <CommunicationContext>
+                    <Interactivity Link="http://www.mpi.nl/IMDI/Schema/Content-Interactivity.xml" Type="ClosedVocabulary">Unspecified</Interactivity>
+                    <PlanningType Link="http://www.mpi.nl/IMDI/Schema/Content-PlanningType.xml" Type="ClosedVocabulary">Unspecified</PlanningType>
+                    <Involvement Link="http://www.mpi.nl/IMDI/Schema/Content-Involvement.xml" Type="ClosedVocabulary">Unspecified</Involvement>
+                    <SocialContext Link="http://www.mpi.nl/IMDI/Schema/Content-SocialContext.xml" Type="ClosedVocabulary">Unspecified</SocialContext>
+                    <EventStructure Link="http://www.mpi.nl/IMDI/Schema/Content-EventStructure.xml" Type="ClosedVocabulary">Unspecified</EventStructure>
+                    <Channel Link="http://www.mpi.nl/IMDI/Schema/Content-Channel.xml" Type="ClosedVocabulary">Unspecified</Channel>
+                </CommunicationContext>
+                <Languages></Languages>
+                <Keys></Keys>
+                <Description LanguageId="" Link=""></Description>
+            </Content>
+            <Actors></Actors>
+        </MDGroup>
+        <Resources>
+{media}{written}        </Resources>
+    </Session>
+</METATRANSCRIPT>
+"#,
+            name = escape(&self.name),
+        )
+    }
+
+    /// Returns record as a CMDI 1.2 envelope. Linked files are listed
+    /// as resource proxies, other values in a `Session` component
+    /// in the profile namespace, with media and written resources
+    /// referring to the proxies.
+    /// `dir` is the directory the record is written to.
+    pub fn to_cmdi(&self, dir: &Path) -> String {
+        let today = time::OffsetDateTime::now_utc().date();
+        let profile = escape(self.cmdi_profile.as_deref().unwrap_or(CMDI_PROFILE));
+
+        let mut proxies = String::new();
+        let mut resources = String::new();
+        for (i, path) in self.files.iter().enumerate() {
+            let (mime, is_media) = mime_type(path);
+            let id = format!("r{}", i + 1);
+            let link = escape(&Self::link(path, dir));
+            proxies.push_str(&format!(
+                "            <cmd:ResourceProxy id=\"{id}\">\n                <cmd:ResourceType mimetype=\"{mime}\">Resource</cmd:ResourceType>\n                <cmd:ResourceRef>{link}</cmd:ResourceRef>\n            </cmd:ResourceProxy>\n"
+            ));
+            let element = match is_media {
+                true => "MediaFile",
+                false => "WrittenResource",
+            };
+            resources.push_str(&format!(
+                "                    <{element} cmd:ref=\"{id}\">\n                        <Format>{mime}</Format>\n                    </{element}>\n"
+            ));
+        }
+
+        let keys: String = self
+            .keys()
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "                        <Key Name=\"{name}\">{}</Key>\n",
+                    escape(value)
+                )
+            })
+            .collect();
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<cmd:CMD xmlns:cmd="http://www.clarin.eu/cmd/1" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" CMDVersion="1.2" xsi:schemaLocation="http://www.clarin.eu/cmd/1 https://infra.clarin.eu/CMDI/1.x/xsd/cmd-envelop.xsd http://www.clarin.eu/cmd/1/profiles/{profile} https://catalog.clarin.eu/ds/ComponentRegistry/rest/registry/1.x/profiles/{profile}/xsd">
+    <cmd:Header>
+        <cmd:MdCreator>GeoELAN {VERSION}</cmd:MdCreator>
+        <cmd:MdCreationDate>{today}</cmd:MdCreationDate>
+        <cmd:MdProfile>{profile}</cmd:MdProfile>
+    </cmd:Header>
+    <cmd:Resources>
+        <cmd:ResourceProxyList>
+{proxies}        </cmd:ResourceProxyList>
+        <cmd:JournalFileProxyList/>
+        <cmd:ResourceRelationList/>
+    </cmd:Resources>
+    <cmd:Components>
+        <Session xmlns="http://www.clarin.eu/cmd/1/profiles/{profile}">
+            <Name>{name}</Name>
+            <Date>{date}</Date>
+            <Duration>{duration}</Duration>
+            <MDGroup>
+                <Keys>
+{keys}                </Keys>
+            </MDGroup>
+            <Resources>
+{resources}            </Resources>
+        </Session>
+    </cmd:Components>
+</cmd:CMD>
+"#,
+            name = escape(&self.name),
+            date = self.date_string(),
+            duration = self
+                .duration
+                .map(|d| timestamp(&d))
+                .unwrap_or(String::from("Unspecified")),
+        )
+    }
+
+    pub fn to_xml(&self, format: MetadataFormat, dir: &Path) -> String {
+        match format {
+            MetadataFormat::Imdi => self.to_imdi(dir),
+            MetadataFormat::Cmdi => self.to_cmdi(dir),
+        }
+    }
+}
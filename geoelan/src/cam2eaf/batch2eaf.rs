@@ -1,11 +1,26 @@
 //! Batch concatenating clips and generating ELAN-files.
 //! Invoked via '--batch' argument.
 
-use std::{io::ErrorKind, path::PathBuf};
+use std::{
+    io::ErrorKind,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
 
 use fit_rs::VirbSession;
 use gpmf_rs::GoProSession;
 
+use crate::{
+    files,
+    logging::{self, debug, error, info},
+    progress::{self, Progress},
+};
+
+use super::cam2eaf;
 use super::gopro2eaf_session;
 use super::virb2eaf_session;
 
@@ -16,42 +31,42 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
         .get_one::<PathBuf>("input-directory")
         .unwrap_or(&PathBuf::default())
         .to_owned();
+    let jobs = *args.get_one::<u64>("jobs").unwrap() as usize; // clap: default 1, min 1
+    if jobs > 1 {
+        // Concurrent prompts can not be answered reliably
+        files::set_interactive(false);
+        // Concurrent FFmpeg progress bars overwrite each other
+        progress::set_nested(false);
+    }
+    let skip_existing = *args.get_one::<bool>("skip-existing").unwrap(); // clap: default
 
     // 1. determine model (gopro/virb)
     match args.get_one::<String>("batch").map(|s| s.as_str()) {
         // Batch GoPro sessions
         Some("g" | "gopro") => {
-            let sessions = GoProSession::sessions_from_path(&indir, None, false, true, true)?;
-            for (i, session) in sessions.iter().enumerate() {
-                println!("--[Session {:02}.]--------", i + 1);
-                match gopro2eaf_session::run(args, session) {
-                    Ok(_) => (),
-                    Err(err) => {
-                        println!("(!) Failed to process GoPro session: {err}");
-                        continue;
-                    }
+            let mut sessions = GoProSession::sessions_from_path(&indir, None, false, true, true)?;
+            let failed = run_jobs(&mut sessions, jobs, "GoPro", |session| {
+                if skip_existing && cam2eaf::is_processed(&session.mp4(), &session.lrv(), args) {
+                    info!("Skipping session, output already exists ('--skip-existing')");
+                    return Ok(());
                 }
-                println!("-----------------------\n");
-            }
+                gopro2eaf_session::run(args, session)
+            });
 
-            Ok(())
+            summary(sessions.len(), &failed)
         }
         // Batch VIRB sessions
         Some("v" | "virb") => {
             let mut sessions = VirbSession::sessions_from_path(&indir, true);
-            for (i, session) in sessions.iter_mut().enumerate() {
-                println!("--[Session {:02}.]--------", i + 1);
-                match virb2eaf_session::run(args, session) {
-                    Ok(_) => (),
-                    Err(err) => {
-                        println!("(!) Failed to process VIRB session: {err}");
-                        continue;
-                    }
+            let failed = run_jobs(&mut sessions, jobs, "VIRB", |session| {
+                if skip_existing && cam2eaf::is_processed(&session.mp4(), &session.glv(), args) {
+                    info!("Skipping session, output already exists ('--skip-existing')");
+                    return Ok(());
                 }
-                println!("-----------------------\n");
-            }
+                virb2eaf_session::run(args, session)
+            });
 
-            Ok(())
+            summary(sessions.len(), &failed)
         }
         // clap should catch this
         Some(m) => {
@@ -64,3 +79,87 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
         None => Ok(()),
     }
 }
+
+/// Prints failed sessions, if any, and returns an error so that
+/// the exit status reflects a partially failed batch.
+fn summary(total: usize, failed: &[(usize, String)]) -> std::io::Result<()> {
+    if failed.is_empty() {
+        info!("Processed {total} sessions.");
+        return Ok(());
+    }
+    for (i, err) in failed.iter() {
+        error!("  Session {:02}: {err}", i + 1);
+    }
+    let msg = format!("(!) {} of {total} sessions failed.", failed.len());
+    Err(std::io::Error::new(ErrorKind::Other, msg))
+}
+
+/// Processes sessions with at most `jobs` sessions running concurrently,
+/// each in its own thread. Sessions are processed in order
+/// if `jobs` is 1. Progress is updated as each session finishes.
+/// A failed session does not stop the batch.
+///
+/// Returns the index and error for each failed session, in session order.
+///
+/// If `jobs` is more than 1, output for each session is printed
+/// as one block when the session finishes.
+fn run_jobs<T, F>(sessions: &mut [T], jobs: usize, device: &str, job: F) -> Vec<(usize, String)>
+where
+    T: Send,
+    F: Fn(&mut T) -> std::io::Result<()> + Sync,
+{
+    let total = sessions.len();
+    let done = AtomicUsize::new(0);
+    let progress = Progress::new(&format!("{device} sessions"), total as u64);
+    let queue = Mutex::new(sessions.iter_mut().enumerate());
+    let failed: Mutex<Vec<(usize, String)>> = Mutex::new(Vec::new());
+
+    let worker = || loop {
+        // Release lock before processing session
+        let next = queue.lock().ok().and_then(|mut q| q.next());
+        let Some((i, session)) = next else {
+            break;
+        };
+
+        if jobs > 1 {
+            logging::buffer();
+        }
+        info!("--[Session {:02}.]--------", i + 1);
+        let timer = Instant::now();
+        let result = job(session);
+        let count = done.fetch_add(1, Ordering::SeqCst) + 1;
+        progress.inc(1);
+        debug!(
+            "{device} session {:02} processed in {:?}",
+            i + 1,
+            timer.elapsed()
+        );
+        match result {
+            Ok(_) => info!("--[Session {:02}. Done ({count}/{total})]--------\n", i + 1),
+            Err(err) => {
+                error!(
+                    "(!) Failed to process {device} session {:02} ({count}/{total}): {err}\n",
+                    i + 1
+                );
+                if let Ok(mut f) = failed.lock() {
+                    f.push((i, err.to_string()));
+                }
+            }
+        }
+        logging::flush();
+    };
+
+    match jobs {
+        0 | 1 => worker(),
+        n => std::thread::scope(|scope| {
+            for _ in 0..n.min(total) {
+                scope.spawn(&worker);
+            }
+        }),
+    }
+    progress.finish();
+
+    let mut failed = failed.into_inner().unwrap_or_default();
+    failed.sort_by_key(|(i, _)| *i);
+    failed
+}
@@ -1,36 +1,106 @@
 //! Locate video-files (GoPro, Garmin VIRB) and FIT (Garmin VIRB), and generate an ELAN-file.
 
-use std::path::{Path, PathBuf};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use fit_rs::VirbFile;
+use gpmf_rs::GoProFile;
 
 use crate::{
-    elan::generate_eaf,
-    files::writefile,
-    geo::{EafPoint, EafPointCluster},
-    media::Media,
+    cam2eaf::archive::{MetadataFormat, SessionRecord},
+    elan::{
+        generate_eaf,
+        layout::{apply_layout, TierSpec},
+        motion::{motion_spans, MotionClass},
+        pfsx::pfsx,
+        sensor::{add_sensor_tiers, SensorTier},
+        tsconf::{acceleration_csv, panels, timeseries_csv, tsconf},
+        xml::Document,
+        GEOTIER_ID,
+    },
+    files::{
+        checksum::sha256, create_dir, dryrun, file_url, has_extension, plan, template_file_name,
+        writefile, WriteStatus,
+    },
+    geo::{geo_gpx::format_datetime, DocumentInfo, EafPoint, EafPointCluster},
+    i18n::{tr, trf, Msg},
+    logging::{debug, error, info, warn},
+    media::{atoms, timeline::SessionSpan, Media},
+    model::{muid_string, session_id, CameraModel},
+    progress::Progress,
+    telemetry::SensorSeries,
+    VERSION,
 };
 
+/// Tier ID for `--chapter-tier`.
+const CHAPTER_TIER_ID: &str = "chapters";
+
+/// ELAN-file property for the start of the media in milliseconds,
+/// relative to the start of the FIT-file (VIRB).
+pub(crate) const FIT_OFFSET_PROPERTY: &str = "geoelan_fit_offset_ms";
+
 // Concatenate clips, generate EAF, KML and GeoJSON.
 pub fn run(
     session_hi: &[PathBuf],
     session_lo: &[PathBuf],
     points: Option<&[EafPoint]>,
-    session_start_ms: Option<i64>, // VIRB ONLY
-    fit_path: Option<&Path>,       // VIRB ONLY
+    acceleration: Option<&SensorSeries>, // for '--timeseries'
+    session_start_ms: Option<i64>,       // VIRB ONLY
+    fit_path: Option<&Path>,             // VIRB ONLY
+    stable_id: Option<String>,           // see model::stable_session_id()
     args: &clap::ArgMatches,
 ) -> std::io::Result<()> {
     let ffmpeg = args.get_one::<PathBuf>("ffmpeg").unwrap().to_owned();
     let output_dir = {
         let p = args.get_one::<PathBuf>("output-directory").unwrap();
-        if !p.exists() {
-            // canonicalise() returns err if p does not exist
-            std::fs::create_dir_all(&p)?
-        };
-        p.canonicalize()?
+        create_dir(&p)?;
+        // canonicalise() returns err if p does not exist, e.g. during a dry run
+        match p.exists() {
+            true => p.canonicalize()?,
+            false => p.to_owned(),
+        }
     };
     let low_res_only = *args.get_one::<bool>("low-res-only").unwrap();
     let link_high_res = *args.get_one::<bool>("link-high-res").unwrap();
     let geotier = *args.get_one::<bool>("geotier").unwrap();
-    let dryrun = *args.get_one::<bool>("dryrun").unwrap();
+    let dryrun = dryrun();
+    let name_template = args.get_one::<String>("name");
+    let concatenated = args.get_one::<PathBuf>("concatenated");
+    let template = args.get_one::<PathBuf>("template");
+    let tier_layout = match args.get_many::<String>("tier") {
+        Some(specs) => specs
+            .map(|s| TierSpec::parse(s))
+            .collect::<std::io::Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    let timeseries = *args.get_one::<bool>("timeseries").unwrap();
+    let sensor_tiers: Vec<SensorTier> = args
+        .get_many::<String>("sensor-tiers")
+        .map(|v| v.filter_map(|s| s.parse::<SensorTier>().ok()).collect())
+        .unwrap_or_default();
+    let drift = *args.get_one::<f64>("drift").unwrap(); // default: 1.0
+    let motion_class = args
+        .get_one::<String>("motion-tier")
+        .and_then(|s| s.parse::<MotionClass>().ok());
+    let stationary_kmh = *args.get_one::<f64>("stationary-speed").unwrap(); // default: 1.0
+    let min_segment = *args.get_one::<f64>("min-segment").unwrap(); // default: 10.0
+    let chapter_tier = *args.get_one::<bool>("chapter-tier").unwrap();
+    let no_checksum = *args.get_one::<bool>("no-checksum").unwrap();
+    let metadata_format = args
+        .get_one::<String>("metadata")
+        .and_then(|s| s.parse::<MetadataFormat>().ok());
+
+    if let Some(path) = concatenated {
+        if !path.exists() {
+            let msg = format!(
+                "(!) {}",
+                trf(Msg::DoesNotExist, &path.display().to_string())
+            );
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
+        }
+    }
 
     // Add 'LO' to denote that low-res video is used,
     // and 'HI' for high-res video.
@@ -44,48 +114,48 @@ pub fn run(
     };
 
     // Set up paths for files in recording session etc.
-    // Use file stem of first clip in session as dir.
-    // In case high-res does not exist use low-res basename and vice versa.
-    let basename_hi = session_hi.first().and_then(|p| p.file_stem());
-    let basename_lo = session_lo.first().and_then(|p| p.file_stem());
-    let maybe_basename = match low_res_only {
-        true => basename_lo.or_else(|| basename_hi),
-        false => basename_hi.or_else(|| basename_lo),
-    };
-
-    let Some(basename) = maybe_basename else {
+    let Some(basename) = session_basename(session_hi, session_lo, low_res_only) else {
         let msg = "(!) Failed to determine basename for session.";
         return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
     };
 
     let outdir_session = output_dir.join(&Path::new(&basename));
-    if !outdir_session.exists() {
-        std::fs::create_dir_all(&outdir_session)?;
-    }
+    create_dir(&outdir_session)?;
 
-    println!("High-resolution clips in session:");
+    // Base names from '--name' template, if set
+    let date = points
+        .and_then(|p| p.first())
+        .and_then(|p| p.datetime)
+        .map(|dt| dt.date().to_string())
+        .unwrap_or_default();
+    let name_hi = output_name(session_hi, name_template, &date);
+    let name_lo = output_name(session_lo, name_template, &date);
+
+    info!("High-resolution clips in session:");
     for (i, clip) in session_hi.iter().enumerate() {
-        println!("      {:2}. {}", i + 1, clip.display());
+        info!("      {:2}. {}", i + 1, clip.display());
     }
 
-    let (video_eaf_hi, audio_eaf_hi) = if dryrun {
-        println!("      Skipping: '--dryrun' set.");
+    let (video_eaf_hi, audio_eaf_hi) = if concatenated.is_some() {
+        info!("      Skipping: '--concatenated' set.");
         (None, None)
     } else if session_hi.is_empty() {
-        println!("      Skipping: Unable to locate high-resolution clips.");
+        info!("      Skipping: Unable to locate high-resolution clips.");
         (None, None)
     } else if low_res_only {
-        println!("      Skipping: '--low-res-only' set.");
+        info!("      Skipping: '--low-res-only' set.");
         (None, None)
     } else {
         Media::concatenate(
             &session_hi,
             &outdir_session,
             true,
+            name_hi.as_deref(),
             None,
             media_suffix_hi,
             // TODO use Path for concatenate()
             &format!("{}", ffmpeg.display()),
+            &provenance(&session_hi, fit_path, stable_id.as_deref()),
         )?
     };
 
@@ -95,32 +165,45 @@ pub fn run(
         Some(_) => false,
     };
 
-    println!("Low-resolution clips in session:");
+    info!("Low-resolution clips in session:");
     for (i, clip) in session_lo.iter().enumerate() {
-        println!("      {:2}. {}", i + 1, clip.display());
+        info!("      {:2}. {}", i + 1, clip.display());
     }
 
-    let (video_eaf_lo, audio_eaf_lo) = if dryrun {
-        println!("      Skipping: '--dryrun' set");
+    let (video_eaf_lo, audio_eaf_lo) = if concatenated.is_some() {
+        info!("      Skipping: '--concatenated' set");
         (None, None)
     } else if session_lo.is_empty() {
-        println!("      Skipping: Unable to locate low-resolution clips");
+        info!("      Skipping: Unable to locate low-resolution clips");
         (None, None)
     } else {
         Media::concatenate(
             &session_lo,
             &outdir_session,
             extract_wav_lo,
+            name_lo.as_deref(),
             None,
             media_suffix_lo,
             // TODO use Path for concatenate()
             &format!("{}", ffmpeg.display()),
+            &provenance(&session_lo, fit_path, stable_id.as_deref()),
         )?
     };
 
+    // Use previously concatenated video, e.g. via GoPro Quik,
+    // if it matches the duration of the original clips.
+    if let Some(path) = concatenated {
+        let clips = match session_hi.is_empty() {
+            true => session_lo,
+            false => session_hi,
+        };
+        check_concatenated(path, clips, drift)?;
+    }
+
     // SET EAF MEDIA PATHS
-    let video_eaf = match (video_eaf_lo, link_high_res) {
-        (Some(v), false) => v,
+    let video_eaf = match (concatenated, video_eaf_lo, link_high_res) {
+        (Some(v), ..) => v.canonicalize()?,
+        (_, Some(v), false) => v,
         // Either low-res does not exist,
         // or 'link_high_res' is true
         _ => match video_eaf_hi {
@@ -131,8 +214,9 @@ pub fn run(
             }
         },
     };
-    let audio_eaf = match (audio_eaf_lo, link_high_res) {
-        (Some(v), false) => v,
+    let audio_eaf = match (concatenated, audio_eaf_lo, link_high_res) {
+        (Some(v), ..) => v.canonicalize()?,
+        (_, Some(v), false) => v,
         // Either low-res does not exist,
         // or 'link_high_res' is true
         _ => match audio_eaf_hi {
@@ -144,34 +228,69 @@ pub fn run(
         },
     };
 
-    println!(
+    info!(
         "ELAN media paths:\n  {}\n  {}",
         video_eaf.display(),
         audio_eaf.display(),
     );
 
-    if dryrun {
-        println!("(!) '--dryrun' set, no files changed.");
-        return Ok(());
-    }
-
-    let eaf_path = Path::new(&video_eaf).with_extension("eaf");
+    // Keep output in session dir for previously concatenated video
+    let eaf_path = match concatenated {
+        Some(_) => outdir_session
+            .join(video_eaf.file_name().unwrap_or_default())
+            .with_extension("eaf"),
+        None => Path::new(&video_eaf).with_extension("eaf"),
+    };
 
     // Generate and write KML + GeoJSON
     if let Some(p) = points.as_deref() {
         let cluster = EafPointCluster::new(p, None);
+        let mut info = DocumentInfo::new(p);
+        if let Some(first) = session_hi.first().or(session_lo.first()) {
+            info = info.with_clip(first);
+        }
+        if let Some(id) = &stable_id {
+            info = info.with_stable_session(id);
+        }
+        if let Some(fit) = fit_path {
+            info = info.with_filter("fit", &fit.display().to_string());
+        }
         let kml_path = eaf_path.with_extension("kml");
-        match cluster.write_kml(true, &kml_path) {
-            Ok(true) => println!("Wrote {}", kml_path.display()),
-            Ok(false) => println!("Aborted writing KML-file"),
-            Err(err) => println!("(!) Failed to write '{}': {err}", kml_path.display()),
+        match cluster.write_kml(true, Some(&info), &kml_path) {
+            Ok(WriteStatus::Written) => info!("Wrote {}", kml_path.display()),
+            Ok(WriteStatus::Planned) => (),
+            Ok(WriteStatus::Skipped) => info!("Aborted writing KML-file"),
+            Err(err) => error!("(!) Failed to write '{}': {err}", kml_path.display()),
         }
         let json_path = eaf_path.with_extension("json");
-        match cluster.write_json(true, &json_path) {
-            Ok(true) => println!("Wrote {}", json_path.display()),
-            Ok(false) => println!("Aborted writing GeoJSON-file"),
-            Err(err) => println!("(!) Failed to write '{}': {err}", json_path.display()),
+        match cluster.write_json(true, Some(&info), &json_path) {
+            Ok(WriteStatus::Written) => info!("Wrote {}", json_path.display()),
+            Ok(WriteStatus::Planned) => (),
+            Ok(WriteStatus::Skipped) => info!("Aborted writing GeoJSON-file"),
+            Err(err) => error!("(!) Failed to write '{}': {err}", json_path.display()),
+        }
+    }
+
+    // EAF generation reads the concatenated video
+    if dryrun {
+        if timeseries && points.is_some() {
+            let stem = eaf_path.file_stem().unwrap_or_default().to_string_lossy();
+            plan(&eaf_path.with_file_name(format!("{stem}_timeseries.csv")));
+            if acceleration.is_some() {
+                plan(&eaf_path.with_file_name(format!("{stem}_acceleration.csv")));
+            }
+            plan(&eaf_path.with_file_name(format!("{stem}_tsconf.xml")));
+        }
+        plan(&eaf_path);
+        plan(&eaf_path.with_extension("pfsx"));
+        plan(&provenance_path(&eaf_path));
+        if let Some(path) = fit_path.and_then(|p| p.file_name()) {
+            plan(&outdir_session.join(path));
         }
+        if let Some(format) = metadata_format {
+            plan(&eaf_path.with_extension(format.extension()));
+        }
+        return Ok(());
     }
 
     // Generate EAF
@@ -186,26 +305,162 @@ pub fn run(
     ) {
         Ok(e) => e,
         Err(err) => {
-            let msg = format!("(!) Failed to generate EAF: {err}"); // !!! error on overlapping annotation timespans for gopro fullgps option
+            let msg = format!("(!) {}: {err}", tr(Msg::FailedToGenerateEaf)); // !!! error on overlapping annotation timespans for gopro fullgps option
             return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
         }
     };
 
-    let eaf_string = match eaf.to_string(Some(4)) {
-        Ok(s) => s,
+    let mut doc = match eaf.to_string(Some(4)) {
+        Ok(s) => Document::parse(&s)?,
         Err(err) => {
-            let msg = format!("(!) Failed to generate EAF: {err}");
+            let msg = format!("(!) {}: {err}", tr(Msg::FailedToGenerateEaf));
             return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
         }
     };
+
+    // Add sensor summary tiers as children to the geotier
+    if let Some(pts) = points.filter(|_| geotier && !sensor_tiers.is_empty()) {
+        if !add_sensor_tiers(&mut doc, GEOTIER_ID, pts, &sensor_tiers) {
+            warn!("(!) No geotier, sensor tiers not added.");
+        }
+    }
+
+    // Add tier with moving/stationary or speed class intervals
+    if let (Some(pts), Some(class)) = (points.filter(|_| geotier), motion_class) {
+        let spans = motion_spans(
+            pts,
+            class,
+            stationary_kmh,
+            (min_segment * 1000.0) as i64,
+            session_start_ms,
+        );
+        doc.add_aligned_tier(&class.tier_id(), &spans);
+        info!(
+            "      Added tier '{}' with {} intervals",
+            class.tier_id(),
+            spans.len()
+        );
+    }
+
+    // Add tier with one annotation per clip, marking missing chapters
+    if chapter_tier {
+        let clips = match session_hi.is_empty() {
+            true => session_lo,
+            false => session_hi,
+        };
+        let spans = SessionSpan::new(clips, None, None).chapter_spans();
+        doc.add_aligned_tier(CHAPTER_TIER_ID, &spans);
+        info!(
+            "      Added tier '{CHAPTER_TIER_ID}' with {} chapters",
+            spans.len()
+        );
+    }
+
+    // Write ELAN time series files and link configuration
+    if let Some(pts) = points.filter(|_| timeseries) {
+        let stem = eaf_path.file_stem().unwrap_or_default().to_string_lossy();
+        let csv_path = eaf_path.with_file_name(format!("{stem}_timeseries.csv"));
+        let acc_path = eaf_path.with_file_name(format!("{stem}_acceleration.csv"));
+        let tsconf_path = eaf_path.with_file_name(format!("{stem}_tsconf.xml"));
+        let mut files = vec![(timeseries_csv(pts, session_start_ms), &csv_path)];
+        if let Some(series) = acceleration {
+            files.push((acceleration_csv(series, session_start_ms), &acc_path));
+        }
+        let conf = tsconf(
+            &csv_path,
+            pts,
+            acceleration.map(|a| (acc_path.as_path(), a)),
+        );
+        files.push((conf, &tsconf_path));
+        for (content, path) in files {
+            match writefile(content.as_bytes(), path) {
+                Ok(WriteStatus::Written) => info!("Wrote {}", path.display()),
+                Ok(WriteStatus::Planned) => (),
+                Ok(WriteStatus::Skipped) => info!("User aborted writing {}", path.display()),
+                Err(err) => error!("(!) Failed to write '{}': {err}", path.display()),
+            }
+        }
+        doc.add_linked_file(&file_url(&tsconf_path), "text/xml");
+    }
+
+    // Apply user defined tier layout
+    if !tier_layout.is_empty() {
+        apply_layout(&mut doc, &tier_layout)?;
+    }
+
+    // Apply ELAN template (tier structure, linguistic types, CVs)
+    if let Some(path) = template {
+        let added = doc.apply_template(&Document::read(path)?);
+        for (element, id) in added.iter() {
+            info!(
+                "      Added {} '{id}' from template",
+                element.to_lowercase()
+            );
+        }
+    }
+
+    if let Some(id) = stable_id.as_deref() {
+        doc.add_property("geoelan_session_id", id);
+    }
+
+    // VIRB: start of media in the FIT-file, for aligning annotations
+    // with FIT data, e.g. 'plot --eaf'
+    if let Some(ms) = session_start_ms {
+        doc.add_property(FIT_OFFSET_PROPERTY, &ms.to_string());
+    }
+
+    // Checksums for source clips and FIT-file, so that the ELAN-file
+    // can be verified against the original recordings
+    let sources = checksums(session_hi, session_lo, fit_path, no_checksum);
+    for (_, path, checksum) in sources.iter() {
+        if let Some(checksum) = checksum {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            doc.add_property(&format!("geoelan_sha256:{name}"), checksum);
+        }
+    }
+
     // Not using the Eaf::write() method, as it does not return a Result<bool, EafError>
-    match writefile(eaf_string.as_bytes(), &eaf_path) {
-        Ok(true) => println!("Wrote {}", eaf_path.display()),
-        Ok(false) => println!("User aborted writing ELAN-file"),
+    let eaf_status = match writefile(doc.to_string().as_bytes(), &eaf_path) {
+        Ok(status) => status,
         Err(err) => {
-            let msg = format!("(!) Failed to write '{}': {err}", eaf_path.display());
+            let msg = format!(
+                "(!) {}: {err}",
+                trf(Msg::FailedToWrite, &eaf_path.display().to_string())
+            );
             return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
         }
+    };
+    match eaf_status {
+        WriteStatus::Written => info!("Wrote {}", eaf_path.display()),
+        WriteStatus::Planned => (),
+        WriteStatus::Skipped => info!("User aborted writing ELAN-file"),
+    }
+
+    // ELAN preferences: tier order, geotier selected, time series panes.
+    // Only if the ELAN-file was written, so that the preferences match it.
+    if eaf_status.is_written() {
+        let pfsx_path = eaf_path.with_extension("pfsx");
+        let panels = match points.is_some() && timeseries {
+            true => panels(acceleration.is_some()),
+            false => Vec::new(),
+        };
+        let prefs = pfsx(&doc, geotier.then_some(GEOTIER_ID), &panels);
+        match writefile(prefs.as_bytes(), &pfsx_path) {
+            Ok(WriteStatus::Written) => info!("Wrote {}", pfsx_path.display()),
+            Ok(WriteStatus::Planned) => (),
+            Ok(WriteStatus::Skipped) => info!("User aborted writing {}", pfsx_path.display()),
+            Err(err) => error!("(!) Failed to write '{}': {err}", pfsx_path.display()),
+        }
+    }
+
+    // Provenance sidecar with checksums
+    let prov_path = provenance_path(&eaf_path);
+    let prov = provenance_json(&eaf_path, stable_id.as_deref(), &sources);
+    match writefile(serde_json::to_string_pretty(&prov)?.as_bytes(), &prov_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", prov_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing {}", prov_path.display()),
+        Err(err) => error!("(!) Failed to write '{}': {err}", prov_path.display()),
     }
 
     // Copy FIT-file (VIRB)
@@ -213,7 +468,7 @@ pub fn run(
         let path_out =
             outdir_session.join(path.file_name().expect("Failed to extract FIT file name."));
         match std::fs::copy(path, &path_out) {
-            Ok(_) => println!("Copied {} to {}", path.display(), outdir_session.display()),
+            Ok(_) => info!("Copied {} to {}", path.display(), outdir_session.display()),
             Err(err) => {
                 let msg = format!(
                     "(!) Failed to copy {} to {}: {err}",
@@ -225,5 +480,279 @@ pub fn run(
         }
     }
 
+    // IMDI/CMDI metadata record for session
+    if let Some(format) = metadata_format {
+        let first = session_hi.first().or(session_lo.first());
+        let mut record = SessionRecord {
+            name: eaf_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            duration: Media::duration(&video_eaf).ok(),
+            equipment: first
+                .map(|p| CameraModel::from(p.as_path()))
+                .filter(|m| !matches!(m, CameraModel::Unknown))
+                .map(|m| m.name()),
+            session_id: first.and_then(|p| session_id(p)),
+            stable_session_id: stable_id.to_owned(),
+            cmdi_profile: args.get_one::<String>("cmdi-profile").cloned(),
+            ..SessionRecord::default()
+        };
+        if let Some(pts) = points {
+            record = record.with_points(pts);
+        }
+        let stem = eaf_path.file_stem().unwrap_or_default().to_string_lossy();
+        record.files = [
+            video_eaf.to_owned(),
+            audio_eaf.to_owned(),
+            eaf_path.to_owned(),
+            eaf_path.with_extension("pfsx"),
+            eaf_path.with_extension("kml"),
+            eaf_path.with_extension("json"),
+            eaf_path.with_file_name(format!("{stem}_timeseries.csv")),
+            eaf_path.with_file_name(format!("{stem}_acceleration.csv")),
+            eaf_path.with_file_name(format!("{stem}_tsconf.xml")),
+            provenance_path(&eaf_path),
+        ]
+        .into_iter()
+        .chain(
+            fit_path
+                .and_then(|p| p.file_name())
+                .map(|f| outdir_session.join(f)),
+        )
+        .filter(|p| p.exists())
+        .collect();
+
+        let record_path = eaf_path.with_extension(format.extension());
+        let dir = record_path.parent().unwrap_or(Path::new(""));
+        match writefile(record.to_xml(format, dir).as_bytes(), &record_path) {
+            Ok(WriteStatus::Written) => info!("Wrote {}", record_path.display()),
+            Ok(WriteStatus::Planned) => (),
+            Ok(WriteStatus::Skipped) => info!("User aborted writing {}", record_path.display()),
+            Err(err) => error!("(!) Failed to write '{}': {err}", record_path.display()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Compares the duration of a previously concatenated video
+/// with the total duration of the original clips,
+/// and prints a warning if these differ more than `drift` seconds.
+fn check_concatenated(path: &Path, clips: &[PathBuf], drift: f64) -> std::io::Result<()> {
+    let duration = Media::duration(path)?;
+    let clips_duration = clips
+        .iter()
+        .map(|p| Media::duration(p))
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .fold(time::Duration::ZERO, |acc, d| acc + d);
+
+    info!(
+        "Concatenated video: {} ({:.3}s, original clips {:.3}s)",
+        path.display(),
+        duration.as_seconds_f64(),
+        clips_duration.as_seconds_f64()
+    );
+    let diff = (duration - clips_duration).abs().as_seconds_f64();
+    if diff > drift {
+        warn!(
+            "(!) Concatenated video and original clips differ by {diff:.3}s. Geotier may not be synchronised."
+        );
+    }
+
     Ok(())
 }
+
+/// Returns the name of the session output directory.
+/// Uses file stem of first clip in session.
+/// In case high-res does not exist use low-res basename and vice versa.
+fn session_basename<'a>(
+    session_hi: &'a [PathBuf],
+    session_lo: &'a [PathBuf],
+    low_res_only: bool,
+) -> Option<&'a OsStr> {
+    let basename_hi = session_hi.first().and_then(|p| p.file_stem());
+    let basename_lo = session_lo.first().and_then(|p| p.file_stem());
+    match low_res_only {
+        true => basename_lo.or_else(|| basename_hi),
+        false => basename_hi.or_else(|| basename_lo),
+    }
+}
+
+/// Returns `true` if the session output directory already contains
+/// an ELAN-file and a concatenated video from a previous run.
+/// The ELAN-file is written last, and partially concatenated
+/// video is removed on failure, so both existing means that
+/// the session was fully processed.
+pub fn is_processed(
+    session_hi: &[PathBuf],
+    session_lo: &[PathBuf],
+    args: &clap::ArgMatches,
+) -> bool {
+    let output_dir = args.get_one::<PathBuf>("output-directory").unwrap(); // clap: default
+    let low_res_only = *args.get_one::<bool>("low-res-only").unwrap();
+    let Some(basename) = session_basename(session_hi, session_lo, low_res_only) else {
+        return false;
+    };
+    let Ok(entries) = std::fs::read_dir(output_dir.join(basename)) else {
+        return false;
+    };
+    let paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    ["eaf", "mp4"]
+        .iter()
+        .all(|ext| paths.iter().any(|p| has_extension(p, ext)))
+}
+
+/// Returns base name for concatenated files
+/// from the '--name' template, if set.
+fn output_name(session: &[PathBuf], template: Option<&String>, date: &str) -> Option<String> {
+    let (first, template) = (session.first()?, template?);
+    let values = [
+        ("session", session_id(first).unwrap_or_default()),
+        ("date", date.to_owned()),
+    ];
+    template_file_name(first, Some(template), &values, None, None)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+}
+
+/// Returns metadata for stamping concatenated video with
+/// session identifiers and processing provenance, in the form `(key, value)`:
+/// - GeoELAN version and processing date
+/// - source clips
+/// - file type brand and encoder tags for the first clip in session
+/// - session UUID (VIRB) or MUID (GoPro) for the first clip in session
+/// - FIT-file (VIRB)
+/// - hardware independent session ID
+fn provenance(
+    session: &[PathBuf],
+    fit_path: Option<&Path>,
+    stable_id: Option<&str>,
+) -> Vec<(String, String)> {
+    let mut metadata = vec![
+        ("geoelan_version".to_owned(), VERSION.to_owned()),
+        (
+            "geoelan_date".to_owned(),
+            time::OffsetDateTime::now_utc().date().to_string(),
+        ),
+        (
+            "geoelan_source".to_owned(),
+            session
+                .iter()
+                .filter_map(|p| p.file_name().map(|f| f.to_string_lossy().to_string()))
+                .collect::<Vec<_>>()
+                .join(";"),
+        ),
+    ];
+
+    if let Some(first) = session.first() {
+        if let Ok(Some(file_type)) = atoms::file_type(first) {
+            metadata.push(("geoelan_source_brand".to_owned(), file_type.major_brand));
+            if !file_type.encoders.is_empty() {
+                metadata.push((
+                    "geoelan_source_encoder".to_owned(),
+                    file_type.encoders.join(";"),
+                ));
+            }
+        }
+        if let Ok(uuid) = VirbFile::uuid_mp4(first) {
+            metadata.push(("geoelan_uuid".to_owned(), uuid));
+        } else if let Ok(gopro) = GoProFile::new(first) {
+            metadata.push(("geoelan_muid".to_owned(), muid_string(&gopro.muid)));
+        }
+    }
+
+    if let Some(name) = fit_path.and_then(|p| p.file_name()) {
+        metadata.push(("geoelan_fit".to_owned(), name.to_string_lossy().to_string()));
+    }
+
+    if let Some(id) = stable_id {
+        metadata.push(("geoelan_session_id".to_owned(), id.to_owned()));
+    }
+
+    metadata
+}
+
+/// Returns path for provenance sidecar, `<EAF_STEM>_provenance.json`.
+fn provenance_path(eaf_path: &Path) -> PathBuf {
+    eaf_path.with_file_name(format!(
+        "{}_provenance.json",
+        eaf_path.file_stem().unwrap_or_default().to_string_lossy()
+    ))
+}
+
+/// Returns SHA-256 for source files in the form `(role, path, checksum)`,
+/// where role is `video_hi`, `video_lo`, or `fit`.
+/// Files that can not be read are skipped with a warning.
+/// Progress is shown unless '--quiet' is set, since clips may be several GB.
+/// If `skip` is set ('--no-checksum'), files are listed without checksums.
+fn checksums(
+    session_hi: &[PathBuf],
+    session_lo: &[PathBuf],
+    fit_path: Option<&Path>,
+    skip: bool,
+) -> Vec<(&'static str, PathBuf, Option<String>)> {
+    let sources = session_hi
+        .iter()
+        .map(|p| ("video_hi", p.as_path()))
+        .chain(session_lo.iter().map(|p| ("video_lo", p.as_path())))
+        .chain(fit_path.map(|p| ("fit", p)))
+        .collect::<Vec<_>>();
+    if skip {
+        return sources
+            .into_iter()
+            .map(|(role, path)| (role, path.to_owned(), None))
+            .collect();
+    }
+
+    let progress = Progress::nested("SHA-256", sources.len() as u64);
+    let mut checksums = Vec::new();
+    for (role, path) in sources {
+        match sha256(path) {
+            Ok(checksum) => {
+                debug!("{checksum}  {}", path.display());
+                checksums.push((role, path.to_owned(), Some(checksum)));
+            }
+            Err(err) => warn!(
+                "(!) Failed to compute SHA-256 for '{}': {err}",
+                path.display()
+            ),
+        }
+        progress.inc(1);
+    }
+    progress.finish();
+    checksums
+}
+
+/// Returns provenance for the ELAN-file as JSON:
+/// GeoELAN version, processing time, stable session ID,
+/// and source files with size and SHA-256 (`null` if '--no-checksum' is set).
+fn provenance_json(
+    eaf_path: &Path,
+    stable_id: Option<&str>,
+    sources: &[(&str, PathBuf, Option<String>)],
+) -> serde_json::Value {
+    let now = time::OffsetDateTime::now_utc();
+    let files: Vec<_> = sources
+        .iter()
+        .map(|(role, path, checksum)| {
+            serde_json::json!({
+                "role": role,
+                "name": path.file_name().map(|f| f.to_string_lossy().to_string()),
+                "path": path.display().to_string(),
+                "bytes": path.metadata().map(|m| m.len()).ok(),
+                "sha256": checksum,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "geoelan_version": VERSION,
+        "created": format_datetime(&time::PrimitiveDateTime::new(now.date(), now.time())),
+        "eaf": eaf_path.file_name().map(|f| f.to_string_lossy().to_string()),
+        "stable_session_id": stable_id,
+        "files": files,
+    })
+}
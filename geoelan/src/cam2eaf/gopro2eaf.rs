@@ -1,6 +1,11 @@
-use std::{io::ErrorKind, path::PathBuf};
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
 
-use gpmf_rs::GoProSession;
+use gpmf_rs::{GoProFile, GoProSession};
+
+use crate::model::has_gopro_id;
 
 pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     let video = args.get_one::<PathBuf>("video").unwrap().canonicalize()?; // clap: required arg
@@ -20,8 +25,12 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     } else {
         let gopro_sessions =
             GoProSession::sessions_from_path(input_dir, Some(&video), verify_gpmf, true, true)?;
-        match gopro_sessions.first() {
-            Some(s) => s.to_owned(),
+        match gopro_sessions
+            .first()
+            .cloned()
+            .or_else(|| session_from_id(&video, input_dir, verify_gpmf))
+        {
+            Some(s) => s,
             None => {
                 let msg = format!(
                     "(!) No recording sessions for {} in {}",
@@ -35,3 +44,22 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
 
     super::gopro2eaf_session::run(args, &mut gopro_session)
 }
+
+/// Fallback for newer models and firmware, where the session for
+/// `video` may not be found via its device name or file name.
+/// Compiles all sessions in `input_dir` and returns the one containing
+/// a clip with the same MUID or GUMI as `video`.
+fn session_from_id(video: &Path, input_dir: &Path, verify_gpmf: bool) -> Option<GoProSession> {
+    let gopro = GoProFile::new(video).ok()?;
+    if !has_gopro_id(&gopro) {
+        return None;
+    }
+    GoProSession::sessions_from_path(input_dir, None, verify_gpmf, true, true)
+        .ok()?
+        .into_iter()
+        .find(|session| {
+            session
+                .iter()
+                .any(|f| f.muid == gopro.muid || f.gumi == gopro.gumi)
+        })
+}
@@ -1,6 +1,13 @@
-use gpmf_rs::{DeviceName, GoProSession};
+use gpmf_rs::GoProSession;
 
-use crate::geo::EafPointCluster;
+use crate::{
+    geo::EafPointCluster,
+    i18n::{tr, Msg},
+    logging::{info, warn},
+    media::timeline::SessionSpan,
+    model::{has_gps, logs_gps9, stable_session_id_gopro},
+    telemetry::{GoProTelemetry, SensorSeries, Telemetry},
+};
 
 use super::cam2eaf;
 
@@ -11,11 +18,25 @@ pub fn run(args: &clap::ArgMatches, gopro_session: &GoProSession) -> std::io::Re
     let gpsfix = *args.get_one::<u32>("gpsfix").unwrap(); // defaults to 2 (2D lock)
     let gpsdop = args.get_one::<f64>("gpsdop"); // defaults to 3 (3D lock)
     let geotier = *args.get_one::<bool>("geotier").unwrap();
+    let timeseries = *args.get_one::<bool>("timeseries").unwrap();
 
     // Get the GPS-data and convert to geo::point::Point:s.
     let mut pointcluster: Option<EafPointCluster> = None;
-    if geotier {
-        print!("Merging GPMF-data for {} files...", gopro_session.len());
+    let mut acceleration: Option<SensorSeries> = None;
+    // Hero12 Black has no GPS module
+    let gps_module = gopro_session.device().map(has_gps).unwrap_or(true);
+    if geotier && !gps_module {
+        warn!(
+            "(!) {} has no GPS module. {}",
+            gopro_session
+                .device()
+                .map(|d| d.to_str().to_owned())
+                .unwrap_or_default(),
+            tr(Msg::NoGeotier)
+        );
+    }
+    if geotier && gps_module {
+        info!("Merging GPMF-data for {} files", gopro_session.len());
         let gpmf = match gopro_session.gpmf() {
             Ok(g) => g,
             Err(err) => {
@@ -23,14 +44,13 @@ pub fn run(args: &clap::ArgMatches, gopro_session: &GoProSession) -> std::io::Re
                 return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
             }
         };
-        println!(" Done");
-        print!(
-            "Extracting GPS data (minimum satellite lock = {}) with time offset {} hours... ",
+        info!(
+            "Extracting GPS data (minimum satellite lock = {}) with time offset {} hours",
             gpsfix, time_offset
         );
 
         let downsample_factor =
-            if matches!(gopro_session.device(), Some(&DeviceName::Hero11Black)) && !fullgps {
+            if gopro_session.device().map(logs_gps9).unwrap_or(false) && !fullgps {
                 // Downsample GPS9 (10Hz) depending on setting
                 10
             } else {
@@ -47,26 +67,83 @@ pub fn run(args: &clap::ArgMatches, gopro_session: &GoProSession) -> std::io::Re
             }
         };
 
+        let mut cluster = EafPointCluster::from_gopro(&gps.0, None, &end, Some(time_offset as i64));
+
+        // Chapters may repeat the last GPS sample of the previous clip,
+        // producing duplicate timestamps at clip boundaries.
+        let duplicates = cluster.dedup_mut();
+        if duplicates > 0 && cluster.len() > 1 {
+            cluster.set_timedelta(None, &end);
+        }
+
         pointcluster = Some(if downsample_factor > 1 {
-            EafPointCluster::from_gopro(&gps.0, None, &end, Some(time_offset as i64))
-                .downsample(downsample_factor, None)
+            cluster.downsample(downsample_factor, None)
         } else {
-            EafPointCluster::from_gopro(&gps.0, None, &end, Some(time_offset as i64))
+            cluster
         });
 
-        println!("OK");
+        if duplicates > 0 {
+            info!("Removed {duplicates} duplicate points at clip boundaries");
+        }
+
+        // Acceleration for the ELAN time series
+        if timeseries {
+            let first = gopro_session
+                .iter()
+                .next()
+                .and_then(|gp| gp.mp4.to_owned().or(gp.lrv.to_owned()))
+                .unwrap_or_default();
+            let telemetry =
+                GoProTelemetry::from_gpmf(&first, gopro_session.device().cloned(), gpmf);
+            match telemetry.sensors("accelerometer", false) {
+                Ok(series) => acceleration = Some(series),
+                Err(err) => warn!("{err}. Acceleration not added to time series."),
+            }
+        }
     }
 
     let session_hi = gopro_session.mp4();
     let session_lo = gopro_session.lrv();
 
+    // Missing or corrupt chapters otherwise only show as jumps in the geotier
+    let span = SessionSpan::new(
+        match session_hi.is_empty() {
+            true => &session_lo,
+            false => &session_hi,
+        },
+        None,
+        None,
+    );
+    for (i, gap) in span.missing_chapters() {
+        warn!(
+            "(!) {:.3}s gap between {} and {}. Chapter missing or corrupt?",
+            gap.as_seconds_f64(),
+            span.clips[i].path.display(),
+            span.clips[i + 1].path.display()
+        );
+    }
+    for (i, chapters) in span.skipped_chapters() {
+        warn!(
+            "(!) Chapter {} missing between {} and {}.",
+            chapters
+                .iter()
+                .map(|c| format!("{c:02}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            span.clips[i].path.display(),
+            span.clips[i + 1].path.display()
+        );
+    }
+
     // Concatenate clips and generate eaf
     cam2eaf::run(
         &session_hi,
         &session_lo,
         pointcluster.map(|pc| pc.points).as_deref(),
+        acceleration.as_ref(),
         None,
         None,
+        Some(stable_session_id_gopro(gopro_session)),
         args,
     )
 }
@@ -2,8 +2,12 @@
 
 use std::{io::ErrorKind, path::PathBuf};
 
-use crate::model::CameraModel;
+use crate::{
+    i18n::{tr, Msg},
+    model::CameraModel,
+};
 
+pub mod archive;
 pub mod batch2eaf;
 pub mod cam2eaf;
 pub mod gopro2eaf;
@@ -24,7 +28,7 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
             CameraModel::Virb(_) => virb2eaf::run(args),
             CameraModel::GoPro(_) => gopro2eaf::run(args),
             CameraModel::Unknown => {
-                let msg = "(!) Unknown or unsupported device.";
+                let msg = format!("(!) {}", tr(Msg::UnsupportedDevice));
                 Err(std::io::Error::new(ErrorKind::Other, msg))
             }
         }
@@ -3,7 +3,11 @@ use std::path::PathBuf;
 
 use fit_rs::{Fit, VirbSession};
 
-use crate::files::virb::select_session;
+use crate::{
+    files::virb::select_session,
+    i18n::{tr, Msg},
+    logging::info,
+};
 
 pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     // Options
@@ -20,11 +24,11 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
         }
     };
 
-    println!("Inpit directory set to {}", input_dir.display());
+    info!("Input directory set to {}", input_dir.display());
 
     let virb_session_result = match (fit_path, video_path, uuid) {
         (Some(p), None, None) => {
-            println!(
+            info!(
                 "Determining recording session via FIT-file {}.",
                 p.display()
             );
@@ -41,18 +45,18 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
             VirbSession::from_uuid(uuid, &input_dir, true)
         }
         (None, Some(p), None) => {
-            println!(
+            info!(
                 "Determining recording session via video clip {}.",
                 p.display()
             );
             VirbSession::from_mp4(p, &input_dir, true)
         }
         (None, None, Some(s)) => {
-            println!("Determining recording session via UUID {}.", s);
+            info!("Determining recording session via UUID {}.", s);
             VirbSession::from_uuid(s, &input_dir, true)
         }
         _ => {
-            let msg = "(!) Failed to determine recording session.";
+            let msg = format!("(!) {}", tr(Msg::NoSession));
             return Err(std::io::Error::new(ErrorKind::Other, msg));
         }
     };
@@ -60,7 +64,10 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     match virb_session_result {
         Some(s) => super::virb2eaf_session::run(args, &mut s.to_owned()),
         None => {
-            let msg = "(!) Failed to determine recording session. At least one of 'video', 'fit, 'uuid' must be specified.";
+            let msg = format!(
+                "(!) {} At least one of 'video', 'fit, 'uuid' must be specified.",
+                tr(Msg::NoSession)
+            );
             Err(std::io::Error::new(ErrorKind::Other, msg))
         }
     }
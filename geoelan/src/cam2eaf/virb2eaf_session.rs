@@ -1,8 +1,16 @@
 use std::io::ErrorKind;
 
 use fit_rs::VirbSession;
+use time::Duration;
 
-use crate::geo::point_cluster::EafPointCluster;
+use crate::{
+    geo::point_cluster::EafPointCluster,
+    i18n::{tr, Msg},
+    logging::{info, warn},
+    media::Media,
+    model::stable_session_id_virb,
+    telemetry::{Telemetry, VirbTelemetry},
+};
 
 use super::cam2eaf;
 
@@ -25,7 +33,7 @@ pub fn run(args: &clap::ArgMatches, virb_session: &mut VirbSession) -> std::io::
     let mut pointcluster: Option<EafPointCluster> = None;
     if let Ok(gps) = virb_session.gps() {
         if gps.is_empty() {
-            println!("(!) No logged points for UUID in FIT-file.");
+            warn!("(!) No logged points for UUID in FIT-file.");
             gpsfail = true;
         } else {
             let (t0, end) = match (virb_session.t0, virb_session.end) {
@@ -55,26 +63,87 @@ pub fn run(args: &clap::ArgMatches, virb_session: &mut VirbSession) -> std::io::
             pointcluster = Some(cluster);
         }
     } else {
-        println!("(!) Failed to extract GPS data.");
+        warn!("(!) Failed to extract GPS data.");
         gpsfail = true;
     }
 
     if geotier && gpsfail {
-        eprintln!("(!) No geotier will be created.")
+        warn!("(!) {}", tr(Msg::NoGeotier))
     }
 
+    // Acceleration for the ELAN time series
+    let timeseries = *args.get_one::<bool>("timeseries").unwrap();
+    let acceleration = match (
+        timeseries && pointcluster.is_some(),
+        virb_session.virb.first(),
+    ) {
+        (true, Some(virb)) => {
+            match VirbTelemetry::from_uuid(&virb_session.fit_path(), &virb.uuid)
+                .and_then(|t| t.sensors("accelerometer", false))
+            {
+                Ok(series) => Some(series),
+                Err(err) => {
+                    warn!("{err}. Acceleration not added to time series.");
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
     let session_start_ms = virb_session.start.map(|n| n.whole_milliseconds() as i64);
 
     let session_hi = virb_session.mp4();
     let session_lo = virb_session.glv();
 
+    // Compare FIT session duration (camera events) with video duration,
+    // since dropped frames or missing clips will cause the geotier to drift.
+    let max_drift = *args.get_one::<f64>("drift").unwrap(); // clap: default 1.0
+    let stretch_times = *args.get_one::<bool>("stretch-times").unwrap();
+    let fit_duration = match (virb_session.start, virb_session.end) {
+        (Some(start), Some(end)) => Some(end - start),
+        _ => None,
+    };
+    let video_duration: Duration = match session_hi.is_empty() {
+        true => &session_lo,
+        false => &session_hi,
+    }
+    .iter()
+    .filter_map(|p| Media::duration(p).ok())
+    .fold(Duration::ZERO, |acc, d| acc + d);
+
+    if let Some(fit_dur) = fit_duration {
+        let drift = (video_duration - fit_dur).as_seconds_f64();
+        if drift.abs() > max_drift && video_duration.is_positive() {
+            warn!(
+                "(!) FIT session duration ({:.3}s) and video duration ({:.3}s) differ by {:.3}s.",
+                fit_dur.as_seconds_f64(),
+                video_duration.as_seconds_f64(),
+                drift
+            );
+            match (stretch_times, pointcluster.as_mut(), virb_session.start) {
+                (true, Some(cluster), Some(start)) => {
+                    let ratio = video_duration.as_seconds_f64() / fit_dur.as_seconds_f64();
+                    info!("    Scaling geotier timestamps by {ratio:.6} ('--stretch-times').");
+                    for point in cluster.points.iter_mut() {
+                        point.timestamp = point.timestamp.map(|t| start + (t - start) * ratio);
+                        point.duration = point.duration.map(|d| d * ratio);
+                    }
+                }
+                _ => warn!("    Use '--stretch-times' to scale the geotier to fit the video."),
+            }
+        }
+    }
+
     // Concatenate clips and generate eaf
     cam2eaf::run(
         &session_hi,
         &session_lo,
         pointcluster.map(|pc| pc.points).as_deref(),
+        acceleration.as_ref(),
         session_start_ms,
         Some(virb_session.fit_path().as_path()),
+        Some(stable_session_id_virb(virb_session)),
         args,
     )
 }
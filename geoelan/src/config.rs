@@ -0,0 +1,291 @@
+//! Configuration file with default argument values, so that long option
+//! chains do not have to be repeated on every invocation.
+//!
+//! The first file found is used:
+//! 1. `--config <PATH>`
+//! 2. `geoelan.toml` in current directory
+//! 3. `$XDG_CONFIG_HOME/geoelan/geoelan.toml` or `~/.config/geoelan/geoelan.toml`
+//!
+//! Keys are long argument names. Top-level keys apply to all subcommands
+//! with a matching argument, and keys in a `[<subcommand>]` table
+//! apply to that subcommand only, overriding top-level keys.
+//! Nested subcommands use nested tables, e.g. `[eaf.diff]`.
+//! Values are set as argument defaults, so that
+//! arguments set on the command line always take precedence.
+//!
+//! ```toml
+//! ffmpeg = "/opt/homebrew/bin/ffmpeg"
+//! gpsfix = 3
+//!
+//! [cam2eaf]
+//! outdir = "~/annotations"
+//! geotier = true
+//!
+//! [eaf2geo]
+//! geoshape = "line-all"
+//! ```
+//!
+//! Arrays are used for arguments that take several values.
+
+use std::{
+    collections::BTreeMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use clap::{Arg, ArgAction, Command};
+use toml::{Table, Value};
+
+/// Config file name.
+pub const CONFIG_FILE: &str = "geoelan.toml";
+
+/// Arguments that are never set from the config file.
+const IGNORED: [&str; 2] = ["config", "no-config"];
+
+/// Command line representation for value, e.g. `3` or `line-all`.
+/// Arrays return one value per item. Tables are ignored.
+fn to_args(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![expand_home(s)],
+        Value::Integer(n) => vec![n.to_string()],
+        Value::Float(n) => vec![n.to_string()],
+        Value::Boolean(b) => vec![b.to_string()],
+        Value::Datetime(d) => vec![d.to_string()],
+        Value::Array(values) => values.iter().flat_map(to_args).collect(),
+        Value::Table(_) => Vec::new(),
+    }
+}
+
+/// Expands a leading `~` to the home directory.
+fn expand_home(value: &str) -> String {
+    match (value.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest).display().to_string(),
+        _ => value.to_owned(),
+    }
+}
+
+/// Parsed config file.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub path: PathBuf,
+    table: Table,
+}
+
+impl Config {
+    /// Parses config file.
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(err) => {
+                let msg = format!("(!) Failed to read config file '{}': {err}", path.display());
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        };
+        Self::from_str(path, &content)
+    }
+
+    fn from_str(path: &Path, content: &str) -> std::io::Result<Self> {
+        match content.parse::<Table>() {
+            Ok(table) => Ok(Self {
+                path: path.to_owned(),
+                table,
+            }),
+            Err(err) => {
+                let msg = format!("(!) Invalid config file '{}': {err}", path.display());
+                Err(std::io::Error::new(ErrorKind::Other, msg))
+            }
+        }
+    }
+
+    /// Returns path to config file, if one exists.
+    /// `explicit` is a path set via `--config`.
+    pub fn locate(explicit: Option<&Path>) -> Option<PathBuf> {
+        if let Some(path) = explicit {
+            return Some(path.to_owned());
+        }
+        let local = PathBuf::from(CONFIG_FILE);
+        if local.exists() {
+            return Some(local);
+        }
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        let user = config_dir.join("geoelan").join(CONFIG_FILE);
+        user.exists().then_some(user)
+    }
+
+    /// Values for the (nested) subcommand `names`, e.g. `["eaf", "diff"]`,
+    /// with each nested table overriding the keys above it.
+    fn values(&self, names: &[String]) -> BTreeMap<String, Value> {
+        let mut values = BTreeMap::new();
+        let mut table = Some(&self.table);
+        for name in std::iter::once(None).chain(names.iter().map(Some)) {
+            table = match name {
+                Some(n) => table.and_then(|t| t.get(n)).and_then(|v| v.as_table()),
+                None => table,
+            };
+            let Some(t) = table else {
+                break;
+            };
+            values.extend(
+                t.iter()
+                    .filter(|(_, v)| !v.is_table())
+                    .map(|(k, v)| (k.to_owned(), v.to_owned())),
+            );
+        }
+        values
+    }
+
+    /// Sets config values as default values for the arguments
+    /// of `cli` and all (nested) subcommands.
+    /// Keys that do not match an argument for a subcommand are ignored,
+    /// so that top-level keys can be shared between subcommands.
+    pub fn apply(&self, cli: Command) -> Command {
+        self.apply_command(cli, &[], &[])
+    }
+
+    /// `globals` are global arguments inherited from parent commands,
+    /// which clap only propagates to subcommands once the CLI is built.
+    /// A global argument set in a subcommand table is added
+    /// to that subcommand with its own default value.
+    fn apply_command(&self, mut cmd: Command, names: &[String], globals: &[Arg]) -> Command {
+        for (key, value) in self.values(names).iter() {
+            let own = cmd
+                .get_arguments()
+                .find(|a| a.get_long() == Some(key.as_str()))
+                .cloned();
+            let Some(arg) = own
+                .clone()
+                .or_else(|| {
+                    globals
+                        .iter()
+                        .find(|a| a.get_long() == Some(key.as_str()))
+                        .cloned()
+                })
+                .filter(|a| !IGNORED.contains(&a.get_id().as_str()))
+            else {
+                continue;
+            };
+            let values = to_args(value);
+            let arg = match (arg.get_action(), value) {
+                (ArgAction::SetTrue | ArgAction::SetFalse, Value::Boolean(b)) => {
+                    arg.default_value(b.to_string())
+                }
+                // A default satisfies required arguments
+                (action, _) if action.takes_values() && !values.is_empty() => {
+                    arg.default_values(values).required(false)
+                }
+                _ => continue,
+            };
+            cmd = match own {
+                Some(a) => cmd.mut_arg(a.get_id(), |_| arg),
+                None => cmd.arg(arg),
+            };
+        }
+
+        let globals: Vec<Arg> = globals
+            .iter()
+            .cloned()
+            .chain(cmd.get_arguments().filter(|a| a.is_global_set()).cloned())
+            .collect();
+        let subcommands: Vec<String> = cmd
+            .get_subcommands()
+            .map(|s| s.get_name().to_owned())
+            .collect();
+        for name in subcommands {
+            let nested: Vec<String> = names.iter().cloned().chain([name.to_owned()]).collect();
+            cmd = cmd.mut_subcommand(name, |sub| self.apply_command(sub, &nested, &globals));
+        }
+
+        cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli() -> Command {
+        Command::new("geoelan")
+            .arg(
+                Arg::new("overwrite")
+                    .long("overwrite")
+                    .global(true)
+                    .default_value("ask"),
+            )
+            .subcommand(
+                Command::new("cam2eaf")
+                    .arg(Arg::new("gpsfix").long("gpsfix").default_value("2"))
+                    .arg(
+                        Arg::new("geotier")
+                            .long("geotier")
+                            .action(ArgAction::SetTrue),
+                    ),
+            )
+            .subcommand(
+                Command::new("eaf").subcommand(
+                    Command::new("diff").arg(Arg::new("tier").long("tier").required(true)),
+                ),
+            )
+    }
+
+    fn config(content: &str) -> Config {
+        Config::from_str(Path::new(CONFIG_FILE), content).unwrap()
+    }
+
+    #[test]
+    fn defaults_from_tables() {
+        let cfg = config(
+            r#"
+            gpsfix = 3
+            overwrite = "never"
+
+            [cam2eaf]
+            geotier = true
+            overwrite = "always"
+            "#,
+        );
+        let m = cfg.apply(cli()).get_matches_from(["geoelan", "cam2eaf"]);
+        let sub = m.subcommand_matches("cam2eaf").unwrap();
+        assert_eq!(sub.get_one::<String>("gpsfix").unwrap(), "3");
+        assert!(*sub.get_one::<bool>("geotier").unwrap());
+        assert_eq!(sub.get_one::<String>("overwrite").unwrap(), "always");
+        assert_eq!(m.get_one::<String>("overwrite").unwrap(), "always");
+    }
+
+    #[test]
+    fn command_line_takes_precedence() {
+        let cfg = config("gpsfix = 3\noverwrite = \"never\"");
+        let m = cfg.apply(cli()).get_matches_from([
+            "geoelan",
+            "cam2eaf",
+            "--gpsfix",
+            "0",
+            "--overwrite",
+            "ask",
+        ]);
+        let sub = m.subcommand_matches("cam2eaf").unwrap();
+        assert_eq!(sub.get_one::<String>("gpsfix").unwrap(), "0");
+        assert_eq!(m.get_one::<String>("overwrite").unwrap(), "ask");
+    }
+
+    #[test]
+    fn nested_subcommand() {
+        let cfg = config("overwrite = \"never\"\n[eaf.diff]\ntier = \"speech\"");
+        let m = cfg
+            .apply(cli())
+            .try_get_matches_from(["geoelan", "eaf", "diff"])
+            .unwrap();
+        let diff = m
+            .subcommand_matches("eaf")
+            .and_then(|m| m.subcommand_matches("diff"))
+            .unwrap();
+        assert_eq!(diff.get_one::<String>("tier").unwrap(), "speech");
+        assert_eq!(m.get_one::<String>("overwrite").unwrap(), "never");
+    }
+
+    #[test]
+    fn invalid_toml() {
+        assert!(Config::from_str(Path::new(CONFIG_FILE), "gpsfix = ").is_err());
+    }
+}
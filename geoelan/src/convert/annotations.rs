@@ -0,0 +1,327 @@
+//! Annotations as JSON, CSV, and Praat TextGrid,
+//! represented as tiers `(tier ID, [(value, start_ms, end_ms)])`.
+
+use std::io::ErrorKind;
+
+use serde_json::{json, Value};
+
+use crate::files::csv_field;
+
+/// Tier ID and annotations as `(value, start_ms, end_ms)`.
+pub type TierSpans = (String, Vec<(String, i64, i64)>);
+
+/// Linked media and tier attributes, kept in JSON so that
+/// an EAF -> JSON -> EAF round trip restores more than annotations.
+#[derive(Debug, Clone, Default)]
+pub struct EafMeta {
+    /// Media URLs.
+    pub media: Vec<String>,
+    /// Tier attributes as `(tier ID, participant, annotator)`.
+    pub tiers: Vec<(String, Option<String>, Option<String>)>,
+}
+
+/// Returns tiers as JSON,
+/// `{"media": [...], "tiers": [{"tier_id": ..., "participant": ..., "annotator": ..., "annotations": [{"value": ..., "start_ms": ..., "end_ms": ...}]}]}`.
+/// `participant` and `annotator` are only included if set.
+pub fn to_json(tiers: &[TierSpans], meta: &EafMeta) -> String {
+    let tiers: Vec<Value> = tiers
+        .iter()
+        .map(|(tier_id, spans)| {
+            let mut tier = json!({
+                "tier_id": tier_id,
+                "annotations": spans.iter().map(|(value, start, end)| json!({
+                    "value": value,
+                    "start_ms": start,
+                    "end_ms": end,
+                })).collect::<Vec<_>>(),
+            });
+            if let Some((_, participant, annotator)) =
+                meta.tiers.iter().find(|(id, ..)| id == tier_id)
+            {
+                if let Some(p) = participant {
+                    tier["participant"] = json!(p);
+                }
+                if let Some(a) = annotator {
+                    tier["annotator"] = json!(a);
+                }
+            }
+            tier
+        })
+        .collect();
+    let value = json!({
+        "media": meta.media,
+        "tiers": tiers,
+    });
+    // safe unwrap: serializing a json value
+    serde_json::to_string_pretty(&value).unwrap()
+}
+
+/// Returns the first value in `value` set for any of `keys`.
+fn any_key<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter().map(|k| &value[k]).find(|v| !v.is_null())
+}
+
+/// Parses tiers, linked media, and tier attributes from JSON
+/// as generated by `to_json`. `media`, `participant`, and `annotator`
+/// are optional.
+///
+/// The simplified JSON from eaf-rs (`Eaf::to_json()`) is also accepted:
+/// a top-level array of tiers or a `tiers` array, tier IDs as `tier_id`
+/// or `id`, annotation values as `value` or `annotation_value`,
+/// times in milliseconds as `start_ms`/`end_ms`, `start`/`end`,
+/// or `time_value1`/`time_value2`, and media as a list of URLs
+/// or of objects with `media_url`.
+pub fn from_json(text: &str) -> std::io::Result<(Vec<TierSpans>, EafMeta)> {
+    let value: Value = serde_json::from_str(text)?;
+    let invalid = || {
+        let msg = "(!) Invalid annotation JSON. Expected '{\"tiers\": [{\"tier_id\": ..., \"annotations\": [...]}]}'.";
+        std::io::Error::new(ErrorKind::Other, msg)
+    };
+
+    let mut tiers: Vec<TierSpans> = Vec::new();
+    let mut meta = EafMeta {
+        media: value["media"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|m| {
+                m.as_str()
+                    .or_else(|| any_key(m, &["media_url", "MEDIA_URL"])?.as_str())
+                    .map(String::from)
+            })
+            .collect(),
+        ..EafMeta::default()
+    };
+    let json_tiers = match value.as_array() {
+        Some(t) => t,
+        None => value["tiers"].as_array().ok_or_else(invalid)?,
+    };
+    for tier in json_tiers.iter() {
+        let tier_id = any_key(tier, &["tier_id", "id"])
+            .and_then(|v| v.as_str())
+            .ok_or_else(invalid)?
+            .to_owned();
+        let spans = tier["annotations"]
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|a| {
+                Some((
+                    any_key(a, &["value", "annotation_value"])
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_owned(),
+                    any_key(a, &["start_ms", "start", "time_value1"])?.as_i64()?,
+                    any_key(a, &["end_ms", "end", "time_value2"])?.as_i64()?,
+                ))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        meta.tiers.push((
+            tier_id.to_owned(),
+            tier["participant"].as_str().map(String::from),
+            tier["annotator"].as_str().map(String::from),
+        ));
+        tiers.push((tier_id, spans));
+    }
+
+    Ok((tiers, meta))
+}
+
+/// Splits CSV text into records and fields (RFC 4180),
+/// allowing quoted fields with line breaks.
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => (),
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+}
+
+/// Returns tiers as CSV, one annotation per row,
+/// with the header `tier,start_ms,end_ms,value`.
+pub fn to_csv(tiers: &[TierSpans]) -> String {
+    let mut output = String::from("tier,start_ms,end_ms,value\n");
+    for (tier_id, spans) in tiers.iter() {
+        for (value, start, end) in spans.iter() {
+            output.push_str(&format!(
+                "{},{start},{end},{}\n",
+                csv_field(tier_id),
+                csv_field(value)
+            ));
+        }
+    }
+    output
+}
+
+/// Parses tiers from CSV as generated by `to_csv`.
+/// Columns are matched on header names, in any order.
+pub fn from_csv(text: &str) -> std::io::Result<Vec<TierSpans>> {
+    let mut records = csv_records(text).into_iter();
+    let header = records.next().unwrap_or_default();
+    let column = |name: &str| -> std::io::Result<usize> {
+        header.iter().position(|h| h.trim() == name).ok_or_else(|| {
+            let msg = format!(
+                "(!) CSV is missing column '{name}'. Expected 'tier,start_ms,end_ms,value'."
+            );
+            std::io::Error::new(ErrorKind::Other, msg)
+        })
+    };
+    let (c_tier, c_start, c_end, c_value) = (
+        column("tier")?,
+        column("start_ms")?,
+        column("end_ms")?,
+        column("value")?,
+    );
+
+    let mut tiers: Vec<TierSpans> = Vec::new();
+    for (i, record) in records.enumerate() {
+        if record.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+        let field = |c: usize| record.get(c).map(|f| f.as_str()).unwrap_or_default();
+        let (Ok(start), Ok(end)) = (field(c_start).trim().parse(), field(c_end).trim().parse())
+        else {
+            // Row 1 is the header
+            let msg = format!("(!) Invalid time value in CSV row {}.", i + 2);
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        };
+        let span = (field(c_value).to_owned(), start, end);
+        match tiers.iter_mut().find(|(id, _)| id == field(c_tier)) {
+            Some((_, spans)) => spans.push(span),
+            None => tiers.push((field(c_tier).to_owned(), vec![span])),
+        }
+    }
+
+    Ok(tiers)
+}
+
+/// Returns tiers as a Praat TextGrid (long text format),
+/// with each tier as an interval tier.
+/// Gaps between annotations are filled with empty intervals,
+/// since interval tiers must cover the full time range.
+pub fn to_textgrid(tiers: &[TierSpans]) -> String {
+    let secs = |ms: i64| ms as f64 / 1000.0;
+    let xmax = tiers
+        .iter()
+        .flat_map(|(_, spans)| spans.iter().map(|(_, _, end)| *end))
+        .max()
+        .unwrap_or(0);
+
+    let mut output = format!(
+        "File type = \"ooTextFile\"\nObject class = \"TextGrid\"\n\nxmin = 0\nxmax = {}\ntiers? <exists>\nsize = {}\nitem []:\n",
+        secs(xmax),
+        tiers.len()
+    );
+
+    for (i, (tier_id, spans)) in tiers.iter().enumerate() {
+        // Intervals must be contiguous and non-overlapping
+        let mut intervals: Vec<(&str, i64, i64)> = Vec::new();
+        let mut last_end = 0;
+        for (value, start, end) in spans.iter() {
+            let start = (*start).max(last_end);
+            if start >= *end {
+                continue;
+            }
+            if start > last_end {
+                intervals.push(("", last_end, start));
+            }
+            intervals.push((value, start, *end));
+            last_end = *end;
+        }
+        if last_end < xmax || intervals.is_empty() {
+            intervals.push(("", last_end, xmax));
+        }
+
+        output.push_str(&format!(
+            "    item [{}]:\n        class = \"IntervalTier\"\n        name = \"{}\"\n        xmin = 0\n        xmax = {}\n        intervals: size = {}\n",
+            i + 1,
+            tier_id.replace('"', "\"\""),
+            secs(xmax),
+            intervals.len()
+        ));
+        for (n, (value, start, end)) in intervals.iter().enumerate() {
+            output.push_str(&format!(
+                "        intervals [{}]:\n            xmin = {}\n            xmax = {}\n            text = \"{}\"\n",
+                n + 1,
+                secs(*start),
+                secs(*end),
+                value.replace('"', "\"\"")
+            ));
+        }
+    }
+
+    output
+}
+
+/// Parses interval tiers in a Praat TextGrid (long text format).
+/// Empty intervals and point tiers are ignored.
+pub fn from_textgrid(text: &str) -> Vec<TierSpans> {
+    let mut tiers: Vec<TierSpans> = Vec::new();
+    // Current interval tier, interval start and end
+    let mut interval_tier = false;
+    let mut start: Option<i64> = None;
+    let mut end: Option<i64> = None;
+
+    let unquote = |value: &str| {
+        let value = value.trim();
+        value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value)
+            .replace("\"\"", "\"")
+    };
+    let ms = |value: &str| {
+        value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|s| (s * 1000.0).round() as i64)
+    };
+
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "class" => interval_tier = unquote(value) == "IntervalTier",
+            "name" if interval_tier => tiers.push((unquote(value), Vec::new())),
+            "xmin" => start = ms(value),
+            "xmax" => end = ms(value),
+            "text" if interval_tier => {
+                let value = unquote(value);
+                if let (Some(s), Some(e), Some((_, spans))) = (start, end, tiers.last_mut()) {
+                    if !value.trim().is_empty() {
+                        spans.push((value, s, e));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    tiers
+}
@@ -0,0 +1,157 @@
+//! Points from and to KML, GeoJSON, and GPX.
+//! Only point coordinates and datetimes are converted,
+//! styles and other properties are not.
+
+use std::{io::ErrorKind, path::Path, str::FromStr};
+
+use geojson::GeoJson;
+use regex::Regex;
+
+use crate::geo::{
+    geo_gpx::{parse_datetime, points_from_track, to_gpx},
+    kml_gen::kml_to_string,
+    DocumentInfo, EafPoint, EafPointCluster,
+};
+
+/// Geo format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoFormat {
+    Kml,
+    GeoJson,
+    Gpx,
+}
+
+impl GeoFormat {
+    /// Returns format for extension.
+    /// `json` is only recognized if `json` is set,
+    /// since it may also refer to annotations.
+    pub fn from_extension(ext: &str, json: bool) -> Option<Self> {
+        match ext {
+            "kml" => Some(Self::Kml),
+            "geojson" => Some(Self::GeoJson),
+            "json" if json => Some(Self::GeoJson),
+            "gpx" => Some(Self::Gpx),
+            _ => None,
+        }
+    }
+}
+
+/// Parses `lon,lat[,alt]` coordinate tuples in a KML `<coordinates>` element.
+fn kml_coordinates(text: &str) -> Vec<(f64, f64, f64)> {
+    text.split_whitespace()
+        .filter_map(|tuple| {
+            let mut values = tuple.split(',').map(|v| v.parse::<f64>().ok());
+            Some((
+                values.next()??,
+                values.next()??,
+                values.next().flatten().unwrap_or_default(),
+            ))
+        })
+        .collect()
+}
+
+/// Parses points in KML placemarks. Line strings and polygons
+/// are returned as one point per coordinate, with the
+/// placemark datetime (`<when>` or `<begin>`) set for the first point only.
+fn points_kml(xml: &str) -> Vec<EafPoint> {
+    let rx_placemark = Regex::new(r"(?s)<Placemark\b.*?</Placemark>").expect("Invalid regex");
+    let rx_coords = Regex::new(r"(?s)<coordinates>(.*?)</coordinates>").expect("Invalid regex");
+    let rx_when = Regex::new(r"<(?:when|begin)>([^<]*)</").expect("Invalid regex");
+
+    let mut points: Vec<EafPoint> = Vec::new();
+    for placemark in rx_placemark.find_iter(xml).map(|m| m.as_str()) {
+        let mut datetime = rx_when
+            .captures(placemark)
+            .and_then(|c| parse_datetime(c.get(1)?.as_str()));
+        for caps in rx_coords.captures_iter(placemark) {
+            for (longitude, latitude, altitude) in kml_coordinates(&caps[1]) {
+                points.push(EafPoint {
+                    latitude,
+                    longitude,
+                    altitude,
+                    datetime: datetime.take(),
+                    ..EafPoint::default()
+                })
+            }
+        }
+    }
+
+    points
+}
+
+/// Parses points in GeoJSON features. Line strings are returned
+/// as one point per position, with the feature datetime
+/// (`datetime` or `datetime_start` property) set for the first point only.
+fn points_geojson(text: &str) -> std::io::Result<Vec<EafPoint>> {
+    let geojson = match GeoJson::from_str(text) {
+        Ok(g) => g,
+        Err(err) => {
+            let msg = format!("(!) Failed to parse GeoJSON: {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+    let features = match geojson {
+        GeoJson::FeatureCollection(collection) => collection.features,
+        GeoJson::Feature(feature) => vec![feature],
+        GeoJson::Geometry(geometry) => vec![geojson::Feature::from(geometry)],
+    };
+
+    let mut points: Vec<EafPoint> = Vec::new();
+    for feature in features.iter() {
+        let mut datetime = ["datetime", "datetime_start"]
+            .iter()
+            .find_map(|name| feature.property(name)?.as_str())
+            .and_then(parse_datetime);
+        let positions = match feature.geometry.as_ref().map(|g| &g.value) {
+            Some(geojson::Value::Point(position)) => vec![position.to_owned()],
+            Some(geojson::Value::MultiPoint(positions))
+            | Some(geojson::Value::LineString(positions)) => positions.to_owned(),
+            _ => continue,
+        };
+        for position in positions.iter().filter(|p| p.len() >= 2) {
+            points.push(EafPoint {
+                latitude: position[1],
+                longitude: position[0],
+                altitude: position.get(2).copied().unwrap_or_default(),
+                datetime: datetime.take(),
+                ..EafPoint::default()
+            })
+        }
+    }
+
+    Ok(points)
+}
+
+/// Reads points from a KML, GeoJSON, GPX, or TCX file.
+pub fn read_points(path: &Path, format: Option<GeoFormat>) -> std::io::Result<Vec<EafPoint>> {
+    let points = match format {
+        Some(GeoFormat::Kml) => points_kml(&std::fs::read_to_string(path)?),
+        Some(GeoFormat::GeoJson) => points_geojson(&std::fs::read_to_string(path)?)?,
+        // GPX, TCX
+        _ => points_from_track(path)?,
+    };
+    if points.is_empty() {
+        let msg = format!("(!) No points in '{}'.", path.display());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+    Ok(points)
+}
+
+/// Returns points as KML, GeoJSON, or GPX.
+pub fn write_points(points: &[EafPoint], format: GeoFormat) -> String {
+    let cluster = EafPointCluster::new(points, None);
+    let info = DocumentInfo::new(points);
+    match format {
+        GeoFormat::Kml => {
+            let mut kml = cluster.to_kml(true);
+            info.add_to_kml(&mut kml);
+            kml_to_string(&kml)
+        }
+        GeoFormat::GeoJson => {
+            let mut json = cluster.to_json(true);
+            info.add_to_geojson(&mut json);
+            json.to_string()
+        }
+        GeoFormat::Gpx => to_gpx(points),
+    }
+}
@@ -0,0 +1,269 @@
+//! Convert between file formats, determined by file extension.
+//! - ELAN-file tier to subtitles (SRT, WebVTT)
+//! - Subtitles (SRT, WebVTT) to a new ELAN-file with a single tier
+//! - ELAN-file tiers to and from JSON, CSV, and Praat TextGrid
+//! - Points between KML, GeoJSON, and GPX (or TCX as input)
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use eaf_rs::eaf::{Eaf, Tier};
+
+use crate::{
+    elan::{
+        interpolated_spans, select_tier,
+        xml::Document,
+    },
+    files::{path_from_url, writefile, WriteStatus},
+    logging::info,
+};
+
+mod annotations;
+mod geo;
+mod subtitles;
+
+use annotations::{EafMeta, TierSpans};
+use geo::GeoFormat;
+use subtitles::Subtitles;
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Export a single tier as subtitles.
+/// Tier is selected interactively if not specified.
+fn eaf2subtitles(args: &clap::ArgMatches, format: Subtitles) -> std::io::Result<String> {
+    let path = args.get_one::<PathBuf>("input").unwrap(); // clap: required arg
+    let eaf = Eaf::read(path)?;
+
+    let tier = match args.get_one::<String>("tier") {
+        Some(id) => match eaf.get_tier(id) {
+            Some(t) => t.to_owned(),
+            None => {
+                let msg = format!("(!) No tier with ID '{id}' in '{}'.", path.display());
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        },
+        None => select_tier(&eaf, false)?,
+    };
+
+    // Interpolate time values for e.g. subdivision tiers
+    let spans = interpolated_spans(&tier);
+    info!(
+        "Exporting {} annotations in '{}'",
+        spans.len(),
+        tier.tier_id
+    );
+
+    Ok(subtitles::to_subtitles(&spans, format))
+}
+
+/// Import subtitles as a tier in a new ELAN-file.
+/// Tier ID defaults to the subtitle file name.
+fn subtitles2eaf(args: &clap::ArgMatches) -> std::io::Result<String> {
+    let path = args.get_one::<PathBuf>("input").unwrap(); // clap: required arg
+    let tier_id = match args.get_one::<String>("tier") {
+        Some(id) => id.to_owned(),
+        None => path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or("subtitles".to_owned()),
+    };
+
+    let spans = subtitles::from_subtitles(&std::fs::read_to_string(path)?);
+    if spans.is_empty() {
+        let msg = format!("(!) No subtitles in '{}'.", path.display());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+    info!("Importing {} subtitles as tier '{tier_id}'", spans.len());
+
+    new_eaf(&[(tier_id, spans)], &EafMeta::default(), args)
+}
+
+/// Generates a new ELAN-file with one top-level tier per item in `tiers`,
+/// linking media files specified via `--media`, or media in `meta` if not set.
+/// Tier attributes in `meta` are set for tiers in the new ELAN-file.
+fn new_eaf(
+    tiers: &[TierSpans],
+    meta: &EafMeta,
+    args: &clap::ArgMatches,
+) -> std::io::Result<String> {
+    let media: Vec<PathBuf> = match args.get_many::<PathBuf>("media") {
+        Some(paths) => paths.cloned().collect(),
+        None => meta
+            .media
+            .iter()
+            .map(|url| path_from_url(url))
+            .collect(),
+    };
+    let Some(((tier_id, spans), rest)) = tiers.split_first() else {
+        let msg = "(!) No tiers to import.";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    };
+
+    let mut eaf = match Eaf::from_values(spans, Some(tier_id)) {
+        Ok(e) => e,
+        Err(err) => {
+            let msg = format!("(!) Failed to generate EAF: {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+    if !media.is_empty() {
+        eaf.with_media_mut(&media);
+    }
+    eaf.index();
+    if let Err(err) = eaf.derive() {
+        let msg = format!("(!) Failed to generate EAF: {err}");
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    let mut doc = match eaf.to_string(Some(4)) {
+        Ok(s) => Document::parse(&s)?,
+        Err(err) => {
+            let msg = format!("(!) Failed to generate EAF: {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    // eaf-rs generates single tier ELAN-files only
+    for (tier_id, spans) in rest.iter() {
+        doc.add_aligned_tier(tier_id, spans);
+    }
+
+    for (tier_id, participant, annotator) in meta.tiers.iter() {
+        for (name, value) in [("PARTICIPANT", participant), ("ANNOTATOR", annotator)] {
+            if let Some(v) = value {
+                doc.set_tier_attribute(tier_id, name, v);
+            }
+        }
+    }
+
+    Ok(doc.to_string())
+}
+
+/// Export all tiers, or a single tier if specified,
+/// with interpolated time values, together with
+/// linked media and tier attributes.
+fn eaf2tiers(args: &clap::ArgMatches) -> std::io::Result<(Vec<TierSpans>, EafMeta)> {
+    let path = args.get_one::<PathBuf>("input").unwrap(); // clap: required arg
+    let eaf = Eaf::read(path)?;
+    let doc = Document::read(path)?;
+
+    // Tiers are borrowed, since ELAN-files may be large
+    let tiers: Vec<&Tier> = match args.get_one::<String>("tier") {
+        Some(id) => match eaf.get_tier(id) {
+            Some(t) => vec![t],
+            None => {
+                let msg = format!("(!) No tier with ID '{id}' in '{}'.", path.display());
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        },
+        None => eaf.tiers.iter().collect(),
+    };
+
+    let meta = EafMeta {
+        media: doc.ids("MEDIA_DESCRIPTOR", "MEDIA_URL"),
+        tiers: tiers
+            .iter()
+            .map(|t| {
+                (
+                    t.tier_id.to_owned(),
+                    t.participant.to_owned(),
+                    t.annotator.to_owned(),
+                )
+            })
+            .collect(),
+    };
+    let tiers: Vec<TierSpans> = tiers
+        .iter()
+        .map(|t| (t.tier_id.to_owned(), interpolated_spans(t)))
+        .collect();
+    info!(
+        "Exporting {} annotations in {} tiers",
+        tiers.iter().map(|(_, s)| s.len()).sum::<usize>(),
+        tiers.len()
+    );
+
+    Ok((tiers, meta))
+}
+
+/// Import JSON, CSV, or TextGrid tiers as a new ELAN-file.
+/// If a tier ID is specified, only that tier is imported.
+fn tiers2eaf(args: &clap::ArgMatches, ext: &str) -> std::io::Result<String> {
+    let path = args.get_one::<PathBuf>("input").unwrap(); // clap: required arg
+    let text = std::fs::read_to_string(path)?;
+    let (mut tiers, meta) = match ext {
+        "json" => annotations::from_json(&text)?,
+        "csv" => (annotations::from_csv(&text)?, EafMeta::default()),
+        _ => (annotations::from_textgrid(&text), EafMeta::default()),
+    };
+    if let Some(id) = args.get_one::<String>("tier") {
+        tiers.retain(|(tier_id, _)| tier_id == id);
+    }
+    tiers.retain(|(_, spans)| !spans.is_empty());
+    if tiers.is_empty() {
+        let msg = format!("(!) No annotations in '{}'.", path.display());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+    info!(
+        "Importing {} annotations in {} tiers",
+        tiers.iter().map(|(_, s)| s.len()).sum::<usize>(),
+        tiers.len()
+    );
+
+    new_eaf(&tiers, &meta, args)
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    // clap: required args
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let (ext_in, ext_out) = (extension(input), extension(output));
+    // '.json' is GeoJSON if the other file is a geo format, annotations otherwise
+    let is_geo = |ext: &str| ["kml", "geojson", "gpx", "tcx"].contains(&ext);
+    let geo_in = GeoFormat::from_extension(&ext_in, is_geo(&ext_out));
+    let geo_out = GeoFormat::from_extension(&ext_out, is_geo(&ext_in));
+    let content = match (ext_in.as_str(), ext_out.as_str()) {
+        ("eaf", "srt" | "vtt") => {
+            // safe unwrap: matched extension
+            eaf2subtitles(args, Subtitles::from_extension(&ext_out).unwrap())?
+        }
+        ("srt" | "vtt", "eaf") => subtitles2eaf(args)?,
+        ("eaf", "json") => {
+            let (tiers, meta) = eaf2tiers(args)?;
+            annotations::to_json(&tiers, &meta)
+        }
+        ("eaf", "csv") => annotations::to_csv(&eaf2tiers(args)?.0),
+        ("eaf", "textgrid") => annotations::to_textgrid(&eaf2tiers(args)?.0),
+        ("json" | "csv" | "textgrid", "eaf") => tiers2eaf(args, &ext_in)?,
+        _ if ext_in != ext_out && (geo_in.is_some() || ext_in == "tcx") && geo_out.is_some() => {
+            let points = geo::read_points(input, geo_in)?;
+            info!("Converting {} points", points.len());
+            // safe unwrap: checked above
+            geo::write_points(&points, geo_out.unwrap())
+        }
+        _ => {
+            let msg = format!(
+                "(!) Unsupported conversion '{ext_in}' -> '{ext_out}'. Run 'geoelan help convert'."
+            );
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    match writefile(content.as_bytes(), output) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", output.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing {}", output.display()),
+        Err(err) => {
+            let msg = format!("(!) Failed to write '{}': {err}", output.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+//! SubRip (SRT) and WebVTT subtitles from and to annotations,
+//! represented as `(value, start_ms, end_ms)`.
+
+/// Subtitle format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Subtitles {
+    Srt,
+    Vtt,
+}
+
+impl Subtitles {
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::Vtt),
+            _ => None,
+        }
+    }
+}
+
+/// Formats milliseconds as `HH:MM:SS,mmm` (SRT) or `HH:MM:SS.mmm` (WebVTT).
+fn timestamp(ms: i64, format: Subtitles) -> String {
+    let ms = ms.max(0);
+    let separator = match format {
+        Subtitles::Srt => ',',
+        Subtitles::Vtt => '.',
+    };
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        ms / 3_600_000,
+        (ms % 3_600_000) / 60_000,
+        (ms % 60_000) / 1000,
+        ms % 1000
+    )
+}
+
+/// Parses `HH:MM:SS,mmm`, `HH:MM:SS.mmm`, or `MM:SS.mmm` (WebVTT) as milliseconds.
+fn parse_timestamp(value: &str) -> Option<i64> {
+    let (hms, frac) = value.trim().split_once([',', '.'])?;
+    let mut units: Vec<i64> = hms
+        .split(':')
+        .map(|n| n.parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    if units.len() == 2 {
+        units.insert(0, 0);
+    }
+    let &[h, m, s] = units.as_slice() else {
+        return None;
+    };
+    // Pad or truncate fraction to milliseconds
+    let ms = format!("{frac:0<3}").get(0..3)?.parse::<i64>().ok()?;
+
+    Some(h * 3_600_000 + m * 60_000 + s * 1000 + ms)
+}
+
+/// Returns annotations as subtitles.
+/// Annotations with empty values are ignored.
+pub fn to_subtitles(spans: &[(String, i64, i64)], format: Subtitles) -> String {
+    let mut output = match format {
+        Subtitles::Srt => String::new(),
+        Subtitles::Vtt => String::from("WEBVTT\n\n"),
+    };
+
+    let cues = spans.iter().filter(|(value, ..)| !value.trim().is_empty());
+    for (i, (value, start, end)) in cues.enumerate() {
+        if format == Subtitles::Srt {
+            output.push_str(&format!("{}\n", i + 1));
+        }
+        output.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            timestamp(*start, format),
+            timestamp(*end, format),
+            value.trim()
+        ));
+    }
+
+    output
+}
+
+/// Parses SRT or WebVTT subtitles as annotations.
+/// Multi-line cues are joined with a space.
+/// WebVTT headers, notes, and cue settings are ignored.
+pub fn from_subtitles(text: &str) -> Vec<(String, i64, i64)> {
+    let text = text.replace("\r\n", "\n");
+
+    text.split("\n\n")
+        .filter_map(|block| {
+            let mut lines = block.lines().skip_while(|l| !l.contains("-->"));
+            let (start, end) = lines.next()?.split_once("-->")?;
+            // WebVTT cue settings may follow end time
+            let end = end.split_whitespace().next()?;
+            let value = lines
+                .map(|l| l.trim())
+                .filter(|l| !l.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            Some((value, parse_timestamp(start)?, parse_timestamp(end)?))
+        })
+        .collect()
+}
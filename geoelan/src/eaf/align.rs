@@ -0,0 +1,94 @@
+//! Align audio from an external recorder with the camera audio in an ELAN-file,
+//! via audio cross-correlation, and link it with the resulting offset
+//! set as time origin in the media descriptor.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    elan::xml::Document,
+    files::{file_url, path_from_url, writefile, WriteStatus},
+    logging::info,
+    media::audio::audio_offset,
+};
+
+/// Returns the path for the first linked video in the ELAN-file.
+/// Absolute media URLs are tried first, then a file with the same
+/// name in the ELAN-file's directory.
+pub(super) fn linked_video(doc: &Document, eaf_path: &Path) -> Option<PathBuf> {
+    let descriptor = doc
+        .elements("MEDIA_DESCRIPTOR")
+        .into_iter()
+        .find(|e| e.attribute("MIME_TYPE").is_some_and(|m| m.starts_with("video")))?;
+    let url = descriptor.attribute("MEDIA_URL")?;
+    let path = path_from_url(url);
+    if path.exists() {
+        return Some(path);
+    }
+    let local = eaf_path.with_file_name(path.file_name()?);
+    local.exists().then_some(local)
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let eaf_path = args.get_one::<PathBuf>("eaf").unwrap(); // clap: required arg
+    let audio = args.get_one::<PathBuf>("audio").unwrap().canonicalize()?; // clap: required arg
+    let ffmpeg = args.get_one::<PathBuf>("ffmpeg").unwrap(); // clap: default value
+    let max_lag = *args.get_one::<f64>("max-lag").unwrap(); // clap: default 30.0
+    let window = *args.get_one::<f64>("window").unwrap(); // clap: default 60.0
+
+    let mut doc = Document::read(eaf_path)?;
+
+    let video = match args.get_one::<PathBuf>("video") {
+        Some(p) => p.to_owned(),
+        None => match linked_video(&doc, eaf_path) {
+            Some(p) => p,
+            None => {
+                let msg = "(!) Failed to locate linked video. Specify camera media via '--video'.";
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        },
+    };
+
+    info!(
+        "Correlating audio in {} and {}...",
+        video.display(),
+        audio.display()
+    );
+    let (offset, score) = audio_offset(&video, &audio, 0, ffmpeg, max_lag, window)?;
+    info!("Correlation score {score:.2}");
+
+    // Annotation times are relative to the video, and ELAN time origins
+    // can not be negative, so the audio must start before the video.
+    if offset > 0 {
+        let msg = format!(
+            "(!) Audio starts {:.3}s after video. Trim the video or link the audio as the main media instead.",
+            offset as f64 / 1000.0
+        );
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+    let time_origin = -offset;
+    info!(
+        "Audio starts {:.3}s before video. Setting time origin to {time_origin} ms.",
+        time_origin as f64 / 1000.0
+    );
+
+    let url = file_url(&audio);
+    if !doc.add_media_descriptor(&url, "audio/x-wav", Some(time_origin)) {
+        let msg = format!("(!) No header in '{}'.", eaf_path.display());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    match writefile(doc.to_string().as_bytes(), eaf_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", eaf_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing {}", eaf_path.display()),
+        Err(err) => {
+            let msg = format!("(!) Failed to write '{}': {err}", eaf_path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! Cut the media linked in an ELAN-file into one clip per annotation
+//! in a tier, together with an ELAN-file for each clip,
+//! e.g. for building stimulus sets.
+//!
+//! Clips are named by annotation index and value,
+//! e.g. `003_greeting.mp4` and `003_greeting.eaf`.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use eaf_rs::eaf::Eaf;
+
+use crate::{
+    elan::{interpolated_spans, tier_by_id, xml::Document},
+    files::{create_dir, writefile, WriteStatus},
+    logging::info,
+    media::Media,
+};
+
+use super::align::linked_video;
+
+/// Annotation value as a file name component, with characters
+/// other than alphanumerics, `-`, and `_` replaced by `_`.
+fn file_stem(index: usize, value: &str) -> String {
+    let value: String = value
+        .trim()
+        .chars()
+        .map(|c| match c.is_alphanumeric() || c == '-' || c == '_' {
+            true => c,
+            false => '_',
+        })
+        .take(40)
+        .collect();
+    match value.is_empty() {
+        true => format!("{index:03}"),
+        false => format!("{index:03}_{value}"),
+    }
+}
+
+/// ELAN-file with a single annotation spanning the full clip.
+fn clip_eaf(
+    clip_path: &Path,
+    tier_id: &str,
+    value: &str,
+    duration_ms: i64,
+) -> std::io::Result<String> {
+    let mut eaf = match Eaf::from_values(&[(value.to_owned(), 0, duration_ms)], Some(tier_id)) {
+        Ok(e) => e,
+        Err(err) => {
+            let msg = format!("(!) Failed to generate EAF: {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+    eaf.with_media_mut(&[clip_path.to_owned()]);
+    eaf.index();
+    if let Err(err) = eaf.derive() {
+        let msg = format!("(!) Failed to generate EAF: {err}");
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+    match eaf.to_string(Some(4)) {
+        Ok(s) => Ok(s),
+        Err(err) => {
+            let msg = format!("(!) Failed to generate EAF: {err}");
+            Err(std::io::Error::new(ErrorKind::Other, msg))
+        }
+    }
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    // clap: required args
+    let eaf_path = args.get_one::<PathBuf>("eaf").unwrap();
+    let tier_id = args.get_one::<String>("tier").unwrap();
+    let output_dir = args.get_one::<PathBuf>("output-directory").unwrap();
+    let ffmpeg = args.get_one::<PathBuf>("ffmpeg").unwrap(); // clap: default value
+    let copy = *args.get_one::<bool>("copy").unwrap();
+
+    let doc = Document::read(eaf_path)?;
+    let video = match args.get_one::<PathBuf>("media") {
+        Some(p) => p.to_owned(),
+        None => match linked_video(&doc, eaf_path) {
+            Some(p) => p,
+            None => {
+                let msg = "(!) Failed to locate linked video. Specify media via '--media'.";
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        },
+    };
+    let extension = video
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or(String::from("mp4"));
+
+    let eaf = Eaf::read(eaf_path)?;
+    let tier = tier_by_id(&eaf, tier_id, false)?;
+    let spans = interpolated_spans(&tier);
+    if spans.is_empty() {
+        let msg = format!("(!) No annotations in tier '{tier_id}'.");
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    create_dir(output_dir)?;
+
+    info!(
+        "Cutting {} clips from {} (tier '{tier_id}')",
+        spans.len(),
+        video.display()
+    );
+    for (i, (value, start, end)) in spans.iter().enumerate() {
+        let stem = file_stem(i + 1, value);
+        let clip_path = output_dir.join(&stem).with_extension(&extension);
+        let eaf_out = output_dir.join(&stem).with_extension("eaf");
+
+        info!(
+            "  {:3}. {}-{}ms '{value}' -> {}",
+            i + 1,
+            start,
+            end,
+            clip_path.display()
+        );
+        Media::clip(
+            &video,
+            ffmpeg,
+            *start as f64 / 1000.,
+            (end - start) as f64 / 1000.,
+            copy,
+            &clip_path,
+        )?;
+
+        let clip_xml = clip_eaf(&clip_path, tier_id, value, end - start)?;
+        match writefile(clip_xml.as_bytes(), &eaf_out) {
+            Ok(WriteStatus::Written) => info!("       Wrote {}", eaf_out.display()),
+            Ok(WriteStatus::Planned) => (),
+            Ok(WriteStatus::Skipped) => {
+                info!("       User aborted writing {}", eaf_out.display())
+            }
+            Err(err) => {
+                let msg = format!("(!) Failed to write '{}': {err}", eaf_out.display());
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        }
+    }
+
+    Ok(())
+}
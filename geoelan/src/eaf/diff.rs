@@ -0,0 +1,321 @@
+//! Document-level diff between two versions of an ELAN-file,
+//! e.g. for reviewing annotation changes against a baseline.
+//!
+//! Annotations are compared tier by tier (tiers are matched on tier ID)
+//! in the following order:
+//! 1. Same value and time span: unchanged.
+//! 2. Same time span, different value: modified.
+//! 3. Same value, overlapping time span: time shift.
+//! 4. Remaining annotations: added or removed.
+
+use std::path::PathBuf;
+
+use eaf_rs::eaf::{Eaf, Tier};
+use serde_json::{json, Value};
+
+/// Annotation in the form (value, start ms, end ms).
+type Annot = (String, i64, i64);
+
+#[derive(Debug, Clone)]
+pub enum AnnotationChange {
+    Added(Annot),
+    Removed(Annot),
+    Modified { old: Annot, new: Annot },
+    Shifted { old: Annot, new: Annot },
+}
+
+impl AnnotationChange {
+    fn to_json(&self, tier_id: &str) -> Value {
+        match self {
+            Self::Added(a) => json!({"tier": tier_id, "change": "added", "new": annot_json(a)}),
+            Self::Removed(a) => json!({"tier": tier_id, "change": "removed", "old": annot_json(a)}),
+            Self::Modified { old, new } => {
+                json!({"tier": tier_id, "change": "modified", "old": annot_json(old), "new": annot_json(new)})
+            }
+            Self::Shifted { old, new } => json!({
+                "tier": tier_id,
+                "change": "shifted",
+                "old": annot_json(old),
+                "new": annot_json(new),
+                "start_delta_ms": new.1 - old.1,
+                "end_delta_ms": new.2 - old.2,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for AnnotationChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Added(a) => write!(f, "+ {}", annot_string(a)),
+            Self::Removed(a) => write!(f, "- {}", annot_string(a)),
+            Self::Modified { old, new } => write!(f, "~ {} -> '{}'", annot_string(old), new.0),
+            Self::Shifted { old, new } => write!(
+                f,
+                "> {} -> {}-{}ms (start {:+}ms, end {:+}ms)",
+                annot_string(old),
+                new.1,
+                new.2,
+                new.1 - old.1,
+                new.2 - old.2
+            ),
+        }
+    }
+}
+
+fn annot_string(annot: &Annot) -> String {
+    format!("{:>8}-{:<8}ms '{}'", annot.1, annot.2, annot.0)
+}
+
+fn annot_json(annot: &Annot) -> Value {
+    json!({"value": annot.0, "start_ms": annot.1, "end_ms": annot.2})
+}
+
+/// Changes to tier attributes, in the form (attribute, old, new).
+type TierAttrChange = (&'static str, Option<String>, Option<String>);
+
+/// Structured change set between two ELAN-files.
+#[derive(Debug, Default)]
+pub struct EafDiff {
+    pub tiers_added: Vec<String>,
+    pub tiers_removed: Vec<String>,
+    /// Tier attribute changes, per tier ID.
+    pub tiers_modified: Vec<(String, Vec<TierAttrChange>)>,
+    /// Annotation changes, per tier ID.
+    pub annotations: Vec<(String, Vec<AnnotationChange>)>,
+}
+
+impl EafDiff {
+    pub fn new(eaf_a: &Eaf, eaf_b: &Eaf) -> Self {
+        let mut diff = Self::default();
+
+        for tier_a in eaf_a.tiers.iter() {
+            match eaf_b.tiers.iter().find(|t| t.tier_id == tier_a.tier_id) {
+                Some(tier_b) => {
+                    let attrs = tier_changes(tier_a, tier_b);
+                    if !attrs.is_empty() {
+                        diff.tiers_modified.push((tier_a.tier_id.to_owned(), attrs));
+                    }
+                    let changes = annotation_changes(tier_a, tier_b);
+                    if !changes.is_empty() {
+                        diff.annotations.push((tier_a.tier_id.to_owned(), changes));
+                    }
+                }
+                None => {
+                    diff.tiers_removed.push(tier_a.tier_id.to_owned());
+                    let changes: Vec<_> = annotations(tier_a)
+                        .into_iter()
+                        .map(AnnotationChange::Removed)
+                        .collect();
+                    if !changes.is_empty() {
+                        diff.annotations.push((tier_a.tier_id.to_owned(), changes));
+                    }
+                }
+            }
+        }
+
+        for tier_b in eaf_b.tiers.iter() {
+            if !eaf_a.tiers.iter().any(|t| t.tier_id == tier_b.tier_id) {
+                diff.tiers_added.push(tier_b.tier_id.to_owned());
+                let changes: Vec<_> = annotations(tier_b)
+                    .into_iter()
+                    .map(AnnotationChange::Added)
+                    .collect();
+                if !changes.is_empty() {
+                    diff.annotations.push((tier_b.tier_id.to_owned(), changes));
+                }
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiers_added.is_empty()
+            && self.tiers_removed.is_empty()
+            && self.tiers_modified.is_empty()
+            && self.annotations.is_empty()
+    }
+
+    /// Returns number of changes for each kind:
+    /// (added, removed, modified, shifted).
+    pub fn count(&self) -> (usize, usize, usize, usize) {
+        let mut count = (0, 0, 0, 0);
+        for change in self.annotations.iter().flat_map(|(_, c)| c) {
+            match change {
+                AnnotationChange::Added(_) => count.0 += 1,
+                AnnotationChange::Removed(_) => count.1 += 1,
+                AnnotationChange::Modified { .. } => count.2 += 1,
+                AnnotationChange::Shifted { .. } => count.3 += 1,
+            }
+        }
+        count
+    }
+
+    pub fn to_json(&self) -> Value {
+        let (added, removed, modified, shifted) = self.count();
+        json!({
+            "tiers": {
+                "added": self.tiers_added,
+                "removed": self.tiers_removed,
+                "modified": self.tiers_modified.iter()
+                    .map(|(id, attrs)| json!({
+                        "tier": id,
+                        "attributes": attrs.iter()
+                            .map(|(attr, old, new)| json!({"attribute": attr, "old": old, "new": new}))
+                            .collect::<Vec<_>>()
+                    }))
+                    .collect::<Vec<_>>(),
+            },
+            "annotations": self.annotations.iter()
+                .flat_map(|(id, changes)| changes.iter().map(|c| c.to_json(id)))
+                .collect::<Vec<_>>(),
+            "summary": {
+                "added": added,
+                "removed": removed,
+                "modified": modified,
+                "shifted": shifted,
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for EafDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for tier_id in self.tiers_added.iter() {
+            writeln!(f, "+ TIER '{tier_id}'")?;
+        }
+        for tier_id in self.tiers_removed.iter() {
+            writeln!(f, "- TIER '{tier_id}'")?;
+        }
+        for (tier_id, attrs) in self.tiers_modified.iter() {
+            for (attr, old, new) in attrs.iter() {
+                writeln!(
+                    f,
+                    "~ TIER '{tier_id}' {attr}: {} -> {}",
+                    old.as_deref().unwrap_or("None"),
+                    new.as_deref().unwrap_or("None")
+                )?;
+            }
+        }
+        for (tier_id, changes) in self.annotations.iter() {
+            writeln!(f, "[{tier_id}]")?;
+            for change in changes.iter() {
+                writeln!(f, "  {change}")?;
+            }
+        }
+        let (added, removed, modified, shifted) = self.count();
+        write!(
+            f,
+            "---\nAnnotations: {added} added, {removed} removed, {modified} modified, {shifted} shifted"
+        )
+    }
+}
+
+/// Returns annotations in tier as (value, start ms, end ms).
+/// Annotations without time values are ignored.
+fn annotations(tier: &Tier) -> Vec<Annot> {
+    tier.annotations
+        .iter()
+        .filter_map(|a| match a.ts_val() {
+            (Some(start), Some(end)) => Some((a.value().to_string(), start, end)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn tier_changes(tier_a: &Tier, tier_b: &Tier) -> Vec<TierAttrChange> {
+    [
+        ("parent", &tier_a.parent_ref, &tier_b.parent_ref),
+        ("participant", &tier_a.participant, &tier_b.participant),
+        ("annotator", &tier_a.annotator, &tier_b.annotator),
+    ]
+    .into_iter()
+    .filter(|(_, a, b)| a != b)
+    .map(|(attr, a, b)| (attr, a.to_owned(), b.to_owned()))
+    .collect()
+}
+
+fn annotation_changes(tier_a: &Tier, tier_b: &Tier) -> Vec<AnnotationChange> {
+    let mut old = annotations(tier_a);
+    let mut new = annotations(tier_b);
+
+    // 1. Unchanged
+    old.retain(|a| match new.iter().position(|b| a == b) {
+        Some(i) => {
+            new.remove(i);
+            false
+        }
+        None => true,
+    });
+
+    let mut changes: Vec<AnnotationChange> = Vec::new();
+
+    // 2. Same time span, different value
+    old.retain(
+        |a| match new.iter().position(|b| a.1 == b.1 && a.2 == b.2) {
+            Some(i) => {
+                changes.push(AnnotationChange::Modified {
+                    old: a.to_owned(),
+                    new: new.remove(i),
+                });
+                false
+            }
+            None => true,
+        },
+    );
+
+    // 3. Same value, overlapping time span
+    old.retain(|a| {
+        match new
+            .iter()
+            .position(|b| a.0 == b.0 && a.1 < b.2 && b.1 < a.2)
+        {
+            Some(i) => {
+                changes.push(AnnotationChange::Shifted {
+                    old: a.to_owned(),
+                    new: new.remove(i),
+                });
+                false
+            }
+            None => true,
+        }
+    });
+
+    // 4. Remaining
+    changes.extend(old.into_iter().map(AnnotationChange::Removed));
+    changes.extend(new.into_iter().map(AnnotationChange::Added));
+
+    // Sort on start time
+    changes.sort_by_key(|c| match c {
+        AnnotationChange::Added(a) | AnnotationChange::Removed(a) => a.1,
+        AnnotationChange::Modified { old, .. } | AnnotationChange::Shifted { old, .. } => old.1,
+    });
+
+    changes
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    // clap: required args
+    let path_a = args.get_one::<PathBuf>("eaf-a").unwrap();
+    let path_b = args.get_one::<PathBuf>("eaf-b").unwrap();
+    let json = *args.get_one::<bool>("json").unwrap();
+
+    let eaf_a = Eaf::read(path_a)?;
+    let eaf_b = Eaf::read(path_b)?;
+
+    let diff = EafDiff::new(&eaf_a, &eaf_b);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff.to_json())?);
+    } else {
+        println!("--- {}\n+++ {}", path_a.display(), path_b.display());
+        if diff.is_empty() {
+            println!("No differences.")
+        } else {
+            println!("{diff}");
+        }
+    }
+
+    Ok(())
+}
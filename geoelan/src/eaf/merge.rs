@@ -0,0 +1,311 @@
+//! Merge several ELAN-files into one, e.g. annotations of the same
+//! recording by different annotators.
+//!
+//! The first ELAN-file is used as base, so that its header, media,
+//! and tier structure are kept. Top-level tiers in the remaining files
+//! are added as new tiers, or merged with the tier with the same ID.
+//! Identical annotations (same value and time span) are only kept once.
+//! Referring tiers in the remaining files are not merged.
+//!
+//! Merged tiers keep the attributes of the tier they originate from,
+//! e.g. participant, annotator, and linguistic type. Annotations in base
+//! tiers are only re-written if changed, and new annotations re-use
+//! time slots with the same time value.
+//!
+//! Overlapping annotations in merged tiers are resolved via `--overlap`:
+//! - `keep-both`: overlapping annotations are added to a new tier,
+//!   named `<TIER_ID>-<FILE_NAME>`, since annotations in a tier can not overlap.
+//! - `join`: overlapping annotations are joined into a single annotation
+//!   spanning both, with values separated by ` | `.
+//! - `error`: merge is aborted.
+
+use std::{
+    collections::BTreeMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use eaf_rs::eaf::Eaf;
+
+use crate::{
+    elan::{
+        interpolated_spans,
+        xml::{Document, Element},
+    },
+    files::{writefile, WriteStatus},
+    logging::{info, warn},
+};
+
+/// Annotation in the form (value, start ms, end ms).
+type Annot = (String, i64, i64);
+
+/// How to resolve overlapping annotations in merged tiers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overlap {
+    KeepBoth,
+    Join,
+    Error,
+}
+
+impl std::str::FromStr for Overlap {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "keep-both" => Ok(Self::KeepBoth),
+            "join" => Ok(Self::Join),
+            "error" => Ok(Self::Error),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("(!) Unknown overlap policy '{value}'"),
+            )),
+        }
+    }
+}
+
+/// Merged tier.
+#[derive(Debug, Default)]
+struct MergedTier {
+    annotations: Vec<Annot>,
+    /// Annotations in base ELAN-file, in document order.
+    base: Vec<Annot>,
+    /// Number of annotations in base ELAN-file. Differs from
+    /// the length of `base` if time values could not be interpolated.
+    base_count: usize,
+    /// Tier exists in base ELAN-file.
+    in_base: bool,
+    /// Tier was changed and needs to be re-written.
+    changed: bool,
+    /// Source for new tiers, as (index in merged ELAN-files, tier without annotations).
+    source: Option<(usize, Element)>,
+}
+
+fn overlaps(a: &Annot, b: &Annot) -> bool {
+    a.1 < b.2 && b.1 < a.2
+}
+
+/// Merges `annotations` into `tier`. Returns annotations
+/// that overlap and are kept separately (`Overlap::KeepBoth`),
+/// and the number of duplicates.
+fn merge_annotations(
+    tier: &mut MergedTier,
+    annotations: &[Annot],
+    overlap: Overlap,
+    tier_id: &str,
+    path: &Path,
+) -> std::io::Result<(Vec<Annot>, usize)> {
+    let mut separate: Vec<Annot> = Vec::new();
+    let mut duplicates = 0;
+
+    for annot in annotations.iter() {
+        if tier.annotations.contains(annot) {
+            duplicates += 1;
+            continue;
+        }
+        let (overlapping, mut rest): (Vec<Annot>, Vec<Annot>) =
+            tier.annotations.drain(..).partition(|a| overlaps(a, annot));
+        if overlapping.is_empty() {
+            rest.push(annot.to_owned());
+        } else {
+            match overlap {
+                Overlap::KeepBoth => {
+                    rest.extend(overlapping);
+                    separate.push(annot.to_owned());
+                }
+                Overlap::Join => {
+                    let mut joined = overlapping;
+                    joined.push(annot.to_owned());
+                    joined.sort_by_key(|a| a.1);
+                    let mut values: Vec<&str> = Vec::new();
+                    for (value, ..) in joined.iter() {
+                        if !values.contains(&value.as_str()) {
+                            values.push(value);
+                        }
+                    }
+                    rest.push((
+                        values.join(" | "),
+                        joined.iter().map(|a| a.1).min().unwrap_or(annot.1),
+                        joined.iter().map(|a| a.2).max().unwrap_or(annot.2),
+                    ));
+                }
+                Overlap::Error => {
+                    let msg = format!(
+                        "(!) Annotation '{}' ({}-{}ms) in tier '{tier_id}' in '{}' overlaps with '{}' ({}-{}ms).",
+                        annot.0,
+                        annot.1,
+                        annot.2,
+                        path.display(),
+                        overlapping[0].0,
+                        overlapping[0].1,
+                        overlapping[0].2,
+                    );
+                    return Err(std::io::Error::new(ErrorKind::Other, msg));
+                }
+            }
+        }
+        rest.sort_by_key(|a| a.1);
+        tier.annotations = rest;
+        tier.changed = true;
+    }
+
+    Ok((separate, duplicates))
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    // clap: required arg, at least two values
+    let paths: Vec<&PathBuf> = args.get_many::<PathBuf>("eaf").unwrap().collect();
+    let output = args.get_one::<PathBuf>("output").unwrap(); // clap: required arg
+    let overlap: Overlap = args.get_one::<String>("overlap").unwrap().parse()?; // clap: default value
+
+    let base_path = paths[0];
+    let base = Eaf::read(base_path)?;
+    let mut doc = Document::read(base_path)?;
+
+    // Top-level tiers in base without referring tiers can be re-written.
+    let mut tiers: BTreeMap<String, MergedTier> = base
+        .tiers
+        .iter()
+        .filter(|t| t.parent_ref.is_none())
+        .filter(|t| {
+            !base
+                .tiers
+                .iter()
+                .any(|r| r.parent_ref.as_deref() == Some(t.tier_id.as_str()))
+        })
+        .map(|t| {
+            let annotations = interpolated_spans(t);
+            let tier = MergedTier {
+                base: annotations.to_owned(),
+                base_count: t.annotations.len(),
+                annotations,
+                in_base: true,
+                ..MergedTier::default()
+            };
+            (t.tier_id.to_owned(), tier)
+        })
+        .collect();
+    // Tier IDs in order of addition, for writing new tiers in order
+    let mut order: Vec<String> = Vec::new();
+    // Merged ELAN-files, for copying tier attributes
+    let mut sources: Vec<Document> = Vec::new();
+
+    for path in paths.iter().skip(1) {
+        let eaf = Eaf::read(path)?;
+        let source = Document::read(path)?;
+        let source_tier = |tier_id: &str| {
+            source
+                .tier(tier_id)
+                .map(|t| (sources.len(), t.without_children()))
+        };
+        let file_name = path
+            .file_stem()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        info!("Merging {}", path.display());
+
+        for tier in eaf.tiers.iter() {
+            if tier.parent_ref.is_some() {
+                warn!(
+                    "  (!) Skipping referring tier '{}'. Only top-level tiers are merged.",
+                    tier.tier_id
+                );
+                continue;
+            }
+            let annotations = interpolated_spans(tier);
+
+            // Tier in base that can not be re-written, e.g. since
+            // it has referring tiers: add annotations as a separate tier
+            let tier_id = match !tiers.contains_key(&tier.tier_id)
+                && base.tiers.iter().any(|t| t.tier_id == tier.tier_id)
+            {
+                true => {
+                    let id = format!("{}-{file_name}", tier.tier_id);
+                    warn!(
+                        "  (!) Tier '{}' can not be re-written in '{}'. Adding annotations as '{id}'.",
+                        tier.tier_id,
+                        base_path.display()
+                    );
+                    id
+                }
+                false => tier.tier_id.to_owned(),
+            };
+
+            if !tiers.contains_key(&tier_id) {
+                order.push(tier_id.to_owned());
+            }
+            let merged = tiers.entry(tier_id.to_owned()).or_default();
+            if !merged.in_base && merged.source.is_none() {
+                merged.source = source_tier(&tier.tier_id);
+            }
+            let (separate, duplicates) =
+                merge_annotations(merged, &annotations, overlap, &tier_id, path)?;
+            info!(
+                "  Tier '{tier_id}': {} annotations, {duplicates} duplicates",
+                annotations.len()
+            );
+
+            if !separate.is_empty() {
+                let separate_id = format!("{tier_id}-{file_name}");
+                info!(
+                    "  Tier '{separate_id}': {} overlapping annotations from '{tier_id}'",
+                    separate.len()
+                );
+                if !tiers.contains_key(&separate_id) {
+                    order.push(separate_id.to_owned());
+                }
+                let separate_tier = tiers.entry(separate_id.to_owned()).or_default();
+                if separate_tier.source.is_none() {
+                    separate_tier.source = source_tier(&tier.tier_id);
+                }
+                // Annotations from the same tier never overlap
+                merge_annotations(separate_tier, &separate, overlap, &separate_id, path)?;
+            }
+        }
+        sources.push(source);
+    }
+
+    // Update changed tiers in base, keeping unchanged annotations as is,
+    // then add new tiers
+    for (tier_id, tier) in tiers.iter().filter(|(_, t)| t.in_base && t.changed) {
+        let (keep, added): (Vec<bool>, Vec<Annot>) = match tier.base.len() == tier.base_count {
+            true => (
+                tier.base
+                    .iter()
+                    .map(|a| tier.annotations.contains(a))
+                    .collect(),
+                tier.annotations
+                    .iter()
+                    .filter(|a| !tier.base.contains(a))
+                    .cloned()
+                    .collect(),
+            ),
+            // Annotations can not be matched, re-write all
+            false => (vec![false; tier.base_count], tier.annotations.to_owned()),
+        };
+        doc.update_aligned_tier(tier_id, &keep, &added);
+    }
+    for tier_id in order.iter() {
+        if let Some(tier) = tiers.get(tier_id) {
+            let (source, element) = match &tier.source {
+                Some((i, element)) => (Some(&sources[*i]), element.to_owned()),
+                None => (
+                    None,
+                    Element::new("TIER").with_attribute("TIER_ID", tier_id),
+                ),
+            };
+            doc.add_aligned_tier_from(source, &element, tier_id, &tier.annotations);
+        }
+    }
+
+    match writefile(doc.to_string().as_bytes(), output) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", output.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing {}", output.display()),
+        Err(err) => {
+            let msg = format!("(!) Failed to write '{}': {err}", output.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! ELAN-file utilities, e.g. comparing two versions of an ELAN-file,
+//! find-and-replace for annotation values, corpus statistics, creating ELAN templates,
+//! aligning external audio, merging ELAN-files, querying annotation values,
+//! cutting media into clips per annotation, or scanning a corpus.
+
+use std::io::ErrorKind;
+
+mod align;
+mod clips;
+mod diff;
+mod merge;
+mod query;
+mod replace;
+mod scan;
+mod stats;
+mod template;
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    match args.subcommand() {
+        Some(("align", arg_matches)) => align::run(arg_matches),
+        Some(("clips", arg_matches)) => clips::run(arg_matches),
+        Some(("diff", arg_matches)) => diff::run(arg_matches),
+        Some(("merge", arg_matches)) => merge::run(arg_matches),
+        Some(("query", arg_matches)) => query::run(arg_matches),
+        Some(("replace", arg_matches)) => replace::run(arg_matches),
+        Some(("scan", arg_matches)) => scan::run(arg_matches),
+        Some(("stats", arg_matches)) => stats::run(arg_matches),
+        Some(("template", arg_matches)) => template::run(arg_matches),
+        // clap: subcommand required
+        _ => {
+            let msg = "(!) No such 'eaf' subcommand. Run 'geoelan help eaf'.";
+            Err(std::io::Error::new(ErrorKind::Other, msg))
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Query annotation values in ELAN-files using regular expressions,
+//! e.g. for finding all occurrences of a term across a corpus.
+//!
+//! Matches can be saved as CSV with time codes, or as a new ELAN-file
+//! that only contains matching annotations. In the latter, all tiers
+//! with matches are added as top-level, time-aligned tiers, with time values
+//! interpolated for annotations that lack these, e.g. in referring tiers.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use eaf_rs::eaf::Eaf;
+use regex::Regex;
+
+use crate::{
+    elan::{
+        interpolated_spans,
+        xml::Document,
+    },
+    files::{csv_field, writefile, WriteStatus},
+    logging::{info, warn},
+};
+
+/// Annotations in the form (value, start ms, end ms) for a tier.
+type TierSpans = (String, Vec<(String, i64, i64)>);
+
+/// Formats milliseconds as `HH:MM:SS.mmm`.
+fn timecode(ms: i64) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        (ms % 3_600_000) / 60_000,
+        (ms % 60_000) / 1000,
+        ms % 1000
+    )
+}
+
+/// Returns annotations with values matching `regex`, per tier.
+/// If `tiers` is not empty, only the specified tiers are queried.
+/// Tiers without matches are not included.
+pub fn filter_by_query(eaf: &Eaf, regex: &Regex, tiers: &[String]) -> Vec<TierSpans> {
+    eaf.tiers
+        .iter()
+        .filter(|t| tiers.is_empty() || tiers.contains(&t.tier_id))
+        .map(|t| {
+            let spans: Vec<_> = interpolated_spans(t)
+                .into_iter()
+                .filter(|(value, ..)| regex.is_match(value))
+                .collect();
+            (t.tier_id.to_owned(), spans)
+        })
+        .filter(|(_, spans)| !spans.is_empty())
+        .collect()
+}
+
+/// Returns ELAN-file as XML text with all tiers replaced by `matches`.
+/// Header, media, and linguistic types etc are kept.
+fn filtered_eaf(path: &Path, eaf: &Eaf, matches: &[TierSpans]) -> std::io::Result<String> {
+    let mut doc = Document::read(path)?;
+    for tier in eaf.tiers.iter() {
+        doc.remove_tier(&tier.tier_id);
+    }
+    for (tier_id, spans) in matches.iter() {
+        doc.add_aligned_tier(tier_id, spans);
+    }
+    Ok(doc.to_string())
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    // clap: required args
+    let paths: Vec<&PathBuf> = args.get_many::<PathBuf>("eaf").unwrap().collect();
+    let pattern = args.get_one::<String>("pattern").unwrap();
+    let tiers: Vec<String> = args
+        .get_many::<String>("tier")
+        .map(|t| t.cloned().collect())
+        .unwrap_or_default();
+    let csv_path = args.get_one::<PathBuf>("csv");
+    let output = args.get_one::<PathBuf>("output");
+
+    if output.is_some() && paths.len() > 1 {
+        let msg = "(!) '--output' can only be used with a single ELAN-file.";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    let regex = match Regex::new(pattern) {
+        Ok(rx) => rx,
+        Err(err) => {
+            let msg = format!("(!) Invalid regular expression '{pattern}': {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    let mut csv = String::from("file,tier,start_ms,end_ms,start,end,value\n");
+    let mut total = 0;
+    for path in paths.iter() {
+        let eaf = Eaf::read(path)?;
+        let matches = filter_by_query(&eaf, &regex, &tiers);
+        let count = matches.iter().map(|(_, s)| s.len()).sum::<usize>();
+        total += count;
+
+        println!("[{}] {count} matches", path.display());
+        for (tier_id, spans) in matches.iter() {
+            for (value, start, end) in spans.iter() {
+                println!(
+                    "  {tier_id:20} {}-{} '{value}'",
+                    timecode(*start),
+                    timecode(*end)
+                );
+                csv.push_str(&format!(
+                    "{},{},{start},{end},{},{},{}\n",
+                    csv_field(&path.display().to_string()),
+                    csv_field(tier_id),
+                    timecode(*start),
+                    timecode(*end),
+                    csv_field(value)
+                ));
+            }
+        }
+
+        if let Some(output) = output {
+            if matches.is_empty() {
+                warn!("(!) No matches, skipping {}", output.display());
+                continue;
+            }
+            let xml = filtered_eaf(path, &eaf, &matches)?;
+            match writefile(xml.as_bytes(), output) {
+                Ok(WriteStatus::Written) => info!("Wrote {}", output.display()),
+                Ok(WriteStatus::Planned) => (),
+                Ok(WriteStatus::Skipped) => info!("User aborted writing {}", output.display()),
+                Err(err) => {
+                    let msg = format!("(!) Failed to write '{}': {err}", output.display());
+                    return Err(std::io::Error::new(ErrorKind::Other, msg));
+                }
+            }
+        }
+    }
+
+    info!("Done. {total} matches in {} files.", paths.len());
+
+    if let Some(csv_path) = csv_path {
+        match writefile(csv.as_bytes(), csv_path) {
+            Ok(WriteStatus::Written) => info!("Wrote {}", csv_path.display()),
+            Ok(WriteStatus::Planned) => (),
+            Ok(WriteStatus::Skipped) => info!("User aborted writing {}", csv_path.display()),
+            Err(err) => {
+                let msg = format!("(!) Failed to write '{}': {err}", csv_path.display());
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+//! Find-and-replace for annotation values in ELAN-files, using regular expressions,
+//! e.g. for orthography fixes across a corpus.
+//!
+//! Annotation values are located by reading the XML text as events with `quick-xml`,
+//! and replaced directly in the text, so that everything except
+//! the changed annotation values is left byte-for-byte identical.
+
+use std::{io::ErrorKind, path::PathBuf};
+
+use quick_xml::{
+    escape::unescape,
+    events::{BytesStart, Event},
+    Reader,
+};
+use regex::Regex;
+
+use crate::elan::xml::escape;
+use crate::files::{writefile, WriteStatus};
+use crate::logging::{info, warn};
+
+/// A single changed annotation value.
+#[derive(Debug, Clone)]
+pub struct Replacement {
+    pub tier_id: String,
+    pub annotation_id: String,
+    pub old: String,
+    pub new: String,
+}
+
+impl std::fmt::Display for Replacement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:20} {:8} '{}' -> '{}'",
+            self.tier_id, self.annotation_id, self.old, self.new
+        )
+    }
+}
+
+/// Returns the unescaped value for attribute `name` in a start tag.
+fn attribute(tag: &BytesStart, name: &str) -> Option<String> {
+    let value = tag.try_get_attribute(name).ok()??.unescape_value().ok()?;
+    Some(value.to_string())
+}
+
+/// Replaces all matches for `regex` with `replacement` in the annotation values
+/// of an ELAN-file as XML text. `replacement` may refer to capture groups,
+/// e.g. `$1`. If `tiers` is not empty, only annotations in the specified tiers
+/// are changed.
+///
+/// Returns the new XML text and the changes made, which
+/// can be used as a preview, or an error if the XML is not well-formed.
+pub fn replace_values(
+    xml: &str,
+    regex: &Regex,
+    replacement: &str,
+    tiers: &[String],
+) -> std::io::Result<(String, Vec<Replacement>)> {
+    let mut reader = Reader::from_str(xml);
+    let mut output = String::with_capacity(xml.len());
+    let mut replacements: Vec<Replacement> = Vec::new();
+    let mut last = 0;
+
+    let mut tier_id = String::new();
+    let mut annotation_id = String::new();
+    // Byte offset for the start of the current annotation value
+    let mut value_start: Option<usize> = None;
+
+    loop {
+        let position = reader.buffer_position() as usize;
+        let event = match reader.read_event() {
+            Ok(e) => e,
+            Err(err) => {
+                let msg = format!("(!) Invalid XML at byte {}: {err}", reader.error_position());
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        };
+        match event {
+            Event::Start(tag) => match tag.name().as_ref() {
+                b"TIER" => tier_id = attribute(&tag, "TIER_ID").unwrap_or_default(),
+                b"ALIGNABLE_ANNOTATION" | b"REF_ANNOTATION" => {
+                    annotation_id = attribute(&tag, "ANNOTATION_ID").unwrap_or_default()
+                }
+                b"ANNOTATION_VALUE" => value_start = Some(reader.buffer_position() as usize),
+                _ => (),
+            },
+            Event::End(tag) if tag.name().as_ref() == b"ANNOTATION_VALUE" => {
+                let Some(start) = value_start.take() else {
+                    continue;
+                };
+                if !tiers.is_empty() && !tiers.contains(&tier_id) {
+                    continue;
+                }
+
+                // Value as is, including any markup, up to the end tag
+                let Ok(old) = unescape(&xml[start..position]) else {
+                    continue;
+                };
+                if !regex.is_match(&old) {
+                    continue;
+                }
+                let new = regex.replace_all(&old, replacement).to_string();
+                if new == old {
+                    continue;
+                }
+
+                output.push_str(&xml[last..start]);
+                output.push_str(&escape(&new));
+                last = position;
+
+                replacements.push(Replacement {
+                    tier_id: tier_id.to_owned(),
+                    annotation_id: annotation_id.to_owned(),
+                    old: old.to_string(),
+                    new,
+                });
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+    output.push_str(&xml[last..]);
+
+    Ok((output, replacements))
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    // clap: required args
+    let paths: Vec<&PathBuf> = args.get_many::<PathBuf>("eaf").unwrap().collect();
+    let pattern = args.get_one::<String>("pattern").unwrap();
+    let replacement = args.get_one::<String>("replacement").unwrap();
+    let tiers: Vec<String> = args
+        .get_many::<String>("tier")
+        .map(|t| t.cloned().collect())
+        .unwrap_or_default();
+    let dryrun = crate::files::dryrun();
+
+    let regex = match Regex::new(pattern) {
+        Ok(rx) => rx,
+        Err(err) => {
+            let msg = format!("(!) Invalid regular expression '{pattern}': {err}");
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
+        }
+    };
+
+    let mut total = 0;
+    for path in paths.iter() {
+        let xml = std::fs::read_to_string(path)?;
+        let (new_xml, replacements) = replace_values(&xml, &regex, replacement, &tiers)?;
+
+        info!("[{}] {} changes", path.display(), replacements.len());
+        for (i, change) in replacements.iter().enumerate() {
+            info!("  {:4}. {change}", i + 1);
+        }
+        total += replacements.len();
+
+        if replacements.is_empty() {
+            continue;
+        }
+
+        match writefile(new_xml.as_bytes(), path) {
+            Ok(WriteStatus::Written) => info!("Wrote {}", path.display()),
+            Ok(WriteStatus::Planned) => (),
+            Ok(WriteStatus::Skipped) => info!("User aborted writing {}", path.display()),
+            Err(err) => {
+                let msg = format!("(!) Failed to write '{}': {err}", path.display());
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
+            }
+        }
+    }
+
+    info!("Done. {total} changes in {} files.", paths.len());
+    if dryrun {
+        warn!("(!) '--dryrun' set, no files changed.");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tier_and_annotation_ids() {
+        let xml = r#"<TIER TIER_ID="A">
+    <ANNOTATION><ALIGNABLE_ANNOTATION ANNOTATION_ID="a1"><ANNOTATION_VALUE>colour</ANNOTATION_VALUE></ALIGNABLE_ANNOTATION></ANNOTATION>
+    <ANNOTATION><ALIGNABLE_ANNOTATION ANNOTATION_ID="a2"><ANNOTATION_VALUE>none</ANNOTATION_VALUE></ALIGNABLE_ANNOTATION></ANNOTATION>
+</TIER>
+<TIER TIER_ID="B">
+    <ANNOTATION><ALIGNABLE_ANNOTATION ANNOTATION_ID="a3"><ANNOTATION_VALUE>colour &amp; flavour</ANNOTATION_VALUE></ALIGNABLE_ANNOTATION></ANNOTATION>
+</TIER>"#;
+        let regex = Regex::new("our").unwrap();
+        let (new, changes) = replace_values(xml, &regex, "or", &[]).unwrap();
+        let ids: Vec<_> = changes
+            .iter()
+            .map(|c| (c.tier_id.as_str(), c.annotation_id.as_str()))
+            .collect();
+        assert_eq!(ids, [("A", "a1"), ("B", "a3")]);
+        assert!(new.contains("<ANNOTATION_VALUE>color &amp; flavor</ANNOTATION_VALUE>"));
+
+        let (_, changes) = replace_values(xml, &regex, "or", &["B".to_owned()]).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].annotation_id, "a3");
+    }
+
+    #[test]
+    fn markup_in_tier_ids() {
+        let xml = r#"<TIER TIER_ID="A &amp; B"><ANNOTATION><ALIGNABLE_ANNOTATION ANNOTATION_ID="a1"><ANNOTATION_VALUE></ANNOTATION_VALUE></ALIGNABLE_ANNOTATION></ANNOTATION></TIER>"#;
+        let regex = Regex::new("^$").unwrap();
+        let (new, changes) = replace_values(xml, &regex, "<empty>", &["A & B".to_owned()]).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].tier_id, "A & B");
+        assert!(new.contains("<ANNOTATION_VALUE>&lt;empty&gt;</ANNOTATION_VALUE>"));
+    }
+}
@@ -0,0 +1,66 @@
+//! Fast corpus overview: tier IDs, linked media, time bounds,
+//! and number of annotations for each ELAN-file,
+//! without deserializing the full documents.
+
+use std::path::PathBuf;
+
+use serde_json::json;
+
+use crate::{
+    elan::scan::scan,
+    files::paths,
+    logging::{info, warn},
+};
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let json = *args.get_one::<bool>("json").unwrap();
+
+    // ELAN-files, or directories to search for ELAN-files (clap: required arg)
+    let mut eaf_paths: Vec<PathBuf> = Vec::new();
+    for path in args.get_many::<PathBuf>("path").unwrap() {
+        match path.is_dir() {
+            true => eaf_paths.extend(paths(path, &["eaf"])),
+            false => eaf_paths.push(path.to_owned()),
+        }
+    }
+
+    let mut files = Vec::new();
+    for path in eaf_paths.iter() {
+        match scan(path) {
+            Ok(summary) => files.push((path, summary)),
+            Err(err) => warn!("(!) Failed to scan '{}': {err}", path.display()),
+        }
+    }
+
+    if json {
+        let output: Vec<_> = files
+            .iter()
+            .map(|(path, summary)| {
+                let mut value = summary.to_json();
+                value["path"] = json!(path.display().to_string());
+                value
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    for (path, summary) in files.iter() {
+        println!("[{}]", path.display());
+        println!(
+            "  Span:        {}",
+            summary
+                .time_bounds
+                .map(|(start, end)| format!("{start}-{end}ms"))
+                .unwrap_or(String::from("No time slots"))
+        );
+        println!("  Annotations: {}", summary.annotations);
+        println!("  Tiers:       {}", summary.tiers.join(", "));
+        for url in summary.media.iter() {
+            println!("  Media:       {url}");
+        }
+    }
+    info!("Done. Scanned {} ELAN-files.", files.len());
+
+    Ok(())
+}
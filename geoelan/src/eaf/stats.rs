@@ -0,0 +1,173 @@
+//! Annotation and token statistics for one or more ELAN-files,
+//! optionally filtered on tier attributes, e.g. for per speaker statistics.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use eaf_rs::eaf::{Eaf, Tier};
+use serde_json::json;
+
+use crate::elan::interpolated_spans;
+
+/// Tier attribute filters. Unset filters match all tiers.
+#[derive(Debug, Default, Clone)]
+pub struct TierFilter {
+    pub tier_id: Option<String>,
+    pub participant: Option<String>,
+    pub annotator: Option<String>,
+    pub language: Option<String>,
+    pub linguistic_type: Option<String>,
+}
+
+impl TierFilter {
+    pub fn from_args(args: &clap::ArgMatches) -> Self {
+        Self {
+            tier_id: args.get_one::<String>("tier").cloned(),
+            participant: args.get_one::<String>("participant").cloned(),
+            annotator: args.get_one::<String>("annotator").cloned(),
+            language: args.get_one::<String>("language").cloned(),
+            linguistic_type: args.get_one::<String>("linguistic-type").cloned(),
+        }
+    }
+
+    /// Returns `true` if `tier` matches all set filters.
+    pub fn matches(&self, tier: &Tier) -> bool {
+        let matches = |filter: &Option<String>, value: Option<&str>| match filter {
+            Some(f) => value == Some(f.as_str()),
+            None => true,
+        };
+        matches(&self.tier_id, Some(tier.tier_id.as_str()))
+            && matches(&self.participant, tier.participant.as_deref())
+            && matches(&self.annotator, tier.annotator.as_deref())
+            && matches(&self.language, tier.lang_ref.as_deref())
+            && matches(
+                &self.linguistic_type,
+                Some(tier.linguistic_type_ref.as_str()),
+            )
+    }
+}
+
+/// Statistics for a single tier.
+#[derive(Debug, Clone)]
+pub struct TierStats {
+    pub tier_id: String,
+    pub participant: Option<String>,
+    pub annotations: usize,
+    pub tokens: usize,
+    pub tokens_unique: usize,
+    /// Total annotated time in milliseconds.
+    pub duration_ms: i64,
+}
+
+impl TierStats {
+    pub fn new(tier: &Tier) -> Self {
+        Self {
+            tier_id: tier.tier_id.to_owned(),
+            participant: tier.participant.to_owned(),
+            annotations: tier.len(),
+            tokens: tier.tokens(None, None, false, false).len(),
+            tokens_unique: tier.tokens(None, None, true, true).len(),
+            duration_ms: interpolated_spans(tier)
+                .iter()
+                .map(|(_, start, end)| end - start)
+                .sum(),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "tier_id": self.tier_id,
+            "participant": self.participant,
+            "annotations": self.annotations,
+            "tokens": self.tokens,
+            "tokens_unique": self.tokens_unique,
+            "duration_ms": self.duration_ms,
+        })
+    }
+}
+
+impl std::fmt::Display for TierStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:20} {:15} {:>11} {:>8} {:>8} {:>12.3}",
+            self.tier_id,
+            self.participant.as_deref().unwrap_or("None"),
+            self.annotations,
+            self.tokens,
+            self.tokens_unique,
+            self.duration_ms as f64 / 1000.0
+        )
+    }
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let paths: Vec<&PathBuf> = args.get_many::<PathBuf>("eaf").unwrap().collect(); // clap: required arg
+    let json = *args.get_one::<bool>("json").unwrap();
+    let filter = TierFilter::from_args(args);
+
+    let mut files: Vec<(&PathBuf, Vec<TierStats>)> = Vec::new();
+    for path in paths.into_iter() {
+        let eaf = Eaf::read(path)?;
+        let stats: Vec<TierStats> = eaf
+            .tiers
+            .iter()
+            .filter(|t| filter.matches(t))
+            .map(TierStats::new)
+            .collect();
+        files.push((path, stats));
+    }
+
+    // Totals per participant across all files, as (annotations, tokens, duration)
+    let mut participants: BTreeMap<String, (usize, usize, i64)> = BTreeMap::new();
+    for stats in files.iter().flat_map(|(_, s)| s.iter()) {
+        let key = stats.participant.to_owned().unwrap_or("None".to_owned());
+        let entry = participants.entry(key).or_default();
+        entry.0 += stats.annotations;
+        entry.1 += stats.tokens;
+        entry.2 += stats.duration_ms;
+    }
+
+    if json {
+        let output = json!({
+            "files": files.iter().map(|(path, stats)| json!({
+                "path": path,
+                "tiers": stats.iter().map(|s| s.to_json()).collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+            "participants": participants.iter().map(|(p, (a, t, d))| json!({
+                "participant": p,
+                "annotations": a,
+                "tokens": t,
+                "duration_ms": d,
+            })).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    for (path, stats) in files.iter() {
+        println!("[{}] {} tiers", path.display(), stats.len());
+        println!(
+            "  {:20} {:15} {:>11} {:>8} {:>8} {:>12}",
+            "Tier", "Participant", "Annotations", "Tokens", "Unique", "Duration (s)"
+        );
+        for tier_stats in stats.iter() {
+            println!("  {tier_stats}");
+        }
+    }
+    println!("---");
+    println!(
+        "  {:15} {:>11} {:>8} {:>12}",
+        "Participant", "Annotations", "Tokens", "Duration (s)"
+    );
+    for (participant, (annotations, tokens, duration)) in participants.iter() {
+        println!(
+            "  {:15} {:>11} {:>8} {:>12.3}",
+            participant,
+            annotations,
+            tokens,
+            *duration as f64 / 1000.0
+        );
+    }
+
+    Ok(())
+}
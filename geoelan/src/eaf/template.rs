@@ -0,0 +1,32 @@
+//! Create an ELAN template (`.etf`) from an existing ELAN-file,
+//! for applying the same tier structure to new ELAN-files.
+
+use std::{io::ErrorKind, path::PathBuf};
+
+use crate::{
+    elan::xml::Document,
+    files::{writefile, WriteStatus},
+    logging::info,
+};
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let path = args.get_one::<PathBuf>("eaf").unwrap(); // clap: required arg
+    let output = match args.get_one::<PathBuf>("output") {
+        Some(p) => p.to_owned(),
+        None => path.with_extension("etf"),
+    };
+
+    let template = Document::read(path)?.to_template();
+
+    match writefile(template.to_string().as_bytes(), &output) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", output.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing {}", output.display()),
+        Err(err) => {
+            let msg = format!("(!) Failed to write '{}': {err}", output.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    }
+
+    Ok(())
+}
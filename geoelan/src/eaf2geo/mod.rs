@@ -3,31 +3,40 @@
 use std::{
     collections::{HashMap, HashSet},
     io::ErrorKind,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use eaf_rs::Eaf;
+use eaf_rs::{eaf::Tier, Eaf};
+use geojson::Feature;
 use kml::types::{Element, Placemark};
 use time::Duration;
 
 use crate::{
-    elan::select_tier,
-    files,
+    elan::{interpolated_spans, select_tier, tier_by_id},
+    files::{self, WriteStatus},
     geo::{
         geoshape::{filter_downsample, GeoShape},
-        json_gen::geojson_from_clusters,
-        kml_gen::{kml_from_placemarks, kml_style, kml_to_string, placemarks_from_geoshape},
+        json_gen::{features_from_geoshape, geojson_from_features},
+        kml_gen::{
+            kml_extended_data, kml_from_placemarks, kml_style, kml_to_string,
+            placemarks_from_geoshape,
+        },
         kml_styles::Rgba,
-        EafPoint,
+        wmm::{decimal_year, MagneticModel},
+        DocumentInfo, EafPoint, GpsFix, PointStrategy,
     },
+    logging::{info, warn},
+    media::{atoms, Media},
+    telemetry,
 };
-mod gopro2points;
-mod virb2points;
+mod summary;
 
 pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     // clap: required arg
     let eaf_path = args.get_one::<PathBuf>("eaf").unwrap().to_owned();
-    let use_geotier = *args.get_one::<bool>("geotier").unwrap();
+    // Geotier ID for non-interactive use implies '--geotier'
+    let geotier_arg = args.get_one::<String>("geotier-id");
+    let use_geotier = *args.get_one::<bool>("geotier").unwrap() || geotier_arg.is_some();
     let fit_present = args.contains_id("fit");
     let gpmf_present = args.contains_id("gpmf");
 
@@ -35,12 +44,21 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     let eaf = Eaf::read(&eaf_path)?;
 
     // Extract points from either VIRB, GoPro, or annotation data.
-    let mut points = match (fit_present, gpmf_present, use_geotier) {
-        (true, false, false) => virb2points::run(args)?,
-        (false, true, false) => gopro2points::run(args)?,
-        (false, false, true) => {
-            print!("[GEO TIER] ");
-            let geotier = select_tier(&eaf, true)?;
+    let location_path = args.get_one::<PathBuf>("location");
+    // Excluded from content tiers for '--all-tiers'
+    let mut geotier_id: Option<String> = None;
+    let mut points = match (fit_present, gpmf_present, use_geotier, location_path) {
+        (true, false, false, None) | (false, true, false, None) => telemetry_points(args)?,
+        (false, false, false, Some(path)) => location_points(path)?,
+        (false, false, true, None) => {
+            let geotier = match geotier_arg {
+                Some(id) => tier_by_id(&eaf, id, true)?,
+                None => {
+                    print!("[GEO TIER] ");
+                    select_tier(&eaf, true)?
+                }
+            };
+            geotier_id = Some(geotier.tier_id.to_owned());
 
             // Try to parse annotations into coordinates.
             // Will use default values if parsing fails.
@@ -50,16 +68,70 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
                 .collect::<Vec<_>>()
         }
         _ => {
-            let msg = "(!) Can only specify one of 'gpmf', 'fit', 'geotier'";
+            let msg = "(!) Can only specify one of 'gpmf', 'fit', 'geotier', 'location'";
             return Err(std::io::Error::new(ErrorKind::Other, msg));
         }
     };
 
+    // Prune points with bad satellite lock, regardless of source.
+    let min_fix = GpsFix::from(*args.get_one::<u32>("gpsfix").unwrap()); // clap: default 2
+    let max_dop = args.get_one::<f64>("gpsdop").copied();
+    let points_len = points.len();
+    points.retain(|p| p.has_quality(min_fix, max_dop));
+    if points.len() < points_len {
+        info!(
+            "{} points pruned due to bad satellite lock (< {min_fix}) or precision.",
+            points_len - points.len()
+        );
+    }
+
     if points.is_empty() {
         let msg = "(!) No points to process.";
         return Err(std::io::Error::new(ErrorKind::Other, msg));
     }
 
+    // Correct magnetic heading (VIRB) to true north
+    if let Some(declination) = args.get_one::<f64>("declination") {
+        points = points
+            .iter()
+            .map(|p| p.with_declination(*declination))
+            .collect();
+    }
+    // ...or compute it per point from WMM coefficients
+    let wmm = match args.get_one::<PathBuf>("wmm") {
+        Some(path) => Some(MagneticModel::read(path)?),
+        None => None,
+    };
+    if let Some(model) = &wmm {
+        let mut undated = 0;
+        let mut invalid = 0;
+        points = points
+            .iter()
+            .map(|p| match p.datetime {
+                Some(dt) => {
+                    let year = decimal_year(&dt);
+                    if !model.is_valid(year) {
+                        invalid += 1;
+                    }
+                    p.with_declination(model.declination(p.latitude, p.longitude, p.altitude, year))
+                }
+                None => {
+                    undated += 1;
+                    p.to_owned()
+                }
+            })
+            .collect();
+        if undated > 0 {
+            warn!("(!) {undated} points have no date, heading not corrected.");
+        }
+        if invalid > 0 {
+            warn!(
+                "(!) {invalid} points are outside the 5-year validity period of {} (epoch {}).",
+                model.name, model.epoch
+            );
+        }
+    }
+
     let time_offset = *args.get_one::<isize>("time-offset").unwrap(); // clap default: 0
 
     // clap: default 1
@@ -79,6 +151,8 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
         return Err(std::io::Error::new(ErrorKind::Other, msg));
     }
 
+    let radius_from_spread = *args.get_one::<bool>("radius-from-spread").unwrap();
+
     // clap default: 40, range: 3 .. 255 (min value checked later)
     let vertices = args.get_one::<u8>("vertices").unwrap().to_owned();
 
@@ -106,6 +180,7 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
             radius,
             vertices,
             height,
+            from_spread: radius_from_spread,
         },
         // Final branch should never be reached, since clap sets default to 'points-all'
         // and checks valid values.
@@ -115,40 +190,333 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
         }
     };
 
+    // clap: default 'average'
+    let point_strategy: PointStrategy =
+        args.get_one::<String>("point-strategy").unwrap().parse()?;
+
+    // Content tiers, via '--tier', '--all-tiers', or selected from list.
+    // Tier IDs must be set to run non-interactively, e.g. in scripts.
+    let tiers: Vec<Tier> = match (
+        args.get_many::<String>("tier"),
+        *args.get_one::<bool>("all-tiers").unwrap(),
+    ) {
+        (_, true) => eaf
+            .tiers
+            .iter()
+            .filter(|t| Some(&t.tier_id) != geotier_id.as_ref())
+            .cloned()
+            .collect(),
+        (Some(ids), false) => ids
+            .map(|id| tier_by_id(&eaf, id, true))
+            .collect::<std::io::Result<Vec<_>>>()?,
+        (None, false) => {
+            print!("[CONTENT TIER] ");
+            vec![select_tier(&eaf, true)?]
+        }
+    };
+    if tiers.is_empty() {
+        let msg = "(!) No content tiers to process.";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    // Add offset hours to datetime
+    for point in points.iter_mut() {
+        point.datetime = point
+            .datetime
+            .map(|dt| dt + Duration::hours(time_offset as i64));
+    }
+
     // Important: Cluster points BEFORE downsampling,
     //            since will otherwise risk not having points corresponding
     //            to annotation time spans, short ones especially.
 
-    print!("[CONTENT TIER] ");
-    let tier = select_tier(&eaf, true)?;
+    info!("Mapping annotation values and downsampling points...");
+    // Clusters before and after downsampling, per tier
+    let tier_clusters: Vec<(&Tier, Vec<Vec<EafPoint>>, Vec<Vec<EafPoint>>)> = tiers
+        .iter()
+        .map(|tier| {
+            // Annotations as (value, start_ms, end_ms). Time values are interpolated
+            // for annotations without these, e.g. in subdivision tiers.
+            let point_clusters = cluster_points(&points, &interpolated_spans(tier));
+            let downsampled_clusters = filter_downsample(
+                &point_clusters,
+                Some(downsample_factor),
+                &geoshape,
+                &point_strategy,
+            );
+            (tier, point_clusters, downsampled_clusters)
+        })
+        .collect();
+
+    info!(
+        "Resulting point clusters with downsample factor {downsample_factor} and geoshape '{}':",
+        geoshape.to_string()
+    );
+    for (tier, point_clusters, downsampled_clusters) in tier_clusters.iter() {
+        if tier_clusters.len() > 1 {
+            info!("[{}]", tier.tier_id);
+        }
+        // For comparing original point count with downsampled result.
+        for (i, (before, after)) in point_clusters
+            .iter()
+            .zip(downsampled_clusters.iter())
+            .enumerate()
+        {
+            info!(
+                "{:4}. {:5} -> {:5} points. Description: {}",
+                i + 1,
+                before.len(),
+                after.len(),
+                after
+                    .first()
+                    .and_then(|p| p.description.as_deref())
+                    .unwrap_or("NONE")
+            )
+        }
+    }
+
+    info!("Generating KML and GeoJSON...");
+    let tier_ids: Vec<&str> = tiers.iter().map(|t| t.tier_id.as_str()).collect();
+    // Document metadata for KML and GeoJSON
+    let mut info = DocumentInfo::new(&points)
+        .with_filter("tier", &tier_ids.join(", "))
+        .with_filter("geoshape", geoshape_arg)
+        .with_filter("downsample_factor", &downsample_factor.to_string())
+        .with_filter("gpsfix", &min_fix.to_string());
+    if let Some(dop) = max_dop {
+        info = info.with_filter("gpsdop", &dop.to_string());
+    }
+    if let Some(declination) = args.get_one::<f64>("declination") {
+        info = info.with_filter("declination", &declination.to_string());
+    }
+    if let Some(model) = &wmm {
+        info = info.with_filter("declination", &model.name);
+    }
+    if let Some(path) = args.get_one::<PathBuf>("gpmf") {
+        info = info.with_clip(path);
+    } else if fit_present {
+        info = info.with_camera("Garmin VIRB");
+    }
+    // KML-only: Substitute basic Placemark description with HTML CDATA
+    let cdata = *args.get_one::<bool>("cdata").unwrap();
+
+    // Generate KML styles. Single tier: one style per unique annotation value,
+    // so that lines with the same description get the same colour.
+    // Multiple tiers: one style per tier.
+    let tier_styles: Vec<HashMap<String, (String, Rgba)>> = match tier_clusters.len() {
+        1 => {
+            let unique_annotations: HashSet<String> = tier_clusters[0]
+                .2
+                .iter()
+                .filter_map(|c| c.first().and_then(|p| p.description.to_owned()))
+                .collect();
+            vec![unique_annotations
+                .into_iter()
+                .enumerate()
+                .map(|(i, s)| (s, (format!("style{}", i + 1), Rgba::random(None))))
+                .collect()]
+        }
+        _ => tier_clusters
+            .iter()
+            .enumerate()
+            .map(|(i, (_, _, clusters))| {
+                let style = (format!("style{}", i + 1), Rgba::random(None));
+                clusters
+                    .iter()
+                    .filter_map(|c| c.first().and_then(|p| p.description.to_owned()))
+                    .map(|s| (s, style.to_owned()))
+                    .collect()
+            })
+            .collect(),
+    };
+    let mut kml_styles: Vec<Element> = tier_styles
+        .iter()
+        .flat_map(|styles| styles.values())
+        .map(|(id, color)| (id, color))
+        .collect::<HashMap<_, _>>()
+        .into_iter()
+        .map(|(id, color)| kml_style(id, &geoshape, color))
+        .collect();
+    kml_styles.sort_by_key(|e| e.name.to_owned());
+
+    // Generate KML and GeoJSON features, tagged with tier ID.
+    // Feature numbering continues across tiers.
+    let mut placemarks: Vec<Placemark> = Vec::new();
+    let mut features: Vec<Feature> = Vec::new();
+    let mut count = 0;
+    for ((tier, _, downsampled_clusters), styles) in tier_clusters.iter().zip(tier_styles.iter()) {
+        for cluster in downsampled_clusters.iter() {
+            let tier_data = kml_extended_data(&[("tier", &tier.tier_id)]);
+            placemarks.extend(
+                placemarks_from_geoshape(cluster, &geoshape, None, cdata, styles, Some(count + 1))
+                    .into_iter()
+                    .map(|mut p| {
+                        p.children.push(tier_data.to_owned());
+                        p
+                    }),
+            );
+            features.extend(
+                features_from_geoshape(cluster, &geoshape, Some(count))
+                    .into_iter()
+                    .map(|mut f| {
+                        f.set_property("tier", tier.tier_id.to_owned());
+                        f
+                    }),
+            );
+            count += 1;
+        }
+    }
+    let mut kml = kml_from_placemarks(&placemarks, &kml_styles);
+    info.add_to_kml(&mut kml);
+
+    // Serialize to KML v2.2. No line breaks/indentation.
+    let kml_doc = kml_to_string(&kml);
+    // Output file names, optionally from '--name' template
+    let name_template = args.get_one::<String>("name").map(|s| s.as_str());
+    let name_values = [
+        ("shape", geoshape_arg.to_owned()),
+        (
+            "date",
+            points
+                .iter()
+                .find_map(|p| p.datetime)
+                .map(|dt| dt.date().to_string())
+                .unwrap_or_default(),
+        ),
+    ];
+    // Geoshape is used as suffix for KML/GeoJSON unless a template is set
+    let shape_suffix = match name_template {
+        Some(_) => None,
+        None => Some(geoshape_arg.as_str()),
+    };
+
+    let kml_path = files::template_file_name(
+        &eaf_path,
+        name_template,
+        &name_values,
+        shape_suffix,
+        Some("kml"),
+    );
+
+    match files::writefile(&kml_doc.as_bytes(), &kml_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", kml_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing KML-file"),
+        Err(err) => return Err(err),
+    }
+
+    // Generate GeoJSON
+    let mut geojson = geojson_from_features(&features);
+    info.add_to_geojson(&mut geojson);
+
+    // Serialize GeoJSON. Not indented (= smaller size for web use).
+    let geojson_doc = geojson.to_string();
+    let geojson_path = files::template_file_name(
+        &eaf_path,
+        name_template,
+        &name_values,
+        shape_suffix,
+        Some("json"),
+    );
+
+    match files::writefile(&geojson_doc.as_bytes(), &geojson_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", geojson_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing JSON-file"),
+        Err(err) => return Err(err),
+    }
+
+    // Generate summary statistics and parameters used
+    let parameters = serde_json::json!({
+        "eaf": eaf_path,
+        "source": match (fit_present, gpmf_present, location_path.is_some()) {
+            (true, ..) => "fit",
+            (_, true, _) => "gpmf",
+            (.., true) => "location",
+            _ => "geotier",
+        },
+        "tier": match tier_ids.as_slice() {
+            [tier_id] => serde_json::json!(tier_id),
+            ids => serde_json::json!(ids),
+        },
+        "geoshape": geoshape_arg,
+        "downsample_factor": downsample_factor,
+        "point_strategy": point_strategy.to_string(),
+        "radius": radius,
+        "radius_from_spread": radius_from_spread,
+        "vertices": vertices,
+        "height": height,
+        "time_offset": time_offset,
+    });
+    // Summary per tier if more than one
+    let summary = match tier_clusters.as_slice() {
+        [(_, point_clusters, _)] => summary::summary(point_clusters, parameters),
+        _ => serde_json::json!({
+            "parameters": parameters,
+            "tiers": tier_clusters.iter().map(|(tier, point_clusters, _)| {
+                let mut tier_summary = summary::summary(point_clusters, serde_json::Value::Null);
+                if let Some(obj) = tier_summary.as_object_mut() {
+                    obj.remove("parameters");
+                    obj.insert("tier_id".to_owned(), serde_json::json!(tier.tier_id));
+                }
+                tier_summary
+            }).collect::<Vec<_>>(),
+        }),
+    };
+    let summary_doc = serde_json::to_string_pretty(&summary)?;
+    let summary_suffix = match name_template {
+        Some(_) => "_summary".to_owned(),
+        None => format!("{geoshape_arg}_summary"),
+    };
+    let summary_path = files::template_file_name(
+        &eaf_path,
+        name_template,
+        &name_values,
+        Some(&summary_suffix),
+        Some("json"),
+    );
+
+    match files::writefile(&summary_doc.as_bytes(), &summary_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", summary_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing summary JSON-file"),
+        Err(err) => return Err(err),
+    }
+
+    // Print results
+    for (tier, _, downsampled_clusters) in tier_clusters.iter() {
+        if tier_clusters.len() > 1 {
+            info!("[{}]", tier.tier_id);
+        }
+        print_results(tier, downsampled_clusters);
+    }
+
+    Ok(())
+}
+
+/// Maps annotation values to point descriptions and clusters
+/// consecutive points with the same description.
+/// Annotations are specified as `(value, start_ms, end_ms)`.
+fn cluster_points(points: &[EafPoint], spans: &[(String, i64, i64)]) -> Vec<Vec<EafPoint>> {
+    let mut points = points.to_owned();
 
-    print!("Mapping annotation values and downsampling points...");
     // For performance reasons outer iteration is points,
     // since these usually outnumber number of annotations in a tier.
     for point in points.iter_mut() {
-        // Add offset hours to datetime
-        point.datetime = point
-            .datetime
-            .map(|dt| dt + Duration::hours(time_offset as i64));
-
         // Map annotation value to point.description if
         // the point's relative timestamp is within
         // the annotation's time span.
         if let Some(t_point) = point.timestamp_ms() {
-            tier.annotations
+            spans
                 .iter()
-                .find(|a| {
-                    if let (Some(t_annot_start), Some(t_annot_end)) = a.ts_val() {
-                        // TODO 1a. Fix edge cases for annotations short enough not to be "contained" by a point "time span".
-                        // TODO 2a. Include points that are logged close to annotation start/end, but at what thresh hold?
-                        // TODO 2b. 2a may introduce edge cases for back-to-back annotations so perhaps not?
-                        // TODO 1a + 1b. VIRB, logs at 10Hz so threshold < 100ms? GoPro logs at 1Hz (clusters) so threshold < 1000ms?
-                        t_point > t_annot_start && t_point < t_annot_end // point logged within annotation boundaries
-                    } else {
-                        false
-                    }
+                .find(|(_, t_annot_start, t_annot_end)| {
+                    // TODO 1a. Fix edge cases for annotations short enough not to be "contained" by a point "time span".
+                    // TODO 2a. Include points that are logged close to annotation start/end, but at what thresh hold?
+                    // TODO 2b. 2a may introduce edge cases for back-to-back annotations so perhaps not?
+                    // TODO 1a + 1b. VIRB, logs at 10Hz so threshold < 100ms? GoPro logs at 1Hz (clusters) so threshold < 1000ms?
+                    t_point > *t_annot_start && t_point < *t_annot_end // point logged within annotation boundaries
                 })
-                .map(|a| point.description = Some(a.value().to_string()));
+                .map(|(value, ..)| point.description = Some(value.to_owned()));
         }
     }
 
@@ -180,103 +548,12 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
         }
     }
 
-    let downsampled_clusters =
-        filter_downsample(&point_clusters, Some(downsample_factor), &geoshape);
-    println!(" Done.");
-
-    println!(
-        "Resulting point clusters with downsample factor {downsample_factor} and geoshape '{}':",
-        geoshape.to_string()
-    );
-    // For comparing original point count with downsampled result.
-    let before_after: Vec<(usize, usize)> = point_clusters
-        .as_slice()
-        .iter()
-        .zip(downsampled_clusters.as_slice())
-        .map(|(bef, aft)| (bef.len(), aft.len()))
-        .collect();
-    // Keeping track of unique annotation values for generating
-    // KML style ID so that for poly-lines, lines with the same
-    // description get the same colour.
-    let mut unique_annotations: HashSet<String> = HashSet::new();
-
-    for (i, cluster) in downsampled_clusters.iter().enumerate() {
-        // Compile unique annotations to generate KML styles
-        // where lines with the same description get the same colour.
-        let description = cluster.first().and_then(|p| p.description.as_deref());
-        if let Some(descr) = description {
-            unique_annotations.insert(descr.to_owned());
-        }
-
-        // indeces should exist and match, compare points before, after downsample
-        let (before, after) = before_after
-            .get(i)
-            .map(|(bef, aft)| (bef, aft))
-            .unwrap_or((&0, &0));
-
-        println!(
-            "{:4}. {:5} -> {:5} points. Description: {}",
-            i + 1,
-            before,
-            after,
-            description.unwrap_or("NONE")
-        )
-    }
-
-    println!("Generating KML and GeoJSON...");
-    // KML-only: Substitute basic Placemark description with HTML CDATA
-    let cdata = *args.get_one::<bool>("cdata").unwrap();
-    // Generate KML styles via unique annotation values
-    let kml_style_id: HashMap<String, (String, Rgba)> = unique_annotations
-        .iter()
-        .enumerate()
-        .map(|(i, s)| {
-            (
-                s.to_owned(),
-                (format!("style{}", i + 1), Rgba::random(None)),
-            )
-        })
-        .collect();
-    let mut kml_styles: Vec<Element> = kml_style_id
-        .iter()
-        .map(|(_, (id, color))| kml_style(id, &geoshape, color))
-        .collect();
-    kml_styles.sort_by_key(|e| e.name.to_owned());
-
-    // Generate KML
-    let placemarks: Vec<Placemark> = downsampled_clusters
-        .iter()
-        .enumerate()
-        .flat_map(|(i, p)| {
-            placemarks_from_geoshape(p, &geoshape, None, cdata, &kml_style_id, Some(i + 1))
-        })
-        .collect();
-    let kml = kml_from_placemarks(&placemarks, &kml_styles);
-
-    // Serialize to KML v2.2. No line breaks/indentation.
-    let kml_doc = kml_to_string(&kml);
-    let kml_path = files::affix_file_name(&eaf_path, None, Some(geoshape_arg), Some("kml"));
-
-    match files::writefile(&kml_doc.as_bytes(), &kml_path) {
-        Ok(true) => println!("Wrote {}", kml_path.display()),
-        Ok(false) => println!("User aborted writing KML-file"),
-        Err(err) => return Err(err),
-    }
-
-    // Generate GeoJSON
-    let geojson = geojson_from_clusters(&downsampled_clusters, &geoshape);
-
-    // Serialize GeoJSON. Not indented (= smaller size for web use).
-    let geojson_doc = geojson.to_string();
-    let geojson_path = files::affix_file_name(&eaf_path, None, Some(geoshape_arg), Some("json"));
-
-    match files::writefile(&geojson_doc.as_bytes(), &geojson_path) {
-        Ok(true) => println!("Wrote {}", geojson_path.display()),
-        Ok(false) => println!("User aborted writing JSON-file"),
-        Err(err) => return Err(err),
-    }
+    point_clusters
+}
 
-    // Print results
+/// Prints relative time stamps for first annotation and point,
+/// and the number of georeferenced annotations for a tier.
+fn print_results(tier: &Tier, downsampled_clusters: &[Vec<EafPoint>]) {
     let first_point = downsampled_clusters.first().and_then(|c| c.first());
     let first_annotated_point = downsampled_clusters
         .iter() // iter outer vec
@@ -289,41 +566,88 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
         .collect::<Vec<String>>();
 
     if let Some(annotation) = first_annotation {
-        println!("Relative time stamps:");
-        print!("  First annotation:   ");
-        if let (Some(t1), Some(t2)) = annotation.ts_val() {
-            println!("    {t1:8} ms - {t2:8} ms '{}'", annotation.value())
-        } else {
-            println!("(!) No relative time set for annotation:\n    {annotation:?}")
+        info!("Relative time stamps:");
+        match annotation.ts_val() {
+            (Some(t1), Some(t2)) => info!(
+                "  First annotation:       {t1:8} ms - {t2:8} ms '{}'",
+                annotation.value()
+            ),
+            _ => warn!("(!) No relative time set for annotation:\n    {annotation:?}"),
         }
     }
 
     if let (Some(point), Some(point_annot)) = (first_point, first_annotated_point) {
-        print!("  First processed point:  ");
-        if let Some(t) = point.timestamp_ms() {
-            print!("{t:8} ms")
-        } else {
-            print!("(!) No relative time set for point:\n    {point}")
+        match point.timestamp_ms() {
+            Some(t) => info!("  First processed point:  {t:8} ms (not first point in GPS log)"),
+            None => warn!("(!) No relative time set for point:\n    {point}"),
         }
-        println!(" (not first point in GPS log)");
-        print!("  First annotated point:  ");
-        if let (Some(t), Some(txt)) = (point_annot.timestamp_ms(), point_annot.description.as_ref())
-        {
-            println!("{t:8} ms '{txt}'")
-        } else {
-            println!("(!) No relative time set for point:\n    {point}")
+        match (point_annot.timestamp_ms(), point_annot.description.as_ref()) {
+            (Some(t), Some(txt)) => info!("  First annotated point:  {t:8} ms '{txt}'"),
+            _ => warn!("(!) No relative time set for point:\n    {point}"),
         }
     }
 
-    println!("Annotations:");
-    println!(
+    info!("Annotations:");
+    info!(
         "  Geo-referenced:        {:4} annotations",
         georefed_annotations.len()
     );
-    println!(
+    info!(
         "  Discarded:             {:4} annotations (preceed GPS logging start time)",
         tier.len() - georefed_annotations.len()
     );
+}
 
-    Ok(())
+/// Extracts GPS log for a recording session from a GoPro MP4 (`--gpmf`)
+/// or a VIRB FIT-file (`--fit`), with timestamps relative to start of session.
+fn telemetry_points(args: &clap::ArgMatches) -> std::io::Result<Vec<EafPoint>> {
+    let gpsfix = *args.get_one::<u32>("gpsfix").unwrap(); // clap: default 2
+    let gpsdop = args.get_one::<f64>("gpsdop").copied();
+
+    let telemetry = telemetry::open(
+        args.get_one::<PathBuf>("gpmf").map(|p| p.as_path()),
+        args.get_one::<PathBuf>("fit").map(|p| p.as_path()),
+        args.get_one::<PathBuf>("input-directory")
+            .map(|p| p.as_path()),
+        *args.get_one::<bool>("verify").unwrap(),
+        true,
+    )?;
+
+    for session in telemetry.sessions()?.iter().filter(|s| !s.files.is_empty()) {
+        info!("Using data from the following session:");
+        for (i, path) in session.files.iter().enumerate() {
+            info!("{:4}. {}", i + 1, path.display());
+        }
+    }
+
+    telemetry.points(gpsfix, gpsdop)
+}
+
+/// Generates one point per second for the duration of the MP4-file,
+/// all set to the single location in its `©xyz` atom.
+/// Places annotations for phones, drones, and other devices
+/// without a GPS log at the recording location.
+fn location_points(path: &Path) -> std::io::Result<Vec<EafPoint>> {
+    let location = match atoms::location(path)? {
+        Some(loc) => loc,
+        None => {
+            let msg = format!("(!) No location ('©xyz') in {}", path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+    info!("Using location {location} for all annotations.");
+
+    let duration = Media::duration(path)?;
+    let points = (0..=duration.whole_seconds())
+        .map(|s| EafPoint {
+            latitude: location.latitude,
+            longitude: location.longitude,
+            altitude: location.altitude.unwrap_or_default(),
+            timestamp: Some(Duration::seconds(s)),
+            duration: Some(Duration::SECOND),
+            ..EafPoint::default()
+        })
+        .collect();
+
+    Ok(points)
 }
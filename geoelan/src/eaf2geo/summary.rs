@@ -0,0 +1,91 @@
+//! Summary statistics for georeferenced annotations,
+//! written as JSON next to KML and GeoJSON output.
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+use crate::geo::{haversine, EafPoint};
+
+/// Total distance in meters between consecutive points.
+fn distance(points: &[EafPoint]) -> f64 {
+    points
+        .windows(2)
+        .map(|w| haversine(w[0].latitude, w[0].longitude, w[1].latitude, w[1].longitude))
+        .sum::<f64>()
+        * 1000.
+}
+
+/// Total duration in seconds for points.
+fn duration(points: &[EafPoint]) -> f64 {
+    points
+        .iter()
+        .filter_map(|p| p.duration.map(|d| d.as_seconds_f64()))
+        .sum()
+}
+
+/// Generates summary statistics for point clusters (before downsampling),
+/// where each cluster corresponds to an annotation
+/// (or unannotated section), together with the parameters used.
+///
+/// - Per annotation value: number of annotations, points, duration (seconds), distance (meters)
+/// - Total georeferenced duration and distance for annotated sections
+/// - Bounding box for all points
+pub fn summary(point_clusters: &[Vec<EafPoint>], parameters: Value) -> Value {
+    // Key: annotation value, Value: (annotations, points, duration, distance)
+    let mut values: BTreeMap<String, (usize, usize, f64, f64)> = BTreeMap::new();
+    for cluster in point_clusters.iter() {
+        if let Some(descr) = cluster.first().and_then(|p| p.description.to_owned()) {
+            let entry = values.entry(descr).or_insert((0, 0, 0., 0.));
+            entry.0 += 1;
+            entry.1 += cluster.len();
+            entry.2 += duration(cluster);
+            entry.3 += distance(cluster);
+        }
+    }
+
+    let (total_duration, total_distance) = values
+        .values()
+        .fold((0., 0.), |acc, v| (acc.0 + v.2, acc.1 + v.3));
+
+    let bbox =
+        point_clusters
+            .iter()
+            .flatten()
+            .fold(None, |bbox: Option<(f64, f64, f64, f64)>, p| {
+                Some(match bbox {
+                    None => (p.latitude, p.longitude, p.latitude, p.longitude),
+                    Some((lat_min, lon_min, lat_max, lon_max)) => (
+                        lat_min.min(p.latitude),
+                        lon_min.min(p.longitude),
+                        lat_max.max(p.latitude),
+                        lon_max.max(p.longitude),
+                    ),
+                })
+            });
+
+    json!({
+        "parameters": parameters,
+        "annotations": values.iter()
+            .map(|(value, (count, points, dur, dist))| json!({
+                "value": value,
+                "count": count,
+                "points": points,
+                "duration_seconds": dur,
+                "distance_meters": dist,
+            }))
+            .collect::<Vec<_>>(),
+        "georeferenced": {
+            "annotations": values.values().map(|v| v.0).sum::<usize>(),
+            "duration_seconds": total_duration,
+            "distance_meters": total_distance,
+        },
+        "points": point_clusters.iter().map(|c| c.len()).sum::<usize>(),
+        "bounding_box": bbox.map(|(lat_min, lon_min, lat_max, lon_max)| json!({
+            "latitude_min": lat_min,
+            "longitude_min": lon_min,
+            "latitude_max": lat_max,
+            "longitude_max": lon_max,
+        })),
+    })
+}
@@ -0,0 +1,168 @@
+//! User defined tier layout for generated ELAN-files,
+//! set via `cam2eaf --tier`, e.g. `--tier "notes,participant=A"`
+//! or `--tier "place,parent=notes,type=place-ref"`.
+//!
+//! Tiers are added to the serialized ELAN-file without annotations.
+//! A tier spec with the ID of the geotier sets attributes
+//! for the geotier instead of adding a new tier.
+
+use std::io::ErrorKind;
+
+use super::xml::{Document, Element};
+
+/// Default linguistic type for top-level tiers,
+/// created by `eaf-rs` for the geotier.
+pub const DEFAULT_TYPE: &'static str = "default-lt";
+
+/// Default linguistic type for child tiers.
+pub const DEFAULT_CHILD_TYPE: &'static str = "included-in";
+
+/// Tier definition.
+#[derive(Debug, Default, Clone)]
+pub struct TierSpec {
+    pub tier_id: String,
+    pub linguistic_type: Option<String>,
+    pub parent: Option<String>,
+    pub participant: Option<String>,
+    pub annotator: Option<String>,
+}
+
+impl TierSpec {
+    /// Parses a tier spec in the form `ID[,key=value...]`,
+    /// where key is one of `type`, `parent`, `participant`, `annotator`.
+    pub fn parse(spec: &str) -> std::io::Result<Self> {
+        let mut parts = spec.split(',').map(|s| s.trim());
+        let tier_id = match parts.next() {
+            Some(id) if !id.is_empty() && !id.contains('=') => id.to_owned(),
+            _ => {
+                let msg = format!("(!) Tier spec '{spec}' must start with a tier ID.");
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        };
+
+        let mut tier = Self {
+            tier_id,
+            ..Self::default()
+        };
+        for part in parts.filter(|p| !p.is_empty()) {
+            let (key, value) = match part.split_once('=') {
+                Some((k, v)) if !v.trim().is_empty() => (k.trim(), v.trim().to_owned()),
+                _ => {
+                    let msg = format!(
+                        "(!) Invalid tier attribute '{part}' in '{spec}'. Use 'key=value'."
+                    );
+                    return Err(std::io::Error::new(ErrorKind::Other, msg));
+                }
+            };
+            match key {
+                "type" => tier.linguistic_type = Some(value),
+                "parent" => tier.parent = Some(value),
+                "participant" => tier.participant = Some(value),
+                "annotator" => tier.annotator = Some(value),
+                _ => {
+                    let msg = format!("(!) Unknown tier attribute '{key}' in '{spec}'. Use 'type', 'parent', 'participant', or 'annotator'.");
+                    return Err(std::io::Error::new(ErrorKind::Other, msg));
+                }
+            }
+        }
+
+        Ok(tier)
+    }
+
+    /// Linguistic type for tier, defaults to
+    /// `DEFAULT_CHILD_TYPE` for child tiers and `DEFAULT_TYPE` otherwise.
+    pub fn linguistic_type(&self) -> &str {
+        match (&self.linguistic_type, &self.parent) {
+            (Some(t), _) => t,
+            (None, Some(_)) => DEFAULT_CHILD_TYPE,
+            (None, None) => DEFAULT_TYPE,
+        }
+    }
+}
+
+/// Applies tier layout to an ELAN-file.
+/// Tiers are added in the order specified, so parent tiers must
+/// be specified before their children (or already exist).
+///
+/// Returns an error if a parent tier does not exist.
+pub fn apply_layout(doc: &mut Document, tiers: &[TierSpec]) -> std::io::Result<()> {
+    for tier in tiers.iter() {
+        let tier_ids = doc.ids("TIER", "TIER_ID");
+        let exists = tier_ids.contains(&tier.tier_id);
+        // Keep linguistic type for existing tiers unless specified
+        let type_id = match exists && tier.linguistic_type.is_none() && tier.parent.is_none() {
+            true => None,
+            false => Some(tier.linguistic_type()),
+        };
+
+        if let Some(parent) = &tier.parent {
+            if !tier_ids.contains(parent) {
+                let msg = format!(
+                    "(!) Parent tier '{parent}' for '{}' does not exist. Specify parent tiers first.",
+                    tier.tier_id
+                );
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        }
+
+        // Linguistic type, added if not already present
+        let missing_type = type_id.filter(|t| {
+            !doc.ids("LINGUISTIC_TYPE", "LINGUISTIC_TYPE_ID")
+                .iter()
+                .any(|i| i == t)
+        });
+        if let Some(type_id) = missing_type {
+            let mut element = Element::new("LINGUISTIC_TYPE");
+            if tier.parent.is_some() {
+                // Child tiers require a constraint
+                if !doc
+                    .ids("CONSTRAINT", "STEREOTYPE")
+                    .iter()
+                    .any(|s| s == "Included_In")
+                {
+                    doc.insert(
+                        Element::new("CONSTRAINT")
+                            .with_attribute(
+                                "DESCRIPTION",
+                                "Time alignable annotations within the parent annotation's time interval, gaps are allowed",
+                            )
+                            .with_attribute("STEREOTYPE", "Included_In"),
+                    );
+                }
+                element.set_attribute("CONSTRAINTS", "Included_In");
+            }
+            doc.insert(
+                element
+                    .with_attribute("GRAPHIC_REFERENCES", "false")
+                    .with_attribute("LINGUISTIC_TYPE_ID", type_id)
+                    .with_attribute("TIME_ALIGNABLE", "true"),
+            );
+        }
+
+        let attributes = [
+            ("LINGUISTIC_TYPE_REF", type_id),
+            ("PARENT_REF", tier.parent.as_deref()),
+            ("PARTICIPANT", tier.participant.as_deref()),
+            ("ANNOTATOR", tier.annotator.as_deref()),
+        ];
+
+        if exists {
+            // Existing tier, e.g. geotier: set attributes only
+            for (name, value) in attributes.iter() {
+                if let Some(v) = value {
+                    doc.set_tier_attribute(&tier.tier_id, name, v);
+                }
+            }
+        } else {
+            let mut element = Element::new("TIER").with_attribute("TIER_ID", &tier.tier_id);
+            for (name, value) in attributes.iter() {
+                if let Some(v) = value {
+                    element.set_attribute(name, v);
+                }
+            }
+            doc.insert(element);
+        }
+    }
+
+    Ok(())
+}
@@ -2,12 +2,29 @@
 
 use eaf_rs::{eaf::{Eaf, Tier}, EafError};
 use mp4iter::Mp4;
-use std::{io::Write, path::Path};
+use std::{
+    io::{ErrorKind, Write},
+    path::Path,
+};
 
-use crate::text::process_string;
+use crate::{
+    i18n::{tr, trf, Msg},
+    text::process_string,
+};
 
 use super::geo::point::EafPoint;
 
+pub mod layout;
+pub mod motion;
+pub mod pfsx;
+pub mod scan;
+pub mod sensor;
+pub mod tsconf;
+pub mod xml;
+
+/// Tier ID for the geotier in generated ELAN-files.
+pub const GEOTIER_ID: &'static str = "geo";
+
 /// Generates an ELAN-file. If points are provided,
 /// a tier named "geo" will be created with these inserted as annotations.
 ///
@@ -26,7 +43,7 @@ pub fn generate_eaf(
 ) -> Result<Eaf, EafError> {
     let mut eaf = if let Some(pts) = points {
         // Generate tier with coordinates is points are passed
-        let geo_tier_id = "geo";
+        let geo_tier_id = GEOTIER_ID;
 
         // Annotations in the form (value, start_ms, end_ms)
         let mut annotations: Vec<(String, i64, i64)> = Vec::new();
@@ -94,8 +111,27 @@ pub fn generate_eaf(
     Ok(eaf)
 }
 
+/// Returns tier with ID `tier_id`, for selecting tiers non-interactively,
+/// e.g. in scripts.
+/// Returns an error if the tier does not exist, or if `no_tokenized` is set
+/// and the tier or one of its parents is tokenized.
+pub fn tier_by_id(eaf: &Eaf, tier_id: &str, no_tokenized: bool) -> std::io::Result<Tier> {
+    let Some(tier) = eaf.get_tier(tier_id) else {
+        let msg = format!("(!) {}", trf(Msg::NoTierWithId, tier_id));
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    };
+    if no_tokenized && eaf.is_tokenized(tier_id, true)? {
+        let msg = format!("(!) {}", trf(Msg::TierTokenized, tier_id));
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+    Ok(tier.to_owned())
+}
+
+/// Select tier interactively from a list of all tiers in `eaf`.
+/// Returns an error if there is no input to read from,
+/// e.g. when run in a script. Use `tier_by_id()` instead.
 pub fn select_tier(eaf: &Eaf, no_tokenized: bool) -> std::io::Result<Tier> {
-    println!("Select tier:");
+    println!("{}", tr(Msg::SelectTier));
     println!("      ID{}Parent              Tokenized  Annotations  Tokens unique/total  Participant     Annotator       Start of first annotation", " ".repeat(19));
     for (i, tier) in eaf.tiers.iter().enumerate() {
         println!(
@@ -143,25 +179,93 @@ pub fn select_tier(eaf: &Eaf, no_tokenized: bool) -> std::io::Result<Tier> {
         print!("> ");
         std::io::stdout().flush()?;
         let mut buffer = String::new();
-        std::io::stdin().read_line(&mut buffer)?;
+        if std::io::stdin().read_line(&mut buffer)? == 0 {
+            // End of input, e.g. stdin is not a terminal
+            let msg = format!("(!) {}", tr(Msg::NoTierSelected));
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
         match buffer.trim_end().parse::<usize>() {
             Ok(i) => {
-                match eaf.tiers.get(i - 1) {
+                match eaf.tiers.get(i.wrapping_sub(1)) {
                     // check if selected tier or any parent tier is tokenized
                     Some(t) => {
                         if eaf.is_tokenized(&t.tier_id, true)? && no_tokenized {
                             println!(
-                                "(!) '{}' or one of its parents is tokenized. ['ctrl + c' to exit]",
-                                t.tier_id
+                                "(!) {} {}",
+                                trf(Msg::TierTokenized, &t.tier_id),
+                                tr(Msg::CtrlC)
                             );
                         } else {
                             return Ok(t.to_owned());
                         }
                     }
-                    None => println!("(!) No such tier. ['ctrl + c' to exit]"),
+                    None => println!("(!) {}. {}", tr(Msg::NoSuchTier), tr(Msg::CtrlC)),
                 }
             }
-            Err(_) => println!("(!) Not a number. ['ctrl + c' to exit]"),
+            Err(_) => println!("(!) {}. {}", tr(Msg::NotANumber), tr(Msg::CtrlC)),
         }
     }
 }
+
+/// Returns annotations in tier as `(value, start_ms, end_ms)`,
+/// with time values interpolated for annotations that lack these,
+/// e.g. those in subdivision tiers with unaligned time slots.
+///
+/// Consecutive annotations without time values are assigned equal
+/// parts of the time span between the closest anchored time values,
+/// similar to how ELAN visualises these.
+/// Annotations that can not be anchored on either side are discarded.
+pub fn interpolated_spans(tier: &Tier) -> Vec<(String, i64, i64)> {
+    let values: Vec<(String, Option<i64>, Option<i64>)> = tier
+        .annotations
+        .iter()
+        .map(|a| {
+            let (start, end) = a.ts_val();
+            (a.value().to_string(), start, end)
+        })
+        .collect();
+
+    let mut spans: Vec<(String, i64, i64)> = Vec::new();
+    let mut idx = 0;
+    while idx < values.len() {
+        let (value, start, end) = &values[idx];
+        if let (Some(s), Some(e)) = (start, end) {
+            spans.push((value.to_owned(), *s, *e));
+            idx += 1;
+            continue;
+        }
+
+        // Find run of annotations lacking at least one time value.
+        // Left anchor: own start value or previous end value.
+        // Right anchor: end value of last annotation in run or next start value.
+        let run_start = idx;
+        let mut run_end = idx;
+        while run_end + 1 < values.len()
+            && values[run_end].2.is_none()
+            && values[run_end + 1].1.is_none()
+        {
+            run_end += 1;
+        }
+
+        let left = values[run_start]
+            .1
+            .or_else(|| run_start.checked_sub(1).and_then(|i| values[i].2));
+        let right = values[run_end]
+            .2
+            .or_else(|| values.get(run_end + 1).and_then(|v| v.1));
+
+        if let (Some(l), Some(r)) = (left, right) {
+            let count = (run_end - run_start + 1) as i64;
+            let step = (r - l) as f64 / count as f64;
+            for (i, (value, ..)) in values[run_start..=run_end].iter().enumerate() {
+                let s = l + (i as f64 * step).round() as i64;
+                let e = l + ((i + 1) as f64 * step).round() as i64;
+                spans.push((value.to_owned(), s, e));
+            }
+        }
+
+        idx = run_end + 1;
+    }
+
+    spans
+}
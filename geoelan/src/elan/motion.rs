@@ -0,0 +1,96 @@
+//! Motion tier for generated ELAN-files: the GPS track segmented into
+//! time-aligned "moving"/"stationary" or speed class intervals,
+//! so that locomotion episodes do not have to be annotated by hand.
+
+use crate::geo::EafPoint;
+
+use super::{sensor::SensorTier, GEOTIER_ID};
+
+/// Motion classification.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MotionClass {
+    /// `moving` or `stationary`, depending on a speed threshold.
+    Stop,
+    /// Speed ranges in km/h, as for the `speed` sensor tier.
+    Speed,
+}
+
+impl std::str::FromStr for MotionClass {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "stop" => Ok(Self::Stop),
+            "speed" => Ok(Self::Speed),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("(!) Unknown motion class '{value}'"),
+            )),
+        }
+    }
+}
+
+impl MotionClass {
+    /// Tier ID, e.g. `geo-motion`.
+    pub fn tier_id(&self) -> String {
+        format!("{GEOTIER_ID}-motion")
+    }
+
+    /// Class for a single point. `stationary_kmh` is the
+    /// speed below which a point is considered stationary.
+    fn value(&self, point: &EafPoint, stationary_kmh: f64) -> String {
+        match self {
+            Self::Stop => match point.speed2d * 3.6 < stationary_kmh {
+                true => "stationary".to_owned(),
+                false => "moving".to_owned(),
+            },
+            Self::Speed => SensorTier::Speed.value(point),
+        }
+    }
+}
+
+/// Segments points into intervals of the same motion class,
+/// as `(class, start_ms, end_ms)`.
+///
+/// Intervals shorter than `min_duration_ms` are merged into the
+/// preceding interval (or the following one, for the first interval)
+/// to avoid flickering between classes, e.g. at traffic lights
+/// or due to GPS noise.
+///
+/// VIRB only: `session_start_ms` is subtracted from point timestamps,
+/// as for the geotier.
+pub fn motion_spans(
+    points: &[EafPoint],
+    class: MotionClass,
+    stationary_kmh: f64,
+    min_duration_ms: i64,
+    session_start_ms: Option<i64>,
+) -> Vec<(String, i64, i64)> {
+    let mut spans: Vec<(String, i64, i64)> = Vec::new();
+    for point in points.iter() {
+        let (Some(timestamp), Some(duration)) = (point.timestamp, point.duration) else {
+            continue;
+        };
+        let start = (timestamp.whole_milliseconds() as i64 - session_start_ms.unwrap_or(0)).max(0);
+        let end = start + duration.whole_milliseconds() as i64;
+        let value = class.value(point, stationary_kmh);
+        match spans.last_mut() {
+            Some(last) if last.0 == value => last.2 = end,
+            _ => spans.push((value, start, end)),
+        }
+    }
+
+    // Merge short intervals into neighbours
+    let mut merged: Vec<(String, i64, i64)> = Vec::new();
+    for span in spans.into_iter() {
+        match merged.last_mut() {
+            Some(last) if span.2 - span.1 < min_duration_ms || last.0 == span.0 => last.2 = span.2,
+            Some(last) if last.2 - last.1 < min_duration_ms && merged.len() == 1 => {
+                *last = (span.0, last.1, span.2)
+            }
+            _ => merged.push(span),
+        }
+    }
+
+    merged
+}
@@ -0,0 +1,76 @@
+//! ELAN preferences (`.pfsx`) for a generated ELAN-file, so that
+//! ELAN shows the geo tier on top and selected, and time series
+//! in separate Timeseries Viewer panes, when the file is opened.
+//! ELAN reads the preferences file with the same name as the ELAN-file.
+
+use super::xml::{escape, Document};
+
+/// Tier colors as `(tier ID, RGB color)`.
+const TIER_COLORS: [(&'static str, &'static str); 1] = [(super::GEOTIER_ID, "0,0,255")];
+
+/// Returns ELAN preferences for the ELAN-file `doc`,
+/// with tiers in document order, except `selected_tier`
+/// which is placed first and selected.
+/// `panels` lists time series track names per Timeseries Viewer pane,
+/// see `tsconf::panels()`.
+pub fn pfsx(doc: &Document, selected_tier: Option<&str>, panels: &[Vec<&str>]) -> String {
+    let mut tiers = doc.ids("TIER", "TIER_ID");
+    let selected = selected_tier.filter(|id| tiers.iter().any(|t| t == id));
+    if let Some(id) = selected {
+        tiers.retain(|t| t != id);
+        tiers.insert(0, id.to_owned());
+    }
+
+    let mut prefs = String::new();
+    prefs.push_str("    <prefList key=\"TierOrder\">\n");
+    for tier_id in tiers.iter() {
+        prefs.push_str(&format!("        <String>{}</String>\n", escape(tier_id)));
+    }
+    prefs.push_str("    </prefList>\n");
+
+    if let Some(id) = selected {
+        prefs.push_str(&format!(
+            "    <pref key=\"SelectedTierName\">\n        <String>{}</String>\n    </pref>\n",
+            escape(id)
+        ));
+    }
+
+    let colors: Vec<_> = TIER_COLORS
+        .iter()
+        .filter(|(id, _)| tiers.iter().any(|t| t == id))
+        .collect();
+    if !colors.is_empty() {
+        prefs.push_str("    <prefGroup key=\"TierColors\">\n");
+        for (tier_id, color) in colors.iter() {
+            prefs.push_str(&format!(
+                "        <pref key=\"{}\">\n            <Object class=\"java.awt.Color\">{color}</Object>\n        </pref>\n",
+                escape(tier_id)
+            ));
+        }
+        prefs.push_str("    </prefGroup>\n");
+    }
+
+    if !panels.is_empty() {
+        prefs.push_str(&format!(
+            "    <pref key=\"TimeSeriesViewer.NumberOfPanels\">\n        <Int>{}</Int>\n    </pref>\n",
+            panels.len()
+        ));
+        for (i, tracks) in panels.iter().enumerate() {
+            prefs.push_str(&format!(
+                "    <prefList key=\"TimeSeriesViewer.Panel-{}\">\n",
+                i + 1
+            ));
+            for track in tracks.iter() {
+                prefs.push_str(&format!("        <String>{}</String>\n", escape(track)));
+            }
+            prefs.push_str("    </prefList>\n");
+        }
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<preferences version="1.1" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:noNamespaceSchemaLocation="http://www.mpi.nl/tools/elan/Prefs_v1.1.xsd">
+{prefs}</preferences>
+"#
+    )
+}
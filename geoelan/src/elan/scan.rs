@@ -0,0 +1,96 @@
+//! Event-based scanning of ELAN-files with `quick-xml`, for extracting tier IDs,
+//! media descriptors, and time bounds without deserializing
+//! the full document, e.g. for quickly scanning thousands of ELAN-files.
+//!
+//! Only start tags are read, one at a time, so memory use does not
+//! depend on the size of the ELAN-file. Annotation values are skipped.
+
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind},
+    path::Path,
+};
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use serde_json::{json, Value};
+
+/// Summary of an ELAN-file.
+#[derive(Debug, Clone, Default)]
+pub struct EafSummary {
+    /// Tier IDs, in document order.
+    pub tiers: Vec<String>,
+    /// Media URLs, in document order.
+    pub media: Vec<String>,
+    /// Lowest and highest time slot value in milliseconds.
+    pub time_bounds: Option<(i64, i64)>,
+    /// Number of annotations in all tiers.
+    pub annotations: usize,
+}
+
+impl EafSummary {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "tiers": self.tiers,
+            "media": self.media,
+            "start_ms": self.time_bounds.map(|(start, _)| start),
+            "end_ms": self.time_bounds.map(|(_, end)| end),
+            "annotations": self.annotations,
+        })
+    }
+}
+
+/// Returns the unescaped value for attribute `name` in a start tag.
+fn attribute(tag: &BytesStart, name: &str) -> std::io::Result<Option<String>> {
+    match tag.try_get_attribute(name) {
+        Ok(Some(a)) => match a.unescape_value() {
+            Ok(value) => Ok(Some(value.to_string())),
+            Err(err) => Err(std::io::Error::new(ErrorKind::Other, err)),
+        },
+        Ok(None) => Ok(None),
+        Err(err) => Err(std::io::Error::new(ErrorKind::Other, err)),
+    }
+}
+
+/// Scans ELAN-file for tier IDs, media, time bounds, and number of annotations.
+pub fn scan(path: &Path) -> std::io::Result<EafSummary> {
+    let mut reader = Reader::from_reader(BufReader::new(File::open(path)?));
+    let mut buf: Vec<u8> = Vec::new();
+
+    let mut summary = EafSummary::default();
+    loop {
+        buf.clear();
+        let tag = match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(tag) | Event::Empty(tag)) => tag,
+            Ok(Event::Eof) => break,
+            Ok(_) => continue,
+            Err(err) => {
+                let msg = format!(
+                    "(!) Invalid XML at byte {} in '{}': {err}",
+                    reader.error_position(),
+                    path.display()
+                );
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        };
+        match tag.name().as_ref() {
+            b"TIER" => summary.tiers.extend(attribute(&tag, "TIER_ID")?),
+            b"MEDIA_DESCRIPTOR" => summary.media.extend(attribute(&tag, "MEDIA_URL")?),
+            b"TIME_SLOT" => {
+                if let Some(ms) = attribute(&tag, "TIME_VALUE")?.and_then(|v| v.parse::<i64>().ok())
+                {
+                    summary.time_bounds = match summary.time_bounds {
+                        Some((start, end)) => Some((start.min(ms), end.max(ms))),
+                        None => Some((ms, ms)),
+                    };
+                }
+            }
+            b"ALIGNABLE_ANNOTATION" | b"REF_ANNOTATION" => summary.annotations += 1,
+            _ => (),
+        }
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,93 @@
+//! Sensor summary tiers for generated ELAN-files, with one annotation
+//! per geotier annotation (symbolic association), so that e.g. speed
+//! or GPS quality can be seen directly in ELAN.
+
+use crate::geo::EafPoint;
+
+use super::xml::Document;
+
+/// Linguistic type for sensor tiers.
+pub const SENSOR_TYPE: &'static str = "sensor";
+
+/// Upper bounds for speed buckets in km/h.
+const SPEED_BUCKETS: [f64; 5] = [5.0, 15.0, 30.0, 60.0, 100.0];
+
+/// Sensor summary tier.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SensorTier {
+    /// 2D speed as a km/h range, e.g. `5-15 km/h`.
+    Speed,
+    /// Altitude in meters.
+    Altitude,
+    /// GPS satellite lock and dilution of precision.
+    Fix,
+}
+
+impl std::str::FromStr for SensorTier {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "speed" => Ok(Self::Speed),
+            "altitude" => Ok(Self::Altitude),
+            "fix" => Ok(Self::Fix),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("(!) Unknown sensor tier '{value}'"),
+            )),
+        }
+    }
+}
+
+impl SensorTier {
+    /// Tier ID, e.g. `geo-speed` for geotier `geo`.
+    pub fn tier_id(&self, parent_id: &str) -> String {
+        let suffix = match self {
+            Self::Speed => "speed",
+            Self::Altitude => "altitude",
+            Self::Fix => "fix",
+        };
+        format!("{parent_id}-{suffix}")
+    }
+
+    /// Annotation value for a single point.
+    pub fn value(&self, point: &EafPoint) -> String {
+        match self {
+            Self::Speed => {
+                let kmh = point.speed2d * 3.6;
+                let mut lower = 0.0;
+                for upper in SPEED_BUCKETS {
+                    if kmh < upper {
+                        return format!("{lower}-{upper} km/h");
+                    }
+                    lower = upper;
+                }
+                format!(">{lower} km/h")
+            }
+            Self::Altitude => format!("{:.0} m", point.altitude),
+            Self::Fix => match point.dop {
+                Some(dop) => format!("{} (DOP {dop:.1})", point.fix),
+                None => point.fix.to_string(),
+            },
+        }
+    }
+}
+
+/// Adds sensor tiers as children to the geotier `parent_id`.
+/// `points` must correspond one-to-one to the geotier annotations.
+///
+/// Returns `false` if the geotier does not exist.
+pub fn add_sensor_tiers(
+    doc: &mut Document,
+    parent_id: &str,
+    points: &[EafPoint],
+    tiers: &[SensorTier],
+) -> bool {
+    for tier in tiers.iter() {
+        let values: Vec<String> = points.iter().map(|p| tier.value(p)).collect();
+        if !doc.add_ref_tier(&tier.tier_id(parent_id), parent_id, SENSOR_TYPE, &values) {
+            return false;
+        }
+    }
+    true
+}
@@ -0,0 +1,163 @@
+//! ELAN time series configuration (`*_tsconf.xml`) with matching CSV-files,
+//! so that GPS derived values and acceleration can be viewed
+//! in ELAN's Timeseries Viewer, aligned with the video.
+
+use std::path::Path;
+
+use crate::{files::file_url, geo::EafPoint, telemetry::SensorSeries};
+
+use super::xml::escape;
+
+/// Time series tracks as `(name, description, units, RGB color)`.
+/// Columns in the CSV-file follow the same order, after the time column.
+const TRACKS: [(&str, &str, &str, &str); 3] = [
+    ("speed2d", "2D speed", "m/s", "0,0,255"),
+    ("speed3d", "3D speed", "m/s", "0,160,0"),
+    ("altitude", "Altitude", "m", "200,0,0"),
+];
+
+/// Acceleration tracks as `(name, description, RGB color)`,
+/// in the same order as the columns in the CSV-file.
+const ACCELERATION_TRACKS: [(&str, &str, &str); 3] = [
+    ("acc_x", "Acceleration X", "200,0,0"),
+    ("acc_y", "Acceleration Y", "0,160,0"),
+    ("acc_z", "Acceleration Z", "0,0,255"),
+];
+
+/// Returns track names for the Timeseries Viewer panes,
+/// one pane per unit: speed, altitude, and optionally acceleration.
+pub fn panels(acceleration: bool) -> Vec<Vec<&'static str>> {
+    let mut panels = vec![vec![TRACKS[0].0, TRACKS[1].0], vec![TRACKS[2].0]];
+    if acceleration {
+        panels.push(ACCELERATION_TRACKS.iter().map(|t| t.0).collect());
+    }
+    panels
+}
+
+fn values(point: &EafPoint) -> [f64; 3] {
+    [point.speed2d, point.speed3d, point.altitude]
+}
+
+/// Returns `(min, max)` for `values`, or `(0.0, 0.0)` if empty.
+fn range(values: &[f64]) -> (f64, f64) {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    match min <= max {
+        true => (min, max),
+        false => (0.0, 0.0), // no values
+    }
+}
+
+/// Returns `<track>` for CSV column `column`, with the value range set from `values`.
+fn track(
+    column: usize,
+    name: &str,
+    description: &str,
+    units: &str,
+    color: &str,
+    values: &[f64],
+) -> String {
+    let (min, max) = range(values);
+    format!(
+        r#"        <track derivative="0" name="{name}">
+            <property key="detect-range" value="false"/>
+            <sample-position>
+                <pos col="{column}" row="0"/>
+            </sample-position>
+            <description>{description}</description>
+            <units>{}</units>
+            <range max="{max:.3}" min="{min:.3}"/>
+            <color>{color}</color>
+        </track>
+"#,
+        escape(units)
+    )
+}
+
+/// Returns `<tracksource>` for the CSV-file at `csv_path`.
+fn tracksource(csv_path: &Path, tracks: &str) -> String {
+    format!(
+        r#"    <tracksource sample-type="Discontinuous Rate" source-url="{}" time-column="0">
+        <property key="provider" value="mpi.eudico.client.annotator.timeseries.csv.CSVServiceProvider"/>
+{tracks}    </tracksource>
+"#,
+        escape(&file_url(csv_path))
+    )
+}
+
+/// Returns time series as CSV, with time in milliseconds
+/// relative to the start of the video in the first column.
+/// Points without a timestamp are ignored.
+///
+/// VIRB only: `session_start_ms` is subtracted from each timestamp,
+/// see `generate_eaf()`.
+pub fn timeseries_csv(points: &[EafPoint], session_start_ms: Option<i64>) -> String {
+    points
+        .iter()
+        .filter_map(|p| {
+            let ms = p.timestamp?.whole_milliseconds() as i64 - session_start_ms.unwrap_or(0);
+            let row = values(p)
+                .iter()
+                .map(|v| format!("{v:.3}"))
+                .collect::<Vec<_>>()
+                .join(",");
+            Some(format!("{ms},{row}\n"))
+        })
+        .collect()
+}
+
+/// Returns acceleration as CSV, with time in milliseconds
+/// relative to the start of the video in the first column,
+/// followed by the x, y, and z axes.
+///
+/// VIRB only: `session_start_ms` is subtracted from each timestamp,
+/// as for `timeseries_csv()`.
+pub fn acceleration_csv(series: &SensorSeries, session_start_ms: Option<i64>) -> String {
+    series
+        .time
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| {
+            let ms = (t * 1000.0).round() as i64 - session_start_ms.unwrap_or(0);
+            let (x, y, z) = (series.x.get(i)?, series.y.get(i)?, series.z.get(i)?);
+            Some(format!("{ms},{x:.3},{y:.3},{z:.3}\n"))
+        })
+        .collect()
+}
+
+/// Returns ELAN time series configuration for the CSV-file at `csv_path`
+/// generated via `timeseries_csv()`, and optionally the CSV-file
+/// generated via `acceleration_csv()`.
+pub fn tsconf(
+    csv_path: &Path,
+    points: &[EafPoint],
+    acceleration: Option<(&Path, &SensorSeries)>,
+) -> String {
+    let mut tracks = String::new();
+    for (i, (name, description, units, color)) in TRACKS.iter().enumerate() {
+        let column: Vec<f64> = points.iter().map(|p| values(p)[i]).collect();
+        tracks.push_str(&track(i + 1, name, description, units, color, &column));
+    }
+    let mut sources = tracksource(csv_path, &tracks);
+
+    if let Some((path, series)) = acceleration {
+        let columns = [&series.x, &series.y, &series.z];
+        let tracks: String = ACCELERATION_TRACKS
+            .iter()
+            .zip(columns)
+            .enumerate()
+            .map(|(i, ((name, description, color), column))| {
+                track(i + 1, name, description, &series.units, color, column)
+            })
+            .collect();
+        sources.push_str(&tracksource(path, &tracks));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<timeseries date="{}" version="1.0">
+{sources}</timeseries>
+"#,
+        time::OffsetDateTime::now_utc()
+    )
+}
@@ -0,0 +1,1054 @@
+//! ELAN-files as an XML element tree, for structure not exposed by `eaf-rs`,
+//! such as applying an ELAN template (`.etf`).
+//!
+//! Documents are read and written with `quick-xml`. Attribute values
+//! and text are unescaped when read, and escaped when written,
+//! so IDs are always compared as unescaped values.
+//! Elements are written with the same indentation as `Eaf::to_string(Some(4))`.
+
+use std::{collections::HashMap, io::ErrorKind, path::Path};
+
+use quick_xml::{
+    events::{BytesEnd, BytesStart, BytesText, Event},
+    Reader, Writer,
+};
+
+/// Order of elements in `ANNOTATION_DOCUMENT`, according to the EAF schema.
+const ELEMENT_ORDER: [&'static str; 10] = [
+    "HEADER",
+    "TIME_ORDER",
+    "TIER",
+    "LINGUISTIC_TYPE",
+    "LOCALE",
+    "LANGUAGE",
+    "CONSTRAINT",
+    "CONTROLLED_VOCABULARY",
+    "LEXICON_REF",
+    "EXTERNAL_REF",
+];
+
+/// Template elements as `(element, ID attribute)`.
+const TEMPLATE_ELEMENTS: [(&'static str, &'static str); 6] = [
+    ("LINGUISTIC_TYPE", "LINGUISTIC_TYPE_ID"),
+    ("LOCALE", "LANGUAGE_CODE"),
+    ("LANGUAGE", "LANG_ID"),
+    ("CONSTRAINT", "STEREOTYPE"),
+    ("CONTROLLED_VOCABULARY", "CV_ID"),
+    ("TIER", "TIER_ID"),
+];
+
+/// Indentation size, matching `Eaf::to_string(Some(4))`.
+const INDENT: usize = 4;
+
+/// Escapes characters not allowed in XML attribute values and text,
+/// for XML written without `Document`, e.g. ELAN preferences.
+pub fn escape(value: &str) -> String {
+    quick_xml::escape::escape(value).into_owned()
+}
+
+/// Child node of an element.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Element(Element),
+    /// Unescaped text.
+    Text(String),
+    /// Comment, as is.
+    Comment(String),
+}
+
+/// XML element with unescaped attribute values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Element {
+    pub name: String,
+    /// Attributes as `(name, value)`, in document order.
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+    /// Byte offset of the start tag in the parsed XML text,
+    /// 0 for new elements.
+    pub offset: usize,
+}
+
+impl Element {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_attribute(mut self, name: &str, value: &str) -> Self {
+        self.set_attribute(name, value);
+        self
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.children.push(Node::Text(text.to_owned()));
+        self
+    }
+
+    pub fn with_child(mut self, child: Element) -> Self {
+        self.children.push(Node::Element(child));
+        self
+    }
+
+    /// Returns the unescaped value for attribute `name`.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Sets attribute `name` to `value`, replacing any existing value.
+    /// New attributes are added last.
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        match self.attributes.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_owned(),
+            None => self.attributes.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    /// Returns all text content, e.g. the value of a `PROPERTY`.
+    pub fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|n| match n {
+                Node::Text(t) => Some(t.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replaces all content with `text`.
+    pub fn set_text(&mut self, text: &str) {
+        self.children = vec![Node::Text(text.to_owned())];
+    }
+
+    /// Returns child elements.
+    pub fn child_elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|n| match n {
+            Node::Element(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Returns this element and all descendants named `tag`, in document order.
+    pub fn elements(&self, tag: &str) -> Vec<&Element> {
+        let mut found: Vec<&Element> = Vec::new();
+        self.collect(Some(tag), &mut found);
+        found
+    }
+
+    fn collect<'a>(&'a self, tag: Option<&str>, found: &mut Vec<&'a Element>) {
+        if tag.is_none_or(|t| t == self.name) {
+            found.push(self);
+        }
+        for child in self.child_elements() {
+            child.collect(tag, found);
+        }
+    }
+
+    /// Returns this element or all descendants named `tag`, in document order,
+    /// for editing. Elements named `tag` nested in elements named `tag`
+    /// are not included.
+    pub fn elements_mut(&mut self, tag: &str) -> Vec<&mut Element> {
+        let mut found: Vec<&mut Element> = Vec::new();
+        self.collect_mut(tag, &mut found);
+        found
+    }
+
+    fn collect_mut<'a>(&'a mut self, tag: &str, found: &mut Vec<&'a mut Element>) {
+        if self.name == tag {
+            found.push(self);
+            return;
+        }
+        for child in self.children.iter_mut() {
+            if let Node::Element(e) = child {
+                e.collect_mut(tag, found);
+            }
+        }
+    }
+
+    /// Returns a copy without content, e.g. a tier without annotations.
+    pub fn without_children(&self) -> Self {
+        Self {
+            name: self.name.to_owned(),
+            attributes: self.attributes.to_owned(),
+            children: Vec::new(),
+            offset: self.offset,
+        }
+    }
+
+    /// Inserts `child` before the first child element named any of `before`,
+    /// or last.
+    fn insert_before(&mut self, child: Element, before: &[&str]) {
+        let position = self
+            .children
+            .iter()
+            .position(|n| matches!(n, Node::Element(e) if before.contains(&e.name.as_str())))
+            .unwrap_or(self.children.len());
+        self.children.insert(position, Node::Element(child));
+    }
+
+    fn write(&self, writer: &mut Writer<Vec<u8>>) -> quick_xml::Result<()> {
+        let mut start = BytesStart::new(self.name.as_str());
+        for (name, value) in self.attributes.iter() {
+            start.push_attribute((name.as_str(), value.as_str()));
+        }
+        if self.children.is_empty() {
+            return writer.write_event(Event::Empty(start));
+        }
+        writer.write_event(Event::Start(start))?;
+        let has_elements = self.child_elements().next().is_some();
+        for child in self.children.iter() {
+            match child {
+                Node::Element(e) => e.write(writer)?,
+                // Whitespace between elements is not content
+                Node::Text(t) if has_elements && t.trim().is_empty() => (),
+                Node::Text(t) => writer.write_event(Event::Text(BytesText::new(t)))?,
+                Node::Comment(c) => {
+                    writer.write_event(Event::Comment(BytesText::from_escaped(c.as_str())))?
+                }
+            }
+        }
+        writer.write_event(Event::End(BytesEnd::new(self.name.as_str())))
+    }
+}
+
+/// ELAN-file or ELAN template as an element tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    /// XML declaration etc before the root element, as is.
+    prolog: String,
+    pub root: Element,
+}
+
+impl Document {
+    /// Parses XML text. Whitespace between elements is not kept.
+    pub fn parse(xml: &str) -> std::io::Result<Self> {
+        Self::try_parse(xml).map_err(|(offset, err)| {
+            let msg = format!("(!) Invalid XML at byte {offset}: {err}");
+            std::io::Error::new(ErrorKind::Other, msg)
+        })
+    }
+
+    /// Parses XML text. Returns the byte offset and
+    /// a description of the error if the XML is not well-formed.
+    pub fn try_parse(xml: &str) -> Result<Self, (usize, String)> {
+        let invalid =
+            |position: u64, err: &dyn std::fmt::Display| (position as usize, err.to_string());
+
+        let mut reader = Reader::from_str(xml);
+        let mut stack: Vec<Element> = Vec::new();
+        let mut prolog = String::new();
+        let mut root: Option<Element> = None;
+
+        loop {
+            let offset = reader.buffer_position() as usize;
+            let event = reader
+                .read_event()
+                .map_err(|err| invalid(reader.error_position(), &err))?;
+            let unescape_err = |err: &dyn std::fmt::Display| invalid(offset as u64, err);
+            match event {
+                Event::Start(ref e) | Event::Empty(ref e) => {
+                    if root.is_some() {
+                        continue;
+                    }
+                    if stack.is_empty() {
+                        prolog = xml[..offset].trim().to_owned();
+                    }
+                    let mut element = Element::new(&String::from_utf8_lossy(e.name().as_ref()));
+                    element.offset = offset;
+                    for attribute in e.attributes() {
+                        let attribute = attribute.map_err(|err| unescape_err(&err))?;
+                        let value = attribute
+                            .unescape_value()
+                            .map_err(|err| unescape_err(&err))?;
+                        element.attributes.push((
+                            String::from_utf8_lossy(attribute.key.as_ref()).to_string(),
+                            value.to_string(),
+                        ));
+                    }
+                    match event {
+                        Event::Start(_) => stack.push(element),
+                        _ => match stack.last_mut() {
+                            Some(parent) => parent.children.push(Node::Element(element)),
+                            None => root = Some(element),
+                        },
+                    }
+                }
+                Event::End(_) => {
+                    let Some(mut element) = stack.pop() else {
+                        continue;
+                    };
+                    // Whitespace between elements is not content
+                    if element.child_elements().next().is_some() {
+                        element
+                            .children
+                            .retain(|n| !matches!(n, Node::Text(t) if t.trim().is_empty()));
+                    } else if element.children.is_empty() {
+                        // Keep '<A></A>' as is
+                        element.children.push(Node::Text(String::new()));
+                    }
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(Node::Element(element)),
+                        None => root = Some(element),
+                    }
+                }
+                Event::Text(e) => {
+                    if let Some(parent) = stack.last_mut() {
+                        let text = e.unescape().map_err(|err| unescape_err(&err))?;
+                        parent.children.push(Node::Text(text.to_string()));
+                    }
+                }
+                Event::CData(e) => {
+                    if let Some(parent) = stack.last_mut() {
+                        let text = String::from_utf8_lossy(&e).to_string();
+                        parent.children.push(Node::Text(text));
+                    }
+                }
+                Event::Comment(e) => {
+                    if let Some(parent) = stack.last_mut() {
+                        let comment = String::from_utf8_lossy(&e).to_string();
+                        parent.children.push(Node::Comment(comment));
+                    }
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+
+        match root {
+            Some(root) => Ok(Self { prolog, root }),
+            None => Err(invalid(reader.buffer_position(), &"no root element")),
+        }
+    }
+
+    /// Reads and parses an XML file.
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Returns all elements `tag`, including the root, in document order.
+    pub fn elements(&self, tag: &str) -> Vec<&Element> {
+        self.root.elements(tag)
+    }
+
+    /// Returns the unescaped values for attribute `id` for all elements `tag`.
+    pub fn ids(&self, tag: &str, id: &str) -> Vec<String> {
+        self.elements(tag)
+            .iter()
+            .filter_map(|e| e.attribute(id).map(String::from))
+            .collect()
+    }
+
+    /// Returns tier `tier_id`.
+    pub fn tier(&self, tier_id: &str) -> Option<&Element> {
+        self.root
+            .child_elements()
+            .find(|e| e.name == "TIER" && e.attribute("TIER_ID") == Some(tier_id))
+    }
+
+    fn tier_mut(&mut self, tier_id: &str) -> Option<&mut Element> {
+        self.root.children.iter_mut().find_map(|n| match n {
+            Node::Element(e) if e.name == "TIER" && e.attribute("TIER_ID") == Some(tier_id) => {
+                Some(e)
+            }
+            _ => None,
+        })
+    }
+
+    fn header_mut(&mut self) -> Option<&mut Element> {
+        self.root.children.iter_mut().find_map(|n| match n {
+            Node::Element(e) if e.name == "HEADER" => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Inserts `element` as a child of `ANNOTATION_DOCUMENT`
+    /// at the position the schema requires for its name,
+    /// i.e. after any existing elements of the same kind.
+    pub fn insert(&mut self, element: Element) {
+        let later: Vec<&str> = ELEMENT_ORDER
+            .iter()
+            .skip_while(|t| **t != element.name)
+            .skip(1)
+            .copied()
+            .collect();
+        self.root.insert_before(element, &later);
+    }
+
+    /// Sets attribute `name` to `value` for tier `tier_id`.
+    ///
+    /// Returns `false` if the tier does not exist.
+    pub fn set_tier_attribute(&mut self, tier_id: &str, name: &str, value: &str) -> bool {
+        match self.tier_mut(tier_id) {
+            Some(tier) => {
+                tier.set_attribute(name, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a linked file (non-media file, e.g. time series configuration)
+    /// to the header, after any media and linked files.
+    ///
+    /// Returns `false` if there is no header.
+    pub fn add_linked_file(&mut self, url: &str, mime_type: &str) -> bool {
+        let Some(header) = self.header_mut() else {
+            return false;
+        };
+        let element = Element::new("LINKED_FILE_DESCRIPTOR")
+            .with_attribute("LINK_URL", url)
+            .with_attribute("MIME_TYPE", mime_type);
+        // Properties follow linked files
+        header.insert_before(element, &["PROPERTY"]);
+        true
+    }
+
+    /// Adds a property (`<PROPERTY NAME="...">value</PROPERTY>`)
+    /// last in the header, e.g. checksums for source media.
+    ///
+    /// Returns `false` if there is no header.
+    pub fn add_property(&mut self, name: &str, value: &str) -> bool {
+        let Some(header) = self.header_mut() else {
+            return false;
+        };
+        let element = Element::new("PROPERTY")
+            .with_attribute("NAME", name)
+            .with_text(value);
+        header.children.push(Node::Element(element));
+        true
+    }
+
+    /// Returns the value for the property `name` in the header, if set.
+    pub fn property(&self, name: &str) -> Option<String> {
+        self.elements("PROPERTY")
+            .into_iter()
+            .find(|e| e.attribute("NAME") == Some(name))
+            .map(|e| e.text().trim().to_owned())
+    }
+
+    /// Adds a media descriptor to the header, after any existing media descriptors,
+    /// or sets `TIME_ORIGIN` if a descriptor with the same URL already exists.
+    /// `time_origin` is the media offset in milliseconds.
+    ///
+    /// Returns `false` if there is no header.
+    pub fn add_media_descriptor(
+        &mut self,
+        url: &str,
+        mime_type: &str,
+        time_origin: Option<i64>,
+    ) -> bool {
+        let Some(header) = self.header_mut() else {
+            return false;
+        };
+        if let Some(descriptor) = header
+            .elements_mut("MEDIA_DESCRIPTOR")
+            .into_iter()
+            .find(|e| e.attribute("MEDIA_URL") == Some(url))
+        {
+            if let Some(origin) = time_origin {
+                descriptor.set_attribute("TIME_ORIGIN", &origin.to_string());
+            }
+            return true;
+        }
+
+        let mut element = Element::new("MEDIA_DESCRIPTOR")
+            .with_attribute("MEDIA_URL", url)
+            .with_attribute("MIME_TYPE", mime_type);
+        if let Some(origin) = time_origin {
+            element.set_attribute("TIME_ORIGIN", &origin.to_string());
+        }
+        // Linked files and properties follow media descriptors
+        header.insert_before(element, &["LINKED_FILE_DESCRIPTOR", "PROPERTY"]);
+        true
+    }
+
+    /// Sets `TIME_ORIGIN` (media offset in milliseconds) for media descriptors,
+    /// in the order they appear in the header.
+    /// Descriptors beyond the length of `origins` are left unchanged.
+    pub fn set_time_origins(&mut self, origins: &[i64]) {
+        for (descriptor, origin) in self
+            .root
+            .elements_mut("MEDIA_DESCRIPTOR")
+            .into_iter()
+            .zip(origins)
+        {
+            descriptor.set_attribute("TIME_ORIGIN", &origin.to_string());
+        }
+    }
+
+    /// Returns the highest number in IDs with `prefix`, e.g. `a` for
+    /// annotation IDs (`a12`) or `ts` for time slot IDs (`ts8`),
+    /// or 0 if there are none.
+    fn last_id(&self, attribute: &str, prefix: &str) -> usize {
+        let mut elements: Vec<&Element> = Vec::new();
+        self.root.collect(None, &mut elements);
+        elements
+            .iter()
+            .filter_map(|e| e.attribute(attribute)?.strip_prefix(prefix)?.parse().ok())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Sets the `lastUsedAnnotationId` header property, used by ELAN for new annotation IDs.
+    fn set_last_annotation_id(&mut self, id: usize) {
+        if let Some(property) = self
+            .root
+            .elements_mut("PROPERTY")
+            .into_iter()
+            .find(|e| e.attribute("NAME") == Some("lastUsedAnnotationId"))
+        {
+            property.set_text(&id.to_string());
+        }
+    }
+
+    /// Adds the linguistic type for new top-level tiers if it does not exist,
+    /// i.e. the first linguistic type without constraints, or `default-lt`.
+    /// Returns the linguistic type ID.
+    fn aligned_linguistic_type(&mut self) -> String {
+        let linguistic_type = self
+            .elements("LINGUISTIC_TYPE")
+            .into_iter()
+            .find(|e| e.attribute("CONSTRAINTS").is_none())
+            .and_then(|e| e.attribute("LINGUISTIC_TYPE_ID"))
+            .unwrap_or("default-lt")
+            .to_owned();
+        if !self
+            .ids("LINGUISTIC_TYPE", "LINGUISTIC_TYPE_ID")
+            .contains(&linguistic_type)
+        {
+            self.insert(
+                Element::new("LINGUISTIC_TYPE")
+                    .with_attribute("GRAPHIC_REFERENCES", "false")
+                    .with_attribute("LINGUISTIC_TYPE_ID", &linguistic_type)
+                    .with_attribute("TIME_ALIGNABLE", "true"),
+            );
+        }
+        linguistic_type
+    }
+
+    /// Time-aligned annotations as `(value, start_ms, end_ms)`, as `ANNOTATION`
+    /// elements, and new time slots for `TIME_ORDER`.
+    /// If `reuse` is `true`, existing time slots with the same time value
+    /// are referred to, rather than adding new ones.
+    ///
+    /// Returns `(annotations, time slots, last annotation ID)`.
+    fn aligned_annotations(
+        &self,
+        annotations: &[(String, i64, i64)],
+        reuse: bool,
+    ) -> (Vec<Element>, Vec<Element>, usize) {
+        // Time value -> time slot ID
+        let mut slots: HashMap<i64, String> = HashMap::new();
+        if reuse {
+            for slot in self.elements("TIME_SLOT") {
+                let ms = slot
+                    .attribute("TIME_VALUE")
+                    .and_then(|v| v.parse::<i64>().ok());
+                if let (Some(id), Some(ms)) = (slot.attribute("TIME_SLOT_ID"), ms) {
+                    slots.entry(ms).or_insert(id.to_owned());
+                }
+            }
+        }
+
+        let mut ts_id = self.last_id("TIME_SLOT_ID", "ts");
+        let mut a_id = self.last_id("ANNOTATION_ID", "a");
+        let mut time_slots: Vec<Element> = Vec::new();
+        let mut elements: Vec<Element> = Vec::new();
+        for (value, start, end) in annotations.iter() {
+            let mut refs: Vec<String> = Vec::new();
+            for ms in [*start, *end] {
+                if let Some(id) = slots.get(&ms) {
+                    refs.push(id.to_owned());
+                    continue;
+                }
+                ts_id += 1;
+                let id = format!("ts{ts_id}");
+                time_slots.push(
+                    Element::new("TIME_SLOT")
+                        .with_attribute("TIME_SLOT_ID", &id)
+                        .with_attribute("TIME_VALUE", &ms.to_string()),
+                );
+                if reuse {
+                    slots.insert(ms, id.to_owned());
+                }
+                refs.push(id);
+            }
+            a_id += 1;
+            elements.push(
+                Element::new("ANNOTATION").with_child(
+                    Element::new("ALIGNABLE_ANNOTATION")
+                        .with_attribute("ANNOTATION_ID", &format!("a{a_id}"))
+                        .with_attribute("TIME_SLOT_REF1", &refs[0])
+                        .with_attribute("TIME_SLOT_REF2", &refs[1])
+                        .with_child(Element::new("ANNOTATION_VALUE").with_text(value)),
+                ),
+            );
+        }
+
+        (elements, time_slots, a_id)
+    }
+
+    /// Appends `time_slots` to `TIME_ORDER`, which is added if it does not exist.
+    fn append_time_slots(&mut self, time_slots: Vec<Element>) {
+        if time_slots.is_empty() {
+            return;
+        }
+        if !self.root.child_elements().any(|e| e.name == "TIME_ORDER") {
+            self.insert(Element::new("TIME_ORDER"));
+        }
+        if let Some(time_order) = self.root.elements_mut("TIME_ORDER").into_iter().next() {
+            time_order
+                .children
+                .extend(time_slots.into_iter().map(Node::Element));
+        }
+    }
+
+    /// Adds top-level tier `tier` (without content)
+    /// with time-aligned annotations.
+    fn write_aligned_tier(
+        &mut self,
+        mut tier: Element,
+        annotations: &[(String, i64, i64)],
+        reuse: bool,
+    ) {
+        let (elements, time_slots, a_id) = self.aligned_annotations(annotations, reuse);
+        tier.children = elements.into_iter().map(Node::Element).collect();
+        self.append_time_slots(time_slots);
+        self.insert(tier);
+        self.set_last_annotation_id(a_id);
+    }
+
+    /// Adds a top-level tier with time-aligned annotations,
+    /// as `(value, start_ms, end_ms)`, using the first
+    /// linguistic type without constraints.
+    pub fn add_aligned_tier(&mut self, tier_id: &str, annotations: &[(String, i64, i64)]) {
+        let linguistic_type = self.aligned_linguistic_type();
+        let tier = Element::new("TIER")
+            .with_attribute("LINGUISTIC_TYPE_REF", &linguistic_type)
+            .with_attribute("TIER_ID", tier_id);
+        self.write_aligned_tier(tier, annotations, false)
+    }
+
+    /// Copies elements referred to by `tier` from `source`
+    /// if they do not exist (matched on ID): the linguistic type
+    /// and its controlled vocabulary, and the content language.
+    fn copy_tier_refs(&mut self, source: &Document, tier: &Element) {
+        let mut refs: Vec<(&str, &str, String)> = Vec::new();
+        if let Some(id) = tier.attribute("LINGUISTIC_TYPE_REF") {
+            refs.push(("LINGUISTIC_TYPE", "LINGUISTIC_TYPE_ID", id.to_owned()));
+            if let Some(cv) = source
+                .elements("LINGUISTIC_TYPE")
+                .into_iter()
+                .find(|e| e.attribute("LINGUISTIC_TYPE_ID") == Some(id))
+                .and_then(|e| e.attribute("CONTROLLED_VOCABULARY_REF"))
+            {
+                refs.push(("CONTROLLED_VOCABULARY", "CV_ID", cv.to_owned()));
+            }
+        }
+        if let Some(id) = tier.attribute("LANG_REF") {
+            refs.push(("LANGUAGE", "LANG_ID", id.to_owned()));
+        }
+        for (tag, id_attribute, id) in refs {
+            if self.ids(tag, id_attribute).contains(&id) {
+                continue;
+            }
+            if let Some(element) = source
+                .elements(tag)
+                .into_iter()
+                .find(|e| e.attribute(id_attribute) == Some(id.as_str()))
+            {
+                self.insert(element.to_owned());
+            }
+        }
+    }
+
+    /// Adds a top-level tier with time-aligned annotations, using the attributes
+    /// of `tier` (from `source`, another ELAN-file) with `TIER_ID` set to `tier_id`,
+    /// so that attributes such as participant, annotator, content language,
+    /// and linguistic type are kept. Referred elements that do not exist
+    /// are copied from `source`. Time slots with the same time value are re-used.
+    pub fn add_aligned_tier_from(
+        &mut self,
+        source: Option<&Document>,
+        tier: &Element,
+        tier_id: &str,
+        annotations: &[(String, i64, i64)],
+    ) {
+        let mut tier = tier.without_children();
+        tier.set_attribute("TIER_ID", tier_id);
+        if let Some(source) = source {
+            self.copy_tier_refs(source, &tier);
+        }
+        let types = self.ids("LINGUISTIC_TYPE", "LINGUISTIC_TYPE_ID");
+        if !tier
+            .attribute("LINGUISTIC_TYPE_REF")
+            .is_some_and(|t| types.iter().any(|id| id == t))
+        {
+            let linguistic_type = self.aligned_linguistic_type();
+            tier.set_attribute("LINGUISTIC_TYPE_REF", &linguistic_type);
+        }
+        self.write_aligned_tier(tier, annotations, true)
+    }
+
+    /// Updates the top-level tier `tier_id` in place. Attributes are kept,
+    /// as are existing annotations where `keep` is `true` (in document order,
+    /// missing values count as `true`), including their time slots.
+    /// `annotations` are added as time-aligned annotations,
+    /// re-using time slots with the same time value.
+    /// Time slots that are no longer referred to are removed.
+    ///
+    /// Returns `false` if the tier does not exist.
+    pub fn update_aligned_tier(
+        &mut self,
+        tier_id: &str,
+        keep: &[bool],
+        annotations: &[(String, i64, i64)],
+    ) -> bool {
+        if self.tier(tier_id).is_none() {
+            return false;
+        }
+        let (added, time_slots, a_id) = self.aligned_annotations(annotations, true);
+
+        let mut removed: Vec<String> = Vec::new();
+        if let Some(tier) = self.tier_mut(tier_id) {
+            let mut i = 0;
+            tier.children.retain(|n| {
+                let Node::Element(e) = n else {
+                    return true;
+                };
+                if e.name != "ANNOTATION" {
+                    return true;
+                }
+                i += 1;
+                let keep = keep.get(i - 1).copied().unwrap_or(true);
+                if !keep {
+                    removed.extend(time_slot_refs(e));
+                }
+                keep
+            });
+            tier.children.extend(added.into_iter().map(Node::Element));
+        }
+        self.append_time_slots(time_slots);
+        self.remove_unused_time_slots(&removed);
+        self.set_last_annotation_id(a_id);
+        true
+    }
+
+    /// Removes time slots in `time_slots` that are not referred to.
+    fn remove_unused_time_slots(&mut self, time_slots: &[String]) {
+        let used = time_slot_refs(&self.root);
+        for time_order in self.root.elements_mut("TIME_ORDER") {
+            time_order.children.retain(|n| match n {
+                Node::Element(e) => e.attribute("TIME_SLOT_ID").is_none_or(|id| {
+                    used.iter().any(|u| u == id) || !time_slots.iter().any(|t| t == id)
+                }),
+                _ => true,
+            });
+        }
+    }
+
+    /// Removes tier `tier_id` and any time slots that are no longer referenced.
+    /// Tiers referring to `tier_id` are left as is.
+    ///
+    /// Returns `false` if the tier does not exist.
+    pub fn remove_tier(&mut self, tier_id: &str) -> bool {
+        let Some(position) = self.root.children.iter().position(
+            |n| matches!(n, Node::Element(e) if e.name == "TIER" && e.attribute("TIER_ID") == Some(tier_id)),
+        ) else {
+            return false;
+        };
+        let Node::Element(tier) = self.root.children.remove(position) else {
+            return false;
+        };
+        self.remove_unused_time_slots(&time_slot_refs(&tier));
+        true
+    }
+
+    /// Adds a tier with symbolic association to `parent_id`,
+    /// with one annotation for each annotation in the parent tier,
+    /// in order. Values beyond the number of parent annotations are ignored.
+    /// A linguistic type `linguistic_type` with the `Symbolic_Association`
+    /// constraint is added if it does not exist.
+    ///
+    /// Returns `false` if the parent tier does not exist.
+    pub fn add_ref_tier(
+        &mut self,
+        tier_id: &str,
+        parent_id: &str,
+        linguistic_type: &str,
+        values: &[String],
+    ) -> bool {
+        let Some(parent) = self.tier(parent_id) else {
+            return false;
+        };
+        let parent_ids: Vec<String> = parent
+            .elements("ALIGNABLE_ANNOTATION")
+            .iter()
+            .filter_map(|e| e.attribute("ANNOTATION_ID").map(String::from))
+            .collect();
+
+        // Annotation IDs follow ELAN's 'a<N>' pattern
+        let mut last_id = self.last_id("ANNOTATION_ID", "a");
+
+        let mut tier = Element::new("TIER")
+            .with_attribute("LINGUISTIC_TYPE_REF", linguistic_type)
+            .with_attribute("PARENT_REF", parent_id)
+            .with_attribute("TIER_ID", tier_id);
+        for (annotation_ref, value) in parent_ids.iter().zip(values.iter()) {
+            last_id += 1;
+            tier = tier.with_child(
+                Element::new("ANNOTATION").with_child(
+                    Element::new("REF_ANNOTATION")
+                        .with_attribute("ANNOTATION_ID", &format!("a{last_id}"))
+                        .with_attribute("ANNOTATION_REF", annotation_ref)
+                        .with_child(Element::new("ANNOTATION_VALUE").with_text(value)),
+                ),
+            );
+        }
+        self.insert(tier);
+
+        if !self
+            .ids("LINGUISTIC_TYPE", "LINGUISTIC_TYPE_ID")
+            .iter()
+            .any(|t| t == linguistic_type)
+        {
+            if !self
+                .ids("CONSTRAINT", "STEREOTYPE")
+                .iter()
+                .any(|s| s == "Symbolic_Association")
+            {
+                self.insert(
+                    Element::new("CONSTRAINT")
+                        .with_attribute("DESCRIPTION", "1-1 association with a parent annotation")
+                        .with_attribute("STEREOTYPE", "Symbolic_Association"),
+                );
+            }
+            self.insert(
+                Element::new("LINGUISTIC_TYPE")
+                    .with_attribute("CONSTRAINTS", "Symbolic_Association")
+                    .with_attribute("GRAPHIC_REFERENCES", "false")
+                    .with_attribute("LINGUISTIC_TYPE_ID", linguistic_type)
+                    .with_attribute("TIME_ALIGNABLE", "false"),
+            );
+        }
+
+        self.set_last_annotation_id(last_id);
+        true
+    }
+
+    /// Applies an ELAN template (`.etf`) to an ELAN-file,
+    /// by adding tiers, linguistic types, languages, constraints,
+    /// and controlled vocabularies in the template that do not already exist
+    /// (matched on ID). Template tiers are added without annotations.
+    ///
+    /// Returns the added elements as `(element, ID)`.
+    pub fn apply_template(&mut self, template: &Document) -> Vec<(String, String)> {
+        let mut added: Vec<(String, String)> = Vec::new();
+
+        for (tag, id_attribute) in TEMPLATE_ELEMENTS {
+            let existing = self.ids(tag, id_attribute);
+            for element in template.elements(tag) {
+                let Some(id) = element.attribute(id_attribute) else {
+                    continue;
+                };
+                if existing.iter().any(|e| e == id) {
+                    continue;
+                }
+                let element = match tag {
+                    "TIER" => element.without_children(),
+                    _ => element.to_owned(),
+                };
+                self.insert(element);
+                added.push((tag.to_owned(), id.to_owned()));
+            }
+        }
+
+        added
+    }
+
+    /// Returns an ELAN template (`.etf`) from an ELAN-file,
+    /// i.e. the document structure without media, time slots,
+    /// or annotations.
+    pub fn to_template(&self) -> Document {
+        let mut template = self.to_owned();
+        for header in template.root.elements_mut("HEADER") {
+            header
+                .children
+                .retain(|n| !matches!(n, Node::Element(e) if e.name == "MEDIA_DESCRIPTOR"));
+        }
+        for element in template.root.children.iter_mut() {
+            if let Node::Element(e) = element {
+                if ["TIME_ORDER", "TIER"].contains(&e.name.as_str()) {
+                    e.children.clear();
+                }
+            }
+        }
+        template
+    }
+}
+
+/// Returns time slot IDs referred to in `element` and its descendants,
+/// sorted and deduplicated.
+fn time_slot_refs(element: &Element) -> Vec<String> {
+    let mut time_slots: Vec<String> = element
+        .elements("ALIGNABLE_ANNOTATION")
+        .iter()
+        .flat_map(|e| ["TIME_SLOT_REF1", "TIME_SLOT_REF2"].map(|r| e.attribute(r)))
+        .flatten()
+        .map(String::from)
+        .collect();
+    time_slots.sort();
+    time_slots.dedup();
+    time_slots
+}
+
+impl std::fmt::Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut writer = Writer::new_with_indent(Vec::new(), b' ', INDENT);
+        self.root.write(&mut writer).map_err(|_| std::fmt::Error)?;
+        if !self.prolog.is_empty() {
+            writeln!(f, "{}", self.prolog)?;
+        }
+        writeln!(f, "{}", String::from_utf8_lossy(&writer.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EAF: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ANNOTATION_DOCUMENT>
+    <HEADER MEDIA_FILE="" TIME_UNITS="milliseconds">
+        <PROPERTY NAME="lastUsedAnnotationId">2</PROPERTY>
+    </HEADER>
+    <TIME_ORDER>
+        <TIME_SLOT TIME_SLOT_ID="ts1" TIME_VALUE="0"/>
+        <TIME_SLOT TIME_SLOT_ID="ts2"/>
+        <TIME_SLOT TIME_SLOT_ID="ts3" TIME_VALUE="2000"/>
+        <TIME_SLOT TIME_SLOT_ID="ts4" TIME_VALUE="3000"/>
+    </TIME_ORDER>
+    <TIER LINGUISTIC_TYPE_REF="default-lt" PARTICIPANT="P1" TIER_ID="A">
+        <ANNOTATION>
+            <ALIGNABLE_ANNOTATION ANNOTATION_ID="a1" TIME_SLOT_REF1="ts1" TIME_SLOT_REF2="ts2">
+                <ANNOTATION_VALUE>one</ANNOTATION_VALUE>
+            </ALIGNABLE_ANNOTATION>
+        </ANNOTATION>
+        <ANNOTATION>
+            <ALIGNABLE_ANNOTATION ANNOTATION_ID="a2" TIME_SLOT_REF1="ts2" TIME_SLOT_REF2="ts3">
+                <ANNOTATION_VALUE>two</ANNOTATION_VALUE>
+            </ALIGNABLE_ANNOTATION>
+        </ANNOTATION>
+        <ANNOTATION>
+            <ALIGNABLE_ANNOTATION ANNOTATION_ID="a3" TIME_SLOT_REF1="ts3" TIME_SLOT_REF2="ts4">
+                <ANNOTATION_VALUE>three</ANNOTATION_VALUE>
+            </ALIGNABLE_ANNOTATION>
+        </ANNOTATION>
+    </TIER>
+    <LINGUISTIC_TYPE GRAPHIC_REFERENCES="false" LINGUISTIC_TYPE_ID="default-lt" TIME_ALIGNABLE="true"/>
+</ANNOTATION_DOCUMENT>
+"#;
+
+    #[test]
+    fn round_trip() {
+        let doc = Document::parse(EAF).unwrap();
+        assert_eq!(doc.to_string(), EAF);
+        let empty = "<A>\n    <B></B>\n    <C/>\n</A>\n";
+        assert_eq!(Document::parse(empty).unwrap().to_string(), empty);
+    }
+
+    #[test]
+    fn attribute_any_whitespace() {
+        let doc = Document::parse("<TIER PARENT_TIER_ID=\"x\"\n\tTIER_ID = \"A\"/>").unwrap();
+        assert_eq!(doc.root.attribute("TIER_ID"), Some("A"));
+        assert_eq!(doc.root.attribute("PARTICIPANT"), None);
+    }
+
+    #[test]
+    fn markup_in_values() {
+        let value = r#"<TIER TIER_ID="B"> & </TIER>"#;
+        let mut doc = Document::parse(EAF).unwrap();
+        doc.add_aligned_tier("<TIER>", &[(value.to_owned(), 0, 1000)]);
+        let xml = doc.to_string();
+        assert!(xml.contains(r#"TIER_ID="&lt;TIER&gt;""#));
+        assert!(xml.contains(&format!(
+            "<ANNOTATION_VALUE>{}</ANNOTATION_VALUE>",
+            escape(value)
+        )));
+        // Values are unescaped when read
+        let mut doc = Document::parse(&xml).unwrap();
+        assert_eq!(doc.ids("TIER", "TIER_ID"), ["A", "<TIER>"]);
+        assert_eq!(doc.elements("ANNOTATION_VALUE")[3].text(), value);
+        // Removing the tier restores the original document
+        assert!(doc.remove_tier("<TIER>"));
+        assert_eq!(
+            doc.to_string().replace(r#"lastUsedAnnotationId">4<"#, ""),
+            EAF.replace(r#"lastUsedAnnotationId">2<"#, "")
+        );
+    }
+
+    #[test]
+    fn ref_tier_with_markup_in_parent_id() {
+        let mut doc =
+            Document::parse(&EAF.replace(r#"TIER_ID="A""#, r#"TIER_ID="&amp;&lt;&quot;""#))
+                .unwrap();
+        let values = ["x".to_owned(), "y".to_owned()];
+        assert!(doc.add_ref_tier("R", "&<\"", "ref", &values));
+        let xml = doc.to_string();
+        assert!(xml.contains(r#"PARENT_REF="&amp;&lt;&quot;" TIER_ID="R""#));
+        assert!(xml.contains(r#"ANNOTATION_ID="a4" ANNOTATION_REF="a1""#));
+        assert!(xml.contains(r#"LINGUISTIC_TYPE_ID="ref""#));
+        assert!(xml.contains(r#"STEREOTYPE="Symbolic_Association""#));
+        assert!(!doc.add_ref_tier("R2", "&amp;&lt;&quot;", "ref", &values));
+    }
+
+    #[test]
+    fn update_keeps_annotations_and_slots() {
+        let added = [("four".to_owned(), 3000, 5000)];
+        let mut doc = Document::parse(EAF).unwrap();
+        assert!(doc.update_aligned_tier("A", &[true, true, false], &added));
+        let xml = doc.to_string();
+        // Unaligned slot is kept, unused slot removed, equal value re-used
+        assert!(xml.contains(r#"<TIME_SLOT TIME_SLOT_ID="ts2"/>"#));
+        assert!(!xml.contains(">three<"));
+        assert!(xml.contains(r#"TIME_SLOT_REF1="ts4" TIME_SLOT_REF2="ts5""#));
+        assert!(xml.contains(r#"<TIME_SLOT TIME_SLOT_ID="ts5" TIME_VALUE="5000"/>"#));
+        assert!(xml.contains(r#"PARTICIPANT="P1" TIER_ID="A""#));
+        assert!(xml.contains(r#"<PROPERTY NAME="lastUsedAnnotationId">4</PROPERTY>"#));
+    }
+
+    #[test]
+    fn add_tier_from_source() {
+        let source = Document::parse(
+            r#"<ANNOTATION_DOCUMENT>
+    <TIER LANG_REF="swe" LINGUISTIC_TYPE_REF="gesture" PARTICIPANT="P2" TIER_ID="G"/>
+    <LINGUISTIC_TYPE CONTROLLED_VOCABULARY_REF="cv1" LINGUISTIC_TYPE_ID="gesture" TIME_ALIGNABLE="true"/>
+    <LANGUAGE LANG_ID="swe" LANG_LABEL="Swedish (swe)"/>
+    <CONTROLLED_VOCABULARY CV_ID="cv1"/>
+</ANNOTATION_DOCUMENT>"#,
+        )
+        .unwrap();
+        let tier = source.tier("G").unwrap();
+        let annotations = [("g".to_owned(), 0, 2000)];
+        let mut doc = Document::parse(EAF).unwrap();
+        doc.add_aligned_tier_from(Some(&source), tier, "G-b", &annotations);
+        let xml = doc.to_string();
+        assert!(xml.contains(
+            r#"<TIER LANG_REF="swe" LINGUISTIC_TYPE_REF="gesture" PARTICIPANT="P2" TIER_ID="G-b">"#
+        ));
+        assert!(xml.contains(r#"TIME_SLOT_REF1="ts1" TIME_SLOT_REF2="ts3""#));
+        assert!(xml.contains(r#"LINGUISTIC_TYPE_ID="gesture""#));
+        assert!(xml.contains(r#"<LANGUAGE LANG_ID="swe""#));
+        assert!(xml.contains(r#"<CONTROLLED_VOCABULARY CV_ID="cv1"/>"#));
+    }
+
+    #[test]
+    fn invalid_xml() {
+        let err = Document::parse("<A>\n<B></C>\n</A>").unwrap_err();
+        assert!(err.to_string().starts_with("(!) Invalid XML at byte"));
+    }
+}
@@ -0,0 +1,315 @@
+//! Export camera telemetry to standalone files.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use gpmf_rs::GoProFile;
+use time::Duration;
+
+use crate::{
+    files::{
+        affix_file_name, create_dir, csv_field,
+        gopro::{complex, salvage::salvage, ExtractGpmf},
+        writefile,
+    },
+    logging::info,
+    telemetry::{self, Telemetry},
+};
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let video = args.get_one::<PathBuf>("video");
+    let fit = args.get_one::<PathBuf>("fit");
+    let gpmf_raw = *args.get_one::<bool>("gpmf-raw").unwrap();
+    let gpmf_salvage = *args.get_one::<bool>("gpmf-salvage").unwrap();
+    let fourccs: Vec<&String> = args
+        .get_many::<String>("gpmf-type")
+        .into_iter()
+        .flatten()
+        .collect();
+    let sensors: Vec<&String> = args
+        .get_many::<String>("sensor")
+        .into_iter()
+        .flatten()
+        .collect();
+
+    // clap: either '--video' or '--fit' is required
+    let input = video.or(fit).unwrap();
+
+    let output_dir = match args.get_one::<PathBuf>("output-directory") {
+        Some(p) => {
+            create_dir(&p)?;
+            p.to_owned()
+        }
+        None => input.parent().map(|p| p.to_owned()).unwrap_or_default(),
+    };
+
+    if !gpmf_raw && !gpmf_salvage && fourccs.is_empty() && sensors.is_empty() {
+        let msg = "(!) Nothing to export. Specify e.g. '--gpmf-raw' or '--sensor'.";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    if let (true, Some(video)) = (gpmf_raw, video) {
+        export_gpmf_raw(video, &output_dir)?;
+    }
+
+    if let (true, Some(video)) = (gpmf_salvage, video) {
+        export_gpmf_salvaged(video, &output_dir)?;
+    }
+
+    if let (false, Some(video)) = (fourccs.is_empty(), video) {
+        export_gpmf_types(video, &fourccs, &output_dir)?;
+    }
+
+    if !sensors.is_empty() {
+        let telemetry = telemetry::open(
+            video.map(|p| p.as_path()),
+            fit.map(|p| p.as_path()),
+            None,
+            false,
+            false,
+        )?;
+        for sensor in sensors.iter() {
+            export_sensor(telemetry.as_ref(), sensor, &output_dir)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads `video` as a GoPro MP4-file.
+fn gopro_file(video: &Path) -> std::io::Result<GoProFile> {
+    GoProFile::new(video).map_err(|err| {
+        let msg = format!("(!) Failed to read {} as GoPro MP4: {err}", video.display());
+        std::io::Error::new(ErrorKind::Other, msg)
+    })
+}
+
+/// Writes the raw GPMF track and a CSV index of sample offsets and timestamps.
+fn export_gpmf_raw(video: &Path, output_dir: &Path) -> std::io::Result<()> {
+    info!("Extracting GPMF track from {}...", video.display());
+    let (gpmf, index) = gopro_file(video)?.extract_gpmf(false)?;
+    info!("Extracted {} samples, {} bytes", index.len(), gpmf.len());
+
+    // Re-use file name, e.g. GH010006.MP4 -> GH010006.gpmf, GH010006_GPMF_INDEX.csv
+    let file_name = video.file_name().map(PathBuf::from).unwrap_or_default();
+    let gpmf_path = affix_file_name(&output_dir.join(&file_name), None, None, Some("gpmf"));
+    let index_path = affix_file_name(
+        &output_dir.join(&file_name),
+        None,
+        Some("_GPMF_INDEX"),
+        Some("csv"),
+    );
+
+    let mut csv: Vec<String> = vec!["SAMPLE,POSITION,MP4_POSITION,SIZE,START,DURATION".to_owned()];
+    for (i, sample) in index.iter().enumerate() {
+        csv.push(format!(
+            "{},{},{},{},{:.6},{:.6}",
+            i + 1,
+            sample.position,
+            sample.mp4_position,
+            sample.size,
+            sample.start,
+            sample.duration
+        ))
+    }
+
+    if writefile(&gpmf, &gpmf_path)?.is_written() {
+        info!("Wrote {}", gpmf_path.display());
+    }
+    if writefile(csv.join("\n").as_bytes(), &index_path)?.is_written() {
+        info!("Wrote {}", index_path.display());
+    }
+
+    Ok(())
+}
+
+/// Writes intact GPMF data from a damaged GoPro MP4-file,
+/// skipping damaged `DEVC` blocks, together with a damage report.
+fn export_gpmf_salvaged(video: &Path, output_dir: &Path) -> std::io::Result<()> {
+    info!("Extracting GPMF track from {}...", video.display());
+    let (gpmf, index) = gopro_file(video)?.extract_gpmf(true)?;
+    info!("Extracted {} samples, {} bytes", index.len(), gpmf.len());
+
+    let salvaged = salvage(&gpmf, &index);
+    let damaged: u64 = salvaged.damage.iter().map(|d| d.size).sum();
+    info!(
+        "Salvaged {} DEVC blocks ({} bytes), skipped {} damaged ranges ({damaged} bytes)",
+        salvaged.devc,
+        salvaged.gpmf.len(),
+        salvaged.damage.len()
+    );
+    for damage in salvaged.damage.iter() {
+        info!(
+            "  Sample {:5} @{:.3}s: {} bytes at {}, {}",
+            damage.sample, damage.start, damage.size, damage.position, damage.reason
+        );
+    }
+
+    let file_name = video.file_name().map(PathBuf::from).unwrap_or_default();
+    let gpmf_path = affix_file_name(
+        &output_dir.join(&file_name),
+        None,
+        Some("_SALVAGED"),
+        Some("gpmf"),
+    );
+    let report_path = affix_file_name(
+        &output_dir.join(&file_name),
+        None,
+        Some("_GPMF_DAMAGE"),
+        Some("csv"),
+    );
+
+    let mut csv: Vec<String> = vec!["SAMPLE,POSITION,SIZE,START,REASON".to_owned()];
+    for damage in salvaged.damage.iter() {
+        csv.push(format!(
+            "{},{},{},{:.6},{}",
+            damage.sample,
+            damage.position,
+            damage.size,
+            damage.start,
+            csv_field(&damage.reason)
+        ))
+    }
+
+    if !salvaged.damage.is_empty()
+        && writefile(csv.join("\n").as_bytes(), &report_path)?.is_written()
+    {
+        info!("Wrote {}", report_path.display());
+    }
+    if salvaged.gpmf.is_empty() {
+        let msg = format!("(!) No intact GPMF data in {}", video.display());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+    if writefile(&salvaged.gpmf, &gpmf_path)?.is_written() {
+        info!("Wrote {}", gpmf_path.display());
+    }
+
+    Ok(())
+}
+
+/// Writes unscaled values for each GPMF FourCC as CSV, one row per structure,
+/// decoding complex structures (`?`) via the stream's `TYPE` definition.
+fn export_gpmf_types(video: &Path, fourccs: &[&String], output_dir: &Path) -> std::io::Result<()> {
+    let keys = fourccs
+        .iter()
+        .map(|f| {
+            <[u8; 4]>::try_from(f.as_bytes()).map_err(|_| {
+                let msg =
+                    format!("(!) '{f}' is not a valid FourCC. Must be 4 characters, e.g. 'HUES'.");
+                std::io::Error::new(ErrorKind::Other, msg)
+            })
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    info!("Extracting GPMF track from {}...", video.display());
+    let (gpmf, index) = gopro_file(video)?.extract_gpmf(false)?;
+
+    let file_name = video.file_name().map(PathBuf::from).unwrap_or_default();
+    for (fourcc, key) in fourccs.iter().zip(keys.iter()) {
+        let mut rows: Vec<String> = Vec::new();
+        let mut fields = 0;
+        for (i, sample) in index.iter().enumerate() {
+            let range = sample.position as usize..(sample.position + sample.size) as usize;
+            let Some(data) = gpmf.get(range) else {
+                let msg = format!(
+                    "(!) GPMF sample {} at {} exceeds extracted GPMF data ({} bytes)",
+                    i + 1,
+                    sample.position,
+                    gpmf.len()
+                );
+                return Err(std::io::Error::new(ErrorKind::InvalidData, msg));
+            };
+            let values = complex::decode(data, key).map_err(|err| {
+                let msg = format!("(!) Failed to decode '{fourcc}' in sample {}: {err}", i + 1);
+                std::io::Error::new(ErrorKind::Other, msg)
+            })?;
+            for row in values.iter() {
+                fields = fields.max(row.len());
+                let values: Vec<String> = row.iter().map(|v| csv_field(&v.to_string())).collect();
+                rows.push(format!(
+                    "{},{:.6},{}",
+                    i + 1,
+                    sample.start,
+                    values.join(",")
+                ));
+            }
+        }
+
+        if rows.is_empty() {
+            let msg = format!("(!) No '{fourcc}' data in {}", video.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+
+        let mut csv: Vec<String> =
+            vec![(1..=fields).fold("SAMPLE,START".to_owned(), |h, n| format!("{h},VALUE{n}"))];
+        csv.extend(rows);
+        // E.g. GH010006.MP4 -> GH010006_HUES.csv
+        let csv_path = affix_file_name(
+            &output_dir.join(&file_name),
+            None,
+            Some(&format!("_{fourcc}")),
+            Some("csv"),
+        );
+
+        if writefile(csv.join("\n").as_bytes(), &csv_path)?.is_written() {
+            info!("Wrote {} ({} rows)", csv_path.display(), csv.len() - 1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes sensor data as CSV, one row per sample,
+/// with time relative to start of data (seconds) and absolute date time.
+/// One-dimensional sensors, e.g. barometer, have empty Y and Z columns.
+///
+/// VIRB values are calibrated by `fit_rs`. GoPro values are scaled by `gpmf_rs`,
+/// with axes in logged order, since sensor orientation (`ORIN`, `MTRX`) is not applied.
+fn export_sensor(
+    telemetry: &dyn Telemetry,
+    sensor: &str,
+    output_dir: &Path,
+) -> std::io::Result<()> {
+    let series = telemetry.sensors(sensor, false)?;
+    let start = telemetry.start_time();
+
+    let mut csv: Vec<String> =
+        vec!["SAMPLE,TIME,DATETIME,SENSOR,PHYSICAL_QUANTITY,UNIT,X,Y,Z".to_owned()];
+    for (i, t) in series.time.iter().enumerate() {
+        csv.push(format!(
+            "{},{t:.6},{},{},{},{},{},{},{}",
+            i + 1,
+            start
+                .map(|dt| csv_field(&(dt + Duration::seconds_f64(*t)).to_string()))
+                .unwrap_or_default(),
+            csv_field(&series.name),
+            csv_field(&series.quantifier),
+            csv_field(&series.units),
+            series.x.get(i).map(|v| v.to_string()).unwrap_or_default(),
+            series.y.get(i).map(|v| v.to_string()).unwrap_or_default(),
+            series.z.get(i).map(|v| v.to_string()).unwrap_or_default(),
+        ))
+    }
+
+    // E.g. GH010006.MP4 -> GH010006_ACCELEROMETER.csv
+    let file_name = telemetry
+        .path()
+        .file_name()
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let suffix = format!("_{}", series.name.to_uppercase().replace(' ', "_"));
+    let csv_path = affix_file_name(
+        &output_dir.join(&file_name),
+        None,
+        Some(&suffix),
+        Some("csv"),
+    );
+
+    if writefile(csv.join("\n").as_bytes(), &csv_path)?.is_written() {
+        info!("Wrote {} ({} samples)", csv_path.display(), series.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! SHA-256 checksums for source media and FIT-files, via the `sha2` crate,
+//! so that generated ELAN-files can be verified against the original
+//! recordings. Files are read in chunks, since clips may be several GB.
+
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use sha2::Digest;
+
+/// Incremental SHA-256.
+#[derive(Debug, Clone, Default)]
+pub struct Sha256(sha2::Sha256);
+
+impl Sha256 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Returns digest as lower case hex string.
+    pub fn finalize(self) -> String {
+        self.0.finalize().iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+/// Returns SHA-256 for file at `path` as lower case hex string.
+pub fn sha256(path: &Path) -> std::io::Result<String> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        match reader.read(&mut buf)? {
+            0 => break,
+            n => hasher.update(&buf[..n]),
+        }
+    }
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test input with `len` bytes, `0, 1, ..., 250, 0, 1, ...`.
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn digest(data: &[u8], chunk_size: usize) -> String {
+        let mut hasher = Sha256::new();
+        for chunk in data.chunks(chunk_size) {
+            hasher.update(chunk);
+        }
+        hasher.finalize()
+    }
+
+    #[test]
+    fn known_answers() {
+        // Lengths around the padding boundaries (55/56 and 119/120 bytes),
+        // expected values from Python's hashlib
+        let expected = [
+            (
+                0,
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                55,
+                "463eb28e72f82e0a96c0a4cc53690c571281131f672aa229e0d45ae59b598b59",
+            ),
+            (
+                56,
+                "da2ae4d6b36748f2a318f23e7ab1dfdf45acdc9d049bd80e59de82a60895f562",
+            ),
+            (
+                64,
+                "fdeab9acf3710362bd2658cdc9a29e8f9c757fcf9811603a8c447cd1d9151108",
+            ),
+            (
+                119,
+                "da18797ed7c3a777f0847f429724a2d8cd5138e6ed2895c3fa1a6d39d18f7ec6",
+            ),
+            (
+                120,
+                "f52b23db1fbb6ded89ef42a23ce0c8922c45f25c50b568a93bf1c075420bbb7c",
+            ),
+        ];
+        for (len, hex) in expected {
+            assert_eq!(digest(&data(len), 64), hex, "{len} bytes");
+            assert_eq!(digest(&data(len), 7), hex, "{len} bytes, 7 byte chunks");
+        }
+        assert_eq!(
+            digest(b"abc", 64),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn multiple_megabytes() {
+        let hex = "f578a61853ca2f4272dba551bd868420e22302fbb6d6dfc0cc80da1d2c7b779f";
+        let data = data(3 * 1024 * 1024 + 7);
+        assert_eq!(digest(&data, 1024 * 1024), hex);
+        assert_eq!(digest(&data, 1000), hex);
+
+        let path = std::env::temp_dir().join(format!("geoelan_sha256_{}", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let result = sha256(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap(), hex);
+    }
+}
@@ -0,0 +1,260 @@
+//! Audio related GPMF streams, logged at 10 Hz by Hero8 and later:
+//! wind processing (`WNDM`), microphone wet detection (`MWET`),
+//! and audio levels (`AALP`, Hero9 and later),
+//! see <https://github.com/gopro/gpmf-parser>.
+//!
+//! Values are decoded from the raw GPMF track via `complex::decode()`.
+//! Each value is timestamped by distributing the values
+//! for a GPMF sample evenly over the sample's duration.
+
+use super::{
+    complex::{self, Value},
+    GpmfSample,
+};
+
+/// Audio related GPMF stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioStream {
+    /// Wind processing (`WNDM`).
+    Wind,
+    /// Microphone wet detection (`MWET`).
+    Wet,
+    /// Audio levels (`AALP`).
+    Level,
+}
+
+impl std::str::FromStr for AudioStream {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "wnd" | "wind" => Ok(Self::Wind),
+            "wet" | "micwet" => Ok(Self::Wet),
+            "aud" | "audiolevel" => Ok(Self::Level),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("(!) Unknown audio stream '{value}'"),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for AudioStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Wind => write!(f, "Wind processing"),
+            Self::Wet => write!(f, "Microphone wet"),
+            Self::Level => write!(f, "Audio level"),
+        }
+    }
+}
+
+impl AudioStream {
+    pub fn fourcc(&self) -> &'static [u8; 4] {
+        match self {
+            Self::Wind => b"WNDM",
+            Self::Wet => b"MWET",
+            Self::Level => b"AALP",
+        }
+    }
+}
+
+/// Wind processing (`WNDM`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wind {
+    /// Seconds relative to start of video.
+    pub time: f64,
+    /// Wind processing is active.
+    pub active: bool,
+    /// Amount of wind processing, 0-100.
+    pub level: u8,
+}
+
+/// Microphone wet detection (`MWET`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MicWet {
+    /// Seconds relative to start of video.
+    pub time: f64,
+    /// Some microphones are wet.
+    pub wet: bool,
+    /// All microphones are wet.
+    pub all_wet: bool,
+    /// Detection confidence, as logged.
+    pub confidence: u8,
+}
+
+/// Audio levels (`AALP`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioLevel {
+    /// Seconds relative to start of video.
+    pub time: f64,
+    /// RMS level in dBFS.
+    pub rms: f64,
+    /// Peak level in dBFS.
+    pub peak: f64,
+}
+
+/// Returns all numerical values for `stream`, one row per value,
+/// together with time in seconds relative to start of video.
+/// `gpmf` and `index` are as returned by `ExtractGpmf::extract_gpmf()`.
+fn decode(
+    gpmf: &[u8],
+    index: &[GpmfSample],
+    stream: AudioStream,
+    fields: usize,
+) -> Result<Vec<(f64, Vec<f64>)>, String> {
+    let fourcc = String::from_utf8_lossy(stream.fourcc());
+    let mut values: Vec<(f64, Vec<f64>)> = Vec::new();
+    for (i, sample) in index.iter().enumerate() {
+        let range = sample.position as usize..(sample.position + sample.size) as usize;
+        let Some(data) = gpmf.get(range) else {
+            return Err(format!("sample {} exceeds GPMF data", i + 1));
+        };
+        let rows = complex::decode(data, stream.fourcc())
+            .map_err(|err| format!("'{fourcc}' in sample {}: {err}", i + 1))?;
+        let count = rows.len();
+        for (j, row) in rows.iter().enumerate() {
+            let row: Vec<f64> = row.iter().filter_map(Value::as_f64).collect();
+            if row.len() < fields {
+                return Err(format!(
+                    "'{fourcc}' in sample {} has {} values, expected {fields}",
+                    i + 1,
+                    row.len()
+                ));
+            }
+            let time = sample.start + sample.duration * j as f64 / count as f64;
+            values.push((time, row));
+        }
+    }
+    Ok(values)
+}
+
+/// Wind processing (`WNDM`), with values as `[active, level]`.
+pub fn wind(gpmf: &[u8], index: &[GpmfSample]) -> Result<Vec<Wind>, String> {
+    Ok(decode(gpmf, index, AudioStream::Wind, 2)?
+        .into_iter()
+        .map(|(time, v)| Wind {
+            time,
+            active: v[0] != 0.0,
+            level: v[1] as u8,
+        })
+        .collect())
+}
+
+/// Microphone wet detection (`MWET`), with values as `[wet, all_wet, confidence]`.
+pub fn mic_wet(gpmf: &[u8], index: &[GpmfSample]) -> Result<Vec<MicWet>, String> {
+    Ok(decode(gpmf, index, AudioStream::Wet, 3)?
+        .into_iter()
+        .map(|(time, v)| MicWet {
+            time,
+            wet: v[0] != 0.0,
+            all_wet: v[1] != 0.0,
+            confidence: v[2] as u8,
+        })
+        .collect())
+}
+
+/// Audio levels (`AALP`), with values as `[rms, peak]`.
+pub fn audio_level(gpmf: &[u8], index: &[GpmfSample]) -> Result<Vec<AudioLevel>, String> {
+    Ok(decode(gpmf, index, AudioStream::Level, 2)?
+        .into_iter()
+        .map(|(time, v)| AudioLevel {
+            time,
+            rms: v[0],
+            peak: v[1],
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// KLV with value padded to 32-bit alignment.
+    fn klv(key: &[u8; 4], value_type: u8, struct_size: u8, repeat: u16, value: &[u8]) -> Vec<u8> {
+        let mut data = key.to_vec();
+        data.extend([value_type, struct_size]);
+        data.extend(repeat.to_be_bytes());
+        data.extend(value);
+        data.resize(8 + value.len().div_ceil(4) * 4, 0);
+        data
+    }
+
+    /// Raw GPMF track with one `DEVC` per sample, each sample 1 second.
+    fn track(samples: &[Vec<u8>]) -> (Vec<u8>, Vec<GpmfSample>) {
+        let mut gpmf = Vec::new();
+        let mut index = Vec::new();
+        for (i, strm) in samples.iter().enumerate() {
+            let strm = klv(b"STRM", 0, 1, strm.len() as u16, strm);
+            let devc = klv(b"DEVC", 0, 1, strm.len() as u16, &strm);
+            index.push(GpmfSample {
+                position: gpmf.len() as u64,
+                mp4_position: 0,
+                size: devc.len() as u64,
+                start: i as f64,
+                duration: 1.0,
+            });
+            gpmf.extend(devc);
+        }
+        (gpmf, index)
+    }
+
+    #[test]
+    fn wind_and_levels() {
+        let (gpmf, index) = track(&[
+            klv(b"WNDM", b'B', 2, 2, &[0, 0, 1, 40]),
+            klv(b"WNDM", b'B', 2, 1, &[1, 100]),
+        ]);
+        assert_eq!(
+            wind(&gpmf, &index).unwrap(),
+            vec![
+                Wind {
+                    time: 0.0,
+                    active: false,
+                    level: 0
+                },
+                Wind {
+                    time: 0.5,
+                    active: true,
+                    level: 40
+                },
+                Wind {
+                    time: 1.0,
+                    active: true,
+                    level: 100
+                },
+            ]
+        );
+        assert!(audio_level(&gpmf, &index).unwrap().is_empty());
+
+        // Signed levels via TYPE definition
+        let mut strm = klv(b"TYPE", b'c', 1, 2, b"bb");
+        strm.extend(klv(b"AALP", b'?', 2, 1, &[(-30_i8) as u8, (-6_i8) as u8]));
+        let (gpmf, index) = track(&[strm]);
+        assert_eq!(
+            audio_level(&gpmf, &index).unwrap(),
+            vec![AudioLevel {
+                time: 0.0,
+                rms: -30.0,
+                peak: -6.0
+            }]
+        );
+    }
+
+    #[test]
+    fn unexpected_structure() {
+        let (gpmf, index) = track(&[klv(b"MWET", b'B', 2, 1, &[1, 0])]);
+        assert!(mic_wet(&gpmf, &index).is_err());
+
+        let (gpmf, index) = track(&[klv(b"MWET", b'B', 3, 1, &[1, 0, 80])]);
+        assert_eq!(
+            mic_wet(&gpmf, &index).unwrap(),
+            vec![MicWet {
+                time: 0.0,
+                wet: true,
+                all_wet: false,
+                confidence: 80
+            }]
+        );
+    }
+}
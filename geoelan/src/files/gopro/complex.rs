@@ -0,0 +1,403 @@
+//! Decode GPMF streams with complex structures, i.e. value type `?`,
+//! where the structure is defined by a preceding `TYPE` key,
+//! e.g. `HUES` and `SROT`, or vendor extensions.
+//!
+//! Values are decoded as is from the raw GPMF track,
+//! without scaling (`SCAL`), see <https://github.com/gopro/gpmf-parser>.
+
+/// A single decoded GPMF value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    /// Strings (`c`), FourCC (`F`), and UTC date time (`U`).
+    Text(String),
+    /// 128-bit identifier (`G`) as hex.
+    Id(String),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Int(n) => write!(f, "{n}"),
+            Self::UInt(n) => write!(f, "{n}"),
+            Self::Float(n) => write!(f, "{n}"),
+            Self::Text(s) | Self::Id(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+impl Value {
+    /// Returns numerical values as `f64`, or `None` for
+    /// strings and identifiers.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(n) => Some(*n as f64),
+            Self::UInt(n) => Some(*n as f64),
+            Self::Float(n) => Some(*n),
+            Self::Text(_) | Self::Id(_) => None,
+        }
+    }
+}
+
+/// Size in bytes for a GPMF value type, or `None` for unknown types.
+fn type_size(value_type: u8) -> Option<usize> {
+    match value_type {
+        b'b' | b'B' | b'c' => Some(1),
+        b's' | b'S' => Some(2),
+        b'f' | b'F' | b'l' | b'L' | b'q' => Some(4),
+        b'd' | b'j' | b'J' | b'Q' => Some(8),
+        b'G' | b'U' => Some(16),
+        _ => None,
+    }
+}
+
+/// Expands a `TYPE` definition, e.g. `f[3]L` to `fffL`.
+fn expand_type(type_def: &str) -> Result<Vec<u8>, String> {
+    let mut types: Vec<u8> = Vec::new();
+    let mut chars = type_def.trim_end_matches('\0').bytes().peekable();
+    while let Some(t) = chars.next() {
+        if chars.peek() != Some(&b'[') {
+            types.push(t);
+            continue;
+        }
+        chars.next();
+        let count: String = chars
+            .by_ref()
+            .take_while(|c| *c != b']')
+            .map(char::from)
+            .collect();
+        let count = count
+            .parse::<usize>()
+            .map_err(|_| format!("invalid array size in TYPE '{type_def}'"))?;
+        types.extend(std::iter::repeat_n(t, count));
+    }
+    match types.iter().find(|t| type_size(**t).is_none()) {
+        Some(t) => Err(format!(
+            "unknown value type '{}' in TYPE '{type_def}'",
+            *t as char
+        )),
+        None => Ok(types),
+    }
+}
+
+/// Decodes a single big-endian value of `value_type`.
+/// `data` must be at least `type_size(value_type)` bytes.
+fn decode_value(value_type: u8, data: &[u8]) -> Value {
+    let be = |n: usize| -> u128 { data[..n].iter().fold(0, |v, b| v << 8 | *b as u128) };
+    match value_type {
+        b'b' => Value::Int(data[0] as i8 as i64),
+        b'B' => Value::UInt(data[0] as u64),
+        b's' => Value::Int(be(2) as u16 as i16 as i64),
+        b'S' => Value::UInt(be(2) as u64),
+        b'l' => Value::Int(be(4) as u32 as i32 as i64),
+        b'L' => Value::UInt(be(4) as u64),
+        b'j' => Value::Int(be(8) as u64 as i64),
+        b'J' => Value::UInt(be(8) as u64),
+        b'f' => Value::Float(f32::from_bits(be(4) as u32) as f64),
+        b'd' => Value::Float(f64::from_bits(be(8) as u64)),
+        // Fixed point Q15.16 and Q31.32
+        b'q' => Value::Float(be(4) as u32 as i32 as f64 / 65536.0),
+        b'Q' => Value::Float(be(8) as u64 as i64 as f64 / 4294967296.0),
+        b'G' => Value::Id(format!("{:032x}", be(16))),
+        // 'c', 'F', 'U'
+        _ => Value::Text(
+            String::from_utf8_lossy(&data[..type_size(value_type).unwrap_or(1)])
+                .trim_end_matches('\0')
+                .to_owned(),
+        ),
+    }
+}
+
+/// Decodes `repeat` structures of `struct_size` bytes each,
+/// with fields as defined by `types`. Consecutive characters (`c`)
+/// are joined into a single string.
+fn decode_structs(
+    types: &[u8],
+    data: &[u8],
+    struct_size: usize,
+    repeat: usize,
+) -> Result<Vec<Vec<Value>>, String> {
+    let size: usize = types.iter().filter_map(|t| type_size(*t)).sum();
+    if size != struct_size {
+        return Err(format!(
+            "TYPE defines {size} bytes, but structure size is {struct_size}"
+        ));
+    }
+    if struct_size * repeat > data.len() {
+        return Err("structure exceeds available data".to_owned());
+    }
+
+    let mut rows: Vec<Vec<Value>> = Vec::new();
+    for chunk in data.chunks_exact(struct_size).take(repeat) {
+        let mut row: Vec<Value> = Vec::new();
+        let mut offset = 0;
+        let mut text: Option<String> = None;
+        for t in types.iter() {
+            let n = type_size(*t).unwrap_or(1);
+            if *t == b'c' {
+                text.get_or_insert_with(String::new)
+                    .push(chunk[offset] as char);
+            } else {
+                if let Some(s) = text.take() {
+                    row.push(Value::Text(s.trim_end_matches('\0').to_owned()));
+                }
+                row.push(decode_value(*t, &chunk[offset..offset + n]));
+            }
+            offset += n;
+        }
+        if let Some(s) = text.take() {
+            row.push(Value::Text(s.trim_end_matches('\0').to_owned()));
+        }
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Decodes all values for `fourcc` in a GPMF sample (one or more `DEVC` blocks),
+/// one row per structure. Complex structures (`?`) are decoded
+/// according to the latest `TYPE` in the same stream,
+/// other value types as a row of values of that type.
+pub fn decode(sample: &[u8], fourcc: &[u8; 4]) -> Result<Vec<Vec<Value>>, String> {
+    let mut rows: Vec<Vec<Value>> = Vec::new();
+    let mut type_def: Option<String> = None;
+    walk(sample, fourcc, &mut type_def, &mut rows)?;
+    Ok(rows)
+}
+
+/// Walks KLVs in `data`, descending into nested KLVs (value type `0`).
+fn walk(
+    data: &[u8],
+    fourcc: &[u8; 4],
+    type_def: &mut Option<String>,
+    rows: &mut Vec<Vec<Value>>,
+) -> Result<(), String> {
+    let mut position = 0;
+    while position + 8 <= data.len() {
+        let header = &data[position..position + 8];
+        // Trailing zero padding
+        if header.iter().all(|b| *b == 0) {
+            break;
+        }
+        let (key, value_type, struct_size) = (&header[..4], header[4], header[5] as usize);
+        let repeat = u16::from_be_bytes([header[6], header[7]]) as usize;
+        let len = struct_size * repeat;
+        let end = position + 8 + len;
+        if end > data.len() {
+            return Err(format!(
+                "'{}' length {len} exceeds available data",
+                String::from_utf8_lossy(key)
+            ));
+        }
+        let value = &data[position + 8..end];
+
+        match (value_type, key) {
+            (0, _) => {
+                // A new stream resets the structure definition
+                if key == b"STRM" {
+                    *type_def = None;
+                }
+                walk(value, fourcc, type_def, rows)?;
+            }
+            (b'c', b"TYPE") => {
+                *type_def = Some(String::from_utf8_lossy(value).to_string());
+            }
+            (b'?', k) if k == fourcc => {
+                let Some(def) = type_def.as_deref() else {
+                    return Err(format!("no TYPE for '{}'", String::from_utf8_lossy(fourcc)));
+                };
+                rows.extend(decode_structs(
+                    &expand_type(def)?,
+                    value,
+                    struct_size,
+                    repeat,
+                )?);
+            }
+            (t, k) if k == fourcc => {
+                let Some(size) = type_size(t).filter(|s| struct_size % s == 0) else {
+                    return Err(format!(
+                        "unsupported value type '{}' for '{}'",
+                        t as char,
+                        String::from_utf8_lossy(fourcc)
+                    ));
+                };
+                let types = vec![t; struct_size / size];
+                rows.extend(decode_structs(&types, value, struct_size, repeat)?);
+            }
+            _ => (),
+        }
+
+        // Values are padded to 32-bit alignment
+        position += 8 + len.div_ceil(4) * 4;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// KLV with value padded to 32-bit alignment.
+    fn klv(key: &[u8; 4], value_type: u8, struct_size: u8, repeat: u16, value: &[u8]) -> Vec<u8> {
+        let mut data = key.to_vec();
+        data.extend([value_type, struct_size]);
+        data.extend(repeat.to_be_bytes());
+        data.extend(value);
+        data.resize(8 + value.len().div_ceil(4) * 4, 0);
+        data
+    }
+
+    #[test]
+    fn expand_arrays() {
+        assert_eq!(expand_type("f[3]L").unwrap(), b"fffL");
+        assert_eq!(expand_type("c[4]B\0").unwrap(), b"ccccB");
+        assert!(expand_type("fX").is_err());
+    }
+
+    #[test]
+    fn complex_structures() {
+        // Two structures: 4 character label, u8, signed Q15.16
+        let mut value = Vec::new();
+        value.extend(b"SKY\0");
+        value.push(3);
+        value.extend((-65536_i32 - 32768).to_be_bytes());
+        value.extend(b"SEA\0");
+        value.push(200);
+        value.extend(131072_i32.to_be_bytes());
+
+        let mut strm = klv(b"TYPE", b'c', 1, 6, b"c[4]Bq");
+        strm.extend(klv(b"HUES", b'?', 9, 2, &value));
+        let strm = klv(b"STRM", 0, 1, strm.len() as u16, &strm);
+        let sample = klv(b"DEVC", 0, 1, strm.len() as u16, &strm);
+
+        let rows = decode(&sample, b"HUES").unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    Value::Text("SKY".into()),
+                    Value::UInt(3),
+                    Value::Float(-1.5)
+                ],
+                vec![
+                    Value::Text("SEA".into()),
+                    Value::UInt(200),
+                    Value::Float(2.0)
+                ],
+            ]
+        );
+    }
+
+    /// Stream with a name, a `TYPE` definition, and complex values for `key`.
+    fn stream(
+        type_def: &[u8],
+        key: &[u8; 4],
+        struct_size: u8,
+        repeat: u16,
+        value: &[u8],
+    ) -> Vec<u8> {
+        // Name is 5 bytes, padded to 8
+        let mut strm = klv(b"STNM", b'c', 1, 5, b"test\0");
+        strm.extend(klv(b"TYPE", b'c', 1, type_def.len() as u16, type_def));
+        strm.extend(klv(key, b'?', struct_size, repeat, value));
+        klv(b"STRM", 0, 1, strm.len() as u16, &strm)
+    }
+
+    #[test]
+    fn type_per_stream() {
+        // Hue and weight, scene FourCC and probability
+        let mut devc = stream(b"BB", b"HUES", 2, 3, &[10, 200, 170, 55, 0, 1]);
+        let mut scen = b"SNOW".to_vec();
+        scen.extend(0.25_f32.to_be_bytes());
+        scen.extend(b"URBA");
+        scen.extend(0.75_f32.to_be_bytes());
+        devc.extend(stream(b"Ff", b"SCEN", 8, 2, &scen));
+        let sample = klv(b"DEVC", 0, 1, devc.len() as u16, &devc);
+
+        assert_eq!(
+            decode(&sample, b"HUES").unwrap(),
+            vec![
+                vec![Value::UInt(10), Value::UInt(200)],
+                vec![Value::UInt(170), Value::UInt(55)],
+                vec![Value::UInt(0), Value::UInt(1)],
+            ]
+        );
+        assert_eq!(
+            decode(&sample, b"SCEN").unwrap(),
+            vec![
+                vec![Value::Text("SNOW".into()), Value::Float(0.25)],
+                vec![Value::Text("URBA".into()), Value::Float(0.75)],
+            ]
+        );
+        assert!(decode(&sample, b"FACE").unwrap().is_empty());
+    }
+
+    #[test]
+    fn type_reset_by_stream() {
+        let mut devc = stream(b"BB", b"HUES", 2, 1, &[10, 200]);
+        let strm = klv(b"SCEN", b'?', 2, 1, &[1, 2]);
+        devc.extend(klv(b"STRM", 0, 1, strm.len() as u16, &strm));
+        let sample = klv(b"DEVC", 0, 1, devc.len() as u16, &devc);
+
+        assert!(decode(&sample, b"HUES").is_ok());
+        assert_eq!(decode(&sample, b"SCEN").unwrap_err(), "no TYPE for 'SCEN'");
+    }
+
+    #[test]
+    fn value_types() {
+        let mut value = vec![(-2_i8) as u8];
+        value.extend((-70000_i32).to_be_bytes());
+        value.extend((-3_i64).to_be_bytes());
+        value.extend(1.5_f64.to_be_bytes());
+        value.extend((9_663_676_416_i64).to_be_bytes()); // 2.25 as Q31.32
+        value.extend(0x0123456789abcdef0011223344556677_u128.to_be_bytes());
+        value.extend(b"250101120000.000");
+        let sample = stream(b"bljdQGU", b"TEST", 61, 1, &value);
+
+        assert_eq!(
+            decode(&sample, b"TEST").unwrap(),
+            vec![vec![
+                Value::Int(-2),
+                Value::Int(-70000),
+                Value::Int(-3),
+                Value::Float(1.5),
+                Value::Float(2.25),
+                Value::Id("0123456789abcdef0011223344556677".into()),
+                Value::Text("250101120000.000".into()),
+            ]]
+        );
+    }
+
+    #[test]
+    fn invalid_data() {
+        // Structure size does not match TYPE
+        let sample = stream(b"f", b"TEST", 8, 1, &[0; 8]);
+        assert!(decode(&sample, b"TEST").is_err());
+
+        // Truncated sample, length exceeds available data
+        let sample = stream(b"BB", b"HUES", 2, 4, &[1; 8]);
+        for len in [sample.len() - 4, 20, 12] {
+            assert!(decode(&sample[..len], b"HUES").is_err(), "{len}");
+        }
+    }
+
+    #[test]
+    fn simple_values() {
+        let value: Vec<u8> = [1_i16, -2, 3, -4]
+            .iter()
+            .flat_map(|n| n.to_be_bytes())
+            .collect();
+        let sample = klv(b"ACCL", b's', 4, 2, &value);
+        let rows = decode(&sample, b"ACCL").unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![Value::Int(1), Value::Int(-2)],
+                vec![Value::Int(3), Value::Int(-4)]
+            ]
+        );
+    }
+}
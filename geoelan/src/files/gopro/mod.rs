@@ -1 +1,126 @@
-//! Currently not in use.
+//! GoPro specific file functions.
+
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use gpmf_rs::GoProFile;
+use mp4iter::{track::Track, Mp4};
+
+pub mod audio;
+pub mod complex;
+pub mod salvage;
+
+/// Name of the MP4 track containing GPMF telemetry.
+pub const GPMF_TRACK: &'static str = "GoPro MET";
+
+/// Index entry for a single GPMF sample
+/// in an extracted, "raw" GPMF stream.
+#[derive(Debug, Clone)]
+pub struct GpmfSample {
+    /// Byte offset in the extracted GPMF stream.
+    pub position: u64,
+    /// Byte offset in the original MP4-file.
+    pub mp4_position: u64,
+    /// Sample size in bytes.
+    pub size: u64,
+    /// Sample start, relative to start of video, in seconds.
+    pub start: f64,
+    /// Sample duration in seconds.
+    pub duration: f64,
+}
+
+/// Raw GPMF track extraction for GoPro MP4-files,
+/// for values and sample positions not exposed by `gpmf_rs`.
+pub trait ExtractGpmf {
+    /// Extracts the raw GPMF track from a GoPro MP4-file,
+    /// by concatenating all samples in the `GoPro MET` track.
+    /// Returns the GPMF stream and an index of sample
+    /// positions and timestamps.
+    ///
+    /// The result can be parsed the same way as a GPMF track
+    /// extracted via FFmpeg, e.g. `geoelan inspect --gpmf GOPR0001.gpmf`.
+    ///
+    /// If `truncated` is `true`, e.g. for damaged files, reading stops
+    /// at the first sample that can not be read in full, rather than
+    /// returning an error. The partial sample is kept, and the remaining
+    /// samples are still listed in the index, so that the missing data
+    /// can be reported via `salvage::salvage()`.
+    fn extract_gpmf(&self, truncated: bool) -> std::io::Result<(Vec<u8>, Vec<GpmfSample>)>;
+}
+
+impl ExtractGpmf for GoProFile {
+    fn extract_gpmf(&self, truncated: bool) -> std::io::Result<(Vec<u8>, Vec<GpmfSample>)> {
+        match &self.mp4 {
+            Some(path) => extract_gpmf(path, truncated),
+            None => {
+                let msg = "(!) No high resolution MP4-file for GoPro clip.";
+                Err(std::io::Error::new(ErrorKind::Other, msg))
+            }
+        }
+    }
+}
+
+/// See `ExtractGpmf::extract_gpmf()`.
+fn extract_gpmf(path: &Path, truncated: bool) -> std::io::Result<(Vec<u8>, Vec<GpmfSample>)> {
+    let mut mp4 = Mp4::new(path)?;
+    let track = Track::from_name(&mut mp4, GPMF_TRACK, false)?;
+
+    // Sample durations are specified in track time scale units,
+    // use total track duration to derive seconds.
+    let units_total: f64 = track.offsets().map(|o| o.duration as f64).sum();
+    let scale = match units_total > 0.0 {
+        true => track.duration().as_seconds_f64() / units_total,
+        false => 0.0,
+    };
+
+    let mut file = File::open(path)?;
+    let mut gpmf: Vec<u8> = Vec::new();
+    let mut index: Vec<GpmfSample> = Vec::new();
+    let mut start = 0.0;
+    let mut short_read = false;
+
+    for offset in track.offsets() {
+        let mut buf: Vec<u8> = Vec::with_capacity(offset.size as usize);
+        if !short_read {
+            let result = file
+                .seek(SeekFrom::Start(offset.position as u64))
+                .and_then(|_| (&mut file).take(offset.size as u64).read_to_end(&mut buf));
+            match (result, truncated) {
+                (Ok(n), _) if n == offset.size as usize => (),
+                (Ok(n), false) => {
+                    let msg = format!(
+                        "(!) Truncated GPMF sample at {} in {}: read {n} of {} bytes",
+                        offset.position,
+                        path.display(),
+                        offset.size
+                    );
+                    return Err(std::io::Error::new(ErrorKind::UnexpectedEof, msg));
+                }
+                (Err(err), false) => return Err(err),
+                (_, true) => short_read = true,
+            }
+        }
+
+        let duration = offset.duration as f64 * scale;
+        index.push(GpmfSample {
+            position: gpmf.len() as u64,
+            mp4_position: offset.position as u64,
+            size: offset.size as u64,
+            start,
+            duration,
+        });
+
+        gpmf.extend(buf);
+        start += duration;
+    }
+
+    if index.is_empty() || (gpmf.is_empty() && !truncated) {
+        let msg = format!("(!) No GPMF data in {}", path.display());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    Ok((gpmf, index))
+}
@@ -0,0 +1,196 @@
+//! Salvage telemetry from a damaged GPMF track.
+//!
+//! GPMF is parsed as a whole, so a single damaged KLV
+//! means the clip is discarded when using `--verify`.
+//! Instead, each `DEVC` block is validated on its own. Damaged
+//! bytes are skipped up to the next `DEVC`, and intact blocks are kept,
+//! so that the result can be parsed as a regular GPMF stream,
+//! e.g. `geoelan inspect --gpmf GH010006_SALVAGED.gpmf`.
+
+use super::GpmfSample;
+
+/// GPMF value types, see <https://github.com/gopro/gpmf-parser>.
+/// `0` denotes nested KLVs.
+const VALUE_TYPES: &[u8] = b"bBcdfFGjJlLqQsSU?\0";
+
+/// Damaged byte range in the GPMF stream.
+#[derive(Debug, Clone)]
+pub struct Damage {
+    /// Sample number, starting on 1.
+    pub sample: usize,
+    /// Byte offset in the extracted GPMF stream.
+    pub position: u64,
+    /// Number of bytes skipped.
+    pub size: u64,
+    /// Sample start, relative to start of video, in seconds.
+    pub start: f64,
+    pub reason: String,
+}
+
+/// Intact GPMF and a report of skipped, damaged data.
+#[derive(Debug, Clone, Default)]
+pub struct Salvaged {
+    /// Intact `DEVC` blocks, in stream order.
+    pub gpmf: Vec<u8>,
+    /// Number of intact `DEVC` blocks.
+    pub devc: usize,
+    pub damage: Vec<Damage>,
+}
+
+/// Validates the KLV structure at the start of `data`, including nested KLVs.
+/// Returns KLV size, including header and padding.
+fn klv_size(data: &[u8]) -> Result<usize, String> {
+    if data.len() < 8 {
+        return Err("truncated KLV header".to_owned());
+    }
+    let fourcc = &data[..4];
+    if !fourcc
+        .iter()
+        .all(|b| b.is_ascii_alphanumeric() || *b == b' ')
+    {
+        return Err(format!("invalid FourCC {:02x?}", fourcc));
+    }
+    let value_type = data[4];
+    if !VALUE_TYPES.contains(&value_type) {
+        return Err(format!(
+            "unknown value type {value_type:#04x} for '{}'",
+            String::from_utf8_lossy(fourcc)
+        ));
+    }
+    let len = data[5] as usize * u16::from_be_bytes([data[6], data[7]]) as usize;
+    // Values are padded to 32-bit alignment
+    let padded = (len + 3) / 4 * 4;
+    if 8 + padded > data.len() {
+        return Err(format!(
+            "'{}' length {len} exceeds available data",
+            String::from_utf8_lossy(fourcc)
+        ));
+    }
+
+    if value_type == 0 {
+        let nested = &data[8..8 + len];
+        let mut position = 0;
+        while position < nested.len() {
+            position += klv_size(&nested[position..])?;
+        }
+    }
+
+    Ok(8 + padded)
+}
+
+/// Returns intact `DEVC` blocks in `gpmf`, skipping damaged data
+/// up to the next `DEVC` in the same sample.
+/// Samples that are missing in full or in part from `gpmf`,
+/// e.g. for truncated files, are reported as damaged.
+pub fn salvage(gpmf: &[u8], index: &[GpmfSample]) -> Salvaged {
+    let mut salvaged = Salvaged::default();
+
+    for (i, sample) in index.iter().enumerate() {
+        let start = (sample.position as usize).min(gpmf.len());
+        let end = (sample.position as usize + sample.size as usize).min(gpmf.len());
+        let data = &gpmf[start..end];
+
+        let missing = sample.size as usize - data.len();
+        if missing > 0 {
+            salvaged.damage.push(Damage {
+                sample: i + 1,
+                position: end as u64,
+                size: missing as u64,
+                start: sample.start,
+                reason: format!(
+                    "truncated sample, {missing} of {} bytes missing",
+                    sample.size
+                ),
+            });
+        }
+
+        let mut position = 0;
+        while position < data.len() {
+            // Trailing zero padding
+            if data[position..].iter().all(|b| *b == 0) {
+                break;
+            }
+            let result = match data[position..].starts_with(b"DEVC") {
+                true => klv_size(&data[position..]),
+                false => Err("expected 'DEVC'".to_owned()),
+            };
+            match result {
+                Ok(size) => {
+                    salvaged
+                        .gpmf
+                        .extend_from_slice(&data[position..position + size]);
+                    salvaged.devc += 1;
+                    position += size;
+                }
+                Err(reason) => {
+                    // Resynchronize on next DEVC
+                    let next = data[position + 1..]
+                        .windows(4)
+                        .position(|w| w == b"DEVC")
+                        .map(|n| position + 1 + n)
+                        .unwrap_or(data.len());
+                    salvaged.damage.push(Damage {
+                        sample: i + 1,
+                        position: (start + position) as u64,
+                        size: (next - position) as u64,
+                        start: sample.start,
+                        reason,
+                    });
+                    position = next;
+                }
+            }
+        }
+    }
+
+    salvaged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `DEVC` containing a single `DVID`, 20 bytes.
+    fn devc() -> Vec<u8> {
+        let mut data = b"DEVC\0\x01\x00\x0c".to_vec();
+        data.extend(b"DVIDL\x04\x00\x01");
+        data.extend(1_u32.to_be_bytes());
+        data
+    }
+
+    fn sample(position: u64, size: u64) -> GpmfSample {
+        GpmfSample {
+            position,
+            mp4_position: position,
+            size,
+            start: 0.0,
+            duration: 1.0,
+        }
+    }
+
+    #[test]
+    fn intact_samples() {
+        let gpmf = [devc(), devc()].concat();
+        let salvaged = salvage(&gpmf, &[sample(0, 20), sample(20, 20)]);
+        assert_eq!(salvaged.devc, 2);
+        assert_eq!(salvaged.gpmf, gpmf);
+        assert!(salvaged.damage.is_empty());
+    }
+
+    #[test]
+    fn truncated_samples_are_damage() {
+        // Second sample cut short, third not read at all
+        let mut gpmf = devc();
+        gpmf.extend(&devc()[..12]);
+        let index = [sample(0, 20), sample(20, 20), sample(32, 20)];
+        let salvaged = salvage(&gpmf, &index);
+        assert_eq!(salvaged.devc, 1);
+        assert_eq!(salvaged.gpmf, devc());
+        let missing: Vec<(usize, u64)> = salvaged
+            .damage
+            .iter()
+            .filter(|d| d.reason.starts_with("truncated"))
+            .map(|d| (d.sample, d.size))
+            .collect();
+        assert_eq!(missing, vec![(2, 8), (3, 20)]);
+    }
+}
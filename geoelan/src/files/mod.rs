@@ -1,34 +1,198 @@
 //! File/path related functions, including filtering data on recording session (Garmin VIRB).
 
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::fs::File;
-use std::io::{stdin, stdout, Write};
+use std::io::{stdin, stdout, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
+use regex::Regex;
 use walkdir::WalkDir;
 
+use crate::i18n::{parse_yes_no, tr, trf, Msg};
+use crate::logging::{info, warn};
+
+pub mod checksum;
 pub mod gopro;
 pub mod virb;
 
+/// Policy for writing files that already exist,
+/// set once via the global `--overwrite` option.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overwrite {
+    /// Overwrite without confirmation.
+    Always,
+    /// Never overwrite, existing files are skipped.
+    Never,
+    /// Ask for confirmation for each file (default).
+    /// Existing files are skipped if stdin is not a terminal,
+    /// e.g. in batch pipelines, rather than waiting for input.
+    Ask,
+}
+
+impl std::str::FromStr for Overwrite {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            "ask" => Ok(Self::Ask),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("(!) Unknown overwrite policy '{value}'"),
+            )),
+        }
+    }
+}
+
+static OVERWRITE: OnceLock<Overwrite> = OnceLock::new();
+
+/// Sets overwrite policy for the rest of the run.
+/// Only the first call has an effect.
+pub fn set_overwrite(policy: Overwrite) {
+    let _ = OVERWRITE.set(policy);
+}
+
+/// Returns current overwrite policy. Defaults to `Overwrite::Ask`.
+pub fn overwrite() -> Overwrite {
+    OVERWRITE.get().copied().unwrap_or(Overwrite::Ask)
+}
+
+static INTERACTIVE: OnceLock<bool> = OnceLock::new();
+
+/// Disables confirmation prompts for the rest of the run,
+/// e.g. for concurrent batch jobs. `Overwrite::Ask` then skips
+/// existing files, as when stdin is not a terminal.
+/// Only the first call has an effect.
+pub fn set_interactive(interactive: bool) {
+    let _ = INTERACTIVE.set(interactive);
+}
+
+/// Returns `true` if the user can be asked for confirmation.
+fn interactive() -> bool {
+    INTERACTIVE.get().copied().unwrap_or(true) && stdin().is_terminal()
+}
+
+static DRYRUN: OnceLock<bool> = OnceLock::new();
+
+/// Enables dry run for the rest of the run, set once via the global
+/// `--dryrun` option. No files are written, instead planned outputs are printed.
+/// Only the first call has an effect.
+pub fn set_dryrun(dryrun: bool) {
+    let _ = DRYRUN.set(dryrun);
+}
+
+/// Returns `true` if no files should be written.
+pub fn dryrun() -> bool {
+    DRYRUN.get().copied().unwrap_or(false)
+}
+
+/// Prints planned output during a dry run, including whether an existing
+/// file would be overwritten or skipped according to the overwrite policy.
+pub fn plan(path: &Path) {
+    let action = match (path.exists(), overwrite()) {
+        (false, _) => "Would write",
+        (true, Overwrite::Always) => "Would overwrite",
+        (true, Overwrite::Never) => "Would skip existing",
+        (true, Overwrite::Ask) if !interactive() => "Would skip existing",
+        (true, Overwrite::Ask) => "Would ask before overwriting",
+    };
+    info!("[dryrun] {action} {}", path.display());
+}
+
+/// Creates directory and any missing parents.
+/// Only prints the planned directory during a dry run.
+pub fn create_dir(path: &Path) -> std::io::Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    if dryrun() {
+        info!("[dryrun] Would create directory {}", path.display());
+        return Ok(());
+    }
+    std::fs::create_dir_all(path)
+}
+
 /// Used for any acknowledgement, e.g. overwrite file.
 pub fn acknowledge(message: &str) -> std::io::Result<bool> {
     loop {
-        print!("(!) {} (y/n): ", message);
+        print!("(!) {} {}: ", message, tr(Msg::YesNo));
         stdout().flush()?;
         let mut overwrite = String::new();
-        let _ = stdin().read_line(&mut overwrite)?;
+        // End of input, e.g. closed stdin, counts as 'no'
+        if stdin().read_line(&mut overwrite)? == 0 {
+            println!();
+            return Ok(false);
+        }
 
-        return match overwrite.to_lowercase().trim() {
-            "y" | "yes" => Ok(true),
-            "n" | "no" => Ok(false),
-            _ => {
-                println!("Enter y/yes or n/no");
+        return match parse_yes_no(overwrite.to_lowercase().trim()) {
+            Some(answer) => Ok(answer),
+            None => {
+                println!("{}", tr(Msg::EnterYesNo));
                 continue;
             }
         };
     }
 }
 
+/// Returns `file://` URL for path, absolute if possible.
+/// Windows paths, e.g. `C:\dir`, become `file:///C:/dir`.
+pub fn file_url(path: &Path) -> String {
+    let path = path.canonicalize().unwrap_or(path.to_path_buf());
+    let path = path.to_string_lossy().replace('\\', "/");
+    // canonicalize() returns verbatim paths on Windows, e.g. '\\?\C:\dir'
+    let path = path.trim_start_matches("//?/");
+    match path.starts_with('/') {
+        true => format!("file://{path}"),
+        false => format!("file:///{path}"),
+    }
+}
+
+/// Returns path for a `file://` URL, e.g. an ELAN-file media URL,
+/// with percent-encoded characters decoded.
+/// Windows URLs, e.g. `file:///C:/dir`, become `C:/dir`.
+/// Values without the scheme are treated as paths.
+pub fn path_from_url(url: &str) -> PathBuf {
+    let encoded = url.strip_prefix("file://").unwrap_or(url).as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let hex = encoded
+            .get(i + 1..i + 3)
+            .filter(|h| h.iter().all(u8::is_ascii_hexdigit));
+        match (encoded[i], hex) {
+            // safe unwrap: two ascii hex digits
+            (b'%', Some(h)) => {
+                decoded.push(u8::from_str_radix(std::str::from_utf8(h).unwrap(), 16).unwrap());
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    let path = String::from_utf8_lossy(&decoded);
+    let is_drive = |p: &str| {
+        let b = p.as_bytes();
+        b.len() > 2 && b[0] == b'/' && b[1].is_ascii_alphabetic() && b[2] == b':'
+    };
+    match is_drive(&path) {
+        true => PathBuf::from(&path[1..]),
+        false => PathBuf::from(path.as_ref()),
+    }
+}
+
+/// Quotes a CSV field if required (RFC 4180).
+pub fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_owned(),
+    }
+}
+
 /// Check if `path` has file extension `ext`.
 pub fn has_extension(path: &Path, ext: &str) -> bool {
     let inpathext = path.extension().map(|o| o.to_ascii_lowercase());
@@ -40,20 +204,116 @@ pub fn has_extension_any(path: &Path, exts: &[&str]) -> bool {
     exts.iter().any(|ext| has_extension(path, ext))
 }
 
-/// Write file with user confirmation if path exists.
-pub fn writefile(content: &[u8], path: &Path) -> std::io::Result<bool> {
-    let write = if path.exists() {
-        acknowledge(&format!("{} already exists. Overwrite?", path.display()))?
-    } else {
-        true
-    };
+/// Result of [`writefile`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WriteStatus {
+    /// File was written.
+    Written,
+    /// Existing file was kept, according to the overwrite policy
+    /// or because the user declined.
+    Skipped,
+    /// Dry run, the planned output was printed instead.
+    Planned,
+}
 
-    if write {
-        let mut outfile = File::create(&path)?;
-        outfile.write_all(content)?;
+impl WriteStatus {
+    /// Returns `true` if the file was written.
+    pub fn is_written(&self) -> bool {
+        *self == Self::Written
     }
+}
 
-    Ok(write)
+/// Returns `true` if `path` does not exist, or if the overwrite policy
+/// allows overwriting it, which by default means user confirmation.
+/// For files written by other means than `writefile()`, e.g. via FFmpeg.
+pub fn may_write(path: &Path) -> std::io::Result<bool> {
+    match (path.exists(), overwrite()) {
+        (false, _) | (true, Overwrite::Always) => Ok(true),
+        (true, Overwrite::Never) => {
+            info!("{}", trf(Msg::SkipExisting, &path.display().to_string()));
+            Ok(false)
+        }
+        (true, Overwrite::Ask) if !interactive() => {
+            let msg = trf(Msg::SkipExistingNonInteractive, &path.display().to_string());
+            info!("{msg}");
+            Ok(false)
+        }
+        (true, Overwrite::Ask) => {
+            acknowledge(&trf(Msg::OverwriteFile, &path.display().to_string()))
+        }
+    }
+}
+
+/// Write file if path does not exist. Otherwise
+/// the overwrite policy decides, which by default means user confirmation.
+/// During a dry run, nothing is written and `WriteStatus::Planned` is returned.
+pub fn writefile(content: &[u8], path: &Path) -> std::io::Result<WriteStatus> {
+    if dryrun() {
+        plan(path);
+        return Ok(WriteStatus::Planned);
+    }
+
+    if !may_write(path)? {
+        return Ok(WriteStatus::Skipped);
+    }
+
+    write_atomic(content, path)?;
+
+    Ok(WriteStatus::Written)
+}
+
+/// As `writefile`, but streams the content from `reader`,
+/// for content too large to hold in memory, e.g. MP4 atoms.
+pub fn writefile_from(reader: &mut impl Read, path: &Path) -> std::io::Result<WriteStatus> {
+    if dryrun() {
+        plan(path);
+        return Ok(WriteStatus::Planned);
+    }
+
+    if !may_write(path)? {
+        return Ok(WriteStatus::Skipped);
+    }
+
+    write_atomic_from(reader, path)?;
+
+    Ok(WriteStatus::Written)
+}
+
+/// Write file without user confirmation, via a temporary file
+/// in the same directory that is renamed to `path` once all
+/// data has been written. Ensures that an interrupted or failed write
+/// never leaves a truncated file at `path`.
+/// The temporary file is removed on failure.
+pub fn write_atomic(content: &[u8], path: &Path) -> std::io::Result<()> {
+    write_atomic_from(&mut &content[..], path)
+}
+
+/// As `write_atomic`, but streams the content from `reader`.
+pub fn write_atomic_from(reader: &mut impl Read, path: &Path) -> std::io::Result<()> {
+    let tmp_path = temp_path(path);
+
+    let result = File::create(&tmp_path)
+        .and_then(|mut tmp_file| {
+            std::io::copy(reader, &mut tmp_file)?;
+            tmp_file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&tmp_path, path));
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+/// Returns path for temporary file used while writing `path`,
+/// e.g. `dir/.GH010006.eaf.tmp` for `dir/GH010006.eaf`.
+fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{file_name}.tmp"))
 }
 
 /// Adds pre/suffix, to existing file stem or changes extension of path and returns the new path.
@@ -83,9 +343,402 @@ pub fn affix_file_name(
     new_path
 }
 
+/// Renders output file name from a user specified template,
+/// e.g. `{stem}_{session}_{date}_{shape}`, and returns the new path.
+/// Falls back on `affix_file_name()` with `suffix` if `template` is `None`.
+///
+/// `{stem}` is the file stem of `path`, and `{type}` is `suffix`
+/// without leading underscores, e.g. `GPS` for `_GPS`.
+/// If `template` contains no `{type}`, `suffix` is appended instead,
+/// so that different outputs for the same input never share a name.
+/// Remaining placeholders are set from `values` as `(placeholder, value)`.
+/// Placeholders without a value are removed, together with
+/// the template separators (`_`, `-`, `.`, space) that join them
+/// to the rest of the name. Placeholder values are never changed.
+pub fn template_file_name(
+    path: &Path,
+    template: Option<&str>,
+    values: &[(&str, String)],
+    suffix: Option<&str>,
+    extension: Option<&str>,
+) -> PathBuf {
+    let Some(template) = template else {
+        return affix_file_name(path, None, suffix, extension);
+    };
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let value = |placeholder: &str| -> String {
+        match placeholder {
+            "stem" => stem.to_owned(),
+            "type" => suffix.unwrap_or("").trim_start_matches('_').to_owned(),
+            p => values
+                .iter()
+                .find(|(name, _)| *name == p)
+                .map(|(_, v)| v.to_owned())
+                .unwrap_or_default(),
+        }
+    };
+    let is_separator = |c: char| "_-. ".contains(c);
+
+    let placeholder = Regex::new(r"\{([a-z]+)\}").expect("Invalid regex");
+    let mut name = String::new();
+    // Skip separators following an empty placeholder at the start
+    let mut skip_separators = false;
+    let mut last = 0;
+    for caps in placeholder.captures_iter(template) {
+        let (Some(full), Some(key)) = (caps.get(0), caps.get(1)) else {
+            continue;
+        };
+        let mut literal = &template[last..full.start()];
+        if skip_separators {
+            literal = literal.trim_start_matches(is_separator);
+        }
+        name.push_str(literal);
+        // Length of `name` without the separators joining the placeholder,
+        // which are removed if the placeholder is empty
+        let joined = name.len() - (literal.len() - literal.trim_end_matches(is_separator).len());
+        last = full.end();
+
+        let value = value(key.as_str());
+        match value.is_empty() {
+            true => {
+                skip_separators = joined == 0;
+                name.truncate(joined);
+            }
+            false => {
+                skip_separators = false;
+                name.push_str(&value);
+            }
+        }
+    }
+    let mut literal = &template[last..];
+    if skip_separators {
+        literal = literal.trim_start_matches(is_separator);
+    }
+    name.push_str(literal);
+    if name.is_empty() {
+        name = stem;
+    }
+    if !template.contains("{type}") {
+        name.push_str(suffix.unwrap_or(""));
+    }
+
+    let new_path = path.with_file_name(name);
+    match (extension, path.extension()) {
+        (Some(ext), _) => new_path.with_extension(ext),
+        (None, Some(ext)) => new_path.with_extension(ext),
+        _ => new_path,
+    }
+}
+
+/// Reads newline-separated file paths from `source`,
+/// or from stdin if `source` is `-`, e.g. output from `find`.
+/// Empty lines and lines starting with `#` are ignored.
+/// Paths that do not exist are ignored with a warning.
+///
+/// Returns canonical paths.
+pub fn paths_from_list(source: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let text = match source.to_str() {
+        Some("-") => std::io::read_to_string(stdin())?,
+        _ => std::fs::read_to_string(source)?,
+    };
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for line in text.lines().map(|l| l.trim()) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match Path::new(line).canonicalize() {
+            Ok(p) => paths.push(p),
+            Err(_) => warn!("(!) Ignoring '{line}': file does not exist"),
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Returns the unique parent directories of `paths`, sorted.
+fn unique_parents(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = paths
+        .iter()
+        .filter_map(|p| p.parent().map(|d| d.to_path_buf()))
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+/// Returns the parent directories of `paths`, excluding directories
+/// nested in another returned directory, since these are searched recursively.
+pub fn parent_dirs(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut top: Vec<PathBuf> = Vec::new();
+    for dir in unique_parents(paths).into_iter() {
+        if !top.iter().any(|t| dir.starts_with(t)) {
+            top.push(dir);
+        }
+    }
+    top
+}
+
+/// Options for recursive file discovery.
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// Follow symbolic links. Link loops are detected and skipped.
+    pub follow_symlinks: bool,
+    /// Files and directories to skip, as glob patterns
+    /// and corresponding regular expressions.
+    /// Patterns containing `/` are matched against the full path,
+    /// other patterns against the file or directory name.
+    pub exclude: Vec<(String, Regex)>,
+    /// Maximum directory depth, where `1` only includes
+    /// files in the start directory.
+    pub max_depth: Option<usize>,
+}
+
+impl WalkOptions {
+    /// Reads `--follow-symlinks`, `--exclude`, and `--max-depth`,
+    /// if defined for the sub-command.
+    pub fn from_args(args: &clap::ArgMatches) -> std::io::Result<Self> {
+        let exclude = match args.try_get_many::<String>("exclude") {
+            Ok(Some(globs)) => globs
+                .map(|g| glob_to_regex(g).map(|rx| (g.to_owned(), rx)))
+                .collect::<std::io::Result<Vec<_>>>()?,
+            _ => Vec::new(),
+        };
+        Ok(Self {
+            follow_symlinks: matches!(args.try_get_one::<bool>("follow-symlinks"), Ok(Some(true))),
+            exclude,
+            max_depth: args
+                .try_get_one::<u64>("max-depth")
+                .ok()
+                .flatten()
+                .map(|d| *d as usize),
+        })
+    }
+
+    /// Returns `true` if any option differs from the default walk.
+    pub fn is_set(&self) -> bool {
+        self.follow_symlinks || !self.exclude.is_empty() || self.max_depth.is_some()
+    }
+
+    /// Returns `true` if `path` matches an exclude pattern.
+    /// Directories are matched with a trailing `/`, so that
+    /// e.g. `**/backup/**` excludes the directory `backup` itself.
+    fn excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut full = path.to_string_lossy().replace('\\', "/");
+        if is_dir && !full.ends_with('/') {
+            full.push('/');
+        }
+        self.exclude
+            .iter()
+            .any(|(glob, rx)| match glob.contains('/') {
+                true => rx.is_match(&full),
+                false => rx.is_match(&name),
+            })
+    }
+}
+
+/// Converts glob pattern to an anchored regular expression.
+/// `**` matches across directories, `*` and `?` within a path component.
+pub fn glob_to_regex(glob: &str) -> std::io::Result<Regex> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                pattern.push_str(".*");
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_err(|err| {
+        let msg = format!("(!) Invalid exclude pattern '{glob}': {err}");
+        std::io::Error::new(std::io::ErrorKind::Other, msg)
+    })
+}
+
+/// Directories to search for clips in, and the listed files if any.
+///
+/// Clip discovery in `gpmf-rs` and `fit-rs` is recursive, so listed files
+/// are linked into a temporary directory, one sub-directory per
+/// parent directory, which is searched instead. This way only the listed files
+/// are parsed, not everything next to or below them. The temporary directory
+/// is removed on drop.
+#[derive(Debug, Default)]
+pub struct SearchDirs {
+    /// Directories to search.
+    pub dirs: Vec<PathBuf>,
+    /// Listed files (canonical paths), if any.
+    pub files: Option<Vec<PathBuf>>,
+    /// Link path -> listed file.
+    links: HashMap<PathBuf, PathBuf>,
+    /// Temporary directory with links.
+    staging: Option<PathBuf>,
+}
+
+impl SearchDirs {
+    /// Searches `dir` recursively.
+    fn dir(dir: &Path) -> std::io::Result<Self> {
+        Ok(Self {
+            dirs: vec![dir.canonicalize()?],
+            ..Self::default()
+        })
+    }
+
+    /// Searches `files` only.
+    fn files(files: Vec<PathBuf>) -> Self {
+        let staging = std::env::temp_dir().join(format!("geoelan-files-{}", std::process::id()));
+        let mut search = Self {
+            staging: Some(staging.to_owned()),
+            ..Self::default()
+        };
+        for (i, dir) in unique_parents(&files).iter().enumerate() {
+            let link_dir = staging.join(i.to_string());
+            if let Err(err) = std::fs::create_dir_all(&link_dir) {
+                warn!("(!) Failed to create '{}': {err}", link_dir.display());
+                return search.fallback(files);
+            }
+            for file in files.iter().filter(|f| f.parent() == Some(dir.as_path())) {
+                // Parent dirs are unique, so file names within a link dir are too
+                let link_path = link_dir.join(file.file_name().unwrap_or_default());
+                if let Err(err) = link(file, &link_path) {
+                    warn!("(!) Failed to link '{}': {err}", file.display());
+                    return search.fallback(files);
+                }
+                search.links.insert(link_path, file.to_owned());
+            }
+            search.dirs.push(link_dir);
+        }
+        search.files = Some(files);
+        search
+    }
+
+    /// Searches the parent directories of `files` if links can not be created.
+    /// Unlisted files in these are parsed, but ignored in the results.
+    fn fallback(mut self, files: Vec<PathBuf>) -> Self {
+        warn!("(!) Searching the parent directories of listed files instead.");
+        self.links.clear();
+        self.dirs = parent_dirs(&files);
+        self.files = Some(files);
+        self
+    }
+
+    /// Returns the listed file for a path found in a search directory.
+    /// Paths outside the temporary link directory are returned as is.
+    pub fn original(&self, path: &Path) -> PathBuf {
+        match self.links.get(path) {
+            Some(p) => p.to_owned(),
+            None => path.to_owned(),
+        }
+    }
+
+    /// Returns `true` if no files are listed, or if `path` is a listed file.
+    pub fn is_listed(&self, path: &Path) -> bool {
+        match &self.files {
+            Some(files) => files.contains(&self.original(path)),
+            None => true,
+        }
+    }
+}
+
+impl Drop for SearchDirs {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.staging {
+            // Only removes the links, not the files linked to
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn link(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+/// Symbolic links require developer mode or elevated privileges on Windows,
+/// hard links only work on the same volume.
+#[cfg(windows)]
+fn link(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(original, link)
+        .or_else(|_| std::fs::hard_link(original, link))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn link(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::fs::hard_link(original, link)
+}
+
+/// Returns directories to search for clips in, and the listed files if any.
+/// Uses `--files-from` if set, otherwise `--indir`.
+///
+/// If `--follow-symlinks`, `--exclude`, or `--max-depth` is set,
+/// camera files in `--indir` are listed as for `--files-from`,
+/// so that only clips found in the walk are parsed.
+pub fn search_dirs(args: &clap::ArgMatches) -> std::io::Result<SearchDirs> {
+    match args.get_one::<PathBuf>("files-from") {
+        Some(source) => {
+            let files = paths_from_list(source)?;
+            if files.is_empty() {
+                let msg = format!("(!) No existing files listed in '{}'", source.display());
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
+            }
+            Ok(SearchDirs::files(files))
+        }
+        None => match args.get_one::<PathBuf>("input-directory") {
+            Some(indir) => {
+                let options = WalkOptions::from_args(args)?;
+                if !options.is_set() {
+                    return SearchDirs::dir(indir);
+                }
+                let files: Vec<PathBuf> = walk(indir, &["mp4", "lrv", "glv", "fit"], &options)
+                    .iter()
+                    .filter_map(|p| p.canonicalize().ok())
+                    .collect();
+                if files.is_empty() {
+                    let msg = format!("(!) No camera files found in '{}'", indir.display());
+                    return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
+                }
+                Ok(SearchDirs::files(files))
+            }
+            None => {
+                let msg = "(!) Either '--indir' or '--files-from' must be set.";
+                Err(std::io::Error::new(std::io::ErrorKind::Other, msg))
+            }
+        },
+    }
+}
+
+/// Returns files in `dir` and sub-directories with any of the extensions
+/// `ext` (lower case, without leading `.`), ignoring case.
 pub fn paths(dir: &Path, ext: &[&str]) -> Vec<PathBuf> {
-    WalkDir::new(dir)
+    walk(dir, ext, &WalkOptions::default())
+}
+
+/// Returns files in `dir` with any of the extensions `ext`
+/// (lower case, without leading `.`), ignoring case.
+/// Symbolic links, excluded paths, and depth according to `options`.
+pub fn walk(dir: &Path, ext: &[&str], options: &WalkOptions) -> Vec<PathBuf> {
+    let mut walker = WalkDir::new(dir).follow_links(options.follow_symlinks);
+    if let Some(depth) = options.max_depth {
+        walker = walker.max_depth(depth);
+    }
+    walker
         .into_iter()
+        // Skip excluded directories entirely, but never the start directory
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !options.excluded(entry.path(), entry.file_type().is_dir())
+        })
         .filter_map(|result| {
             if let Ok(entry) = result {
                 let p = entry.path();
@@ -108,3 +761,120 @@ pub fn paths(dir: &Path, ext: &[&str]) -> Vec<PathBuf> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_keeps_values() {
+        let path = Path::new("/media/GX010026 - copy.MP4");
+        let values = [
+            ("session", String::new()),
+            ("date", "2024-05-01".to_owned()),
+        ];
+        let name = |template: &str| {
+            template_file_name(path, Some(template), &values, Some("_GPS"), Some("kml"))
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        };
+        assert_eq!(
+            name("{stem}_{session}_{date}_{type}"),
+            "GX010026 - copy_2024-05-01_GPS.kml"
+        );
+        assert_eq!(name("{session}_{stem}"), "GX010026 - copy_GPS.kml");
+        assert_eq!(name("{stem}__{session}"), "GX010026 - copy_GPS.kml");
+        assert_eq!(
+            name("{stem}__{date}"),
+            "GX010026 - copy__2024-05-01_GPS.kml"
+        );
+        assert_eq!(name("{unknown}"), "GX010026 - copy_GPS.kml");
+        let path = Path::new("a__b.MP4");
+        assert_eq!(
+            template_file_name(path, Some("{stem}-{session}"), &[], None, None),
+            Path::new("a__b.MP4")
+        );
+    }
+
+    #[test]
+    fn path_from_url_decodes() {
+        assert_eq!(
+            path_from_url("file:///home/user/my%20clip.MP4"),
+            PathBuf::from("/home/user/my clip.MP4")
+        );
+        assert_eq!(
+            path_from_url("file:///C:/Users/R%C3%A9my/clip.MP4"),
+            PathBuf::from("C:/Users/Rémy/clip.MP4")
+        );
+        assert_eq!(path_from_url("clip.MP4"), PathBuf::from("clip.MP4"));
+        assert_eq!(
+            path_from_url("file:///100%.mp4"),
+            PathBuf::from("/100%.mp4")
+        );
+    }
+
+    fn options(globs: &[&str]) -> WalkOptions {
+        WalkOptions {
+            exclude: globs
+                .iter()
+                .map(|g| (g.to_string(), glob_to_regex(g).unwrap()))
+                .collect(),
+            ..WalkOptions::default()
+        }
+    }
+
+    #[test]
+    fn glob_single_star_stays_in_component() {
+        let rx = glob_to_regex("*.LRV").unwrap();
+        assert!(rx.is_match("GH010026.LRV"));
+        assert!(!rx.is_match("dir/GH010026.LRV"));
+        assert!(!rx.is_match("GH010026.LRV.bak"));
+    }
+
+    #[test]
+    fn glob_double_star_crosses_directories() {
+        let rx = glob_to_regex("**/backup/**").unwrap();
+        assert!(rx.is_match("/media/card/backup/GH010026.MP4"));
+        assert!(rx.is_match("/media/card/backup/"));
+        assert!(!rx.is_match("/media/card/backups/GH010026.MP4"));
+    }
+
+    #[test]
+    fn glob_question_mark_and_literals() {
+        let rx = glob_to_regex("GH0?0026.MP4").unwrap();
+        assert!(rx.is_match("GH010026.MP4"));
+        assert!(!rx.is_match("GH0/0026.MP4"));
+        // '.' is literal, not any character
+        assert!(!rx.is_match("GH010026xMP4"));
+        let rx = glob_to_regex("Trash (1)").unwrap();
+        assert!(rx.is_match("Trash (1)"));
+    }
+
+    #[test]
+    fn excluded_directory_matches_path_pattern() {
+        let options = options(&["**/backup/**"]);
+        assert!(options.excluded(Path::new("/media/card/backup"), true));
+        assert!(!options.excluded(Path::new("/media/card/backup"), false));
+        assert!(options.excluded(Path::new("/media/card/backup/GH010026.MP4"), false));
+    }
+
+    #[test]
+    fn excluded_name_pattern() {
+        let options = options(&["Trash*"]);
+        assert!(options.excluded(Path::new("/media/card/Trash-1000"), true));
+        assert!(!options.excluded(Path::new("/media/Trash/GH010026.MP4"), false));
+    }
+
+    #[test]
+    fn walk_prunes_excluded_directory() {
+        let dir = std::env::temp_dir().join(format!("geoelan-walk-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("backup")).unwrap();
+        std::fs::write(dir.join("GH010026.MP4"), b"").unwrap();
+        std::fs::write(dir.join("backup").join("GH010027.MP4"), b"").unwrap();
+        let found = walk(&dir, &["mp4"], &options(&["**/backup/**"]));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, vec![dir.join("GH010026.MP4")]);
+    }
+}
@@ -1,7 +1,90 @@
 //! Filtering FIT data on recording session.
 
-use fit_rs::{Fit, FitError, FitSession, FitSessions};
-use std::io::Write;
+use fit_rs::{DataMessage, Fit, FitError, FitSession, FitSessions};
+use std::{
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use crate::i18n::{tr, Msg};
+
+/// Returns numerical values for the field with definition number `field`
+/// in a FIT data message, e.g. `3` for `heart_rate` in `record/20`.
+/// Scale and offset are applied if the FIT-file has been augmented
+/// (`Fit::augment()`). Returns an empty `Vec` if the field is not present
+/// or not numerical.
+pub fn field_values(record: &DataMessage, field: u8) -> Vec<f64> {
+    record
+        .fields
+        .iter()
+        .find(|f| f.field_definition_number == field)
+        .and_then(|f| f.data.as_vec_f64())
+        .unwrap_or_default()
+}
+
+/// CRC-16 lookup table as specified in the FIT SDK.
+const CRC_TABLE: [u16; 16] = [
+    0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800, 0xB401,
+    0x5000, 0x9C01, 0x8801, 0x4400,
+];
+
+/// Returns FIT CRC-16 for `bytes`.
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0_u16, |mut crc, byte| {
+        // lower nibble
+        let tmp = CRC_TABLE[(crc & 0xF) as usize];
+        crc = (crc >> 4) & 0x0FFF;
+        crc = crc ^ tmp ^ CRC_TABLE[(byte & 0xF) as usize];
+        // upper nibble
+        let tmp = CRC_TABLE[(crc & 0xF) as usize];
+        crc = (crc >> 4) & 0x0FFF;
+        crc ^ tmp ^ CRC_TABLE[((byte >> 4) & 0xF) as usize]
+    })
+}
+
+/// Verifies header CRC (if set) and file CRC for a FIT-file,
+/// including any chained FIT-files.
+/// Returns an error describing the first mismatch.
+pub fn verify_fit(path: &Path) -> std::io::Result<()> {
+    let bytes = std::fs::read(path)?;
+    let err = |msg: String| std::io::Error::new(ErrorKind::Other, msg);
+
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let header_size = bytes[offset] as usize;
+        let header = bytes
+            .get(offset..offset + header_size)
+            .filter(|h| h.len() >= 12 && &h[8..12] == b".FIT")
+            .ok_or_else(|| err(format!("(!) Invalid FIT header at offset {offset}")))?;
+
+        // Header CRC is optional, 0 means not computed
+        if header_size >= 14 {
+            let crc = u16::from_le_bytes([header[12], header[13]]);
+            if crc != 0 && crc != crc16(&header[..12]) {
+                return Err(err(format!("(!) Header CRC mismatch at offset {offset}")));
+            }
+        }
+
+        let data_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let end = offset + header_size + data_size;
+        let crc = bytes
+            .get(end..end + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| {
+                err(format!(
+                    "(!) FIT-file is truncated at offset {}",
+                    bytes.len()
+                ))
+            })?;
+        if crc != crc16(&bytes[offset..end]) {
+            return Err(err(format!("(!) File CRC mismatch at offset {end}")));
+        }
+
+        offset = end + 2;
+    }
+
+    Ok(())
+}
 
 /// Select session from those present in FIT-file
 /// by returning UUID for first clip in session
@@ -32,21 +115,21 @@ pub fn select_session(fit: &Fit) -> Result<FitSession, FitError> {
     println!(".......................{}", ".".repeat(100));
 
     loop {
-        print!("Select session: ");
+        print!("{}", tr(Msg::SelectSession));
         std::io::stdout().flush()?;
         let mut select = String::new();
         std::io::stdin().read_line(&mut select)?;
         let num = match select.trim().parse::<usize>() {
             Ok(n) => n - 1,
             Err(_) => {
-                println!("Not a number");
+                println!("{}", tr(Msg::NotANumber));
                 continue;
             }
         };
         match sessions.sessions().get(num) {
             Some(s) => return Ok(s.to_owned()),
             None => {
-                println!("No such item");
+                println!("{}", tr(Msg::NoSuchItem));
                 continue;
             }
         }
@@ -0,0 +1,171 @@
+//! GPS related functions for GPX and TCX tracks, e.g. from handheld GPS loggers.
+
+use std::{io::ErrorKind, path::Path};
+
+use regex::Regex;
+use time::{Date, Duration, Month, PrimitiveDateTime, Time};
+
+use super::{haversine, EafPoint};
+
+/// Parses an ISO 8601 datetime string, e.g. `2024-05-01T10:00:00.250Z`.
+/// Fractional seconds and UTC offset (`Z`, `+02:00`) are optional.
+/// Single digit hours are accepted, as output by `PrimitiveDateTime::to_string()`
+/// for e.g. KML timestamps.
+/// Datetimes with a UTC offset are converted to UTC.
+pub fn parse_datetime(value: &str) -> Option<PrimitiveDateTime> {
+    let rx = Regex::new(
+        r"^(\d{4})-(\d{2})-(\d{2})[T ](\d{1,2}):(\d{2}):(\d{2})(\.\d+)?(Z|([+-])(\d{2}):?(\d{2}))?$",
+    )
+    .expect("Invalid regex");
+    let caps = rx.captures(value.trim())?;
+    let num = |i: usize| caps.get(i).and_then(|m| m.as_str().parse::<i32>().ok());
+
+    let date =
+        Date::from_calendar_date(num(1)?, Month::try_from(num(2)? as u8).ok()?, num(3)? as u8)
+            .ok()?;
+    let time = Time::from_hms(num(4)? as u8, num(5)? as u8, num(6)? as u8).ok()?;
+    let mut datetime = PrimitiveDateTime::new(date, time);
+
+    if let Some(frac) = caps.get(7) {
+        let secs = frac.as_str().parse::<f64>().ok()?;
+        datetime += Duration::seconds_f64(secs);
+    }
+
+    if let (Some(sign), Some(hrs), Some(min)) = (caps.get(9), num(10), num(11)) {
+        let offset = Duration::hours(hrs as i64) + Duration::minutes(min as i64);
+        match sign.as_str() {
+            "+" => datetime -= offset,
+            _ => datetime += offset,
+        }
+    }
+
+    Some(datetime)
+}
+
+/// Returns text content for the first element `tag` in `xml`.
+fn element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{tag}>"))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{tag}>"))?;
+    Some(xml[start..end].trim())
+}
+
+/// Returns value for attribute `name` in `attributes`.
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let start = attributes.find(&format!("{name}=\""))? + name.len() + 2;
+    let end = start + attributes[start..].find('"')?;
+    Some(&attributes[start..end])
+}
+
+/// Parses track points in a GPX-file.
+fn points_gpx(xml: &str) -> Vec<EafPoint> {
+    let rx = Regex::new(r"(?s)<trkpt\b([^>]*)>(.*?)</trkpt>").expect("Invalid regex");
+    rx.captures_iter(xml)
+        .filter_map(|caps| {
+            let (attrs, content) = (caps.get(1)?.as_str(), caps.get(2)?.as_str());
+            Some(EafPoint {
+                latitude: attribute(attrs, "lat")?.parse().ok()?,
+                longitude: attribute(attrs, "lon")?.parse().ok()?,
+                altitude: element(content, "ele")
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or_default(),
+                datetime: Some(parse_datetime(element(content, "time")?)?),
+                ..EafPoint::default()
+            })
+        })
+        .collect()
+}
+
+/// Parses track points in a TCX-file.
+/// Track points without a position are ignored.
+fn points_tcx(xml: &str) -> Vec<EafPoint> {
+    let rx = Regex::new(r"(?s)<Trackpoint>(.*?)</Trackpoint>").expect("Invalid regex");
+    rx.captures_iter(xml)
+        .filter_map(|caps| {
+            let content = caps.get(1)?.as_str();
+            Some(EafPoint {
+                latitude: element(content, "LatitudeDegrees")?.parse().ok()?,
+                longitude: element(content, "LongitudeDegrees")?.parse().ok()?,
+                altitude: element(content, "AltitudeMeters")
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or_default(),
+                datetime: Some(parse_datetime(element(content, "Time")?)?),
+                ..EafPoint::default()
+            })
+        })
+        .collect()
+}
+
+/// Reads track points from a GPX or TCX file, sorted by datetime.
+/// Only points with a logged time are returned,
+/// since these can not be synchronised with video otherwise.
+/// 2D speed is derived from the distance between consecutive points.
+pub fn points_from_track(path: &Path) -> std::io::Result<Vec<EafPoint>> {
+    let xml = std::fs::read_to_string(path)?;
+
+    let mut points = match path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .as_deref()
+    {
+        Some("gpx") => points_gpx(&xml),
+        Some("tcx") => points_tcx(&xml),
+        _ => {
+            let msg = format!(
+                "(!) Unsupported track format for '{}'. Use GPX or TCX.",
+                path.display()
+            );
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    points.sort_by_key(|p| p.datetime);
+
+    for i in 1..points.len() {
+        let (p1, p2) = (&points[i - 1], &points[i]);
+        if let (Some(t1), Some(t2)) = (p1.datetime, p2.datetime) {
+            let secs = (t2 - t1).as_seconds_f64();
+            if secs > 0.0 {
+                let km = haversine(p1.latitude, p1.longitude, p2.latitude, p2.longitude);
+                points[i].speed2d = km * 1000.0 / secs;
+            }
+        }
+    }
+
+    Ok(points)
+}
+
+/// Formats datetime as ISO 8601 in UTC, e.g. `2024-05-01T10:00:00.250Z`.
+/// Also valid as RFC 3339.
+pub fn format_datetime(datetime: &PrimitiveDateTime) -> String {
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        datetime.year(),
+        datetime.month() as u8,
+        datetime.day(),
+        datetime.hour(),
+        datetime.minute(),
+        datetime.second(),
+        datetime.millisecond()
+    )
+}
+
+/// Returns points as a GPX-file with a single track.
+/// Datetime is assumed to be UTC.
+pub fn to_gpx(points: &[EafPoint]) -> String {
+    let mut output = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gpx version=\"1.1\" creator=\"GeoELAN {}\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n  <trk>\n    <trkseg>\n",
+        crate::VERSION
+    );
+    for point in points.iter() {
+        output.push_str(&format!(
+            "      <trkpt lat=\"{}\" lon=\"{}\">\n        <ele>{}</ele>\n",
+            point.latitude, point.longitude, point.altitude
+        ));
+        if let Some(dt) = point.datetime.as_ref() {
+            output.push_str(&format!("        <time>{}</time>\n", format_datetime(dt)));
+        }
+        output.push_str("      </trkpt>\n");
+    }
+    output.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+    output
+}
@@ -1,6 +1,6 @@
 //! Geometry output types.
 
-use super::{downsample, EafPoint};
+use super::{downsample, point_cluster_representative, EafPoint, PointStrategy};
 
 #[derive(Debug)]
 /// Output geometry types
@@ -38,10 +38,13 @@ pub enum GeoShape {
     /// using the `radius`, `vertices`, and the optional `height` values.
     /// I.e. point selection is exactly the same as for `PointSingle`,
     /// only representation differs.
+    /// If `from_spread` is set, the positional spread of each
+    /// cluster is used as radius instead, with `radius` as fallback.
     Circle {
         radius: f64,
         vertices: u8,
         height: Option<f64>,
+        from_spread: bool,
     },
 }
 
@@ -58,6 +61,16 @@ impl GeoShape {
     }
 }
 
+/// Returns circle radius in meters for `point`:
+/// its positional spread if `from_spread` is set and the point
+/// represents a cluster with a non-zero spread, otherwise `radius`.
+pub fn circle_radius(point: &EafPoint, radius: f64, from_spread: bool) -> f64 {
+    match from_spread {
+        true => point.spread.filter(|s| *s > 0.0).unwrap_or(radius),
+        false => radius,
+    }
+}
+
 /// Returns `true` if the first point in a cluster
 /// has a description and `false` otherwise.
 /// Returns `false` if the cluster is empty.
@@ -72,10 +85,13 @@ fn is_marked(point_cluster: &[EafPoint]) -> bool {
 /// Ensures poly-lines will have at least two points,
 /// and that any point variants will return at least
 /// a single point, regardless of `downsample_factor`.
+/// `strategy` decides how clusters are reduced to a single
+/// point for `PointSingle` and `Circle`.
 pub fn filter_downsample(
     point_clusters: &[Vec<EafPoint>],
     downsample_factor: Option<usize>,
     geoshape: &GeoShape,
+    strategy: &PointStrategy,
 ) -> Vec<Vec<EafPoint>> {
     let sample_factor = downsample_factor.unwrap_or(1);
 
@@ -139,13 +155,13 @@ pub fn filter_downsample(
 
         // Discard marked points/points without description,
         // ignore sample factor,
-        // and downsample each cluster to single point or
+        // and reduce each cluster to single point, using specified strategy, or
         // polygonal circle (with single point becoming its center).
         GeoShape::PointSingle { .. } | GeoShape::Circle { .. } => point_clusters
             .iter()
             .filter_map(|cluster| {
                 if is_marked(cluster) {
-                    Some(downsample(cluster.len(), cluster, None))
+                    Some(vec![point_cluster_representative(cluster, strategy)])
                 } else {
                     None
                 }
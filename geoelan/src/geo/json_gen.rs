@@ -3,7 +3,10 @@
 use geojson::{feature::Id, Feature, FeatureCollection, GeoJson, Geometry, Value};
 use serde_json::{to_value, Map, Number};
 
-use super::{geoshape::GeoShape, EafPoint};
+use super::{
+    geoshape::{circle_radius, GeoShape},
+    EafPoint, GpsFix,
+};
 
 /// Generate GeoJSON Feature ID from numerical value.
 fn geojson_id(id: usize) -> Id {
@@ -61,6 +64,43 @@ fn geojson_properties(points: &[EafPoint]) -> Map<String, serde_json::Value> {
         }
     }
 
+    // Altitude, speed, and GPS quality for single points
+    if let (1, Some(point)) = (points.len(), points.first()) {
+        properties.insert(String::from("altitude"), to_value(point.altitude).unwrap());
+        properties.insert(String::from("speed2d"), to_value(point.speed2d).unwrap());
+        properties.insert(String::from("speed3d"), to_value(point.speed3d).unwrap());
+        if point.fix != GpsFix::Unknown {
+            properties.insert(
+                String::from("gpsfix"),
+                to_value(point.fix.to_string()).unwrap(),
+            );
+        }
+        if let Some(dop) = point.dop {
+            properties.insert(String::from("dop"), to_value(dop).unwrap());
+        }
+        // Uncertainty for points representing a cluster
+        if let Some(spread) = point.spread {
+            properties.insert(String::from("spread_m"), to_value(spread).unwrap());
+        }
+        if let Some(var) = point.speed_variance {
+            properties.insert(String::from("speed2d_variance"), to_value(var).unwrap());
+        }
+    }
+
+    // Heading for single points, with reference (true or magnetic north)
+    if let (1, Some(point)) = (points.len(), points.first()) {
+        if let Some(hdg) = point.heading {
+            properties.insert(String::from("heading"), to_value(hdg).unwrap());
+            properties.insert(
+                String::from("heading_reference"),
+                to_value(point.heading_reference()).unwrap(),
+            );
+            if let Some(decl) = point.declination {
+                properties.insert(String::from("declination"), to_value(decl).unwrap());
+            }
+        }
+    }
+
     properties
 }
 
@@ -145,11 +185,21 @@ pub fn features_from_geoshape(
             vec![geojson_linestring(points, Some(count.unwrap_or(idx)))]
         }
         GeoShape::Circle {
-            radius, vertices, ..
+            radius,
+            vertices,
+            from_spread,
+            ..
         } => points
             .iter()
             .enumerate()
-            .map(|(i, p)| geojson_circle(p, Some(count.unwrap_or(idx + i)), *radius, *vertices))
+            .map(|(i, p)| {
+                geojson_circle(
+                    p,
+                    Some(count.unwrap_or(idx + i)),
+                    circle_radius(p, *radius, *from_spread),
+                    *vertices,
+                )
+            })
             .collect(),
     }
 }
@@ -8,7 +8,7 @@ use std::collections::HashMap;
 use time::PrimitiveDateTime;
 
 use super::{
-    geoshape::GeoShape,
+    geoshape::{circle_radius, GeoShape},
     kml_styles::{KmlLineStyle, KmlPolyStyle, KmlStyle, KmlStyleType, Rgba},
     EafPoint,
 };
@@ -69,6 +69,30 @@ fn kml_styleurl(id: &str) -> Element {
     }
 }
 
+/// Extended data element for placemarks, as `(name, value)`,
+/// e.g. `<ExtendedData><Data name="tier"><value>speech</value></Data></ExtendedData>`.
+pub fn kml_extended_data(data: &[(&str, &str)]) -> Element {
+    Element {
+        name: "ExtendedData".to_owned(),
+        attrs: HashMap::new(),
+        content: None,
+        children: data
+            .iter()
+            .map(|(name, value)| Element {
+                name: "Data".to_owned(),
+                attrs: HashMap::from([("name".to_owned(), name.to_string())]),
+                content: None,
+                children: vec![Element {
+                    name: "value".to_owned(),
+                    attrs: HashMap::new(),
+                    content: Some(value.to_string()),
+                    children: Vec::new(),
+                }],
+            })
+            .collect(),
+    }
+}
+
 /// KML style definition element
 pub fn kml_style(id: &str, geoshape: &GeoShape, color: &Rgba) -> Element {
     let mut style = KmlStyle::default();
@@ -205,6 +229,27 @@ pub fn kml_cdata(point_start: &EafPoint, point_end: Option<&EafPoint>) -> String
     if let Some(end) = t_end {
         content.push(end)
     }
+    if point_end.is_none() {
+        content.push(format!(
+            "<tr><td>Altitude: {:.1} m</td></tr>",
+            point_start.altitude
+        ));
+        content.push(format!(
+            "<tr><td>Speed (2D, 3D): {:.2}, {:.2} m/s</td></tr>",
+            point_start.speed2d, point_start.speed3d
+        ));
+        if let (Some(spread), Some(var)) = (point_start.spread, point_start.speed_variance) {
+            content.push(format!(
+                "<tr><td>Spread: {spread:.1} m, speed variance: {var:.2} (m/s)²</td></tr>"
+            ));
+        }
+    }
+    if let Some(hdg) = point_start.heading {
+        content.push(format!(
+            "<tr><td>Heading: {hdg:.1} ({})</td></tr>",
+            point_start.heading_reference()
+        ))
+    }
 
     content.push("</table>".to_owned());
 
@@ -418,6 +463,7 @@ pub fn placemarks_from_geoshape(
             radius,
             vertices,
             height,
+            from_spread,
         } => {
             points
                 .iter()
@@ -431,7 +477,7 @@ pub fn placemarks_from_geoshape(
                     kml_linearring(
                         point,
                         Some(name.unwrap_or(&format!("{}", idx + i))),
-                        *radius,
+                        circle_radius(point, *radius, *from_spread),
                         *vertices,
                         // *extrude,
                         // false,
@@ -0,0 +1,186 @@
+//! Document level metadata for KML and GeoJSON output,
+//! so that exported files describe their own origin when shared:
+//! camera, recording sessions, date range, distance,
+//! filters applied, and GeoELAN version.
+
+use std::{collections::HashMap, path::Path};
+
+use geojson::GeoJson;
+use kml::{types::Element, Kml, KmlDocument};
+use serde_json::{json, Value};
+use time::PrimitiveDateTime;
+
+use crate::{
+    model::{session_id, CameraModel},
+    VERSION,
+};
+
+use super::{haversine, EafPoint};
+
+/// Document metadata.
+#[derive(Debug, Default, Clone)]
+pub struct DocumentInfo {
+    /// Camera model, e.g. `GoPro HERO11 Black`.
+    pub camera: Option<String>,
+    /// Recording session identifiers, UUID (VIRB) or MUID (GoPro).
+    pub sessions: Vec<String>,
+    /// Hardware independent session identifiers,
+    /// see `model::stable_session_id()`.
+    pub stable_sessions: Vec<String>,
+    pub start: Option<PrimitiveDateTime>,
+    pub end: Option<PrimitiveDateTime>,
+    pub points: usize,
+    /// Distance in kilometers between consecutive points.
+    pub distance: f64,
+    /// Filters and options applied, as `(name, value)`.
+    pub filters: Vec<(String, String)>,
+}
+
+impl DocumentInfo {
+    /// Date range, number of points and distance from `points`.
+    pub fn new(points: &[EafPoint]) -> Self {
+        Self {
+            start: points.iter().find_map(|p| p.datetime),
+            end: points.iter().rev().find_map(|p| p.datetime),
+            points: points.len(),
+            distance: points
+                .windows(2)
+                .map(|w| haversine(w[0].latitude, w[0].longitude, w[1].latitude, w[1].longitude))
+                .sum(),
+            ..Self::default()
+        }
+    }
+
+    /// Date range, number of points and distance for separate tracks,
+    /// e.g. one per recording session. Distance is summed per track,
+    /// so that jumps between the end of one track and the start
+    /// of the next are not included.
+    pub fn from_tracks(tracks: &[&[EafPoint]]) -> Self {
+        let infos: Vec<Self> = tracks.iter().map(|t| Self::new(t)).collect();
+        Self {
+            start: infos.iter().filter_map(|i| i.start).min(),
+            end: infos.iter().filter_map(|i| i.end).max(),
+            points: infos.iter().map(|i| i.points).sum(),
+            distance: infos.iter().map(|i| i.distance).sum(),
+            ..Self::default()
+        }
+    }
+
+    /// Sets camera and recording session from an unedited clip.
+    pub fn with_clip(self, path: &Path) -> Self {
+        let info = match CameraModel::from(path) {
+            CameraModel::Unknown => self,
+            model => self.with_camera(&model.name()),
+        };
+        match session_id(path) {
+            Some(id) => info.with_session(&id),
+            None => info,
+        }
+    }
+
+    pub fn with_camera(self, camera: &str) -> Self {
+        Self {
+            camera: Some(camera.to_owned()),
+            ..self
+        }
+    }
+
+    pub fn with_session(mut self, session: &str) -> Self {
+        if !self.sessions.iter().any(|s| s == session) {
+            self.sessions.push(session.to_owned());
+        }
+        self
+    }
+
+    pub fn with_stable_session(mut self, session: &str) -> Self {
+        if !self.stable_sessions.iter().any(|s| s == session) {
+            self.stable_sessions.push(session.to_owned());
+        }
+        self
+    }
+
+    pub fn with_filter(mut self, name: &str, value: &str) -> Self {
+        self.filters.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "camera": self.camera,
+            "sessions": self.sessions,
+            "stable_sessions": self.stable_sessions,
+            "start": self.start.map(|t| t.to_string()),
+            "end": self.end.map(|t| t.to_string()),
+            "points": self.points,
+            "distance_km": self.distance,
+            "filters": self.filters.iter()
+                .map(|(k, v)| (k.to_owned(), Value::from(v.as_str())))
+                .collect::<serde_json::Map<_, _>>(),
+            "geoelan_version": VERSION,
+        })
+    }
+
+    /// Returns metadata as lines of text, `Name: value`.
+    pub fn to_text(&self) -> String {
+        let unknown = || "Unknown".to_owned();
+        let mut lines = vec![
+            format!("Camera: {}", self.camera.to_owned().unwrap_or_else(unknown)),
+            format!(
+                "Sessions: {}",
+                match self.sessions.is_empty() {
+                    true => unknown(),
+                    false => self.sessions.join(", "),
+                }
+            ),
+            format!(
+                "Stable session IDs: {}",
+                match self.stable_sessions.is_empty() {
+                    true => unknown(),
+                    false => self.stable_sessions.join(", "),
+                }
+            ),
+            format!(
+                "Start: {}",
+                self.start.map(|t| t.to_string()).unwrap_or_else(unknown)
+            ),
+            format!(
+                "End: {}",
+                self.end.map(|t| t.to_string()).unwrap_or_else(unknown)
+            ),
+            format!("Points: {}", self.points),
+            format!("Distance: {:.3} km", self.distance),
+        ];
+        for (name, value) in self.filters.iter() {
+            lines.push(format!("{name}: {value}"));
+        }
+        lines.push(format!("GeoELAN version: {VERSION}"));
+        lines.join("\n")
+    }
+
+    /// Adds metadata as `<description>` to the KML `<Document>`.
+    pub fn add_to_kml(&self, doc: &mut KmlDocument) {
+        let description = Element {
+            name: "description".to_owned(),
+            attrs: HashMap::new(),
+            content: Some(self.to_text()),
+            children: Vec::new(),
+        };
+        for element in doc.elements.iter_mut() {
+            if let Kml::Document { elements, .. } = element {
+                // Document description precedes styles and placemarks
+                elements.insert(0, Kml::Element(description));
+                return;
+            }
+        }
+    }
+
+    /// Adds metadata as top-level `properties` to a GeoJSON feature collection.
+    pub fn add_to_geojson(&self, geojson: &mut GeoJson) {
+        if let GeoJson::FeatureCollection(collection) = geojson {
+            collection
+                .foreign_members
+                .get_or_insert_with(serde_json::Map::new)
+                .insert("properties".to_owned(), self.to_json());
+        }
+    }
+}
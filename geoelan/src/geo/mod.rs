@@ -4,14 +4,18 @@ use time::Duration;
 
 pub mod geo_fit;
 pub mod geo_gpmf;
+pub mod geo_gpx;
 pub mod geoshape;
 pub mod json_gen;
 pub mod kml_gen;
 pub mod kml_styles;
+pub mod metadata;
 pub mod point;
 pub mod point_cluster;
+pub mod wmm;
 
-pub use point::EafPoint;
+pub use metadata::DocumentInfo;
+pub use point::{EafPoint, GpsFix};
 pub use point_cluster::EafPointCluster;
 
 fn average(nums: &[f64]) -> f64 {
@@ -165,9 +169,151 @@ pub fn point_cluster_average(points: &[point::EafPoint]) -> point::EafPoint {
         duration: Some(dur_total), // TODO test! hero11 then virb (remove set_timedelta for virb)
         // duration: points.first().and_then(|p| p.duration), // OLD
         description,
+        // Worst known GPS quality in cluster. Unknown only if no point has a fix,
+        // since Unknown sorts lowest.
+        fix: points
+            .iter()
+            .map(|p| p.fix)
+            .filter(|f| *f != point::GpsFix::Unknown)
+            .min()
+            .unwrap_or_default(),
+        dop: points.iter().filter_map(|p| p.dop).reduce(f64::max),
+        declination: points.first().and_then(|p| p.declination),
+        spread: None,
+        speed_variance: None,
     }
 }
 
+/// Strategy for reducing a point cluster to a single,
+/// representative point.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum PointStrategy {
+    /// Latitude dependent average of all points.
+    /// May land off-path for sinuous tracks.
+    #[default]
+    Average,
+    /// The point with the smallest total distance to
+    /// all other points in the cluster.
+    /// Always lies on the actual track.
+    Medoid,
+    /// First point in cluster.
+    First,
+    /// Point logged closest to the middle of the cluster's timespan.
+    MidTime,
+}
+
+impl std::str::FromStr for PointStrategy {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "average" => Ok(Self::Average),
+            "medoid" => Ok(Self::Medoid),
+            "first" => Ok(Self::First),
+            "midtime" => Ok(Self::MidTime),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("(!) Unknown point strategy '{value}'"),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for PointStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Average => write!(f, "average"),
+            Self::Medoid => write!(f, "medoid"),
+            Self::First => write!(f, "first"),
+            Self::MidTime => write!(f, "midtime"),
+        }
+    }
+}
+
+/// Returns a single, representative point for specified coordinate cluster,
+/// selected according to `strategy`.
+/// For all strategies, timestamp, datetime and description are set
+/// to that of the first point, and duration to the sum of all durations,
+/// so that timestamp + duration = timespan within which all points were logged.
+/// Positional spread and speed variance are set for all strategies.
+pub fn point_cluster_representative(
+    points: &[point::EafPoint],
+    strategy: &PointStrategy,
+) -> point::EafPoint {
+    let index = match strategy {
+        PointStrategy::Average => return with_spread(point_cluster_average(points), points),
+        PointStrategy::First => 0,
+        PointStrategy::Medoid => points
+            .iter()
+            .enumerate()
+            .map(|(i, p1)| {
+                let dist: f64 = points
+                    .iter()
+                    .map(|p2| haversine(p1.latitude, p1.longitude, p2.latitude, p2.longitude))
+                    .sum();
+                (i, dist)
+            })
+            .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+            .map(|(i, _)| i)
+            .unwrap_or(0),
+        PointStrategy::MidTime => {
+            let ts_first = points.first().and_then(|p| p.timestamp);
+            let dur_total: Duration = points.iter().filter_map(|p| p.duration).sum();
+            match ts_first {
+                Some(t0) => {
+                    let mid = t0 + dur_total / 2;
+                    points
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, p)| p.timestamp.map(|t| (i, (t - mid).abs())))
+                        .min_by_key(|(_, delta)| *delta)
+                        .map(|(i, _)| i)
+                        .unwrap_or(points.len() / 2)
+                }
+                // No timestamps, use middle point by index
+                None => points.len() / 2,
+            }
+        }
+    };
+
+    let mut point = match points.get(index) {
+        Some(p) => p.to_owned(),
+        None => return point::EafPoint::default(),
+    };
+
+    point.description = points.first().and_then(|p| p.description.to_owned());
+    point.datetime = points.first().and_then(|p| p.datetime);
+    point.timestamp = points.first().and_then(|p| p.timestamp);
+    point.duration = Some(points.iter().filter_map(|p| p.duration).sum());
+
+    with_spread(point, points)
+}
+
+/// Sets positional spread (root mean square distance in meters from `point`)
+/// and 2D speed variance for a point representing `points`,
+/// so that maps can communicate the uncertainty of reduced clusters.
+fn with_spread(mut point: point::EafPoint, points: &[point::EafPoint]) -> point::EafPoint {
+    if points.is_empty() {
+        return point;
+    }
+    let n = points.len() as f64;
+    let sq_dist: f64 = points
+        .iter()
+        .map(|p| {
+            (haversine(point.latitude, point.longitude, p.latitude, p.longitude) * 1000.0).powi(2)
+        })
+        .sum();
+    let speed_avg = points.iter().map(|p| p.speed2d).sum::<f64>() / n;
+    let speed_var = points
+        .iter()
+        .map(|p| (p.speed2d - speed_avg).powi(2))
+        .sum::<f64>()
+        / n;
+    point.spread = Some((sq_dist / n).sqrt());
+    point.speed_variance = Some(speed_var);
+    point
+}
+
 /// Calculate the great circle distance in kilmeters between two points
 /// on earth's surface (specified in decimal degrees)
 pub fn haversine(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
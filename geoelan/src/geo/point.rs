@@ -5,6 +5,43 @@ use fit_rs::{FitPoint, GpsMetadata};
 use gpmf_rs::GoProPoint;
 use time::{ext::NumericalDuration, format_description, Duration, PrimitiveDateTime};
 
+use super::geo_gpx::parse_datetime;
+
+/// GPS satellite lock, harmonized across devices.
+/// GoPro logs this explicitly, VIRB does not
+/// (only undocumented fields), hence `Unknown`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GpsFix {
+    #[default]
+    Unknown,
+    NoLock,
+    Lock2D,
+    Lock3D,
+}
+
+impl From<u32> for GpsFix {
+    /// Converts GoPro GPS fix value (0, 2, 3).
+    fn from(value: u32) -> Self {
+        match value {
+            0 => Self::NoLock,
+            2 => Self::Lock2D,
+            3 => Self::Lock3D,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for GpsFix {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "Unknown"),
+            Self::NoLock => write!(f, "No lock"),
+            Self::Lock2D => write!(f, "2D lock"),
+            Self::Lock3D => write!(f, "3D lock"),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct EafPoint {
     /// Latitude.
@@ -40,6 +77,27 @@ pub struct EafPoint {
     pub duration: Option<Duration>,
     /// Description.
     pub description: Option<String>,
+    /// GPS satellite lock.
+    /// - FIT: Not supported.
+    /// - GPMF: Supported.
+    pub fix: GpsFix,
+    /// Dilution of precision. Below 5.0 is considered good.
+    /// - FIT: Not supported.
+    /// - GPMF: Supported.
+    pub dop: Option<f64>,
+    /// Magnetic declination in degrees (east positive)
+    /// applied to `heading`, i.e. heading is relative to true north.
+    /// `None` if heading has not been corrected.
+    /// - FIT: Heading is relative to magnetic north.
+    /// - GPMF: No heading.
+    pub declination: Option<f64>,
+    /// Positional spread in meters for a point representing a cluster,
+    /// i.e. root mean square distance from this point to all points in the cluster.
+    /// `None` for logged points.
+    pub spread: Option<f64>,
+    /// 2D speed variance in (m/s)² for a point representing a cluster.
+    /// `None` for logged points.
+    pub speed_variance: Option<f64>,
 }
 
 impl std::fmt::Display for EafPoint {
@@ -55,12 +113,14 @@ impl std::fmt::Display for EafPoint {
   datetime:    {}
   timestamp:   {}s
   duration:    {}s
+  gps fix:     {}
+  dop:         {}
   description: {}",
             self.latitude,
             self.longitude,
             self.altitude,
             self.heading
-                .map(|h| format!("{:.1}", h))
+                .map(|h| format!("{:.1} ({})", h, self.heading_reference()))
                 .as_deref()
                 .unwrap_or("NONE"),
             self.speed2d,
@@ -79,6 +139,11 @@ impl std::fmt::Display for EafPoint {
             //     format!("{}s {}ms", t.num_seconds(), t.num_milliseconds() - t.num_seconds() * 1000))
             //     .as_deref()
             //     .unwrap_or("NONE"),
+            self.fix,
+            self.dop
+                .map(|d| format!("{:.2}", d))
+                .as_deref()
+                .unwrap_or("NONE"),
             self.description.as_deref().unwrap_or("NONE"),
         )
     }
@@ -107,6 +172,11 @@ impl From<&GpsMetadata> for EafPoint {
             // duration: None,
             duration: Some(relative_time), // ????
             description: None,
+            fix: GpsFix::Unknown,
+            dop: None,
+            declination: None,
+            spread: None,
+            speed_variance: None,
         }
     }
 }
@@ -128,6 +198,11 @@ impl From<&FitPoint> for EafPoint {
             timestamp: Some(point.time),
             duration: None,
             description: None,
+            fix: GpsFix::Unknown,
+            dop: None,
+            declination: None,
+            spread: None,
+            speed_variance: None,
         }
     }
 }
@@ -149,6 +224,11 @@ impl From<&GoProPoint> for EafPoint {
             // timestamp: point.time.as_ref().map(|ts| ts.relative), // derived from MP4 atom
             // duration: point.time.as_ref().map(|ts| ts.duration), // derived from MP4 atom
             description: None,
+            fix: GpsFix::from(point.fix),
+            dop: Some(point.dop),
+            declination: None,
+            spread: None,
+            speed_variance: None,
         }
     }
 }
@@ -156,8 +236,12 @@ impl From<&GoProPoint> for EafPoint {
 // impl TryFrom<&Annotation> for Point {
 impl From<&Annotation> for EafPoint {
     /// Convert EAF annotation value to a `Point`.
-    /// May fail if annotation value is not in the form
-    /// `LAT:55.791765;LON:13.501448;ALT:101.6;TIME:2023-01-25 12:15:45.399`.
+    /// Expects key-value pairs in the form generated by GeoELAN for the geotier,
+    /// `LAT:55.791765;LON:13.501448;ALT:101.6;TIME:2023-01-25 12:15:45.399`,
+    /// in any order. Keys are case-insensitive. Optional keys are
+    /// `S2D` (2D speed), `S3D` (3D speed), `HDG` (heading), `FIX` (GPS fix), and `DOP`.
+    /// Older geotiers without e.g. `ALT` or `TIME` are supported.
+    /// Missing or unparsable coordinates default to 0.0.
     ///
     /// If timevalues are not set for annotation boundaries,,
     /// `Point::timstamp` and `Point::duration` will be set to `None`.
@@ -169,43 +253,39 @@ impl From<&Annotation> for EafPoint {
             (Some(t1), Some(t2)) => (Some(t1.milliseconds()), Some((t2 - t1).milliseconds())),
             _ => (None, None),
         };
-        // split LAT:55.791765;LON:13.501448;...
-        let split = value
-            .split(";")
-            // split e.g. LAT:55.791765
-            .filter_map(|spl| spl.split_once(":"))
-            // keep 55.791765
-            .map(|spl| spl.1) // TODO do unwrap_or here to get a "" on None?
-            // [55.791765, 13.501448, 101.6, 2023-01-25 12:15:45.399]
-            .collect::<Vec<_>>();
-
-        // TODO parse time string
-        // TODO better to iterate + enumerate and use idx 0 for lat etc
-        let (lat, lon, alt, time) = match split.len() {
-            4 => (
-                split[0].parse::<f64>().unwrap_or_default(),
-                split[1].parse::<f64>().unwrap_or_default(),
-                split[2].parse::<f64>().unwrap_or_default(),
-                split[3].to_owned(),
-            ),
-            _ => (
-                f64::default(),
-                f64::default(),
-                f64::default(),
-                String::default(),
-            ),
-        };
 
-        Self {
-            latitude: lat,
-            longitude: lon,
-            altitude: alt,
-            heading: None,
-            datetime: None, // TODO parse str into datetime
+        let mut point = Self {
             timestamp,
             duration,
             ..Self::default()
+        };
+
+        // split LAT:55.791765;LON:13.501448;...
+        // into e.g. ("LAT", "55.791765"). Split on first ':' only,
+        // since TIME contains ':'.
+        for (key, val) in value.split(';').filter_map(|spl| spl.split_once(':')) {
+            let val = val.trim();
+            let num = || val.parse::<f64>().ok();
+            match key.trim().to_uppercase().as_str() {
+                "LAT" => point.latitude = num().unwrap_or_default(),
+                "LON" => point.longitude = num().unwrap_or_default(),
+                "ALT" => point.altitude = num().unwrap_or_default(),
+                "TIME" => point.datetime = parse_datetime(val),
+                "S2D" => point.speed2d = num().unwrap_or_default(),
+                "S3D" => point.speed3d = num().unwrap_or_default(),
+                "HDG" => point.heading = num(),
+                "FIX" => {
+                    point.fix = val
+                        .parse::<u32>()
+                        .map(GpsFix::from)
+                        .unwrap_or(GpsFix::Unknown)
+                }
+                "DOP" => point.dop = num(),
+                _ => (),
+            }
         }
+
+        point
     }
 }
 
@@ -226,6 +306,44 @@ impl EafPoint {
         self.datetime.and_then(|dt| dt.format(&format).ok()) // result instead?
     }
 
+    /// Returns `true` if the point meets the minimum GPS fix
+    /// and maximum dilution of precision.
+    /// Points without GPS quality data (e.g. VIRB) always pass.
+    /// Points with precision but an unknown fix (an invalid fix value)
+    /// only pass if no fix is required.
+    pub fn has_quality(&self, min_fix: GpsFix, max_dop: Option<f64>) -> bool {
+        let fix_ok = match self.fix {
+            GpsFix::Unknown => self.dop.is_none() || min_fix <= GpsFix::NoLock,
+            fix => fix >= min_fix,
+        };
+        let dop_ok = match (self.dop, max_dop) {
+            (Some(dop), Some(max)) => dop <= max,
+            _ => true,
+        };
+        fix_ok && dop_ok
+    }
+
+    /// Returns point with heading relative to true north,
+    /// using magnetic declination in degrees (east positive)
+    /// for the recording location and date, e.g. via
+    /// the NOAA World Magnetic Model calculator.
+    pub fn with_declination(&self, declination: f64) -> Self {
+        Self {
+            heading: self.heading.map(|h| (h + declination).rem_euclid(360.0)),
+            declination: Some(declination),
+            ..self.to_owned()
+        }
+    }
+
+    /// Returns heading reference: `true` if corrected
+    /// for magnetic declination, otherwise `magnetic`.
+    pub fn heading_reference(&self) -> &'static str {
+        match self.declination {
+            Some(_) => "true",
+            None => "magnetic",
+        }
+    }
+
     pub fn with_offset_hrs(&self, offset_hrs: i64) -> Self {
         Self {
             datetime: self.datetime.map(|dt| dt + Duration::hours(offset_hrs)),
@@ -272,6 +390,11 @@ impl EafPoint {
             timestamp: Some(t),
             duration: None,
             description: None,
+            fix: GpsFix::Unknown,
+            dop: None,
+            declination: None,
+            spread: None,
+            speed_variance: None,
         }
     }
 
@@ -313,3 +436,92 @@ impl EafPoint {
         circle
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use eaf_rs::Eaf;
+
+    use super::*;
+
+    /// Geotier annotation with `value`, spanning 0-1000 ms.
+    fn annotation(value: &str) -> Annotation {
+        let eaf = Eaf::from_values(&[(value.to_owned(), 0, 1000)], Some("geotier")).unwrap();
+        eaf.tiers[0].annotations[0].to_owned()
+    }
+
+    #[test]
+    fn lat_lon_only() {
+        let point = EafPoint::from(&annotation("LAT:55.791765;LON:13.501448"));
+        assert_eq!(point.latitude, 55.791765);
+        assert_eq!(point.longitude, 13.501448);
+        assert_eq!(point.altitude, 0.0);
+        assert_eq!(point.datetime, None);
+        assert_eq!(point.fix, GpsFix::Unknown);
+    }
+
+    #[test]
+    fn lat_lon_alt() {
+        let point = EafPoint::from(&annotation("LAT:55.791765;LON:13.501448;ALT:101.6"));
+        assert_eq!(point.latitude, 55.791765);
+        assert_eq!(point.longitude, 13.501448);
+        assert_eq!(point.altitude, 101.6);
+        assert_eq!(point.datetime, None);
+    }
+
+    #[test]
+    fn all_values() {
+        let value = "LAT:55.791765;LON:13.501448;ALT:101.6;TIME:2023-01-25 12:15:45.399;\
+            S2D:1.5;S3D:1.7;HDG:270.0;FIX:3;DOP:1.2";
+        let point = EafPoint::from(&annotation(value));
+        assert_eq!(point.latitude, 55.791765);
+        assert_eq!(point.longitude, 13.501448);
+        assert_eq!(point.altitude, 101.6);
+        assert_eq!(point.datetime, parse_datetime("2023-01-25T12:15:45.399"));
+        assert!(point.datetime.is_some());
+        assert_eq!(point.speed2d, 1.5);
+        assert_eq!(point.speed3d, 1.7);
+        assert_eq!(point.heading, Some(270.0));
+        assert_eq!(point.fix, GpsFix::Lock3D);
+        assert_eq!(point.dop, Some(1.2));
+    }
+
+    #[test]
+    fn keys_in_any_case_and_order() {
+        let point = EafPoint::from(&annotation(
+            "alt:101.6; Lon : 13.501448;lat:55.791765;time:2023-01-25 12:15:45.399",
+        ));
+        assert_eq!(point.latitude, 55.791765);
+        assert_eq!(point.longitude, 13.501448);
+        assert_eq!(point.altitude, 101.6);
+        assert_eq!(point.datetime, parse_datetime("2023-01-25T12:15:45.399"));
+    }
+
+    #[test]
+    fn unknown_fix_quality() {
+        let virb = EafPoint::default();
+        assert!(virb.has_quality(GpsFix::Lock3D, Some(5.0)));
+        let invalid = EafPoint {
+            dop: Some(1.0),
+            ..EafPoint::default()
+        };
+        assert!(!invalid.has_quality(GpsFix::Lock2D, None));
+        assert!(invalid.has_quality(GpsFix::NoLock, None));
+    }
+
+    #[test]
+    fn cluster_fix_ignores_unknown() {
+        let points = [GpsFix::Lock3D, GpsFix::Unknown, GpsFix::Lock2D].map(|fix| EafPoint {
+            fix,
+            ..EafPoint::default()
+        });
+        assert_eq!(
+            crate::geo::point_cluster_average(&points).fix,
+            GpsFix::Lock2D
+        );
+        let unknown = [EafPoint::default(), EafPoint::default()];
+        assert_eq!(
+            crate::geo::point_cluster_average(&unknown).fix,
+            GpsFix::Unknown
+        );
+    }
+}
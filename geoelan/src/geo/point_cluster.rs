@@ -9,12 +9,12 @@ use gpmf_rs::GoProPoint;
 use kml::KmlDocument;
 use time::{Duration, PrimitiveDateTime};
 
-use crate::files::writefile;
+use crate::files::{writefile, WriteStatus};
 
 use super::{
     json_gen::{geojson_from_features, geojson_point},
     kml_gen::{kml_from_placemarks, kml_point, kml_to_string},
-    EafPoint,
+    DocumentInfo, EafPoint,
 };
 
 /// Point cluster with optional description.
@@ -127,10 +127,18 @@ impl EafPointCluster {
         kml_to_string(&self.to_kml(indexed))
     }
 
-    /// Write KML to specified path.
-    pub fn write_kml(&self, indexed: bool, path: &Path) -> std::io::Result<bool> {
-        let string = self.to_kml_string(indexed);
-        writefile(&string.as_bytes(), &path)
+    /// Write KML to specified path, optionally with document metadata.
+    pub fn write_kml(
+        &self,
+        indexed: bool,
+        info: Option<&DocumentInfo>,
+        path: &Path,
+    ) -> std::io::Result<WriteStatus> {
+        let mut kml = self.to_kml(indexed);
+        if let Some(i) = info {
+            i.add_to_kml(&mut kml);
+        }
+        writefile(&kml_to_string(&kml).as_bytes(), &path)
     }
 
     /// Generate GeoJson object that can be serialized into a string.
@@ -154,10 +162,18 @@ impl EafPointCluster {
         self.to_json(indexed).to_string()
     }
 
-    /// Write GeoJson to specified path.
-    pub fn write_json(&self, indexed: bool, path: &Path) -> std::io::Result<bool> {
-        let string = self.to_json_string(indexed);
-        writefile(&string.as_bytes(), &path)
+    /// Write GeoJson to specified path, optionally with document metadata.
+    pub fn write_json(
+        &self,
+        indexed: bool,
+        info: Option<&DocumentInfo>,
+        path: &Path,
+    ) -> std::io::Result<WriteStatus> {
+        let mut json = self.to_json(indexed);
+        if let Some(i) = info {
+            i.add_to_geojson(&mut json);
+        }
+        writefile(&json.to_string().as_bytes(), &path)
     }
 
     /// Set time offset in hours.
@@ -228,6 +244,27 @@ impl EafPointCluster {
         self.points = super::downsample(sample_factor, &self.points, min)
     }
 
+    /// Removes points with a date time that is equal to or earlier than
+    /// that of the preceding point, e.g. GoPro chapters that repeat
+    /// the last GPS payload of the previous clip.
+    /// Points without date time are kept.
+    ///
+    /// Returns the number of removed points. Sample durations
+    /// must be re-calculated via `set_timedelta()` if points were removed.
+    pub fn dedup_mut(&mut self) -> usize {
+        let len = self.len();
+        let mut previous: Option<PrimitiveDateTime> = None;
+        self.points.retain(|p| match (previous, p.datetime) {
+            (Some(prev), Some(dt)) if dt <= prev => false,
+            (_, Some(dt)) => {
+                previous = Some(dt);
+                true
+            }
+            _ => true,
+        });
+        len - self.len()
+    }
+
     /// Returns date time for first point.
     pub fn start_datetime(&self) -> Option<&PrimitiveDateTime> {
         self.points.first().and_then(|p| p.datetime.as_ref())
@@ -0,0 +1,300 @@
+//! Magnetic declination from the World Magnetic Model (WMM),
+//! for correcting magnetic heading (VIRB) to true north.
+//!
+//! Coefficients are read from a WMM coefficient file (`WMM.COF`),
+//! as distributed by NOAA:
+//! <https://www.ncei.noaa.gov/products/world-magnetic-model>.
+//! The model is evaluated as described in the WMM technical report,
+//! using Schmidt semi-normalized associated Legendre functions.
+
+use std::{io::ErrorKind, path::Path};
+
+use time::PrimitiveDateTime;
+
+/// WGS84 semi-major axis in km.
+const WGS84_A: f64 = 6378.137;
+/// WGS84 flattening.
+const WGS84_F: f64 = 1.0 / 298.257223563;
+/// Geomagnetic reference radius in km.
+const REFERENCE_RADIUS: f64 = 6371.2;
+/// Years a model is valid for after its epoch.
+const VALID_YEARS: f64 = 5.0;
+
+/// Magnetic model coefficients.
+#[derive(Debug, Clone)]
+pub struct MagneticModel {
+    /// Model name, e.g. `WMM-2025`.
+    pub name: String,
+    /// Model epoch as decimal year, e.g. `2025.0`.
+    pub epoch: f64,
+    /// Maximum degree.
+    degree: usize,
+    /// Gauss coefficients `g`, `h`, and secular variation
+    /// `dg`, `dh` per year, indexed `[n][m]`.
+    g: Vec<Vec<f64>>,
+    h: Vec<Vec<f64>>,
+    dg: Vec<Vec<f64>>,
+    dh: Vec<Vec<f64>>,
+}
+
+impl MagneticModel {
+    /// Reads coefficients from a WMM coefficient file.
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_cof(&text).map_err(|err| {
+            let msg = format!(
+                "(!) Invalid WMM coefficient file '{}': {err}",
+                path.display()
+            );
+            std::io::Error::new(ErrorKind::Other, msg)
+        })
+    }
+
+    /// Parses WMM coefficient file content. The first line holds
+    /// the epoch and model name, following lines `n m g h dg dh`,
+    /// until a line of 9s.
+    pub fn from_cof(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let header: Vec<&str> = lines
+            .next()
+            .ok_or("no header")?
+            .split_whitespace()
+            .collect();
+        let epoch = header
+            .first()
+            .and_then(|e| e.parse::<f64>().ok())
+            .ok_or("no epoch in header")?;
+        let name = header.get(1).unwrap_or(&"WMM").to_string();
+
+        let mut rows: Vec<(usize, usize, [f64; 4])> = Vec::new();
+        for line in lines {
+            if line.trim_start().starts_with("9999") {
+                break;
+            }
+            let values: Vec<&str> = line.split_whitespace().collect();
+            let parse = |i: usize| -> Result<f64, String> {
+                values
+                    .get(i)
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .ok_or(format!("invalid line '{}'", line.trim()))
+            };
+            let (n, m) = (parse(0)? as usize, parse(1)? as usize);
+            if m > n || n == 0 {
+                return Err(format!("invalid degree/order in '{}'", line.trim()));
+            }
+            rows.push((n, m, [parse(2)?, parse(3)?, parse(4)?, parse(5)?]));
+        }
+
+        let degree = rows
+            .iter()
+            .map(|(n, ..)| *n)
+            .max()
+            .ok_or("no coefficients")?;
+        let zeros = vec![vec![0.0; degree + 1]; degree + 1];
+        let mut model = Self {
+            name,
+            epoch,
+            degree,
+            g: zeros.to_owned(),
+            h: zeros.to_owned(),
+            dg: zeros.to_owned(),
+            dh: zeros,
+        };
+        for (n, m, [g, h, dg, dh]) in rows {
+            model.g[n][m] = g;
+            model.h[n][m] = h;
+            model.dg[n][m] = dg;
+            model.dh[n][m] = dh;
+        }
+
+        Ok(model)
+    }
+
+    /// Returns `true` if `year` is within the model's validity period.
+    pub fn is_valid(&self, year: f64) -> bool {
+        (self.epoch..=self.epoch + VALID_YEARS).contains(&year)
+    }
+
+    /// Returns magnetic declination in degrees (east positive) for geodetic
+    /// `latitude` and `longitude` in degrees, `altitude` in meters above
+    /// the WGS84 ellipsoid, at `year` as a decimal year, e.g. `2025.5`.
+    pub fn declination(&self, latitude: f64, longitude: f64, altitude: f64, year: f64) -> f64 {
+        let (x, y, _) = self.field(latitude, longitude, altitude / 1000.0, year);
+        y.atan2(x).to_degrees()
+    }
+
+    /// Returns the geomagnetic field as north, east, and down
+    /// components in nT, for altitude in km.
+    fn field(&self, latitude: f64, longitude: f64, altitude: f64, year: f64) -> (f64, f64, f64) {
+        // Geodetic to geocentric spherical coordinates
+        let lat = latitude.to_radians();
+        let e2 = WGS84_F * (2.0 - WGS84_F);
+        let rc = WGS84_A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        let p = (rc + altitude) * lat.cos();
+        let z = (rc * (1.0 - e2) + altitude) * lat.sin();
+        let r = p.hypot(z);
+        let lat_gc = (z / r).asin();
+        let lon = longitude.to_radians();
+
+        // Legendre functions of cos(colatitude), and their
+        // derivatives with respect to colatitude
+        let (ct, st) = (lat_gc.sin(), lat_gc.cos().max(1e-10));
+        let (pnm, dpnm) = legendre(self.degree, ct, st);
+
+        let dt = year - self.epoch;
+        let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+        for n in 1..=self.degree {
+            let ratio = (REFERENCE_RADIUS / r).powi(n as i32 + 2);
+            for m in 0..=n {
+                let g = self.g[n][m] + dt * self.dg[n][m];
+                let h = self.h[n][m] + dt * self.dh[n][m];
+                let (sin_ml, cos_ml) = (m as f64 * lon).sin_cos();
+                let gh = g * cos_ml + h * sin_ml;
+                x += ratio * gh * dpnm[n][m];
+                y += ratio * m as f64 * (g * sin_ml - h * cos_ml) * pnm[n][m];
+                z -= ratio * (n as f64 + 1.0) * gh * pnm[n][m];
+            }
+        }
+        y /= st;
+
+        // Rotate from geocentric to geodetic
+        let psi = lat_gc - lat;
+        (
+            x * psi.cos() - z * psi.sin(),
+            y,
+            x * psi.sin() + z * psi.cos(),
+        )
+    }
+}
+
+/// Returns Schmidt semi-normalized associated Legendre functions
+/// `P[n][m]` for `ct` = cos(colatitude), `st` = sin(colatitude),
+/// and their derivatives with respect to colatitude.
+fn legendre(degree: usize, ct: f64, st: f64) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+    let mut p = vec![vec![0.0; degree + 1]; degree + 1];
+    let mut dp = vec![vec![0.0; degree + 1]; degree + 1];
+    p[0][0] = 1.0;
+
+    // Gauss normalized
+    for n in 1..=degree {
+        for m in 0..=n {
+            if n == m {
+                p[n][m] = st * p[n - 1][m - 1];
+                dp[n][m] = st * dp[n - 1][m - 1] + ct * p[n - 1][m - 1];
+            } else if n == 1 {
+                p[n][m] = ct * p[n - 1][m];
+                dp[n][m] = ct * dp[n - 1][m] - st * p[n - 1][m];
+            } else {
+                let k =
+                    ((n - 1).pow(2) as f64 - (m * m) as f64) / ((2 * n - 1) * (2 * n - 3)) as f64;
+                p[n][m] = ct * p[n - 1][m] - k * p[n - 2][m];
+                dp[n][m] = ct * dp[n - 1][m] - st * p[n - 1][m] - k * dp[n - 2][m];
+            }
+        }
+    }
+
+    // Gauss to Schmidt semi-normalization
+    let mut schmidt = 1.0;
+    for n in 1..=degree {
+        schmidt *= (2 * n - 1) as f64 / n as f64;
+        let mut s = schmidt;
+        for m in 0..=n {
+            if m > 0 {
+                let delta = if m == 1 { 2.0 } else { 1.0 };
+                s *= ((n - m + 1) as f64 * delta / (n + m) as f64).sqrt();
+            }
+            p[n][m] *= s;
+            dp[n][m] *= s;
+        }
+    }
+
+    (p, dp)
+}
+
+/// Returns `datetime` as a decimal year, e.g. `2025.5` for early July 2025.
+pub fn decimal_year(datetime: &PrimitiveDateTime) -> f64 {
+    let year = datetime.year();
+    let days = time::util::days_in_year(year) as f64;
+    let (h, m, s) = datetime.time().as_hms();
+    let seconds = h as f64 * 3600.0 + m as f64 * 60.0 + s as f64;
+    let day = datetime.ordinal() as f64 - 1.0 + seconds / 86400.0;
+    year as f64 + day / days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tilted dipole: at the equator and prime meridian,
+    /// north = -g10 and east = -h11, so declination = atan2(-h11, -g10).
+    const DIPOLE: &str = "    2025.0            WMM-TEST        11/13/2024
+  1  0  -30000.0       0.0        0.0        0.0
+  1  1   -2000.0    5000.0        0.0        0.0
+999999999999999999999999999999999999999999999999
+";
+
+    #[test]
+    fn dipole_declination() {
+        let model = MagneticModel::from_cof(DIPOLE).unwrap();
+        assert_eq!(model.name, "WMM-TEST");
+        assert_eq!(model.epoch, 2025.0);
+        let expected = (-5000.0_f64).atan2(30000.0).to_degrees();
+        let declination = model.declination(0.0, 0.0, 0.0, 2025.0);
+        assert!((declination - expected).abs() < 1e-9, "{declination}");
+    }
+
+    #[test]
+    fn axial_dipole_points_north() {
+        let model =
+            MagneticModel::from_cof("2025.0 WMM-TEST\n  1  0  -30000.0  0.0  0.0  0.0\n9999\n")
+                .unwrap();
+        for (lat, lon) in [(55.7, 13.2), (-33.9, 151.2), (64.1, -21.9)] {
+            assert!(model.declination(lat, lon, 100.0, 2026.0).abs() < 1e-9);
+        }
+    }
+
+    /// Declination for the test values published with WMM2020
+    /// (NOAA Technical Report, The US/UK World Magnetic Model for 2020-2025),
+    /// as decimal year, height above the WGS84 ellipsoid in km,
+    /// latitude, longitude, and declination rounded to 0.01 degrees.
+    const WMM2020_TEST_VALUES: [(f64, f64, f64, f64, f64); 12] = [
+        (2020.0, 0.0, 80.0, 0.0, -1.28),
+        (2020.0, 0.0, 0.0, 120.0, 0.16),
+        (2020.0, 0.0, -80.0, 240.0, 69.36),
+        (2020.0, 100.0, 80.0, 0.0, -1.70),
+        (2020.0, 100.0, 0.0, 120.0, 0.16),
+        (2020.0, 100.0, -80.0, 240.0, 68.78),
+        (2022.5, 0.0, 80.0, 0.0, 0.01),
+        (2022.5, 0.0, 0.0, 120.0, -0.06),
+        (2022.5, 0.0, -80.0, 240.0, 69.13),
+        (2022.5, 100.0, 80.0, 0.0, -0.41),
+        (2022.5, 100.0, 0.0, 120.0, -0.05),
+        (2022.5, 100.0, -80.0, 240.0, 68.55),
+    ];
+
+    #[test]
+    fn wmm2020_test_values() {
+        let model = MagneticModel::from_cof(include_str!("../../tests/data/WMM2020.COF")).unwrap();
+        assert_eq!(model.name, "WMM-2020");
+        for (year, height, lat, lon, expected) in WMM2020_TEST_VALUES {
+            let declination = model.declination(lat, lon, height * 1000.0, year);
+            assert!(
+                (declination - expected).abs() <= 0.005 + 1e-9,
+                "{year} {height}km {lat},{lon}: {declination} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn secular_variation() {
+        let model = MagneticModel::from_cof(
+            "2025.0 WMM-TEST\n  1  0  -30000.0  0.0  0.0  0.0\n  1  1  0.0  0.0  0.0  1000.0\n9999\n",
+        )
+        .unwrap();
+        // h11 = 2000 two years after epoch
+        let expected = (-2000.0_f64).atan2(30000.0).to_degrees();
+        assert!((model.declination(0.0, 0.0, 0.0, 2027.0) - expected).abs() < 1e-9);
+        assert!(model.is_valid(2027.0));
+        assert!(!model.is_valid(2031.0));
+    }
+}
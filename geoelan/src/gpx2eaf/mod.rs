@@ -0,0 +1,148 @@
+//! Generate an ELAN-file with a geotier from a GPX/TCX track,
+//! for GPS loggers other than GoPro and Garmin VIRB.
+//! The track is synchronised with the video via its start time.
+
+use std::{io::ErrorKind, path::PathBuf};
+
+use crate::{
+    elan::generate_eaf,
+    files::{create_dir, writefile, WriteStatus},
+    geo::{geo_gpx, DocumentInfo, EafPointCluster},
+    logging::{info, warn},
+    media::{atoms, Media},
+};
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let track_path = args.get_one::<PathBuf>("track").unwrap(); // clap: required arg
+    let video_path = args.get_one::<PathBuf>("video").unwrap(); // clap: required arg
+    let audio_path = args.get_one::<PathBuf>("audio").unwrap_or(video_path);
+    let time_offset: isize = *args.get_one("time-offset").unwrap(); // default: 0
+    let downsample_factor = *args.get_one::<u64>("downsample-factor").unwrap() as usize; // default: 1
+    let output_dir = match args.get_one::<PathBuf>("output-directory") {
+        Some(p) => p.to_owned(),
+        None => video_path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default(),
+    };
+
+    // Video start, either specified or MP4 creation time.
+    let start = match args.get_one::<String>("start") {
+        Some(s) => geo_gpx::parse_datetime(s).ok_or_else(|| {
+            let msg = format!("(!) Invalid start time '{s}'. Use e.g. '2024-05-01T10:00:00'.");
+            std::io::Error::new(ErrorKind::Other, msg)
+        })?,
+        None => atoms::creation_time(video_path)?.ok_or_else(|| {
+            let msg = format!(
+                "(!) Failed to determine start time for '{}'. Use '--start'.",
+                video_path.display()
+            );
+            std::io::Error::new(ErrorKind::Other, msg)
+        })?,
+    };
+    let duration = Media::duration(video_path)?;
+    let end = start + duration;
+
+    info!("Video:  {}", video_path.display());
+    info!(
+        "        {start} - {end} ({:.3}s)",
+        duration.as_seconds_f64()
+    );
+
+    // Track points are usually logged in UTC.
+    let points: Vec<_> = geo_gpx::points_from_track(track_path)?
+        .iter()
+        .map(|p| p.with_offset_hrs(time_offset as i64))
+        .collect();
+
+    info!("Track:  {} ({} points)", track_path.display(), points.len());
+    if let (Some(first), Some(last)) = (
+        points.first().and_then(|p| p.datetime),
+        points.last().and_then(|p| p.datetime),
+    ) {
+        info!("        {first} - {last}");
+    }
+
+    // Keep points logged during recording,
+    // timestamped relative to video start.
+    let synced: Vec<_> = points
+        .into_iter()
+        .filter_map(|mut p| {
+            let dt = p.datetime?;
+            if dt < start || dt > end {
+                return None;
+            }
+            p.timestamp = Some(dt - start);
+            Some(p)
+        })
+        .collect();
+
+    if synced.is_empty() {
+        let msg = "(!) No track points logged during recording. Check time zone ('--time-offset') or start time ('--start').";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    let mut cluster = EafPointCluster::new(&synced, None);
+    if downsample_factor > 1 {
+        cluster.downsample_mut(downsample_factor, None);
+    }
+    // Set annotation durations
+    if cluster.len() > 1 {
+        cluster.set_timedelta(None, &duration);
+    } else if let Some(point) = cluster.iter_mut().next() {
+        point.duration = point.timestamp.map(|t| duration - t);
+    }
+
+    info!("Synchronised {} points", cluster.len());
+
+    create_dir(&output_dir)?;
+    let eaf_path = output_dir
+        .join(video_path.file_name().unwrap_or_default())
+        .with_extension("eaf");
+
+    // Generate and write KML + GeoJSON
+    let info = DocumentInfo::new(&cluster.points)
+        .with_clip(video_path)
+        .with_filter("track", &track_path.display().to_string());
+    let kml_path = eaf_path.with_extension("kml");
+    match cluster.write_kml(true, Some(&info), &kml_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", kml_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("Aborted writing KML-file"),
+        Err(err) => warn!("(!) Failed to write '{}': {err}", kml_path.display()),
+    }
+    let json_path = eaf_path.with_extension("json");
+    match cluster.write_json(true, Some(&info), &json_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", json_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("Aborted writing GeoJSON-file"),
+        Err(err) => warn!("(!) Failed to write '{}': {err}", json_path.display()),
+    }
+
+    // Generate EAF
+    let eaf = match generate_eaf(video_path, audio_path, Some(&cluster.points), None) {
+        Ok(e) => e,
+        Err(err) => {
+            let msg = format!("(!) Failed to generate EAF: {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+    let eaf_string = match eaf.to_string(Some(4)) {
+        Ok(s) => s,
+        Err(err) => {
+            let msg = format!("(!) Failed to generate EAF: {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+    match writefile(eaf_string.as_bytes(), &eaf_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", eaf_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing ELAN-file"),
+        Err(err) => {
+            let msg = format!("(!) Failed to write '{}': {err}", eaf_path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    }
+
+    Ok(())
+}
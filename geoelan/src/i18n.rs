@@ -0,0 +1,195 @@
+//! Minimal message catalog for interactive prompts and
+//! common error messages, selected via the global `--lang` option.
+//!
+//! Messages may contain a single `{}` placeholder, filled in via `trf()`.
+//! Untranslated messages fall back to English.
+
+use std::sync::OnceLock;
+
+/// Language for prompts and messages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    /// English (default).
+    En,
+    /// Swedish.
+    Sv,
+    /// Spanish.
+    Es,
+}
+
+impl std::str::FromStr for Lang {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "en" => Ok(Self::En),
+            "sv" => Ok(Self::Sv),
+            "es" => Ok(Self::Es),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("(!) Unknown language '{value}'"),
+            )),
+        }
+    }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Sets language for the rest of the run.
+/// Only the first call has an effect.
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+/// Returns current language. Defaults to `Lang::En`.
+pub fn lang() -> Lang {
+    LANG.get().copied().unwrap_or(Lang::En)
+}
+
+/// Translatable message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Msg {
+    /// Confirmation suffix, e.g. `(y/n)`.
+    YesNo,
+    /// Invalid confirmation input.
+    EnterYesNo,
+    /// `{}` = path.
+    OverwriteFile,
+    /// `{}` = path.
+    SkipExisting,
+    /// `{}` = path. Stdin is not a terminal, so confirmation is not possible.
+    SkipExistingNonInteractive,
+    SelectTier,
+    SelectSession,
+    NotANumber,
+    NoSuchItem,
+    NoSuchTier,
+    /// Hint for exiting interactive selection.
+    CtrlC,
+    NoTierSelected,
+    /// `{}` = tier ID.
+    NoTierWithId,
+    /// `{}` = tier ID.
+    TierTokenized,
+    /// `{}` = path.
+    DoesNotExist,
+    /// `{}` = path.
+    FailedToWrite,
+    FailedToGenerateEaf,
+    UnsupportedDevice,
+    NoSession,
+    NoGeotier,
+}
+
+impl Msg {
+    fn text(&self, lang: Lang) -> &'static str {
+        match (self, lang) {
+            (Self::YesNo, Lang::Sv) => "(j/n)",
+            (Self::YesNo, Lang::Es) => "(s/n)",
+            (Self::YesNo, _) => "(y/n)",
+
+            (Self::EnterYesNo, Lang::Sv) => "Ange j/ja eller n/nej",
+            (Self::EnterYesNo, Lang::Es) => "Introduzca s/sí o n/no",
+            (Self::EnterYesNo, _) => "Enter y/yes or n/no",
+
+            (Self::OverwriteFile, Lang::Sv) => "{} finns redan. Skriva över?",
+            (Self::OverwriteFile, Lang::Es) => "{} ya existe. ¿Sobrescribir?",
+            (Self::OverwriteFile, _) => "{} already exists. Overwrite?",
+
+            (Self::SkipExisting, Lang::Sv) => "{} finns redan, hoppar över ('--overwrite never')",
+            (Self::SkipExisting, Lang::Es) => "{} ya existe, se omite ('--overwrite never')",
+            (Self::SkipExisting, _) => "{} already exists, skipping ('--overwrite never')",
+
+            (Self::SkipExistingNonInteractive, Lang::Sv) => "{} finns redan, hoppar över (ingen terminal för bekräftelse, använd '--overwrite always' för att skriva över)",
+            (Self::SkipExistingNonInteractive, Lang::Es) => "{} ya existe, se omite (sin terminal para confirmar, use '--overwrite always' para sobrescribir)",
+            (Self::SkipExistingNonInteractive, _) => "{} already exists, skipping (no terminal for confirmation, use '--overwrite always' to overwrite)",
+
+            (Self::SelectTier, Lang::Sv) => "Välj nivå:",
+            (Self::SelectTier, Lang::Es) => "Seleccione un nivel:",
+            (Self::SelectTier, _) => "Select tier:",
+
+            (Self::SelectSession, Lang::Sv) => "Välj inspelningssession: ",
+            (Self::SelectSession, Lang::Es) => "Seleccione una sesión: ",
+            (Self::SelectSession, _) => "Select session: ",
+
+            (Self::NotANumber, Lang::Sv) => "Inte ett nummer",
+            (Self::NotANumber, Lang::Es) => "No es un número",
+            (Self::NotANumber, _) => "Not a number",
+
+            (Self::NoSuchItem, Lang::Sv) => "Finns inte i listan",
+            (Self::NoSuchItem, Lang::Es) => "No existe en la lista",
+            (Self::NoSuchItem, _) => "No such item",
+
+            (Self::NoSuchTier, Lang::Sv) => "Nivån finns inte",
+            (Self::NoSuchTier, Lang::Es) => "El nivel no existe",
+            (Self::NoSuchTier, _) => "No such tier",
+
+            (Self::CtrlC, Lang::Sv) => "['ctrl + c' för att avsluta]",
+            (Self::CtrlC, Lang::Es) => "['ctrl + c' para salir]",
+            (Self::CtrlC, _) => "['ctrl + c' to exit]",
+
+            (Self::NoTierSelected, Lang::Sv) => {
+                "Ingen nivå vald. Ange nivå-ID för att köra utan interaktion."
+            }
+            (Self::NoTierSelected, Lang::Es) => {
+                "Ningún nivel seleccionado. Especifique el ID del nivel para ejecutar sin interacción."
+            }
+            (Self::NoTierSelected, _) => {
+                "No tier selected. Specify tier ID to run non-interactively."
+            }
+
+            (Self::NoTierWithId, Lang::Sv) => "Ingen nivå med ID '{}'.",
+            (Self::NoTierWithId, Lang::Es) => "No hay ningún nivel con ID '{}'.",
+            (Self::NoTierWithId, _) => "No tier with ID '{}'.",
+
+            (Self::TierTokenized, Lang::Sv) => "'{}' eller en av dess föräldranivåer är tokeniserad.",
+            (Self::TierTokenized, Lang::Es) => "'{}' o uno de sus niveles padre está tokenizado.",
+            (Self::TierTokenized, _) => "'{}' or one of its parents is tokenized.",
+
+            (Self::DoesNotExist, Lang::Sv) => "'{}' finns inte.",
+            (Self::DoesNotExist, Lang::Es) => "'{}' no existe.",
+            (Self::DoesNotExist, _) => "'{}' does not exist.",
+
+            (Self::FailedToWrite, Lang::Sv) => "Kunde inte skriva '{}'",
+            (Self::FailedToWrite, Lang::Es) => "No se pudo escribir '{}'",
+            (Self::FailedToWrite, _) => "Failed to write '{}'",
+
+            (Self::FailedToGenerateEaf, Lang::Sv) => "Kunde inte skapa ELAN-fil",
+            (Self::FailedToGenerateEaf, Lang::Es) => "No se pudo generar el archivo ELAN",
+            (Self::FailedToGenerateEaf, _) => "Failed to generate EAF",
+
+            (Self::UnsupportedDevice, Lang::Sv) => "Okänd kameramodell eller kameramodell som inte stöds.",
+            (Self::UnsupportedDevice, Lang::Es) => "Dispositivo desconocido o no compatible.",
+            (Self::UnsupportedDevice, _) => "Unknown or unsupported device.",
+
+            (Self::NoSession, Lang::Sv) => "Kunde inte fastställa inspelningssession.",
+            (Self::NoSession, Lang::Es) => "No se pudo determinar la sesión de grabación.",
+            (Self::NoSession, _) => "Failed to determine recording session.",
+
+            (Self::NoGeotier, Lang::Sv) => "Ingen geonivå kommer att skapas.",
+            (Self::NoGeotier, Lang::Es) => "No se creará ningún nivel geográfico.",
+            (Self::NoGeotier, _) => "No geotier will be created.",
+        }
+    }
+}
+
+/// Returns message in the current language.
+pub fn tr(msg: Msg) -> &'static str {
+    msg.text(lang())
+}
+
+/// Returns message in the current language,
+/// with the `{}` placeholder replaced by `arg`.
+pub fn trf(msg: Msg, arg: &str) -> String {
+    tr(msg).replacen("{}", arg, 1)
+}
+
+/// Parses a yes/no answer in English or the current language.
+/// Returns `None` for any other input.
+pub fn parse_yes_no(input: &str) -> Option<bool> {
+    match (input, lang()) {
+        ("y" | "yes", _) | ("j" | "ja", Lang::Sv) | ("s" | "sí" | "si", Lang::Es) => Some(true),
+        ("n" | "no", _) | ("nej", Lang::Sv) => Some(false),
+        _ => None,
+    }
+}
@@ -0,0 +1,152 @@
+//! Export MP4 atom hierarchy as JSON, and compare the atom structure
+//! of two MP4-files, e.g. to check whether a file has been re-muxed
+//! and lost telemetry (GoPro GPMF track, VIRB UUID).
+
+use std::{collections::HashMap, io::ErrorKind, path::Path};
+
+use fit_rs::VirbFile;
+use serde_json::json;
+
+use crate::{
+    files::{gopro::GPMF_TRACK, writefile, WriteStatus},
+    logging::info,
+    media::Media,
+};
+
+use super::{atom_tree, output_path};
+
+/// Returns atoms as `(path, size)`, where path is the fourcc hierarchy
+/// with repeated siblings indexed, e.g. `moov/trak[2]/mdia/hdlr`.
+fn atom_paths(path: &Path) -> std::io::Result<Vec<(String, u64)>> {
+    let mp4 = match mp4iter::Mp4::new(path) {
+        Ok(v) => v,
+        Err(err) => {
+            let msg = format!("(!) Failed to read '{}': {err}", path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    let mut stack: Vec<String> = Vec::new();
+    // Sibling counts per parent path
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut paths: Vec<(String, u64)> = Vec::new();
+    for (depth, name, _offset, size) in atom_tree(mp4)?.into_iter() {
+        stack.truncate(depth);
+        let key = format!("{}/{name}", stack.join("/"));
+        let count = counts.entry(key).or_insert(0);
+        *count += 1;
+        let segment = match *count {
+            1 => name,
+            n => format!("{name}[{n}]"),
+        };
+        stack.push(segment);
+        paths.push((stack.join("/"), size));
+    }
+
+    Ok(paths)
+}
+
+/// Telemetry present in an MP4-file, e.g. `GoPro GPMF track`.
+fn telemetry(path: &Path) -> Vec<&'static str> {
+    let mut found = Vec::new();
+    if Media::has_track(path, GPMF_TRACK) {
+        found.push("GoPro GPMF track");
+    }
+    if VirbFile::uuid_mp4(path).is_ok() {
+        found.push("VIRB UUID");
+    }
+    found
+}
+
+/// Writes atom hierarchy as JSON, e.g. `GH010006_atoms.json`.
+pub fn atoms_json(path: &Path, name_template: Option<&str>) -> std::io::Result<()> {
+    let mp4 = match mp4iter::Mp4::new(path) {
+        Ok(v) => v,
+        Err(err) => {
+            let msg = format!("(!) Failed to read MP4: {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+    let atoms: Vec<_> = atom_tree(mp4)?
+        .into_iter()
+        .map(|(depth, name, offset, size)| {
+            json!({
+                "depth": depth,
+                "name": name,
+                "offset": offset,
+                "size": size,
+            })
+        })
+        .collect();
+    let json = json!({
+        "path": path,
+        "atoms": atoms,
+    });
+
+    let json_path = output_path(name_template, path, None, "_atoms", "json");
+    match writefile(serde_json::to_string_pretty(&json)?.as_bytes(), &json_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", json_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing {}", json_path.display()),
+        Err(err) => {
+            let msg = format!("(!) Failed to write '{}': {err}", json_path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints differences in atom structure and size between two MP4-files,
+/// and whether telemetry present in the first is missing in the second.
+pub fn atoms_diff(path1: &Path, path2: &Path) -> std::io::Result<()> {
+    let atoms1 = atom_paths(path1)?;
+    let atoms2 = atom_paths(path2)?;
+    let sizes2: HashMap<&str, u64> = atoms2.iter().map(|(p, s)| (p.as_str(), *s)).collect();
+    let sizes1: HashMap<&str, u64> = atoms1.iter().map(|(p, s)| (p.as_str(), *s)).collect();
+
+    println!("Atom differences:");
+    println!("  A: {}", path1.display());
+    println!("  B: {}", path2.display());
+    let mut changes = 0;
+    for (atom, size1) in atoms1.iter() {
+        match sizes2.get(atom.as_str()) {
+            None => println!("  - {atom} (A only, size: {size1})"),
+            Some(size2) if size2 != size1 => {
+                println!("  ~ {atom} (size: {size1} -> {size2})")
+            }
+            Some(_) => continue,
+        }
+        changes += 1;
+    }
+    for (atom, size2) in atoms2.iter() {
+        if !sizes1.contains_key(atom.as_str()) {
+            println!("  + {atom} (B only, size: {size2})");
+            changes += 1;
+        }
+    }
+    if changes == 0 {
+        println!("  None");
+    }
+    println!("---");
+    println!(
+        "Atoms: {} (A), {} (B), {changes} differences",
+        atoms1.len(),
+        atoms2.len()
+    );
+
+    let (telemetry1, telemetry2) = (telemetry(path1), telemetry(path2));
+    for t in telemetry1.iter() {
+        match telemetry2.contains(t) {
+            true => println!("{t}: present in both files"),
+            false => println!(
+                "(!) {t}: present in A, missing in B. B has likely been re-muxed or edited."
+            ),
+        }
+    }
+    for t in telemetry2.iter().filter(|t| !telemetry1.contains(t)) {
+        println!("{t}: present in B only");
+    }
+
+    Ok(())
+}
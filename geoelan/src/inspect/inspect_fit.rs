@@ -1,16 +1,17 @@
 //! Inspect Garmin FIT files. Supports non-VIRB files.
 
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{ErrorKind, Write};
+use std::io::ErrorKind;
 use std::path::PathBuf;
 
 use fit_rs::{Fit, FitSessions, SensorType};
 
-use crate::files::virb::select_session;
-use crate::files::{affix_file_name, writefile};
+use crate::files::virb::{select_session, verify_fit};
+use crate::files::{write_atomic, writefile, WriteStatus};
 use crate::geo::geo_fit::set_datetime_fit;
 use crate::geo::{downsample, EafPoint, EafPointCluster};
+use crate::inspect::{debug_cells, output_path, write_type_table, TypeRow};
+use crate::logging::{info, warn};
 
 pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
     let fit_path: Option<&PathBuf> = args.get_one("fit");
@@ -25,6 +26,14 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
 
     let path = fit_path.unwrap();
 
+    if *args.get_one::<bool>("verify").unwrap() {
+        if let Err(err) = verify_fit(path) {
+            let msg = format!("(!) {} is corrupt: {err}", path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+        info!("FIT CRC OK for {}", path.display());
+    }
+
     let mut fit = Fit::new(&path)?;
     if let Err(err) = fit.index() {
         let msg = format!("(!) Failed to map sessions for {}: {err}", path.display());
@@ -43,7 +52,10 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
         *args.get_one::<bool>("indexed-kml").unwrap(),
     );
     let full_gps = *args.get_one::<bool>("fullgps").unwrap();
-    let save_json = *args.get_one::<bool>("json").unwrap();
+    // GeoJSON for GPS log, unless used with 'data-type'
+    let save_json = *args.get_one::<bool>("json").unwrap() && !args.contains_id("data-type");
+    let save_type_json = *args.get_one::<bool>("json").unwrap() && args.contains_id("data-type");
+    let name_template = args.get_one::<String>("name").map(|s| s.as_str());
     let save_csv = *args.get_one::<bool>("csv").unwrap(); // only for sensor data gyro, grav, accl, gps
                                                           // NOTE data-type is u16 for fit, string for gpmf...
     let global_id: Option<u16> = match args.get_one::<String>("data-type") {
@@ -76,8 +88,8 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
             Ok(gm) => {
                 let mut pts: Vec<EafPoint> = gm.iter().map(EafPoint::from).collect();
                 match set_datetime_fit(&mut pts, &fit, 0) {
-                    Ok(_) => println!("Set date time for points."),
-                    Err(_) => println!("Unable to set date time for points, not a VIRB file."),
+                    Ok(_) => info!("Set date time for points."),
+                    Err(_) => info!("Unable to set date time for points, not a VIRB file."),
                 };
                 Some(pts)
             }
@@ -86,12 +98,16 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
         false => None,
     };
 
+    let first_datetime = points
+        .as_ref()
+        .and_then(|pts| pts.iter().find_map(|p| p.datetime));
+
     if let Some(pts) = &points {
         if pts.is_empty() {
-            println!("(!) No GPS log found.")
+            warn!("(!) No GPS log found.")
         } else {
             let mut csv: Vec<String> = vec![
-                "INDEX\tDATETIME\tTIMESTAMP\tLATITUDE\tLONGITUDE\tALTITUDE\tSPEED2D\tSPEED3D"
+                "INDEX\tDATETIME\tTIMESTAMP\tLATITUDE\tLONGITUDE\tALTITUDE\tSPEED2D\tSPEED3D\tFIX\tDOP"
                     .to_owned(),
             ];
 
@@ -99,7 +115,7 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
                 for (i, point) in pts.iter().enumerate() {
                     println!("[{:6}]\n{point}", i + 1);
                     csv.push(format!(
-                        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
                         // counter,
                         i + 1,
                         // !!! datetime = None? works for gpmf...
@@ -114,6 +130,8 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
                         point.altitude,
                         point.speed2d,
                         point.speed3d,
+                        point.fix,
+                        point.dop.map(|d| d.to_string()).as_deref().unwrap_or("Unspecified"),
                     ))
                 }
 
@@ -125,10 +143,9 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
                 if save_csv {
                     // Re-use and filename from e.g. GH010006.MP4 to GH010006_GPS,csv
                     // !!! TODO change affix_file_name to return Option<PathBuf> to avoid overwriting
-                    let csv_path = affix_file_name(&path, None, Some("_GPS"), Some("csv"));
-                    let mut csv_file = File::create(&csv_path)?;
-                    csv_file.write_all(csv.join("\n").as_bytes())?;
-                    println!("Wrote {}", csv_path.display());
+                    let csv_path = output_path(name_template, &path, first_datetime, "_GPS", "csv");
+                    write_atomic(csv.join("\n").as_bytes(), &csv_path)?;
+                    info!("Wrote {}", csv_path.display());
                 }
 
                 return Ok(());
@@ -145,10 +162,11 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
                 if save_kml {
                     let kml_doc =
                         EafPointCluster::new(&downsampled_points, None).to_kml_string(indexed_kml);
-                    let kml_path = affix_file_name(&path, None, Some("_points"), Some("kml"));
+                    let kml_path = output_path(name_template, &path, first_datetime, "_points", "kml");
                     match writefile(&kml_doc.as_bytes(), &kml_path) {
-                        Ok(true) => println!("Wrote {}", kml_path.display()),
-                        Ok(false) => println!("User aborted writing KML-file"),
+                        Ok(WriteStatus::Written) => info!("Wrote {}", kml_path.display()),
+                        Ok(WriteStatus::Planned) => (),
+                        Ok(WriteStatus::Skipped) => info!("User aborted writing KML-file"),
                         Err(err) => return Err(err),
                     }
                 }
@@ -158,15 +176,16 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
                     let geojson_doc =
                         EafPointCluster::new(&downsampled_points, None).to_json_string(indexed_kml);
                     let geojson_path =
-                        affix_file_name(&path, None, Some("points"), Some("json"));
+                        output_path(name_template, &path, first_datetime, "points", "json");
                     match writefile(&geojson_doc.as_bytes(), &geojson_path) {
-                        Ok(true) => println!("Wrote {}", geojson_path.display()),
-                        Ok(false) => println!("User aborted writing GeoJSON-file"),
+                        Ok(WriteStatus::Written) => info!("Wrote {}", geojson_path.display()),
+                        Ok(WriteStatus::Planned) => (),
+                        Ok(WriteStatus::Skipped) => info!("User aborted writing GeoJSON-file"),
                         Err(err) => return Err(err),
                     }
                 }
 
-                println!("Done");
+                info!("Done");
                 return Ok(());
             }
         }
@@ -196,7 +215,7 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
             println!("{data:?}");
         }
 
-        println!("Done");
+        info!("Done");
         return Ok(());
     }
 
@@ -215,6 +234,43 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
         }
     }
 
+    // Export specified message type as table.
+    // Records are already filtered on global ID.
+    if let Some(global) = global_id {
+        if save_csv || save_type_json {
+            // One column per field definition number, numerical values
+            // with scale and offset applied
+            let rows: Vec<TypeRow<u16>> = records
+                .iter()
+                .map(|r| {
+                    let cells = r
+                        .fields
+                        .iter()
+                        .map(|f| {
+                            let values = match f.data.as_vec_f64() {
+                                Some(v) => v.iter().map(|n| n.to_string()).collect(),
+                                None => debug_cells(&format!("{:?}", f.data)),
+                            };
+                            (
+                                format!("field_{}", f.field_definition_number),
+                                values.join(";"),
+                            )
+                        })
+                        .collect();
+                    (r.global, r.name(), cells)
+                })
+                .collect();
+            write_type_table(
+                &path,
+                name_template,
+                &global.to_string(),
+                &rows,
+                save_csv,
+                save_type_json,
+            )?;
+        }
+    }
+
     let mut stats_sorted: Vec<_> = stats
         .iter()
         .map(|((global, name), count)| (global, name, count))
@@ -244,7 +300,7 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
 
     if let Some(session) = &mut fit_session {
         if let Err(err) = session.derive() {
-            println!("(!) Failed to derive session: {err}");
+            warn!("(!) Failed to derive session: {err}");
         };
         if let Ok((start, end)) = session.timespan_abs(None, true) {
             println!("Session time span:");
@@ -290,7 +346,7 @@ pub fn inspect_fit(args: &clap::ArgMatches) -> std::io::Result<()> {
         }
     };
 
-    println!("Done");
+    info!("Done");
 
     Ok(())
 }
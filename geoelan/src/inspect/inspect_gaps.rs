@@ -0,0 +1,156 @@
+//! Report time gaps between clips in recording sessions,
+//! and between separate recording sessions.
+//! Reveals missing chapters, and recordings that
+//! are easily mistaken for a single session.
+
+use std::{io::ErrorKind, path::PathBuf};
+
+use fit_rs::VirbSession;
+use gpmf_rs::GoProSession;
+use time::{Duration, PrimitiveDateTime};
+
+use crate::{
+    files::search_dirs,
+    logging::info,
+    media::timeline::{SessionSpan, CHAPTER_GAP_TOLERANCE},
+};
+
+/// Width of the timeline diagram in characters.
+const TIMELINE_WIDTH: usize = 60;
+
+fn hms(duration: Duration) -> String {
+    let secs = duration.whole_seconds().abs();
+    format!(
+        "{}{:02}:{:02}:{:02}",
+        if duration.is_negative() { "-" } else { "" },
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )
+}
+
+fn time_string(datetime: Option<PrimitiveDateTime>) -> String {
+    datetime
+        .map(|t| t.to_string())
+        .unwrap_or("Unknown".to_owned())
+}
+
+pub fn inspect_gaps(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let search = search_dirs(args)?;
+
+    // Session start/end is derived from telemetry,
+    // clip start from MP4 creation time.
+    let mut sessions: Vec<SessionSpan> = Vec::new();
+    if let Some(files) = &search.files {
+        info!("Locating sessions for {} listed files...", files.len());
+    }
+    for indir in search.dirs.iter() {
+        if search.files.is_none() {
+            info!("Locating sessions in {}...", indir.display());
+        }
+
+        sessions.extend(
+            GoProSession::sessions_from_path(indir, None, false, true, true)?
+                .iter()
+                .map(|s| {
+                    let clips: Vec<PathBuf> = s
+                        .iter()
+                        .filter_map(|f| f.mp4.as_deref().or(f.lrv.as_deref()))
+                        .map(|p| search.original(p))
+                        .collect();
+                    SessionSpan::new(&clips, s.start(), s.end())
+                }),
+        );
+        sessions.extend(
+            VirbSession::sessions_from_path(indir, true)
+                .iter()
+                .map(|s| {
+                    let clips: Vec<PathBuf> = s
+                        .virb
+                        .iter()
+                        .filter_map(|v| v.mp4().or(v.glv()))
+                        .map(|p| search.original(p))
+                        .collect();
+                    SessionSpan::new(&clips, s.start(), s.end())
+                }),
+        );
+    }
+    sessions.retain(|s| !s.clips.is_empty());
+    // Only keep sessions with listed clips
+    if search.files.is_some() {
+        sessions.retain(|s| s.clips.iter().any(|c| search.is_listed(&c.path)));
+    }
+    sessions.sort_by_key(|s| s.start);
+
+    if sessions.is_empty() {
+        let msg = "(!) No recording sessions found.";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    println!("---");
+    for (i1, session) in sessions.iter().enumerate() {
+        println!(
+            "┏━[ Session {} | {} - {} ({} clips)]",
+            i1 + 1,
+            time_string(session.start),
+            time_string(session.end),
+            session.clips.len()
+        );
+        let gaps = session.chapter_gaps();
+        for (i2, clip) in session.clips.iter().enumerate() {
+            let gap = match i2.checked_sub(1).and_then(|i| gaps.get(i)) {
+                Some((_, Some(g))) if g.abs() > CHAPTER_GAP_TOLERANCE => {
+                    format!(" (!) gap {:.3}s, chapter missing?", g.as_seconds_f64())
+                }
+                Some((_, Some(g))) => format!(" gap {:.3}s", g.as_seconds_f64()),
+                Some((_, None)) => " gap unknown".to_owned(),
+                None => String::new(),
+            };
+            println!(
+                "┃{:3}. {} {} ({:.3}s){gap}",
+                i2 + 1,
+                clip.path
+                    .file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                time_string(clip.start),
+                clip.duration.as_seconds_f64()
+            );
+        }
+        println!("┗━━━━");
+
+        if let Some(next) = sessions.get(i1 + 1) {
+            if let (Some(end), Some(start)) = (session.end, next.start) {
+                println!("   Gap to session {}: {}", i1 + 2, hms(start - end));
+            }
+        }
+    }
+
+    // Timeline diagram, one row per session
+    let first = sessions.iter().filter_map(|s| s.start).min();
+    let last = sessions.iter().filter_map(|s| s.end).max();
+    if let (Some(first), Some(last)) = (first, last) {
+        let total = (last - first).as_seconds_f64().max(1.0);
+        let column = |t: PrimitiveDateTime| {
+            (((t - first).as_seconds_f64() / total) * TIMELINE_WIDTH as f64).round() as usize
+        };
+
+        println!("---");
+        println!("Timeline {} - {} ({})", first, last, hms(last - first));
+        for (i, session) in sessions.iter().enumerate() {
+            let (Some(start), Some(end)) = (session.start, session.end) else {
+                continue;
+            };
+            let (c1, c2) = (column(start), column(end).max(column(start) + 1));
+            println!(
+                "  {:3} |{}{}{}|",
+                i + 1,
+                " ".repeat(c1),
+                "█".repeat(c2 - c1),
+                " ".repeat((TIMELINE_WIDTH + 1).saturating_sub(c2))
+            );
+        }
+    }
+
+    Ok(())
+}
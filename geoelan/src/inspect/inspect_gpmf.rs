@@ -1,14 +1,15 @@
 //! Inspect GoPro GPMF data. Supports "raw" GPMP-files, e.g. by having extracted the `GoPro MET` track from a GoPro MP4-file.
 
-use std::io::{ErrorKind, Write};
-use std::path::PathBuf;
-use std::{fs::File, path::Path};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 
 use gpmf_rs::{DataType, FourCC, GoProFile, GoProSession, Gpmf, GpmfError, SensorType};
 
 use crate::{
-    files::{affix_file_name, has_extension},
-    geo::{downsample, point::EafPoint, EafPointCluster},
+    files::{has_extension, write_atomic, WriteStatus},
+    geo::{downsample, point::EafPoint, DocumentInfo, EafPointCluster},
+    inspect::{debug_cells, output_path, write_type_table, TypeRow},
+    logging::{info, warn},
 };
 
 pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
@@ -41,7 +42,10 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
         *args.get_one::<bool>("kml").unwrap() || *args.get_one::<bool>("indexed-kml").unwrap(),
         *args.get_one::<bool>("indexed-kml").unwrap(),
     );
-    let save_json = *args.get_one::<bool>("json").unwrap();
+    // GeoJSON for GPS log, unless used with 'data-type'
+    let save_json = *args.get_one::<bool>("json").unwrap() && !args.contains_id("data-type");
+    let save_type_json = *args.get_one::<bool>("json").unwrap() && args.contains_id("data-type");
+    let name_template = args.get_one::<String>("name").map(|s| s.as_str());
     let save_csv = *args.get_one::<bool>("csv").unwrap(); // only for sensor data gyro, grav, accl, gps
     let session = *args.get_one::<bool>("session").unwrap(); // clap: conflicts with debug, verbose
     let verify_gpmf = *args.get_one::<bool>("verify").unwrap();
@@ -90,7 +94,7 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
             println!("{}", gpmf.device_name().join(", "));
         }
 
-        println!("Done.");
+        info!("Done.");
         return Ok(());
     }
 
@@ -99,7 +103,7 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
     let gpmf: Gpmf;
 
     if session {
-        println!("Locating GoPro-files and parsing GPMF-data...");
+        info!("Locating GoPro-files and parsing GPMF-data...");
 
         // TODO 220813 REGRESSION CHECK: DONE. GoProSession::from_path 2-3x slower with new code if parsing immediately. Code change to only parse when files in the same session have been matched. Only Stream::new/compile remains as performance issue now (20-30ms slower with new code on M1)
 
@@ -113,9 +117,9 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
         //     }
         // };
         // If 'session' flag is passed the file/s must parse as MP4s
-        println!("Located the following session files:");
+        info!("Located the following session files:");
         for (i, gopro_file) in gopro_session.iter().enumerate() {
-            println!(
+            info!(
                 "{:4}. MP4: {}",
                 i + 1,
                 gopro_file
@@ -124,7 +128,7 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
                     .and_then(|f| f.to_str())
                     .unwrap_or("High-resolution MP4 not set")
             );
-            println!(
+            info!(
                 "      LRV: {}",
                 gopro_file
                     .lrv
@@ -134,18 +138,18 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
             );
         }
 
-        println!("Merging GPMF-data for {} files...", gopro_session.len());
+        info!("Merging GPMF-data for {} files...", gopro_session.len());
         gpmf = match gopro_session.gpmf() {
             Ok(g) => g,
             Err(err) => {
                 // Print error then retry to parse as binary GPMF file
-                println!("(!) Failed to merge GPMF: {err}");
-                println!("--> Retrying specified file as raw GPMF-track...");
+                warn!("(!) Failed to merge GPMF: {err}");
+                warn!("--> Retrying specified file as raw GPMF-track...");
                 Gpmf::from_raw(&path, debug)?
             }
         };
 
-        println!(
+        info!(
             "Done ({} ms{})",
             timer_gpmf.elapsed().as_millis(),
             if debug { ", debug parse" } else { "" }
@@ -161,8 +165,8 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
         gpmf = match gopro_file.gpmf() {
             Ok(g) => g,
             Err(err) => {
-                println!("(!) Failed to extract GPMF: {err}");
-                println!("--> Retrying as raw GPMF-track...");
+                warn!("(!) Failed to extract GPMF: {err}");
+                warn!("--> Retrying as raw GPMF-track...");
                 Gpmf::from_raw(&path, debug)?
             }
         };
@@ -176,9 +180,11 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
         0
     };
 
+    let first_datetime = gps.iter().find_map(|p| EafPoint::from(p).datetime);
+
     if print_gps {
         let mut csv: Vec<String> = vec![
-            "INDEX\tDATETIME\tTIMESTAMP\tLATITUDE\tLONGITUDE\tALTITUDE\tSPEED2D\tSPEED3D"
+            "INDEX\tDATETIME\tTIMESTAMP\tLATITUDE\tLONGITUDE\tALTITUDE\tSPEED2D\tSPEED3D\tFIX\tDOP"
                 .to_owned(),
         ];
         let point_cluster =
@@ -188,7 +194,7 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
             println!("[{:4}]\n{}", i + 1, point);
             if save_csv {
                 csv.push(format!(
-                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
                     i + 1,
                     point.datetime_string().as_deref().unwrap_or("Unspecified"),
                     point
@@ -201,6 +207,8 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
                     point.altitude,
                     point.speed2d,
                     point.speed3d,
+                    point.fix,
+                    point.dop.map(|d| d.to_string()).as_deref().unwrap_or("Unspecified"),
                 ))
             }
         }
@@ -213,10 +221,9 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
         if save_csv {
             // Re-use and filename from e.g. GH010006.MP4 to GH010006_GPS,csv
             // !!! TODO change affix_file_name to return Option<PathBuf> to avoid overwriting
-            let csv_path = affix_file_name(&path, None, Some("_GPS"), Some("csv"));
-            let mut csv_file = File::create(&csv_path)?;
-            csv_file.write_all(csv.join("\n").as_bytes())?;
-            println!("Wrote {}", csv_path.display());
+            let csv_path = output_path(name_template, &path, first_datetime, "_GPS", "csv");
+            write_atomic(csv.join("\n").as_bytes(), &csv_path)?;
+            info!("Wrote {}", csv_path.display());
         }
 
         println!("---");
@@ -287,10 +294,15 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
 
         if save_csv {
             // Re-use and filename from e.g. GH010006.MP4 to GH010006_GPS,csv
-            let csv_path = affix_file_name(&path, None, Some(&format!("_{}", sensor)), Some("csv"));
-            let mut csv_file = File::create(&csv_path)?;
-            csv_file.write_all(csv.join("\n").as_bytes())?;
-            println!("Wrote {}", csv_path.display());
+            let csv_path = output_path(
+                name_template,
+                &path,
+                first_datetime,
+                &format!("_{}", sensor),
+                "csv",
+            );
+            write_atomic(csv.join("\n").as_bytes(), &csv_path)?;
+            info!("Wrote {}", csv_path.display());
         }
 
         if sensor_data.is_empty() {
@@ -300,8 +312,24 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
 
     if let Some(dt) = data_type {
         let dtype = DataType::from_str(dt);
+        // One row per sample, one column per value in the sample,
+        // e.g. 'x', 'y', 'z' for ACCL as columns '1', '2', '3'
+        let mut rows: Vec<TypeRow<usize>> = Vec::new();
         for (i, stream) in gpmf.filter_iter(&dtype).enumerate() {
-            stream.print(Some(i + 1), None)
+            stream.print(Some(i + 1), None);
+            if save_csv || save_type_json {
+                for value in stream.values().iter() {
+                    let cells = debug_cells(&format!("{value:?}"))
+                        .into_iter()
+                        .enumerate()
+                        .map(|(j, v)| ((j + 1).to_string(), v))
+                        .collect();
+                    rows.push((i + 1, dt.to_owned(), cells));
+                }
+            }
+        }
+        if save_csv || save_type_json {
+            write_type_table(&path, name_template, dt, &rows, save_csv, save_type_json)?;
         }
     }
 
@@ -314,13 +342,15 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
         };
 
         let cluster = EafPointCluster::new(&downsampled_points, None);
+        let info = DocumentInfo::new(&downsampled_points).with_clip(&path);
 
         // Generate KML and save to disk
         if save_kml {
-            let kml_path = affix_file_name(&path, None, Some("_points"), Some("kml"));
-            match cluster.write_kml(indexed_kml, &kml_path) {
-                Ok(true) => println!("Wrote {}", kml_path.display()),
-                Ok(false) => println!("Aborted writing KML-file"),
+            let kml_path = output_path(name_template, &path, first_datetime, "_points", "kml");
+            match cluster.write_kml(indexed_kml, Some(&info), &kml_path) {
+                Ok(WriteStatus::Written) => info!("Wrote {}", kml_path.display()),
+                Ok(WriteStatus::Planned) => (),
+                Ok(WriteStatus::Skipped) => info!("Aborted writing KML-file"),
                 Err(err) => {
                     let msg = format!("(!) Failed to write '{}': {err}", kml_path.display());
                     return Err(std::io::Error::new(ErrorKind::Other, msg));
@@ -330,10 +360,11 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
 
         // Generate GeiJSON and save to disk
         if save_json {
-            let geojson_path = affix_file_name(&path, None, Some("_points"), Some("json"));
-            match cluster.write_json(indexed_kml, &geojson_path) {
-                Ok(true) => println!("Wrote {}", geojson_path.display()),
-                Ok(false) => println!("Aborted writing GeoJSON-file"),
+            let geojson_path = output_path(name_template, &path, first_datetime, "_points", "json");
+            match cluster.write_json(indexed_kml, Some(&info), &geojson_path) {
+                Ok(WriteStatus::Written) => info!("Wrote {}", geojson_path.display()),
+                Ok(WriteStatus::Planned) => (),
+                Ok(WriteStatus::Skipped) => info!("Aborted writing GeoJSON-file"),
                 Err(err) => {
                     let msg = format!("(!) Failed to write '{}': {err}", geojson_path.display());
                     return Err(std::io::Error::new(ErrorKind::Other, msg));
@@ -362,7 +393,7 @@ pub fn inspect_gpmf(args: &clap::ArgMatches) -> std::io::Result<()> {
         }
     );
 
-    println!("Done");
+    info!("Done");
 
     Ok(())
 }
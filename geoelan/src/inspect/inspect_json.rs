@@ -0,0 +1,273 @@
+//! Structured JSON output for `inspect --format json`:
+//! MP4 tracks and atoms, GoPro/VIRB identifiers, session info and GPS summaries.
+//! Printed to stdout for use in other tools.
+
+use std::{
+    collections::BTreeMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use fit_rs::Fit;
+use gpmf_rs::GoProFile;
+use serde_json::{json, Value};
+
+use crate::{
+    geo::{geo_fit::set_datetime_fit, DocumentInfo, EafPoint},
+    media::atoms,
+    model::{muid_string, CameraModel},
+    telemetry::{GoProTelemetry, Telemetry, VirbTelemetry},
+};
+
+use super::atom_tree;
+
+/// GPS summary: time span, point count, distance.
+fn gps_summary(points: &[EafPoint]) -> Value {
+    DocumentInfo::new(points).to_json()
+}
+
+/// Recording sessions in `telemetry`: identifiers and time span.
+fn sessions_json(telemetry: &dyn Telemetry) -> std::io::Result<Vec<Value>> {
+    Ok(telemetry
+        .sessions()?
+        .iter()
+        .map(|s| {
+            json!({
+                "id": s.id,
+                "clip_ids": s.clip_ids,
+                "start": s.start.map(|t| t.to_string()),
+                "end": s.end.map(|t| t.to_string()),
+                "duration": s.start.zip(s.end).map(|(start, end)| (end - start).as_seconds_f64()),
+            })
+        })
+        .collect())
+}
+
+/// MP4 tracks, edit lists, timecode, location, file type,
+/// atom hierarchy, and camera identifiers.
+fn video_json(path: &Path) -> std::io::Result<Value> {
+    let mut mp4 = match mp4iter::Mp4::new(path) {
+        Ok(v) => v,
+        Err(err) => {
+            let msg = format!("(!) Failed to read MP4: {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    let tracks: Vec<Value> = mp4
+        .track_list(false)?
+        .iter()
+        .map(|track| {
+            json!({
+                "name": track.name(),
+                "id": track.id(),
+                "type": track.track_type(),
+                "duration": track.duration().as_seconds_f64(),
+                "samples": track.offsets().len(),
+                "width": (track.track_type() == "vide").then(|| track.width()),
+                "height": (track.track_type() == "vide").then(|| track.height()),
+            })
+        })
+        .collect();
+
+    let edit_lists: Vec<Value> = match atoms::tracks(path) {
+        Ok((movie_timescale, track_atoms)) => track_atoms
+            .iter()
+            .filter(|t| !t.edits.is_empty())
+            .map(|t| {
+                json!({
+                    "track_id": t.id,
+                    "handler_name": t.handler_name,
+                    "start_offset": t.edit_offset(movie_timescale),
+                    "edits": t.edits.iter().map(|e| json!({
+                        "segment_duration": e.segment_duration,
+                        "media_time": e.media_time,
+                        "media_rate": e.media_rate,
+                    })).collect::<Vec<_>>(),
+                })
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    let timecode = atoms::timecode(path).ok().flatten().map(|tc| {
+        json!({
+            "timecode": tc.to_string(),
+            "fps": tc.fps,
+            "drop_frame": tc.drop_frame,
+        })
+    });
+    let location = atoms::location(path).ok().flatten().map(|l| {
+        json!({
+            "latitude": l.latitude,
+            "longitude": l.longitude,
+            "altitude": l.altitude,
+        })
+    });
+    let file_type = atoms::file_type(path).ok().flatten().map(|ft| {
+        json!({
+            "major_brand": ft.major_brand,
+            "minor_version": ft.minor_version,
+            "compatible_brands": ft.compatible_brands,
+            "encoders": ft.encoders,
+            "handler_names": ft.handler_names,
+            "edited": ft.is_edited(),
+        })
+    });
+
+    let atoms: Vec<Value> = atom_tree(mp4)?
+        .into_iter()
+        .map(|(depth, name, offset, size)| {
+            json!({
+                "depth": depth,
+                "name": name,
+                "offset": offset,
+                "size": size,
+            })
+        })
+        .collect();
+
+    let model = CameraModel::from(path);
+    let camera = match &model {
+        CameraModel::GoPro(_) => match GoProFile::new(path) {
+            Ok(gopro) => json!({
+                "model": model.name(),
+                "muid": muid_string(&gopro.muid),
+                "gumi": gopro.gumi.iter().map(|n| format!("{n:02x}")).collect::<String>(),
+                "creation_time": gopro.start().to_string(),
+                "duration": gopro.duration().as_seconds_f64(),
+            }),
+            Err(_) => json!({ "model": model.name() }),
+        },
+        CameraModel::Virb(uuid) => json!({
+            "model": model.name(),
+            "uuid": uuid,
+        }),
+        CameraModel::Unknown => Value::Null,
+    };
+
+    Ok(json!({
+        "path": path,
+        "camera": camera,
+        "tracks": tracks,
+        "edit_lists": edit_lists,
+        "timecode": timecode,
+        "location": location,
+        "file_type": file_type,
+        "atoms": atoms,
+    }))
+}
+
+/// GoPro identifiers, session files, data stream types,
+/// device name, and GPS summary.
+fn gpmf_json(args: &clap::ArgMatches, path: &Path) -> std::io::Result<Value> {
+    let session = *args.get_one::<bool>("session").unwrap();
+    let verify = *args.get_one::<bool>("verify").unwrap();
+    let min_gps_fix = args.get_one::<u32>("gpsfix");
+    let max_dilution = args.get_one::<f64>("gpsdop").copied();
+    let indir = args
+        .get_one::<PathBuf>("input-directory")
+        .map(|p| p.to_owned())
+        .or_else(|| path.parent().map(|p| p.to_owned()));
+
+    let file_json = |f: &GoProFile| {
+        json!({
+            "muid": muid_string(&f.muid),
+            "mp4": f.mp4,
+            "lrv": f.lrv,
+            "start": f.start().to_string(),
+        })
+    };
+
+    let telemetry = GoProTelemetry::new(path, indir.as_deref(), verify, session)?;
+    let files: Vec<Value> = match telemetry.session() {
+        Some(gopro_session) => gopro_session.iter().map(file_json).collect(),
+        None => match GoProFile::new(path) {
+            Ok(gopro) => vec![file_json(&gopro)],
+            Err(err) => {
+                let msg = format!("(!) Failed to read MP4: {err}");
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        },
+    };
+    let gpmf = telemetry.gpmf();
+
+    let mut gps = gpmf.gps();
+    let pruned = min_gps_fix.map(|fix| gps.prune_mut(*fix, max_dilution));
+    let points: Vec<EafPoint> = gps.iter().map(EafPoint::from).collect();
+
+    Ok(json!({
+        "path": path,
+        "model": telemetry.model().name(),
+        "device": gpmf.device_name(),
+        "sessions": sessions_json(&telemetry)?,
+        "files": files,
+        "streams": gpmf.len(),
+        "stream_types": gpmf.types(),
+        "gps": gps_summary(&points),
+        "gps_pruned": pruned,
+    }))
+}
+
+/// FIT header, message counts, recording sessions with UUIDs
+/// and time spans, and GPS summary.
+fn fit_json(path: &Path) -> std::io::Result<Value> {
+    let mut fit = Fit::new(path)?;
+    if let Err(err) = fit.index() {
+        let msg = format!("(!) Failed to map sessions for {}: {err}", path.display());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    };
+    fit.augment();
+    let telemetry = VirbTelemetry::from_fit(path, fit, false)?;
+    let fit = telemetry.fit();
+
+    // Key: (Global ID, Message Type), Value: count
+    let mut messages: BTreeMap<(u16, String), usize> = BTreeMap::new();
+    for record in fit.filter(None, None).iter() {
+        *messages.entry((record.global, record.name())).or_insert(0) += 1;
+    }
+
+    let mut points: Vec<EafPoint> = fit
+        .points(None)
+        .map(|gm| gm.iter().map(EafPoint::from).collect())
+        .unwrap_or_default();
+    let _ = set_datetime_fit(&mut points, fit, 0);
+
+    Ok(json!({
+        "path": path,
+        "header": {
+            "size": fit.header.headersize,
+            "protocol": fit.header.protocol,
+            "profile": fit.header.profile,
+            "datasize": fit.header.datasize,
+        },
+        "messages": messages.iter().map(|((global, name), count)| json!({
+            "global_id": global,
+            "name": name,
+            "count": count,
+        })).collect::<Vec<_>>(),
+        "sessions": sessions_json(&telemetry)?,
+        "gps": gps_summary(&points),
+    }))
+}
+
+/// Prints JSON report for `--video`, `--gpmf`, or `--fit` to stdout.
+pub fn inspect_json(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let report = match (
+        args.get_one::<PathBuf>("video"),
+        args.get_one::<PathBuf>("gpmf"),
+        args.get_one::<PathBuf>("fit"),
+    ) {
+        (Some(path), ..) => video_json(path)?,
+        (_, Some(path), _) => gpmf_json(args, path)?,
+        (.., Some(path)) => fit_json(path)?,
+        _ => {
+            let msg = "(!) '--format json' requires '--video', '--gpmf', or '--fit'.";
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
@@ -1,17 +1,41 @@
 //! Inspect camera telemetry, such as GPS logs.
 
-use std::{io::ErrorKind, path::PathBuf};
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
 
 use fit_rs::VirbFile;
 use gpmf_rs::GoProFile;
 use mp4iter::{track::Track, Mp4};
 
-use crate::{files::has_extension_any, model::CameraModel};
+use serde_json::json;
+use time::PrimitiveDateTime;
 
+use crate::{
+    files::{csv_field, has_extension_any, template_file_name, writefile},
+    logging::{info, warn},
+    media::{atoms, dump},
+    model::{session_id, CameraModel},
+};
+
+mod inspect_atoms;
 mod inspect_fit;
+mod inspect_gaps;
 mod inspect_gpmf;
+mod inspect_json;
 
 pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    // Report time gaps between clips and sessions
+    if *args.get_one::<bool>("gaps").unwrap() {
+        return inspect_gaps::inspect_gaps(args);
+    }
+
+    // Structured output for other tools
+    if args.get_one::<String>("format").map(|s| s.as_str()) == Some("json") {
+        return inspect_json::inspect_json(args);
+    }
+
     // Inspect GoPro GPMF or Garmin FIT telemetry
     if args.get_one::<PathBuf>("gpmf").is_some() {
         return inspect_gpmf::inspect_gpmf(args);
@@ -25,7 +49,9 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
 
         let print_atoms = *args.get_one::<bool>("atoms").unwrap();
         let print_meta = *args.get_one::<bool>("meta").unwrap();
+        let save_atoms_json = *args.get_one::<bool>("atoms-json").unwrap();
         let track_offsets = args.get_one::<String>("offsets");
+        let track_dump = args.get_one::<String>("dump");
 
         let mut mp4 = match mp4iter::Mp4::new(path) {
             Ok(v) => v,
@@ -64,6 +90,23 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
             }
         }
 
+        if let Some(track_id) = track_dump {
+            match dump::dump_track(&path, track_id)? {
+                Some(p) => info!("Wrote {}", p.display()),
+                None => info!("User aborted writing track"),
+            }
+            return Ok(());
+        }
+
+        if let Some(name) = args.get_one::<String>("dump-atom") {
+            let hex = *args.get_one::<bool>("hex").unwrap();
+            return dump::dump_atom(path, name, hex);
+        }
+
+        if let Some(other) = args.get_one::<PathBuf>("atoms-diff") {
+            return inspect_atoms::atoms_diff(path, other);
+        }
+
         println!("Tracks:");
         let tracks = mp4.track_list(false)?;
         for (i, track) in tracks.iter().enumerate() {
@@ -82,53 +125,55 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
             }
         }
 
+        // Edit lists and timecode are not exposed by mp4iter
+        if let Ok((movie_timescale, track_atoms)) = atoms::tracks(path) {
+            for track in track_atoms.iter().filter(|t| !t.edits.is_empty()) {
+                println!("  Edit list for track {} ({}), start offset: {:.3}s",
+                    track.id,
+                    track.handler_name,
+                    track.edit_offset(movie_timescale)
+                );
+                for (i, edit) in track.edits.iter().enumerate() {
+                    println!("    {:2}. Segment duration: {:<10} Media time: {:<10} Rate: {}",
+                        i+1,
+                        edit.segment_duration,
+                        edit.media_time,
+                        edit.media_rate
+                    );
+                }
+            }
+        }
+        if let Ok(Some(timecode)) = atoms::timecode(path) {
+            println!("Start timecode: {timecode} ({} fps)", timecode.fps);
+        }
+        if let Ok(Some(location)) = atoms::location(path) {
+            println!("Location (udta): {location}");
+        }
+        if let Ok(Some(file_type)) = atoms::file_type(path) {
+            println!("File type (ftyp): {file_type}");
+            if file_type.is_edited() {
+                warn!("(!) File appears to have been processed by editing software.");
+            }
+        }
+
         println!("---");
 
         if print_atoms {
-
-            mp4.reset()?;
-
             // Print atom fourcc, size, offsets
-            // 'sizes' contains 'atom size - 8' since 8 byte header is already read.
-            // Each value will decrease until it's 0 which flags that it shold be removed.
-            // Last value is added last and will be removed first as it indicates
-            // the container atom is child to another container atom.
-            let mut sizes: Vec<u64> = Vec::new();
-            for header in mp4.into_iter() {
-                let mut pop = false;
-                let indent = sizes.len();
-                let is_container = header.is_container();
-                for size in sizes.iter_mut() {
-                    if is_container {
-                        *size -= 8;
-                    } else {
-                        *size -= header.atom_size();
-                    }
-                    if size == &mut 0 {
-                        pop = true;
-                    }
-                }
-
+            for (indent, name, offset, size) in atom_tree(mp4)?.iter() {
                 println!(
                     "{}{} @{} size: {}",
-                    "    ".repeat(indent as usize),
-                    header.name().to_str(),
-                    header.offset(),
-                    header.atom_size(),
+                    "    ".repeat(*indent),
+                    name,
+                    offset,
+                    size,
                 );
-                if is_container {
-                    sizes.push(header.atom_size() - 8);
-                }
-                if pop {
-                    loop {
-                        match sizes.last() {
-                            Some(&0) => {let _popped = sizes.pop();},
-                            _ => break,
-                        }
-                    }
-                }
             }
             println!("---");
+            if save_atoms_json {
+                let name_template = args.get_one::<String>("name").map(|s| s.as_str());
+                inspect_atoms::atoms_json(path, name_template)?;
+            }
         }
 
         match model {
@@ -253,3 +298,192 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
 
     Ok(())
 }
+
+/// Returns MP4 atom hierarchy as `(depth, fourcc, offset, size)`,
+/// in file order.
+pub(crate) fn atom_tree(mut mp4: Mp4) -> std::io::Result<Vec<(usize, String, u64, u64)>> {
+    mp4.reset()?;
+
+    // 'sizes' contains 'atom size - 8' since 8 byte header is already read.
+    // Each value will decrease until it's 0 which flags that it shold be removed.
+    // Last value is added last and will be removed first as it indicates
+    // the container atom is child to another container atom.
+    let mut tree: Vec<(usize, String, u64, u64)> = Vec::new();
+    let mut sizes: Vec<u64> = Vec::new();
+    for header in mp4.into_iter() {
+        let mut pop = false;
+        let indent = sizes.len();
+        let is_container = header.is_container();
+        for size in sizes.iter_mut() {
+            if is_container {
+                *size -= 8;
+            } else {
+                *size -= header.atom_size();
+            }
+            if size == &mut 0 {
+                pop = true;
+            }
+        }
+
+        tree.push((
+            indent,
+            header.name().to_str().to_string(),
+            header.offset(),
+            header.atom_size(),
+        ));
+        if is_container {
+            sizes.push(header.atom_size() - 8);
+        }
+        if pop {
+            while let Some(&0) = sizes.last() {
+                sizes.pop();
+            }
+        }
+    }
+
+    Ok(tree)
+}
+
+/// Returns output path for data derived from `path`,
+/// e.g. `GH010006_GPS.csv` for suffix `_GPS` and extension `csv`,
+/// or a name rendered from the '--name' template if set.
+/// `datetime` (e.g. first logged point) sets the `{date}` placeholder.
+pub(crate) fn output_path(
+    template: Option<&str>,
+    path: &Path,
+    datetime: Option<PrimitiveDateTime>,
+    suffix: &str,
+    extension: &str,
+) -> PathBuf {
+    let values = match template {
+        Some(_) => vec![
+            ("session", session_id(path).unwrap_or_default()),
+            (
+                "date",
+                datetime.map(|dt| dt.date().to_string()).unwrap_or_default(),
+            ),
+        ],
+        None => Vec::new(),
+    };
+    template_file_name(path, template, &values, Some(suffix), Some(extension))
+}
+
+/// Table row for `write_type_table()`, in the form `(id, type, cells)`,
+/// where `id` is the FIT global ID or the GPMF stream index,
+/// and `cells` are `(column name, value)`.
+pub(crate) type TypeRow<T> = (T, String, Vec<(String, String)>);
+
+/// Splits the debug output for a value, e.g. `Sint16([1, -2, 3])`,
+/// into cells `["1", "-2", "3"]`. Quoted strings are unquoted.
+pub(crate) fn debug_cells(debug: &str) -> Vec<String> {
+    let mut inner = debug.trim();
+    // Strip enum variant name, e.g. 'Sint16(...)', 'Some(...)'
+    if let Some(start) = inner.find('(').filter(|i| {
+        inner[..*i]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }) {
+        inner = inner[start + 1..]
+            .strip_suffix(')')
+            .unwrap_or(&inner[start + 1..]);
+    }
+    let inner = inner.trim_start_matches('[').trim_end_matches(']');
+    if inner.is_empty() {
+        return Vec::new();
+    }
+    inner
+        .split(", ")
+        .map(|c| c.trim().trim_matches('"').to_owned())
+        .collect()
+}
+
+/// Writes data for a FIT message type or GPMF stream type
+/// ('--type') as CSV and/or JSON, one row per message/sample,
+/// with one column per field. Columns are the union of all
+/// rows' cells, in the order first encountered.
+/// File name is derived from `path`, e.g. `GH010006_<TYPE>.csv`,
+/// or from `template` if set.
+pub(crate) fn write_type_table<T: std::fmt::Display>(
+    path: &Path,
+    template: Option<&str>,
+    data_type: &str,
+    rows: &[TypeRow<T>],
+    csv: bool,
+    json: bool,
+) -> std::io::Result<()> {
+    let suffix = format!(
+        "_{}",
+        data_type
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+    );
+
+    let mut columns: Vec<&str> = Vec::new();
+    for (name, _) in rows.iter().flat_map(|(_, _, cells)| cells.iter()) {
+        if !columns.contains(&name.as_str()) {
+            columns.push(name);
+        }
+    }
+    let cell = |cells: &[(String, String)], column: &str| {
+        cells
+            .iter()
+            .find(|(name, _)| name == column)
+            .map(|(_, value)| value.to_owned())
+    };
+
+    if csv {
+        let header = ["INDEX", "ID", "TYPE"]
+            .into_iter()
+            .chain(columns.iter().copied())
+            .map(csv_field)
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut table: Vec<String> = vec![header];
+        for (i, (id, dtype, cells)) in rows.iter().enumerate() {
+            let row = [(i + 1).to_string(), id.to_string(), dtype.to_owned()]
+                .into_iter()
+                .chain(columns.iter().map(|c| cell(cells, c).unwrap_or_default()))
+                .map(|v| csv_field(&v))
+                .collect::<Vec<_>>()
+                .join(",");
+            table.push(row);
+        }
+        let csv_path = output_path(template, path, None, &suffix, "csv");
+        if writefile(table.join("\n").as_bytes(), &csv_path)?.is_written() {
+            info!("Wrote {}", csv_path.display());
+        }
+    }
+
+    if json {
+        let values = rows
+            .iter()
+            .enumerate()
+            .map(|(i, (id, dtype, cells))| {
+                let mut row = json!({
+                    "index": i + 1,
+                    "id": id.to_string(),
+                    "type": dtype,
+                });
+                for column in columns.iter() {
+                    // Numerical values as JSON numbers
+                    row[*column] = match cell(cells, column) {
+                        Some(v) => match v.parse::<f64>() {
+                            Ok(n) if n.is_finite() => json!(n),
+                            _ => json!(v),
+                        },
+                        None => serde_json::Value::Null,
+                    };
+                }
+                row
+            })
+            .collect::<Vec<_>>();
+        let json_path = output_path(template, path, None, &suffix, "json");
+        let json_string = serde_json::to_string_pretty(&values)?;
+        if writefile(json_string.as_bytes(), &json_path)?.is_written() {
+            info!("Wrote {}", json_path.display());
+        }
+    }
+
+    Ok(())
+}
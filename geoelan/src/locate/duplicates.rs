@@ -0,0 +1,226 @@
+//! Detect duplicate clips, e.g. when memory cards have been backed up
+//! into several directories. Clips are duplicates if they share
+//! MUID (GoPro) or UUID (VIRB), chapter number (GoPro), file type,
+//! and file size, or, optionally, if the media data (`mdat`) is identical.
+//!
+//! MUID alone is not enough, since all chapters in a GoPro recording
+//! session share the same MUID.
+//!
+//! For each group of duplicates, the copy with the oldest modification
+//! time is kept, the remaining copies are reported as redundant.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde_json::json;
+
+use crate::{
+    files::{csv_field, has_extension_any, paths, search_dirs},
+    logging::{info, warn},
+    media::{atoms::mdat_sha256, timeline::gopro_chapter},
+    model::session_id,
+    progress::Progress,
+};
+
+/// Clips that are copies of each other.
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    /// `id` (same MUID/UUID, chapter, and size)
+    /// or `mdat` (identical media data).
+    pub reason: &'static str,
+    /// MUID with chapter number (GoPro), e.g. `<MUID>:02`,
+    /// UUID (VIRB), or `mdat` SHA-256.
+    pub key: String,
+    /// Copy to keep, i.e. the one with the oldest modification time.
+    pub keep: PathBuf,
+    /// Remaining copies.
+    pub redundant: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    /// Returns group if there is more than one path.
+    fn new(reason: &'static str, key: &str, mut paths: Vec<PathBuf>) -> Option<Self> {
+        if paths.len() < 2 {
+            return None;
+        }
+        paths.sort_by_key(|p| (modified(p), p.to_owned()));
+        let keep = paths.remove(0);
+        Some(Self {
+            reason,
+            key: key.to_owned(),
+            keep,
+            redundant: paths,
+        })
+    }
+}
+
+fn modified(path: &Path) -> SystemTime {
+    path.metadata()
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Returns duplicate clips among `clips`, grouped by MUID/UUID,
+/// chapter number, file extension (high- and low-resolution clips share
+/// identifiers), and file size.
+/// If `mdat` is `true`, clips with identical media data but differing
+/// identifiers, or none, are also grouped. This requires reading all clips in full.
+pub fn duplicates(clips: &[PathBuf], mdat: bool) -> Vec<DuplicateGroup> {
+    // Key: (MUID/UUID, chapter, extension, size)
+    let mut by_id: HashMap<(String, Option<u32>, String, u64), Vec<PathBuf>> = HashMap::new();
+    let progress = Progress::new("Reading clip IDs", clips.len() as u64);
+    for path in clips.iter() {
+        progress.inc(1);
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let size = match path.metadata() {
+            Ok(m) => m.len(),
+            Err(err) => {
+                warn!("(!) Failed to read '{}': {err}", path.display());
+                continue;
+            }
+        };
+        if let Some(id) = session_id(path) {
+            by_id
+                .entry((id, gopro_chapter(path), ext, size))
+                .or_default()
+                .push(path.to_owned());
+        }
+    }
+    progress.finish();
+
+    let mut groups: Vec<DuplicateGroup> = by_id
+        .into_iter()
+        .filter_map(|((id, chapter, ..), paths)| {
+            let key = match chapter {
+                Some(n) => format!("{id}:{n:02}"),
+                None => id,
+            };
+            DuplicateGroup::new("id", &key, paths)
+        })
+        .collect();
+
+    if mdat {
+        let mut by_hash: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let progress = Progress::new("Hashing media data", clips.len() as u64);
+        for path in clips.iter() {
+            progress.inc(1);
+            match mdat_sha256(path) {
+                Ok(Some(hash)) => by_hash.entry(hash).or_default().push(path.to_owned()),
+                Ok(None) => (),
+                Err(err) => warn!("(!) Failed to read '{}': {err}", path.display()),
+            }
+        }
+        progress.finish();
+
+        // Skip groups already reported as same ID
+        let identical: Vec<DuplicateGroup> = by_hash
+            .into_iter()
+            .filter_map(|(hash, paths)| DuplicateGroup::new("mdat", &hash, paths))
+            .filter(|g| {
+                !g.redundant.iter().all(|p| {
+                    groups
+                        .iter()
+                        .any(|i| i.keep == g.keep && i.redundant.contains(p))
+                })
+            })
+            .collect();
+        groups.extend(identical);
+    }
+
+    groups.sort_by(|a, b| a.keep.cmp(&b.keep));
+    groups
+}
+
+fn print_text(groups: &[DuplicateGroup]) {
+    for (i, group) in groups.iter().enumerate() {
+        let reason = match group.reason {
+            "mdat" => "identical media data",
+            _ => "same MUID/UUID, chapter, and size",
+        };
+        println!("{:4}. {} ({reason})", i + 1, group.key);
+        println!("      Keep:      {}", group.keep.display());
+        for path in group.redundant.iter() {
+            println!("      Redundant: {}", path.display());
+        }
+    }
+    let redundant: Vec<&PathBuf> = groups.iter().flat_map(|g| g.redundant.iter()).collect();
+    let bytes: u64 = redundant
+        .iter()
+        .filter_map(|p| p.metadata().ok())
+        .map(|m| m.len())
+        .sum();
+    info!(
+        "Done. {} redundant copies in {} groups ({:.2} GB).",
+        redundant.len(),
+        groups.len(),
+        bytes as f64 / 1_000_000_000.
+    );
+}
+
+fn print_json(groups: &[DuplicateGroup]) {
+    let json: Vec<_> = groups
+        .iter()
+        .map(|g| {
+            json!({
+                "reason": g.reason,
+                "key": g.key,
+                "keep": g.keep.display().to_string(),
+                "redundant": g.redundant.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json).unwrap_or_default()
+    );
+}
+
+fn print_csv(groups: &[DuplicateGroup]) {
+    println!("group,reason,key,status,path");
+    for (i, group) in groups.iter().enumerate() {
+        let rows = std::iter::once(("keep", &group.keep))
+            .chain(group.redundant.iter().map(|p| ("redundant", p)));
+        for (status, path) in rows {
+            println!(
+                "{},{},{},{status},{}",
+                i + 1,
+                group.reason,
+                group.key,
+                csv_field(&path.display().to_string())
+            );
+        }
+    }
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let mdat = *args.get_one::<bool>("mdat-hash").unwrap();
+    let format = args.get_one::<String>("format").unwrap().as_str(); // clap: default 'text'
+
+    let search = search_dirs(args)?;
+    let clips: Vec<PathBuf> = match &search.files {
+        Some(files) => files.to_owned(),
+        None => search
+            .dirs
+            .iter()
+            .flat_map(|dir| paths(dir, &["mp4", "lrv", "glv"]))
+            .collect(),
+    }
+    .into_iter()
+    .filter(|p| has_extension_any(p, &["mp4", "lrv", "glv"]))
+    .collect();
+
+    let groups = duplicates(&clips, mdat);
+    match format {
+        "json" => print_json(&groups),
+        "csv" => print_csv(&groups),
+        _ => print_text(&groups),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+//! Filter located recording sessions by date range (`--after`, `--before`)
+//! and location (`--near LAT,LON,RADIUS`).
+
+use std::io::ErrorKind;
+
+use fit_rs::VirbSession;
+use gpmf_rs::GoProSession;
+use time::PrimitiveDateTime;
+
+use crate::geo::{geo_gpx::parse_datetime, haversine, EafPoint, EafPointCluster};
+
+/// Session filter. Unset fields always match.
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter {
+    pub after: Option<PrimitiveDateTime>,
+    pub before: Option<PrimitiveDateTime>,
+    /// Latitude, longitude, radius in meters.
+    pub near: Option<(f64, f64, f64)>,
+}
+
+/// Parses datetime, e.g. `2024-03-01T12:00:00`, or date only, e.g. `2024-03-01`,
+/// which is interpreted as the start of the day for `--after`,
+/// and the end of the day for `--before`, so that the whole day is included.
+fn parse_date_arg(value: &str, id: &str) -> std::io::Result<PrimitiveDateTime> {
+    let value = value.trim();
+    let datetime = match (value.len(), id) {
+        (10, "before") => parse_datetime(&format!("{value}T23:59:59.999")),
+        (10, _) => parse_datetime(&format!("{value}T00:00:00")),
+        _ => parse_datetime(value),
+    };
+    datetime.ok_or_else(|| {
+        let msg = format!("(!) Invalid datetime '{value}' for '--{id}'. Use e.g. '2024-03-01' or '2024-03-01T12:00:00'.");
+        std::io::Error::new(ErrorKind::Other, msg)
+    })
+}
+
+/// Parses `LAT,LON,RADIUS`, with radius in meters.
+fn parse_near(value: &str) -> std::io::Result<(f64, f64, f64)> {
+    let values: Vec<f64> = value
+        .split(',')
+        .filter_map(|v| v.trim().parse::<f64>().ok())
+        .collect();
+    match values[..] {
+        [lat, lon, radius]
+            if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) && radius > 0.0 =>
+        {
+            Ok((lat, lon, radius))
+        }
+        _ => {
+            let msg = format!("(!) Invalid value '{value}' for '--near'. Use 'LAT,LON,RADIUS' in decimal degrees and meters, e.g. '57.70,11.97,500'.");
+            Err(std::io::Error::new(ErrorKind::Other, msg))
+        }
+    }
+}
+
+impl SessionFilter {
+    pub fn from_args(args: &clap::ArgMatches) -> std::io::Result<Self> {
+        Ok(Self {
+            after: args
+                .get_one::<String>("after")
+                .map(|s| parse_date_arg(s, "after"))
+                .transpose()?,
+            before: args
+                .get_one::<String>("before")
+                .map(|s| parse_date_arg(s, "before"))
+                .transpose()?,
+            near: args
+                .get_one::<String>("near")
+                .map(|s| parse_near(s))
+                .transpose()?,
+        })
+    }
+
+    /// Returns `true` if any filter is set.
+    pub fn is_set(&self) -> bool {
+        self.after.is_some() || self.before.is_some() || self.near.is_some()
+    }
+
+    /// Returns `true` if the session overlaps the date range.
+    /// Sessions with unknown start never match a date range.
+    pub fn in_range(
+        &self,
+        start: Option<PrimitiveDateTime>,
+        end: Option<PrimitiveDateTime>,
+    ) -> bool {
+        if self.after.is_none() && self.before.is_none() {
+            return true;
+        }
+        let Some(start) = start else {
+            return false;
+        };
+        let end = end.unwrap_or(start);
+        self.after.is_none_or(|after| end >= after)
+            && self.before.is_none_or(|before| start <= before)
+    }
+
+    /// Returns `true` if any point lies within the radius.
+    pub fn is_near(&self, points: &[EafPoint]) -> bool {
+        let Some((lat, lon, radius)) = self.near else {
+            return true;
+        };
+        points
+            .iter()
+            .any(|p| haversine(lat, lon, p.latitude, p.longitude) * 1000.0 <= radius)
+    }
+
+    /// Returns `true` if the GoPro session matches all filters.
+    /// GPS is only parsed if `--near` is set.
+    pub fn gopro(&self, session: &GoProSession) -> bool {
+        if !self.in_range(session.start(), session.end()) {
+            return false;
+        }
+        if self.near.is_none() {
+            return true;
+        }
+        let points: Vec<EafPoint> = match session.gpmf() {
+            Ok(gpmf) => gpmf
+                .gps()
+                .prune(2, None)
+                .iter()
+                .map(EafPoint::from)
+                .collect(),
+            Err(_) => return false,
+        };
+        self.is_near(&points)
+    }
+
+    /// Returns `true` if the VIRB session matches all filters.
+    /// GPS is only parsed if `--near` is set.
+    pub fn virb(&self, session: &mut VirbSession) -> bool {
+        if !self.in_range(session.start(), session.end()) {
+            return false;
+        }
+        if self.near.is_none() {
+            return true;
+        }
+        if session.process(0).is_err() {
+            return false;
+        }
+        let (Ok(gps), Some(t0), Some(end)) = (session.gps(), session.t0, session.end) else {
+            return false;
+        };
+        self.is_near(&EafPointCluster::from_virb(&gps, None, &t0, &end, None).points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_only_covers_whole_day() {
+        let filter = SessionFilter {
+            after: parse_date_arg("2024-03-01", "after").ok(),
+            before: parse_date_arg("2024-03-01", "before").ok(),
+            near: None,
+        };
+        let time = |t: &str| parse_datetime(&format!("2024-03-{t}"));
+        assert!(filter.in_range(time("01T00:00:00"), None));
+        assert!(filter.in_range(time("01T23:59:59.5"), None));
+        assert!(!filter.in_range(time("02T00:00:00"), None));
+        assert!(!filter.in_range(time("29T23:00:00"), time("29T23:59:59")));
+    }
+}
@@ -7,6 +7,15 @@ use std::{
 
 use gpmf_rs::{GoProSession, GOPRO_DATETIME_DEFAULT};
 
+use crate::logging::info;
+use crate::{
+    files::search_dirs,
+    model::{muid_string, stable_session_id_gopro},
+    progress::Progress,
+};
+
+use super::{filter::SessionFilter, print_listing, ClipListing, SessionListing};
+
 fn path2string(path: &Path, count: Option<usize>) -> String {
     if let Some(c) = count {
         format!("{:02}. {}", c + 1, path.display())
@@ -19,23 +28,49 @@ fn path2string(path: &Path, count: Option<usize>) -> String {
 pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     let timer = Instant::now();
 
-    // required arg
-    let indir: PathBuf = args
-        .get_one::<PathBuf>("input-directory")
-        .unwrap()
-        .canonicalize()?;
+    let search = search_dirs(args)?;
     let video = args.get_one::<PathBuf>("video");
     let verify_gpmf = *args.get_one::<bool>("verify").unwrap();
     let verbose = *args.get_one::<bool>("verbose").unwrap();
     let halt_on_error = *args.get_one::<bool>("halt-on-error").unwrap();
+    let format = args.get_one::<String>("format").unwrap().as_str(); // clap: default 'text'
+    let filter = SessionFilter::from_args(args)?;
+    // Status output only for text listings, so that stdout
+    // contains nothing but the listing for json and csv
+    let listing_only = format != "text";
 
-    let mut sessions = GoProSession::sessions_from_path(
-        &indir,
-        video.map(|p| p.as_path()),
-        verify_gpmf,
-        true,
-        !halt_on_error,
-    )?;
+    let mut sessions: Vec<GoProSession> = Vec::new();
+    let progress = Progress::new("Scanning directories", search.dirs.len() as u64);
+    for indir in search.dirs.iter() {
+        sessions.extend(GoProSession::sessions_from_path(
+            indir,
+            video.map(|p| p.as_path()),
+            verify_gpmf,
+            !listing_only,
+            !halt_on_error,
+        )?);
+        progress.inc(1);
+    }
+    progress.finish();
+    // Only keep sessions with listed clips
+    if search.files.is_some() {
+        sessions.retain(|s| {
+            s.iter().any(|f| {
+                [&f.mp4, &f.lrv]
+                    .iter()
+                    .any(|p| p.as_ref().map(|p| search.is_listed(p)).unwrap_or(false))
+            })
+        });
+    }
+    // Only keep sessions within date range and/or near location
+    if filter.is_set() {
+        let progress = Progress::new("Filtering sessions", sessions.len() as u64);
+        sessions.retain(|s| {
+            progress.inc(1);
+            filter.gopro(s)
+        });
+        progress.finish();
+    }
     // let sessions = GoProSession::sessions_from_path_par(
     //     &indir,
     //     video.map(|p| p.as_path()),
@@ -45,6 +80,36 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     // );
     sessions.sort_by_key(|s| s.start().unwrap_or(GOPRO_DATETIME_DEFAULT)); // Add this to sessions_from_path instead
 
+    if listing_only {
+        let listing: Vec<SessionListing> = sessions
+            .iter()
+            .map(|session| SessionListing {
+                camera: "gopro",
+                session_id: session
+                    .iter()
+                    .next()
+                    .map(|f| muid_string(&f.muid))
+                    .unwrap_or_default(),
+                stable_session_id: stable_session_id_gopro(session),
+                start: session.start(),
+                end: session.end(),
+                duration: Some(session.duration().as_seconds_f64()),
+                fit: None,
+                clips: session
+                    .iter()
+                    .map(|file| ClipListing {
+                        id: muid_string(&file.muid),
+                        mp4: file.mp4.as_deref().map(|p| search.original(p)),
+                        low_res: file.lrv.as_deref().map(|p| search.original(p)),
+                        start: Some(file.start()),
+                    })
+                    .collect(),
+            })
+            .collect();
+        print_listing(&listing, format);
+        return Ok(());
+    }
+
     println!("---");
     for (i1, session) in sessions.iter().enumerate() {
         println!(
@@ -78,32 +143,32 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
                 println!(
                     "┃     MP4: {}",
                     file.mp4
-                        .as_ref()
-                        .and_then(|f| f.to_str())
-                        .unwrap_or("High-resolution MP4 not found")
+                        .as_deref()
+                        .map(|f| search.original(f).display().to_string())
+                        .unwrap_or("High-resolution MP4 not found".to_owned())
                 );
             } else {
                 println!(
                     "┃{:2}.  MP4: {}",
                     i2 + 1,
                     file.mp4
-                        .as_ref()
-                        .and_then(|f| f.to_str())
-                        .unwrap_or("High-resolution MP4 not found")
+                        .as_deref()
+                        .map(|f| search.original(f).display().to_string())
+                        .unwrap_or("High-resolution MP4 not found".to_owned())
                 );
             }
             println!(
                 "┃     LRV: {}",
                 file.lrv
-                    .as_ref()
-                    .and_then(|f| f.to_str())
-                    .unwrap_or("Low-resolution MP4 not found")
+                    .as_deref()
+                    .map(|f| search.original(f).display().to_string())
+                    .unwrap_or("Low-resolution MP4 not found".to_owned())
             );
         }
         println!("┗━━━━");
     }
 
-    println!(
+    info!(
         "Done ({:?}). {}",
         timer.elapsed(),
         if verify_gpmf {
@@ -112,7 +177,7 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
             " Run with '--verify' to skip clips with GPMF errors."
         }
     );
-    println!("Sessions are sorted by time for start of recording, but may be misreprepresentative, depending on camera setup.");
+    info!("Sessions are sorted by time for start of recording, but may be misreprepresentative, depending on camera setup.");
 
     Ok(())
 }
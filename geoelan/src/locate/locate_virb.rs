@@ -5,20 +5,40 @@ use std::{io::ErrorKind, path::PathBuf};
 
 use fit_rs::{Fit, VirbSession, FIT_DEFAULT_DATETIME};
 
-use crate::files::virb::select_session;
+use crate::{
+    files::{
+        search_dirs,
+        virb::{select_session, verify_fit},
+    },
+    logging::{info, warn},
+    model::stable_session_id_virb,
+    progress::Progress,
+};
+
+use super::{filter::SessionFilter, print_listing, ClipListing, SessionListing};
 
 // MAIN VIRB LOCATE
 pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     let timer = Instant::now();
 
-    let indir = args.get_one::<PathBuf>("input-directory").unwrap(); // required arg
+    let search = search_dirs(args)?;
     let video_path_opt = args.get_one::<PathBuf>("video");
     let fit_path_opt = args.get_one::<PathBuf>("fit");
     let uuid_opt = args.get_one::<String>("uuid");
     let verbose = *args.get_one::<bool>("verbose").unwrap();
+    let verify = *args.get_one::<bool>("verify").unwrap();
+    let format = args.get_one::<String>("format").unwrap().as_str(); // clap: default 'text'
+    let filter = SessionFilter::from_args(args)?;
+    // Status output only for text listings, so that stdout
+    // contains nothing but the listing for json and csv
+    let listing_only = format != "text";
+
+    if let (Some(path), true) = (fit_path_opt, verify) {
+        verify_fit(path)?;
+    }
 
-    let session = match (video_path_opt, fit_path_opt, uuid_opt) {
-        (Some(path), ..) => VirbSession::from_mp4(path, indir, true),
+    // UUID for first clip in session
+    let uuid = match (video_path_opt, fit_path_opt, uuid_opt) {
         (_, Some(path), _) => {
             let fit = Fit::parse(path, Some(161), false)?; // only need camera_event/161
             let fit_session = select_session(&fit)?;
@@ -27,17 +47,21 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
             // but a recording session
             // must consist of at least one video clip,
             // and thus have at least corresponding UUID.
-            let uuid = fit_session.uuid.get(0);
-
-            match uuid {
-                Some(u) => VirbSession::from_uuid(u, indir, true),
-                None => None,
-            }
+            fit_session.uuid.get(0).cloned()
         }
-        (.., Some(string)) => VirbSession::from_uuid(string, indir, true),
+        (.., Some(string)) => Some(string.to_owned()),
         _ => None,
     };
 
+    let session = search
+        .dirs
+        .iter()
+        .find_map(|indir| match (video_path_opt, &uuid) {
+            (Some(path), _) => VirbSession::from_mp4(path, indir, !listing_only),
+            (_, Some(u)) => VirbSession::from_uuid(u, indir, !listing_only),
+            _ => None,
+        });
+
     // Check if session was specified and found...
     let session_specified =
         video_path_opt.is_some() || fit_path_opt.is_some() || uuid_opt.is_some();
@@ -51,11 +75,93 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
 
     let mut sessions = match session {
         Some(s) => vec![s],
-        None => VirbSession::sessions_from_path(&indir, true),
+        None => {
+            let progress = Progress::new("Scanning directories", search.dirs.len() as u64);
+            let sessions = search
+                .dirs
+                .iter()
+                .flat_map(|indir| {
+                    let s = VirbSession::sessions_from_path(indir, !listing_only);
+                    progress.inc(1);
+                    s
+                })
+                .collect();
+            progress.finish();
+            sessions
+        }
     };
 
+    // Only keep sessions with listed clips or FIT-files
+    if search.files.is_some() {
+        sessions.retain(|s| {
+            search.is_listed(&s.fit_path())
+                || s.virb.iter().any(|v| {
+                    [v.mp4(), v.glv()]
+                        .iter()
+                        .any(|p| p.map(|p| search.is_listed(p)).unwrap_or(false))
+                })
+        });
+    }
+
+    // Skip sessions with a corrupt FIT-file
+    if verify {
+        let progress = Progress::new("Verifying FIT-files", sessions.len() as u64);
+        sessions.retain(|s| {
+            progress.inc(1);
+            match verify_fit(&s.fit_path()) {
+                Ok(_) => true,
+                Err(err) => {
+                    warn!("(!) Skipping {}: {err}", s.fit_path().display());
+                    false
+                }
+            }
+        });
+        progress.finish();
+    }
+
+    // Only keep sessions within date range and/or near location
+    if filter.is_set() {
+        let progress = Progress::new("Filtering sessions", sessions.len() as u64);
+        sessions.retain_mut(|s| {
+            progress.inc(1);
+            filter.virb(s)
+        });
+        progress.finish();
+    }
+
     sessions.sort_by_key(|v| v.start().unwrap_or_else(|| FIT_DEFAULT_DATETIME));
 
+    if listing_only {
+        let listing: Vec<SessionListing> = sessions
+            .iter()
+            .map(|session| SessionListing {
+                camera: "virb",
+                session_id: session
+                    .virb
+                    .first()
+                    .map(|v| v.uuid.to_owned())
+                    .unwrap_or_default(),
+                stable_session_id: stable_session_id_virb(session),
+                start: session.start(),
+                end: session.end(),
+                duration: session.video_duration().map(|d| d.as_seconds_f64()),
+                fit: Some(search.original(&session.fit_path())),
+                clips: session
+                    .virb
+                    .iter()
+                    .map(|virbfile| ClipListing {
+                        id: virbfile.uuid.to_owned(),
+                        mp4: virbfile.mp4().map(|p| search.original(p)),
+                        low_res: virbfile.glv().map(|p| search.original(p)),
+                        start: virbfile.created(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        print_listing(&listing, format);
+        return Ok(());
+    }
+
     println!("---");
     for (i1, session) in sessions.iter().enumerate() {
         // println!("[ Session {} ]\n      FIT: {}", i1+1, session.fit.path.display());
@@ -76,7 +182,10 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
                 .unwrap_or("Failed to determine duration".to_owned()),
         );
 
-        println!("┃ FIT       {}", session.fit_path().display());
+        println!(
+            "┃ FIT       {}",
+            search.original(&session.fit_path()).display()
+        );
         println!("┠─────");
 
         for (i2, virbfile) in session.virb.iter().enumerate() {
@@ -93,8 +202,8 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
                     "┃      MP4: {}",
                     virbfile
                         .mp4()
-                        .and_then(|f| f.to_str())
-                        .unwrap_or("High-resolution MP4 not found")
+                        .map(|f| search.original(f).display().to_string())
+                        .unwrap_or("High-resolution MP4 not found".to_owned())
                 );
             } else {
                 println!(
@@ -102,23 +211,23 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
                     i2 + 1,
                     virbfile
                         .mp4()
-                        .and_then(|f| f.to_str())
-                        .unwrap_or("High-resolution MP4 not found")
+                        .map(|f| search.original(f).display().to_string())
+                        .unwrap_or("High-resolution MP4 not found".to_owned())
                 );
             }
             println!(
                 "┃      GLV: {}",
                 virbfile
                     .glv()
-                    .and_then(|f| f.to_str())
-                    .unwrap_or("Low-resolution MP4 not found")
+                    .map(|f| search.original(f).display().to_string())
+                    .unwrap_or("Low-resolution MP4 not found".to_owned())
             );
         }
         println!("┗━━━━");
     }
 
-    println!("Done ({:?})", timer.elapsed());
-    println!("Sessions are sorted by time for start of recording, but may be misreprepresentative, depending on camera setup.");
+    info!("Done ({:?})", timer.elapsed());
+    info!("Sessions are sorted by time for start of recording, but may be misreprepresentative, depending on camera setup.");
 
     Ok(())
 }
@@ -2,13 +2,137 @@
 
 use std::{io::ErrorKind, path::PathBuf};
 
-use crate::model::CameraModel;
+use serde_json::json;
+use time::PrimitiveDateTime;
 
+use crate::{files::csv_field, media::Media, model::CameraModel};
+
+pub mod duplicates;
+pub mod filter;
 pub mod locate_gopro;
 pub mod locate_virb;
 
+/// Single clip in a located recording session,
+/// for machine-readable output (`--format json|csv`).
+#[derive(Debug, Clone)]
+pub struct ClipListing {
+    /// MUID (GoPro) or UUID (VIRB).
+    pub id: String,
+    /// High-resolution MP4.
+    pub mp4: Option<PathBuf>,
+    /// Low-resolution MP4 (GoPro LRV, VIRB GLV).
+    pub low_res: Option<PathBuf>,
+    pub start: Option<PrimitiveDateTime>,
+}
+
+impl ClipListing {
+    /// Clip duration in seconds, read from the MP4 or the low-resolution clip.
+    fn duration(&self) -> Option<f64> {
+        [&self.mp4, &self.low_res]
+            .iter()
+            .find_map(|p| Media::duration(p.as_deref()?).ok())
+            .map(|d| d.as_seconds_f64())
+    }
+}
+
+/// Located recording session, for machine-readable output (`--format json|csv`).
+#[derive(Debug, Clone)]
+pub struct SessionListing {
+    /// `gopro` or `virb`.
+    pub camera: &'static str,
+    /// MUID (GoPro) or UUID (VIRB) for the first clip in the session.
+    pub session_id: String,
+    /// Hardware independent session identifier.
+    /// See `model::stable_session_id()`.
+    pub stable_session_id: String,
+    pub start: Option<PrimitiveDateTime>,
+    pub end: Option<PrimitiveDateTime>,
+    /// Session duration in seconds.
+    pub duration: Option<f64>,
+    /// VIRB only.
+    pub fit: Option<PathBuf>,
+    pub clips: Vec<ClipListing>,
+}
+
+fn path_string(path: &Option<PathBuf>) -> Option<String> {
+    path.as_ref().map(|p| p.display().to_string())
+}
+
+/// Returns sessions as a JSON array, one object per session.
+pub fn listing_json(sessions: &[SessionListing]) -> String {
+    let json: Vec<_> = sessions
+        .iter()
+        .map(|s| {
+            json!({
+                "camera": s.camera,
+                "session_id": s.session_id,
+                "stable_session_id": s.stable_session_id,
+                "start": s.start.map(|t| t.to_string()),
+                "end": s.end.map(|t| t.to_string()),
+                "duration": s.duration,
+                "fit": path_string(&s.fit),
+                "clips": s.clips.iter().map(|c| json!({
+                    "id": c.id,
+                    "mp4": path_string(&c.mp4),
+                    "low_res": path_string(&c.low_res),
+                    "start": c.start.map(|t| t.to_string()),
+                    "duration": c.duration(),
+                })).collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&json).unwrap_or_default()
+}
+
+/// Returns sessions as CSV, one row per clip.
+pub fn listing_csv(sessions: &[SessionListing]) -> String {
+    let mut rows = vec![
+        "camera,session,session_id,stable_session_id,session_start,session_duration,fit,clip,clip_id,clip_start,clip_duration,mp4,low_res"
+            .to_owned(),
+    ];
+    for (i1, session) in sessions.iter().enumerate() {
+        for (i2, clip) in session.clips.iter().enumerate() {
+            let fields = [
+                session.camera.to_owned(),
+                (i1 + 1).to_string(),
+                session.session_id.to_owned(),
+                session.stable_session_id.to_owned(),
+                session.start.map(|t| t.to_string()).unwrap_or_default(),
+                session.duration.map(|d| d.to_string()).unwrap_or_default(),
+                path_string(&session.fit).unwrap_or_default(),
+                (i2 + 1).to_string(),
+                clip.id.to_owned(),
+                clip.start.map(|t| t.to_string()).unwrap_or_default(),
+                clip.duration().map(|d| d.to_string()).unwrap_or_default(),
+                path_string(&clip.mp4).unwrap_or_default(),
+                path_string(&clip.low_res).unwrap_or_default(),
+            ];
+            rows.push(
+                fields
+                    .iter()
+                    .map(|f| csv_field(f))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
+    }
+    rows.join("\n")
+}
+
+/// Prints sessions to stdout in the specified format (`json` or `csv`).
+pub fn print_listing(sessions: &[SessionListing], format: &str) {
+    match format {
+        "csv" => println!("{}", listing_csv(sessions)),
+        _ => println!("{}", listing_json(sessions)),
+    }
+}
+
 // MAIN LOCATE SUB-COMMAND
 pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    if *args.get_one::<bool>("duplicates").unwrap() {
+        return duplicates::run(args);
+    }
+
     if args.get_one::<PathBuf>("fit").is_some() || args.get_one::<String>("uuid").is_some() {
         // If FIT or UUID specified run VIRB locate...
         if let Err(err) = locate_virb::run(&args) {
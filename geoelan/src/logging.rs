@@ -0,0 +1,208 @@
+//! Logging with levels and an optional log file, so that batch runs
+//! produce auditable logs.
+//!
+//! Messages at or below the level set via `--log-level` are printed
+//! as is (errors and warnings to stderr, other levels to stdout).
+//! With `--quiet` only errors and warnings are printed.
+//! If `--log-file` is set, messages are also appended to the log file
+//! with a UTC timestamp and level, e.g.
+//! `2024-03-01 12:00:00 UTC [INFO] Wrote GH010006.eaf`.
+//!
+//! Use the `error!`, `warn!`, `info!`, and `debug!` macros
+//! in place of `println!`/`eprintln!` for status messages.
+//! Output the user asked for (listings, reports, `--json` documents)
+//! and interactive prompts are printed directly.
+
+use std::{
+    cell::RefCell,
+    fs::{File, OpenOptions},
+    io::{stderr, stdout, Write},
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl std::str::FromStr for Level {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("(!) Unknown log level '{value}'"),
+            )),
+        }
+    }
+}
+
+impl Level {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+        }
+    }
+}
+
+struct Logger {
+    level: Level,
+    /// Only print errors and warnings.
+    /// Other messages are still written to the log file.
+    quiet: bool,
+    file: Option<Mutex<File>>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+thread_local! {
+    /// Messages held back for the current thread, see `buffer()`.
+    static BUFFER: RefCell<Option<Vec<(Level, String)>>> = const { RefCell::new(None) };
+}
+
+/// Sets log level, quiet mode, and optional log file for the rest of the run.
+/// The log file is created if it does not exist, otherwise appended to.
+/// Only the first call has an effect.
+pub fn init(level: Level, quiet: bool, log_file: Option<&Path>) -> std::io::Result<()> {
+    let file = match log_file {
+        Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(f) => Some(Mutex::new(f)),
+            Err(err) => {
+                let msg = format!("(!) Failed to open log file '{}': {err}", path.display());
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
+            }
+        },
+        None => None,
+    };
+    let _ = LOGGER.set(Logger { level, quiet, file });
+    Ok(())
+}
+
+/// Returns current log level. Defaults to `Level::Info`.
+pub fn level() -> Level {
+    LOGGER.get().map(|l| l.level).unwrap_or(Level::Info)
+}
+
+/// Current UTC time, e.g. `2024-03-01 12:00:00 UTC`.
+fn timestamp() -> String {
+    let now = OffsetDateTime::now_utc();
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        now.year(),
+        now.month() as u8,
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second()
+    )
+}
+
+/// Writes message to log file only, if set, regardless of level.
+/// E.g. for recording the command line.
+pub fn record(level: Level, message: &str) {
+    let Some(file) = LOGGER.get().and_then(|l| l.file.as_ref()) else {
+        return;
+    };
+    if let Ok(mut f) = file.lock() {
+        for line in message.lines().filter(|l| !l.trim().is_empty()) {
+            let _ = writeln!(
+                f,
+                "{} [{}] {}",
+                timestamp(),
+                level.to_str(),
+                line.trim_end()
+            );
+        }
+    }
+}
+
+/// Holds back printed messages for the current thread until `flush()`,
+/// e.g. so that output from concurrent batch jobs does not interleave.
+/// Messages are still written to the log file as they occur.
+pub fn buffer() {
+    BUFFER.with(|b| *b.borrow_mut() = Some(Vec::new()));
+}
+
+/// Prints messages held back for the current thread since `buffer()`
+/// as one block, and stops holding back messages.
+pub fn flush() {
+    let Some(messages) = BUFFER.with(|b| b.borrow_mut().take()) else {
+        return;
+    };
+    // Lock both streams so that other threads can not print in between
+    let (mut out, mut err) = (stdout().lock(), stderr().lock());
+    for (level, message) in messages.iter() {
+        let _ = match level {
+            Level::Error | Level::Warn => writeln!(err, "{message}"),
+            Level::Info | Level::Debug => writeln!(out, "{message}"),
+        };
+    }
+}
+
+/// Prints message and writes it to the log file,
+/// if `level` is at or below current log level.
+/// Only errors and warnings are printed in quiet mode.
+pub fn log(level: Level, message: &str) {
+    if level > self::level() {
+        return;
+    }
+    record(level, message);
+    if level > Level::Warn && LOGGER.get().is_some_and(|l| l.quiet) {
+        return;
+    }
+    let buffered = BUFFER.with(|b| match b.borrow_mut().as_mut() {
+        Some(messages) => {
+            messages.push((level, message.to_owned()));
+            true
+        }
+        None => false,
+    });
+    if !buffered {
+        match level {
+            Level::Error | Level::Warn => eprintln!("{message}"),
+            Level::Info | Level::Debug => println!("{message}"),
+        }
+    }
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Error, &format!($($arg)*))
+    };
+}
+
+// Named `warning` to avoid ambiguity with the built-in `warn` attribute,
+// re-exported as `warn`.
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Warn, &format!($($arg)*))
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Info, &format!($($arg)*))
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Debug, &format!($($arg)*))
+    };
+}
+
+pub(crate) use {debug, error, info, warning as warn};
@@ -6,19 +6,32 @@ use time::OffsetDateTime;
 use kml;
 
 mod cam2eaf;
+mod config;
+mod convert;
+mod eaf;
 mod eaf2geo;
 mod elan;
+mod export;
 mod files;
 mod geo;
+mod gpx2eaf;
+mod i18n;
 mod inspect;
 mod locate;
+mod logging;
 mod manual;
 mod media;
 mod model;
+mod overview;
 mod plot;
+mod progress;
+mod sync;
+mod telemetry;
 mod text;
+mod update;
+mod validate;
 
-const VERSION: &'static str = "2.7.0";
+const VERSION: &'static str = "2.8.0";
 const AUTHOR: &'static str = "Jens Larsson";
 const REPO: &'static str = "https://github.com/jenslar/geoelan";
 
@@ -49,6 +62,7 @@ IMPORTANT:
 REQUIREMENTS:
 - FFmpeg:              https://ffmpeg.org ('cam2eaf')
 - ELAN:                https://archive.mpi.nl/tla/elan
+- curl (optional):     https://curl.se ('update --check')
 
 HELP:
 - Specific subcommand: geoelan help <subcommand>
@@ -63,7 +77,7 @@ SOURCE:
 
 ---");
 
-    let args = Command::new("geoelan")
+    let mut cli = Command::new("geoelan")
 
         .version(VERSION)
         .author(AUTHOR)
@@ -71,6 +85,55 @@ SOURCE:
         .long_about(long_help)
         .term_width(80)
         .arg_required_else_help(true)
+        .arg(Arg::new("overwrite")
+            .help("Policy for output files that already exist: 'ask' for confirmation per file, 'always' overwrite, or 'never' overwrite (existing files are skipped). With 'ask', existing files are skipped if there is no terminal to confirm in, e.g. in scripts and batch pipelines.")
+            .long("overwrite")
+            .global(true)
+            .value_parser(PossibleValuesParser::new(["always", "never", "ask"]))
+            .default_value("ask"))
+        .arg(Arg::new("dryrun")
+            .help("Print a plan of the files that would be created, including existing files that would be overwritten or skipped, without writing anything. FFmpeg is not run.")
+            .long("dryrun")
+            .global(true)
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("quiet")
+            .help("Only print errors and warnings, no status messages or progress for long-running operations, such as directory scans, batch processing, and FFmpeg concatenation. Listings, reports, and prompts are still printed. Messages are still written to '--log-file'.")
+            .long("quiet")
+            .global(true)
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("config")
+            .help("Config file with default argument values. Defaults to 'geoelan.toml' in current directory, or in '$XDG_CONFIG_HOME/geoelan' ('~/.config/geoelan'). Arguments on the command line take precedence.")
+            .long("config")
+            .global(true)
+            .value_parser(clap::value_parser!(PathBuf)))
+        .arg(Arg::new("no-config")
+            .help("Ignore config files.")
+            .long("no-config")
+            .global(true)
+            .action(ArgAction::SetTrue)
+            .conflicts_with("config"))
+        .arg(Arg::new("log-file")
+            .help("Append log messages with timestamps and levels to specified file, e.g. for auditing batch runs. Created if it does not exist.")
+            .long("log-file")
+            .global(true)
+            .value_parser(clap::value_parser!(PathBuf)))
+        .arg(Arg::new("log-level")
+            .help("Only print and log messages at or above specified level.")
+            .long("log-level")
+            .global(true)
+            .value_parser(PossibleValuesParser::new(["error", "warn", "info", "debug"]))
+            .default_value("info"))
+        .arg(Arg::new("lang")
+            .help("Language for interactive prompts and common error messages: 'en' (English), 'sv' (Swedish), 'es' (Spanish).")
+            .long("lang")
+            .global(true)
+            .value_parser(PossibleValuesParser::new(["en", "sv", "es"]))
+            .default_value("en"))
+        .arg(Arg::new("offline")
+            .help("Guarantee that no network access is made. GeoELAN is offline by default, this also disables 'geoelan update --check' and 'geoelan plot --osm'.")
+            .long("offline")
+            .global(true)
+            .action(ArgAction::SetTrue))
 
         .subcommand(Command::new("cam2eaf")
             .about("Generate an ELAN-file from GoPro/VIRB footage.")
@@ -121,6 +184,43 @@ SOURCE:
                 .help("Insert tier with synchronised coordinates in ELAN-file.")
                 .long("geotier")
                 .action(ArgAction::SetTrue))
+            .arg(Arg::new("sensor-tiers")
+                .help("Add tiers with sensor summaries for each geotier annotation: 'speed' (km/h range), 'altitude', 'fix' (GPS lock, DOP). Comma separated, e.g. 'speed,fix'.")
+                .long("sensor-tiers")
+                .requires("geotier")
+                .value_delimiter(',')
+                .num_args(1..)
+                .value_parser(PossibleValuesParser::new(["speed", "altitude", "fix"])))
+            .arg(Arg::new("motion-tier")
+                .help("Add a tier segmenting the GPS track into 'moving'/'stationary' intervals ('stop', default), or into speed classes in km/h ('speed').")
+                .long("motion-tier")
+                .requires("geotier")
+                .num_args(0..=1)
+                .default_missing_value("stop")
+                .value_parser(PossibleValuesParser::new(["stop", "speed"])))
+            .arg(Arg::new("stationary-speed")
+                .help("Speed in km/h below which a point is considered stationary for '--motion-tier'.")
+                .long("stationary-speed")
+                .value_parser(clap::value_parser!(f64))
+                .default_value("1.0"))
+            .arg(Arg::new("min-segment")
+                .help("Minimum duration in seconds for '--motion-tier' intervals. Shorter intervals are merged into neighbouring ones.")
+                .long("min-segment")
+                .value_parser(clap::value_parser!(f64))
+                .default_value("10"))
+            .arg(Arg::new("chapter-tier")
+                .help("Add a tier with one annotation per clip (chapter). Clips preceded by a gap in recording time or chapter numbering, i.e. a missing or corrupt chapter, are marked.")
+                .long("chapter-tier")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("no-checksum")
+                .help("Skip SHA-256 checksums for source clips and FIT-file. Checksums require reading all source media, which may take some time for long recording sessions.")
+                .long("no-checksum")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("timeseries")
+                .help("Write GPS speed, altitude, and acceleration as an ELAN time series (CSV + '_tsconf.xml'), linked in the ELAN-file for the Timeseries Viewer.")
+                .long("timeseries")
+                .requires("geotier")
+                .action(ArgAction::SetTrue))
             .arg(Arg::new("fullgps")
                 .help("Use the full GPS log for the ELAN geotier. Results in large ELAN-files.")
                 .long("fullgps")
@@ -142,10 +242,42 @@ SOURCE:
                     "uuid",
                     "fit" // TODO all sessions in specified fit
                 ]))
-            .arg(Arg::new("dryrun")
-                .help("Only show results, does not concatenate video or generate ELAN-file.")
-                .long("dryrun")
+            .arg(Arg::new("jobs")
+                .help("Number of sessions to process concurrently in batch mode. Higher values may cause excessive disk load, since each session runs FFmpeg. Above 1, output is printed per session as it finishes, and existing files are skipped rather than prompted for ('--overwrite ask'). FFmpeg progress is not shown.")
+                .long("jobs")
+                .short('j')
+                .requires("batch")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("1"))
+            .arg(Arg::new("skip-existing")
+                .help("Skip sessions in batch mode whose output directory already contains an ELAN-file and a concatenated video, e.g. to resume an interrupted batch run.")
+                .long("skip-existing")
+                .requires("batch")
                 .action(ArgAction::SetTrue))
+            .arg(Arg::new("name")
+                .help("Output file name template, e.g. '{stem}_{session}_{date}'. Placeholders: {stem} (first clip), {session} (UUID or MUID), {date} (recording date). '_HI'/'_LO' is always appended.")
+                .long("name"))
+            .arg(Arg::new("concatenated")
+                .help("Previously concatenated video, e.g. via GoPro Quik, to link in the ELAN-file instead of concatenating clips. The original clips are still required for telemetry.")
+                .long("concatenated")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with_all(["batch", "low-res-only", "link-high-res"]))
+            .arg(Arg::new("tier")
+                .help("Additional tier as 'ID[,key=value...]', keys: type (linguistic type), parent, participant, annotator. E.g. 'notes,participant=A' or 'place,parent=notes'. Use ID 'geo' to set attributes for the geotier. Can be used multiple times, parent tiers first.")
+                .long("tier")
+                .action(ArgAction::Append))
+            .arg(Arg::new("template")
+                .help("ELAN template (.etf) to apply to the generated ELAN-file. Tiers, linguistic types, languages, and controlled vocabularies are added if not already present.")
+                .long("template")
+                .value_parser(clap::value_parser!(PathBuf)))
+            .arg(Arg::new("metadata")
+                .help("Write a minimal IMDI or CMDI metadata record for the session, with date, location centroid, duration, equipment, and linked files. Remaining fields are left to complete in a metadata editor.")
+                .long("metadata")
+                .value_parser(PossibleValuesParser::new(["imdi", "cmdi"])))
+            .arg(Arg::new("cmdi-profile")
+                .help("CMDI profile ID for '--metadata cmdi', as required by the archive. Defaults to the IMDI session profile 'clarin.eu:cr1:p_1271859438204'.")
+                .long("cmdi-profile")
+                .value_name("ID"))
 
             .next_help_heading("GoPro")
             .arg(Arg::new("verify")
@@ -196,6 +328,61 @@ SOURCE:
                     "batch",
                 ])
                 .required_unless_present_any(&["video", "fit", "batch"]))
+            .arg(Arg::new("drift")
+                .help("Max allowed difference in seconds between FIT session duration and video duration, or between '--concatenated' video and original clips, before a warning is shown.")
+                .long("drift")
+                .value_parser(clap::value_parser!(f64))
+                .default_value("1.0"))
+            .arg(Arg::new("stretch-times")
+                .help("Scale geotier timestamps to fit video duration if the FIT session duration differs (e.g. due to dropped frames).")
+                .long("stretch-times")
+                .action(ArgAction::SetTrue))
+        )
+
+        // Generate EAF from GPX/TCX track
+        .subcommand(Command::new("gpx2eaf")
+            .about("Generate an ELAN-file with a geotier from a GPX/TCX track.")
+            .long_about("Generate an ELAN-file with a geotier from a GPX/TCX track, e.g. from a handheld GPS logger. The track is synchronised with the video via the video start time, which defaults to the MP4 creation time. Also generates KML and GeoJSON files. Does not concatenate video clips.")
+            .visible_alias("g2e")
+            .arg(Arg::new("track")
+                .help("GPX or TCX file.")
+                .long("gpx")
+                .short('g')
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(true))
+            .arg(Arg::new("video")
+                .help("Video to link in the ELAN-file.")
+                .long("video")
+                .short('v')
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(true))
+            .arg(Arg::new("audio")
+                .help("Audio to link in the ELAN-file. Defaults to '--video'.")
+                .long("audio")
+                .short('a')
+                .value_parser(clap::value_parser!(PathBuf)))
+            .arg(Arg::new("start")
+                .help("Video start time, e.g. '2024-05-01T10:00:00'. Defaults to the MP4 creation time.")
+                .long("start")
+                .short('s'))
+            .arg(Arg::new("time-offset")
+                .help("Time offset, +/- hours. Modifies logged timestamps, which are usually UTC, to match video start time.")
+                .long("time-offset")
+                .short('t')
+                .value_parser(clap::value_parser!(isize))
+                .allow_hyphen_values(true) // negative values and value > 24 ok
+                .default_value("0"))
+            .arg(Arg::new("downsample-factor")
+                .help("Downsample factor for coordinates. Must be a positive value.")
+                .long("downsample")
+                .short('d')
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("1"))
+            .arg(Arg::new("output-directory")
+                .help("Output path for resulting files. Defaults to the directory of the video.")
+                .long("outdir")
+                .short('o')
+                .value_parser(clap::value_parser!(PathBuf)))
         )
 
         // Generate KML and GeoJson from EAF
@@ -250,11 +437,22 @@ Geoshape options:
                     "line-all", "line-multi",
                     "circle"
                 ])))
+            .arg(Arg::new("point-strategy")
+                .help("Strategy for reducing the points within each annotation's timespan to a single point. Only affects geoshapes 'point-single' and 'circle'. 'medoid' guarantees the point lies on the actual track.")
+                .long("point-strategy")
+                .default_value("average")
+                .value_parser(PossibleValuesParser::new([
+                    "average", "medoid", "first", "midtime"
+                ])))
             .arg(Arg::new("radius")
                 .help("Circle radius as a float value, e.g. 3.2 (m). Only affects geoshape 'circle'.")
                 .long("radius")
                 .value_parser(clap::value_parser!(f64))
                 .default_value("2.0"))
+            .arg(Arg::new("radius-from-spread")
+                .help("Use the positional spread (root mean square distance in meters) of the points within each annotation's timespan as circle radius, with '--radius' as fallback. Only affects geoshape 'circle'.")
+                .long("radius-from-spread")
+                .action(ArgAction::SetTrue))
             .arg(Arg::new("vertices")
                 .help("Circle vertices ('roundness' of the circle polygon). An integer between 3-255. Only affects geoshape 'circle'")
                 .long("vertices")
@@ -268,10 +466,42 @@ Geoshape options:
                 .help("Use an ELAN-tier with coordinates for geo-referencing.")
                 .long("geotier")
                 .action(ArgAction::SetTrue))
+            .arg(Arg::new("geotier-id")
+                .help("Geotier ID. Implies '--geotier'. Selects geotier without prompting, e.g. in scripts.")
+                .long("geo-tier")
+                .conflicts_with_all(["fit", "gpmf", "location"]))
+            .arg(Arg::new("tier")
+                .help("Content tier ID to georeference. Repeat for multiple tiers. Selected from list if not set, so set this to run in scripts. Output features are tagged with tier ID, and styled per tier for multiple tiers.")
+                .long("tier")
+                .visible_alias("content-tier")
+                .action(ArgAction::Append))
+            .arg(Arg::new("all-tiers")
+                .help("Georeference all tiers, except the geotier if '--geotier' is set. Use with geoshape 'point-multi', 'line-multi', 'point-single', or 'circle' to avoid duplicate unannotated points.")
+                .long("all-tiers")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("tier"))
+            .arg(Arg::new("location")
+                .help("Use the single location embedded in an MP4-file (udta '©xyz', e.g. phones and drones) for all annotations.")
+                .long("location")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with_all(["geotier", "fit", "gpmf"]))
             .arg(Arg::new("cdata")
                 .help("KML-option, added visuals in Google Earth")
                 .long("cdata")
                 .action(ArgAction::SetTrue))
+            .arg(Arg::new("declination")
+                .help("Magnetic declination in degrees (east positive) for the recording location and date, e.g. via the NOAA World Magnetic Model calculator. Corrects magnetic heading (VIRB) to true north in KML/GeoJSON.")
+                .long("declination")
+                .value_parser(clap::value_parser!(f64))
+                .allow_hyphen_values(true))
+            .arg(Arg::new("wmm")
+                .help("World Magnetic Model coefficient file ('WMM.COF', available from NOAA). Computes magnetic declination per point from position, altitude, and date. Corrects magnetic heading (VIRB) to true north in KML/GeoJSON.")
+                .long("wmm")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with("declination"))
+            .arg(Arg::new("name")
+                .help("Output file name template, e.g. '{stem}_{date}_{shape}'. Placeholders: {stem} (ELAN-file), {date} (date of first point), {shape} (geoshape), {type} (e.g. 'summary').")
+                .long("name"))
 
             .next_help_heading("VIRB")
             .arg(Arg::new("fit")
@@ -279,7 +509,7 @@ Geoshape options:
                 .short('f')
                 .long("fit")
                 .value_parser(clap::value_parser!(PathBuf))
-                .required_unless_present_any(["gpmf", "geotier"]))
+                .required_unless_present_any(["gpmf", "geotier", "geotier-id", "location"]))
 
             .next_help_heading("GoPro")
             .arg(Arg::new("gpmf")
@@ -287,27 +517,25 @@ Geoshape options:
                 .short('g')
                 .long("gpmf")
                 .value_parser(clap::value_parser!(PathBuf))
-                .required_unless_present_any(["fit", "geotier"]))
+                .required_unless_present_any(["fit", "geotier", "geotier-id", "location"]))
             .arg(Arg::new("input-directory")
                 .help("Start path for locating files")
                 .short('i')
                 .long("indir")
                 .value_parser(clap::value_parser!(PathBuf))
-                .required_unless_present_any(["fit", "geotier"]))
+                .required_unless_present_any(["fit", "geotier", "geotier-id", "location"]))
             .arg(Arg::new("verify")
                 .help("Verifies GPMF data and ignores corrupt clips.")
                 .long("verify")
                 .action(ArgAction::SetTrue))
             .arg(Arg::new("gpsfix")
-                .help("Min GPS fix threshold. 0 = No lock, 2 = 2D lock, 3 = 3D lock.")
+                .help("Min GPS fix threshold. 0 = No lock, 2 = 2D lock, 3 = 3D lock. Points without GPS fix data (e.g. VIRB) are kept.")
                 .long("gpsfix")
                 .default_value("2") // 3D lock for eaf
-                .conflicts_with_all(["fit", "geotier"])
                 .value_parser(clap::value_parser!(u32)))
             .arg(Arg::new("gpsdop")
-                .help("Min GPS dilution of position threshold. 5.0 = good precision.")
+                .help("Min GPS dilution of position threshold. 5.0 = good precision. Points without dilution of precision data (e.g. VIRB) are kept.")
                 .long("gpsdop")
-                .conflicts_with_all(["fit", "geotier"])
                 .value_parser(clap::value_parser!(f64)))
         )
 
@@ -322,7 +550,27 @@ Geoshape options:
                 .short('i')
                 .long("indir")
                 .value_parser(clap::value_parser!(PathBuf))
-                .required(true))
+                .required_unless_present("files-from"))
+            .arg(Arg::new("files-from")
+                .help("Newline-separated list of clip paths to use instead of searching '--indir', e.g. output from 'find'. Use '-' to read from stdin. Only listed files are parsed, so list all clips in a session, and the FIT-file for VIRB.")
+                .long("files-from")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with("input-directory"))
+            .arg(Arg::new("follow-symlinks")
+                .help("Follow symbolic links when searching '--indir', e.g. on mounted archive shares. Link loops are skipped.")
+                .long("follow-symlinks")
+                .requires("input-directory")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("exclude")
+                .help("Skip files and directories matching a glob pattern when searching '--indir', e.g. 'Trash*' or '**/backup/**'. Patterns with '/' are matched against the full path, other patterns against the name. Can be used multiple times.")
+                .long("exclude")
+                .requires("input-directory")
+                .action(ArgAction::Append))
+            .arg(Arg::new("max-depth")
+                .help("Maximum directory depth when searching '--indir'. 1 = only files directly in '--indir'.")
+                .long("max-depth")
+                .requires("input-directory")
+                .value_parser(clap::value_parser!(u64).range(1..)))
             .arg(Arg::new("kind")
                 .help("If no other options are given, specify camera type to locate and match. Other arguments will be ignored if 'kind' is specified.")
                 .short('k')
@@ -332,17 +580,13 @@ Geoshape options:
                     "g", "gopro", // g short for gopro
                     "v", "virb"   // v short for virb
                 ]))
-                .required_unless_present_any(&["uuid", "video", "fit"]))
+                .required_unless_present_any(&["uuid", "video", "fit", "duplicates"]))
             .arg(Arg::new("video")
                 .help("Any unedited GoPro or VIRB clip.")
                 .short('v')
                 .long("video")
                 .value_parser(clap::value_parser!(PathBuf))
                 .conflicts_with("uuid"))
-            .arg(Arg::new("quiet")
-                .help("Do not print file-by-file search progress")
-                .long("quiet")
-                .action(ArgAction::SetTrue))
             .arg(Arg::new("halt-on-error")
                 .help("Halts on errors relating to locating clips.")
                 .long("ignore-errors")
@@ -351,10 +595,35 @@ Geoshape options:
                 .help("Print additional info for each clip")
                 .long("verbose")
                 .action(ArgAction::SetTrue))
+            .arg(Arg::new("after")
+                .help("Only list sessions recorded at or after this date or datetime, e.g. '2024-03-01' or '2024-03-01T12:00:00'.")
+                .long("after"))
+            .arg(Arg::new("before")
+                .help("Only list sessions recorded at or before this date or datetime, e.g. '2024-04-01' or '2024-03-31T18:00:00'. A date only includes the whole day.")
+                .long("before"))
+            .arg(Arg::new("near")
+                .help("Only list sessions with GPS points within RADIUS meters of a location, as 'LAT,LON,RADIUS', e.g. '57.70,11.97,500'. Requires parsing GPS for each session.")
+                .long("near")
+                .allow_hyphen_values(true)) // negative coordinates
+            .arg(Arg::new("format")
+                .help("Output format. 'json' and 'csv' print session listings (session ID, MUID/UUID, clip paths, start times, durations) to stdout for use in other tools.")
+                .long("format")
+                .value_parser(PossibleValuesParser::new(["text", "json", "csv"]))
+                .default_value("text"))
+            .arg(Arg::new("duplicates")
+                .help("Report duplicate clips instead of sessions, i.e. clips with the same MUID and chapter (GoPro) or UUID (VIRB), and the same size, at different paths, e.g. from backing up a card several times. The copy with the oldest modification time is kept, others are listed as redundant.")
+                .long("duplicates")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["video", "uuid", "fit", "after", "before", "near"]))
+            .arg(Arg::new("mdat-hash")
+                .help("Also compare media data (MP4 'mdat') for '--duplicates', to find copies with differing or missing IDs. Reads all clips in full.")
+                .long("mdat-hash")
+                .requires("duplicates")
+                .action(ArgAction::SetTrue))
 
             .next_help_heading("GoPro")
             .arg(Arg::new("verify")
-                .help("Verifies GPMF data and ignores corrupt clips.")
+                .help("Verifies GPMF data (GoPro) or FIT CRC (VIRB) and ignores corrupt files.")
                 .long("verify")
                 .action(ArgAction::SetTrue))
 
@@ -390,6 +659,17 @@ Geoshape options:
                 .long("atoms")
                 .requires("video")
                 .conflicts_with_all(["gpmf", "fit", "meta"]))
+            .arg(Arg::new("atoms-json")
+                .help("Save MP4 atom hierarchy as JSON, e.g. 'GH010006_atoms.json'.")
+                .action(ArgAction::SetTrue)
+                .long("atoms-json")
+                .requires("atoms"))
+            .arg(Arg::new("atoms-diff")
+                .help("Compare atom structure and sizes of '--video' with another MP4-file, and check whether telemetry (GoPro GPMF track, VIRB UUID) has been lost, e.g. due to re-muxing.")
+                .long("atoms-diff")
+                .value_parser(clap::value_parser!(PathBuf))
+                .requires("video")
+                .conflicts_with_all(["gpmf", "fit", "meta", "atoms", "offsets", "dump", "dump-atom"]))
             .arg(Arg::new("meta")
                 .help("Print MP4 custom metadata if '--video' is used.")
                 .action(ArgAction::SetTrue)
@@ -402,6 +682,24 @@ Geoshape options:
                 .short('o')
                 .value_parser(clap::value_parser!(String))
                 .requires("video")) // list all conflicts...?
+            .arg(Arg::new("dump")
+                .help("Extract specified track (name or id) in MP4-file. H.264/H.265 video is saved as Annex-B ('.h264', '.h265'), AAC audio with ADTS headers ('.aac'), other tracks as raw data.")
+                .long("dump")
+                .value_parser(clap::value_parser!(String))
+                .requires("video")
+                .conflicts_with("offsets"))
+            .arg(Arg::new("dump-atom")
+                .help("Extract payload for all atoms with specified FourCC in MP4-file, e.g. 'udta', 'firm', or vendor specific atoms. Saved as '<FILE>_<FOURCC>.bin'.")
+                .long("dump-atom")
+                .value_name("FOURCC")
+                .value_parser(clap::value_parser!(String))
+                .requires("video")
+                .conflicts_with_all(["offsets", "dump"]))
+            .arg(Arg::new("hex")
+                .help("Print a hex view for '--dump-atom' instead of saving to file. Limited to the first 4096 bytes of each atom.")
+                .long("hex")
+                .action(ArgAction::SetTrue)
+                .requires("dump-atom"))
                 .arg(Arg::new("sensor")
                 .help("Print sensor data. Sensors differ between brands and models.")
                 .long("sensor")
@@ -426,7 +724,7 @@ Geoshape options:
                 .long("ikml")
                 .action(ArgAction::SetTrue))
             .arg(Arg::new("json")
-                .help("Generate a GeoJSON file from GPS-logs. Points only, downsampled to roughly 1 point/second.")
+                .help("Generate a GeoJSON file from GPS-logs. Points only, downsampled to roughly 1 point/second. If used with '--type', saves the specified data type as JSON instead.")
                 .long("json")
                 .action(ArgAction::SetTrue))
             .arg(Arg::new("fullgps")
@@ -458,10 +756,30 @@ Geoshape options:
                 .action(ArgAction::SetTrue)
                 .conflicts_with_all(&["gps", "verbose", "data-type"])) // gps always prints points
             .arg(Arg::new("csv")
-                .help("Save sensor data or GPS data as CSV.")
+                .help("Save sensor data, GPS data, or data specified via '--type' as CSV.")
                 .long("csv")
                 // how to require EITHER --gps or --sensor <SENSOR>?
                 .action(ArgAction::SetTrue))
+            .arg(Arg::new("name")
+                .help("Output file name template, e.g. '{stem}_{session}_{type}'. Placeholders: {stem} (input file), {session} (UUID or MUID), {date} (date of first point), {type} (data type, e.g. 'GPS').")
+                .long("name"))
+            .arg(Arg::new("format")
+                .help("Output format. 'json' prints MP4 tracks and atoms, GoPro/VIRB identifiers, session info, and a GPS summary as JSON to stdout, for use in other tools. Use with '--video', '--gpmf', or '--fit'.")
+                .long("format")
+                .value_parser(PossibleValuesParser::new(["text", "json"]))
+                .default_value("text")
+                .conflicts_with_all(["gaps", "verbose", "debug", "gps", "sensor", "data-type", "offsets", "dump", "dump-atom"]))
+            .arg(Arg::new("gaps")
+                .help("Report time gaps between clips and between recording sessions in '--indir' or '--files-from', e.g. missing chapters. Prints a session timeline.")
+                .long("gaps")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["video", "gpmf", "fit"]))
+            .arg(Arg::new("files-from")
+                .help("Newline-separated list of clip paths to use instead of searching '--indir', e.g. output from 'find'. Use '-' to read from stdin. Only sessions with listed clips are reported.")
+                .long("files-from")
+                .value_parser(clap::value_parser!(PathBuf))
+                .requires("gaps")
+                .conflicts_with("input-directory"))
 
             .next_help_heading("GoPro")
             .arg(Arg::new("gpmf")
@@ -469,7 +787,7 @@ Geoshape options:
                 .long("gpmf")
                 .short('g')
                 .value_parser(clap::value_parser!(PathBuf))
-                .required_unless_present_any(&["video", "fit"])
+                .required_unless_present_any(&["video", "fit", "gaps"])
                 .conflicts_with_all(&["fit", "video", "global"]))
             .arg(Arg::new("input-directory")
                 .help("Start path for locating GoPro MP4 clips.")
@@ -487,9 +805,8 @@ Geoshape options:
                 .requires("gpmf")
                 .value_parser(clap::value_parser!(f64)))
             .arg(Arg::new("verify")
-                .help("Verifies GPMF data and ignores corrupt clips.")
+                .help("Verifies GPMF data and ignores corrupt clips, or verifies FIT CRC.")
                 .long("verify")
-                .requires("gpmf")
                 .action(ArgAction::SetTrue))
 
             .next_help_heading("VIRB")
@@ -498,7 +815,7 @@ Geoshape options:
                 .long("fit")
                 .short('f')
                 .value_parser(clap::value_parser!(PathBuf))
-                .required_unless_present_any(["video", "gpmf"])
+                .required_unless_present_any(["video", "gpmf", "gaps"])
                 .conflicts_with("gpmf"))
         )
 
@@ -508,10 +825,11 @@ Geoshape options:
 
             .next_help_heading("GoPro")
             .arg(Arg::new("gpmf")
-                .help("Unedited GoPro MP4-file, or extracted GPMF-track. Exctracted GPMF-tracks do not contain relative timestamps, since these are derived via the MP4 file.")
+                .help("Unedited GoPro MP4-file, or extracted GPMF-track. Exctracted GPMF-tracks do not contain relative timestamps, since these are derived via the MP4 file. Use several times to compare files.")
                 .long("gpmf")
                 .short('g')
-                .required_unless_present("fit")
+                .required_unless_present_any(["fit", "from-spec"])
+                .action(ArgAction::Append)
                 .value_parser(clap::value_parser!(PathBuf)))
             .arg(Arg::new("input-directory")
                 .help("Input directory for locating GoPro clips.")
@@ -520,18 +838,19 @@ Geoshape options:
                 .requires("gpmf")
                 .value_parser(clap::value_parser!(PathBuf)))
             .arg(Arg::new("gps5")
-                .help("Force the use of GPS5 for cameras that log both (currently only Hero11).")
+                .help("Force the use of GPS5 for cameras that log both (currently Hero11 and Hero13).")
                 .long("gps5")
                 .requires("gpmf")
                 .action(clap::ArgAction::SetTrue))
 
             .next_help_heading("VIRB")
             .arg(Arg::new("fit")
-                .help("Garmin FIT-file. Non-VIRB FIT-files work depending on options used.")
+                .help("Garmin FIT-file. Non-VIRB FIT-files work depending on options used. Use several times to compare files.")
                 .long("fit")
                 .short('f')
+                .action(ArgAction::Append)
                 .value_parser(clap::value_parser!(PathBuf))
-                .required_unless_present("gpmf"))
+                .required_unless_present_any(["gpmf", "from-spec"]))
 
             .next_help_heading("General")
             .arg(Arg::new("session")
@@ -540,10 +859,11 @@ Geoshape options:
                 .short('s')
                 .action(ArgAction::SetTrue))
             .arg(Arg::new("y-axis")
-                .help("Data to plot on Y-axis.")
+                .help("Data to plot on Y-axis. Use twice to plot a second data type on a secondary Y-axis, e.g. '-y alt -y s2d'.")
                 .long("y-axis")
                 .short('y')
-                .required(true)
+                .action(ArgAction::Append)
+                .required_unless_present_any(["from-spec", "map"])
                 .value_parser([
                     // Sensors
                     "acc", "accelerometer", // GoPro, VIRB
@@ -560,6 +880,16 @@ Geoshape options:
                     "s3d", "speed3d",
                     "dop", "dilution",  // GoPro dilution of precision, GoPro 11 and later
                     "fix", "gpsfix",   // GoPro satellite lock level/GPS fix, 2D or 3D lock etc
+                    "gpsacc", "gpsaccuracy", // FIT GPS accuracy in meters, if logged
+
+                    // Fitness
+                    "hr", "heartrate", // VIRB, paired chest strap
+                    "cad", "cadence",  // VIRB, paired cadence sensor
+
+                    // Audio
+                    "wnd", "wind",       // GoPro wind processing, Hero8 and later
+                    "wet", "micwet",     // GoPro microphone wet detection, Hero8 and later
+                    "aud", "audiolevel", // GoPro RMS and peak audio level, Hero9 and later
                 ]))
             .arg(Arg::new("x-axis")
                 .help("Data to plot on X-axis. Defaults to count/data index if not specified.")
@@ -574,15 +904,551 @@ Geoshape options:
                     "dst", "distance", // GPS travel distance/displacement
                 ])
                 .default_value("count"))
+            .arg(Arg::new("align")
+                .help("Alignment of time series when comparing several files with '--x-axis time'. 'relative' aligns the start of each file or session, 'absolute' aligns on recording date and time (start of FIT-file for VIRB).")
+                .long("align")
+                .value_parser(["relative", "absolute"])
+                .default_value("relative"))
+            .arg(Arg::new("eaf")
+                .help("ELAN-file with annotations to draw as shaded regions over the plot, e.g. to verify annotation/telemetry alignment. Requires '--x-axis time'. Aligned with the first data file. For VIRB, the start of the media in the FIT-file is read from ELAN-files generated with 'cam2eaf'.")
+                .long("eaf")
+                .requires("tier")
+                .conflicts_with("map")
+                .value_parser(clap::value_parser!(PathBuf)))
+            .arg(Arg::new("tier")
+                .help("Tier ID for annotations in '--eaf'.")
+                .long("tier")
+                .short('t')
+                .requires("eaf"))
+            .arg(Arg::new("map")
+                .help("Plot the GPS track on a map (latitude over longitude), color-coded via '--color-by', instead of a time series.")
+                .long("map")
+                .conflicts_with("y-axis")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("color-by")
+                .help("Data for color-coding the GPS track in map view. Defaults to 'speed'.")
+                .long("color-by")
+                .requires("map")
+                .value_parser(["speed", "altitude", "fix"]))
+            .arg(Arg::new("osm")
+                .help("Draw the GPS track over OpenStreetMap tiles in map view. Requires network access: the tiles are loaded by the browser when viewing the plot. Not allowed with '--offline'.")
+                .long("osm")
+                .requires("map")
+                .action(ArgAction::SetTrue))
             .arg(Arg::new("fill")
                 .help("Fill area under plot.")
                 .long("fill")
                 .action(clap::ArgAction::SetTrue))
             .arg(Arg::new("average")
-                .help("Generate a linear average for each sensor data cluster before plotting.")
+                .help("Generate a linear average for each sensor data cluster (GoPro) or FIT record (VIRB) before plotting.")
                 .long("average")
                 .short('a')
                 .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("downsample")
+                .help("Only plot every Nth sample, e.g. for responsive HTML output for dense sensor data (200 Hz accelerometer). Applied after '--rolling-mean'.")
+                .long("downsample")
+                .value_parser(clap::value_parser!(u64).range(1..)))
+            .arg(Arg::new("rolling-mean")
+                .help("Smooth data with a centered rolling mean over specified window in seconds. Requires '--x-axis time'.")
+                .long("rolling-mean")
+                .value_parser(clap::value_parser!(f64)))
+            .arg(Arg::new("spec")
+                .help("Save plot specification (inputs, axes, options, GeoELAN version) as JSON, for regenerating the figure via '--from-spec'. Defaults to 'plot.json'.")
+                .long("spec")
+                .num_args(0..=1)
+                .default_missing_value("plot.json")
+                .value_parser(clap::value_parser!(PathBuf)))
+            .arg(Arg::new("from-spec")
+                .help("Regenerate a plot from a specification saved via '--spec'. Other options are ignored.")
+                .long("from-spec")
+                .value_parser(clap::value_parser!(PathBuf)))
+            .arg(Arg::new("export-data")
+                .help("Also write the plotted series as tidy CSV with the columns X-axis (time, count, or distance), value, and variable. Written as JSON if the path ends in '.json'.")
+                .long("export-data")
+                .conflicts_with("map")
+                .value_parser(clap::value_parser!(PathBuf)))
+            .arg(Arg::new("output")
+                .help("Write plot as HTML to specified path instead of opening it in a browser.")
+                .long("output")
+                .short('o')
+                .value_parser(clap::value_parser!(PathBuf)))
+        )
+
+        // ELAN-file utilities
+        .subcommand(Command::new("eaf")
+            .about("ELAN-file utilities, e.g. comparing two versions of an ELAN-file.")
+            .subcommand_required(true)
+            .subcommand(Command::new("diff")
+                .about("List changes between two ELAN-files, e.g. a baseline and an annotated version.")
+                .long_about(r#"List changes between two ELAN-files, e.g. a baseline and an annotated version.
+
+Tiers are matched on tier ID. Annotations are listed as:
+  '+' added
+  '-' removed
+  '~' modified value (same time span)
+  '>' time shift (same value, overlapping time span)"#)
+                .arg(Arg::new("eaf-a")
+                    .help("Original ELAN-file.")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .required(true))
+                .arg(Arg::new("eaf-b")
+                    .help("Modified ELAN-file.")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .required(true))
+                .arg(Arg::new("json")
+                    .help("Print changes as JSON.")
+                    .long("json")
+                    .action(ArgAction::SetTrue))
+            )
+            .subcommand(Command::new("merge")
+                .about("Merge two or more ELAN-files, e.g. annotations by different annotators.")
+                .long_about(r#"Merge two or more ELAN-files, e.g. annotations by different annotators.
+
+The first ELAN-file is used as base, keeping its header, media, and tier structure. Top-level tiers in the remaining files are added as new tiers, or merged with the tier with the same ID. Identical annotations are only kept once. Referring tiers in the remaining files are not merged.
+
+Overlapping annotations in merged tiers are resolved via '--overlap':
+  'keep-both' add overlapping annotations to a new tier '<TIER_ID>-<FILE_NAME>'
+  'join'      join into a single annotation, with values separated by ' | '
+  'error'     abort merge"#)
+                .arg(Arg::new("eaf")
+                    .help("Two or more ELAN-files. The first file is used as base.")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .num_args(2..)
+                    .required(true))
+                .arg(Arg::new("overlap")
+                    .help("How to resolve overlapping annotations in merged tiers.")
+                    .long("overlap")
+                    .value_parser(PossibleValuesParser::new(["keep-both", "join", "error"]))
+                    .default_value("keep-both"))
+                .arg(Arg::new("output")
+                    .help("Output ELAN-file.")
+                    .long("output")
+                    .short('o')
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .required(true))
+            )
+            .subcommand(Command::new("query")
+                .about("Find annotations with values matching a regular expression.")
+                .long_about(r#"Find annotations with values matching a regular expression.
+
+Matches are listed with time codes, and can be saved as CSV via '--csv'. '--output' saves a new ELAN-file containing only matching annotations. All tiers with matches are then added as top-level, time-aligned tiers."#)
+                .arg(Arg::new("eaf")
+                    .help("One or more ELAN-files.")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .num_args(1..)
+                    .required(true))
+                .arg(Arg::new("pattern")
+                    .help("Regular expression to match in annotation values.")
+                    .long("pattern")
+                    .short('p')
+                    .required(true))
+                .arg(Arg::new("tier")
+                    .help("Only query annotations in this tier (tier ID). Can be used multiple times.")
+                    .long("tier")
+                    .short('t')
+                    .action(ArgAction::Append))
+                .arg(Arg::new("csv")
+                    .help("Save matches as CSV, with time codes.")
+                    .long("csv")
+                    .value_parser(clap::value_parser!(PathBuf)))
+                .arg(Arg::new("output")
+                    .help("Save a new ELAN-file containing only matching annotations. Single ELAN-file only.")
+                    .long("output")
+                    .short('o')
+                    .value_parser(clap::value_parser!(PathBuf)))
+            )
+            .subcommand(Command::new("replace")
+                .about("Find and replace annotation values using a regular expression.")
+                .long_about(r#"Find and replace annotation values using a regular expression.
+
+Replacement is done on the XML text, so that formatting and everything except changed annotation values is kept as is. Each file is written via a temporary file, so that a failed write never leaves a truncated ELAN-file.
+
+Capture groups can be used in the replacement, e.g. '--pattern "(\w+)-(\w+)" --replacement "$2-$1"'.
+
+Use '--dryrun' to preview changes."#)
+                .arg(Arg::new("eaf")
+                    .help("One or more ELAN-files.")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .num_args(1..)
+                    .required(true))
+                .arg(Arg::new("pattern")
+                    .help("Regular expression to match in annotation values.")
+                    .long("pattern")
+                    .short('p')
+                    .required(true))
+                .arg(Arg::new("replacement")
+                    .help("Replacement string. Use '$1' etc for capture groups.")
+                    .long("replacement")
+                    .short('r')
+                    .allow_hyphen_values(true)
+                    .required(true))
+                .arg(Arg::new("tier")
+                    .help("Only change annotations in this tier (tier ID). Can be used multiple times.")
+                    .long("tier")
+                    .short('t')
+                    .action(ArgAction::Append))
+            )
+            .subcommand(Command::new("scan")
+                .about("List tier IDs, linked media, time span, and number of annotations for ELAN-files.")
+                .long_about(r#"List tier IDs, linked media, time span, and number of annotations for ELAN-files.
+
+ELAN-files are scanned without being fully parsed, so that thousands of ELAN-files can be listed quickly, e.g. for an overview of a corpus."#)
+                .arg(Arg::new("path")
+                    .help("One or more ELAN-files, or directories to search for ELAN-files.")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .num_args(1..)
+                    .required(true))
+                .arg(Arg::new("json")
+                    .help("Print results as JSON.")
+                    .long("json")
+                    .action(ArgAction::SetTrue))
+            )
+            .subcommand(Command::new("stats")
+                .about("Annotation and token statistics per tier and participant.")
+                .long_about(r#"Annotation and token statistics per tier and participant.
+
+Tiers can be filtered on tier attributes, e.g. '--participant' for statistics for a single speaker, without relying on tier naming conventions. Filters are combined."#)
+                .arg(Arg::new("eaf")
+                    .help("One or more ELAN-files.")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .num_args(1..)
+                    .required(true))
+                .arg(Arg::new("tier")
+                    .help("Only include tier with this tier ID.")
+                    .long("tier")
+                    .short('t'))
+                .arg(Arg::new("participant")
+                    .help("Only include tiers with this participant.")
+                    .long("participant")
+                    .short('p'))
+                .arg(Arg::new("annotator")
+                    .help("Only include tiers with this annotator.")
+                    .long("annotator")
+                    .short('a'))
+                .arg(Arg::new("language")
+                    .help("Only include tiers with this content language (language reference, e.g. 'eng').")
+                    .long("language")
+                    .short('l'))
+                .arg(Arg::new("linguistic-type")
+                    .help("Only include tiers with this linguistic type.")
+                    .long("linguistic-type"))
+                .arg(Arg::new("json")
+                    .help("Print statistics as JSON.")
+                    .long("json")
+                    .action(ArgAction::SetTrue))
+            )
+            .subcommand(Command::new("align")
+                .about("Align audio from an external recorder with the camera audio in an ELAN-file.")
+                .long_about(r#"Align audio from an external recorder with the camera audio in an ELAN-file.
+
+The offset is determined via cross-correlation of the audio in the WAV-file and the first linked video (requires FFmpeg). The WAV-file is then linked in the ELAN-file, with the offset set as its time origin. The recording must start before the video."#)
+                .arg(Arg::new("eaf")
+                    .help("ELAN-file.")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .required(true))
+                .arg(Arg::new("audio")
+                    .help("WAV-file from external recorder.")
+                    .long("audio")
+                    .short('a')
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .required(true))
+                .arg(Arg::new("video")
+                    .help("Camera media to align against. Defaults to the first video linked in the ELAN-file.")
+                    .long("video")
+                    .short('v')
+                    .value_parser(clap::value_parser!(PathBuf)))
+                .arg(Arg::new("max-lag")
+                    .help("Max offset in seconds to search for.")
+                    .long("max-lag")
+                    .value_parser(clap::value_parser!(f64))
+                    .default_value("30.0"))
+                .arg(Arg::new("window")
+                    .help("Length in seconds of the audio segment used for correlation.")
+                    .long("window")
+                    .value_parser(clap::value_parser!(f64))
+                    .default_value("60.0"))
+                .arg(Arg::new("ffmpeg")
+                    .help("Custom path to FFmpeg.")
+                    .long("ffmpeg")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .default_value(if cfg!(windows) {"ffmpeg.exe"} else {"ffmpeg"}))
+            )
+            .subcommand(Command::new("clips")
+                .about("Cut linked media into one clip per annotation in a tier, e.g. for stimulus sets.")
+                .long_about(r#"Cut linked media into one clip per annotation in a tier, e.g. for stimulus sets.
+
+Each clip is named by annotation index and value, e.g. '003_greeting.mp4', and gets an ELAN-file with the same name, containing the annotation for the full clip (requires FFmpeg)."#)
+                .arg(Arg::new("eaf")
+                    .help("ELAN-file.")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .required(true))
+                .arg(Arg::new("tier")
+                    .help("Tier ID for annotations to cut clips for.")
+                    .long("tier")
+                    .short('t')
+                    .required(true))
+                .arg(Arg::new("output-directory")
+                    .help("Output directory for clips and ELAN-files.")
+                    .long("outdir")
+                    .short('o')
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .required(true))
+                .arg(Arg::new("media")
+                    .help("Media to cut clips from. Defaults to the first video linked in the ELAN-file.")
+                    .long("media")
+                    .short('m')
+                    .value_parser(clap::value_parser!(PathBuf)))
+                .arg(Arg::new("copy")
+                    .help("Copy streams without re-encoding. Faster, but clips start at the closest preceding keyframe.")
+                    .long("copy")
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("ffmpeg")
+                    .help("Custom path to FFmpeg.")
+                    .long("ffmpeg")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .default_value(if cfg!(windows) {"ffmpeg.exe"} else {"ffmpeg"}))
+            )
+            .subcommand(Command::new("template")
+                .about("Create an ELAN template (.etf) from an ELAN-file.")
+                .long_about(r#"Create an ELAN template (.etf) from an ELAN-file.
+
+The template keeps tiers, linguistic types, languages, constraints, and controlled vocabularies, but not media, time slots, or annotations. Apply to new ELAN-files via 'cam2eaf --template'."#)
+                .arg(Arg::new("eaf")
+                    .help("ELAN-file.")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .required(true))
+                .arg(Arg::new("output")
+                    .help("Output ELAN template. Defaults to ELAN-file name with '.etf' extension.")
+                    .long("output")
+                    .short('o')
+                    .value_parser(clap::value_parser!(PathBuf)))
+            )
+        )
+
+        // Convert between file formats
+        .subcommand(Command::new("convert")
+            .about("Convert between file formats, e.g. an ELAN tier to subtitles.")
+            .long_about(r#"Convert between file formats, e.g. an ELAN tier to subtitles. Conversion is determined by file extensions.
+
+Supported conversions:
+  EAF -> SRT, VTT   Export a tier as SubRip or WebVTT subtitles.
+  SRT, VTT -> EAF   Import subtitles as a tier in a new ELAN-file.
+  EAF -> JSON, CSV, TEXTGRID
+                    Export all tiers, or a single tier if '--tier' is set.
+                    CSV columns are 'tier,start_ms,end_ms,value'.
+                    JSON also keeps linked media, participant, and annotator.
+  JSON, CSV, TEXTGRID -> EAF
+                    Import tiers, or a single tier if '--tier' is set, in a new ELAN-file.
+                    Media and tier attributes in JSON are restored.
+  KML, GEOJSON, GPX, TCX -> KML, GEOJSON, GPX
+                    Convert points. Only coordinates and datetimes are kept.
+                    '.json' is GeoJSON if the other file is a geo format."#)
+            .visible_alias("cv")
+            .arg(Arg::new("input")
+                .help("Input file.")
+                .long("input")
+                .short('i')
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(true))
+            .arg(Arg::new("output")
+                .help("Output file.")
+                .long("output")
+                .short('o')
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(true))
+            .arg(Arg::new("tier")
+                .help("Tier ID. EAF: tier to export, selected from list if not set for subtitles. Subtitles: ID for new tier, defaults to file name. JSON, CSV, TextGrid: tier to import.")
+                .long("tier")
+                .short('t'))
+            .arg(Arg::new("media")
+                .help("Media files to link in a new ELAN-file.")
+                .long("media")
+                .short('m')
+                .value_parser(clap::value_parser!(PathBuf))
+                .action(ArgAction::Append))
+        )
+
+        // Validate ELAN-files
+        .subcommand(Command::new("validate")
+            .about("Validate ELAN-files against the EAF schema constraints.")
+            .long_about("Validate ELAN-files against the EAF schema constraints: required elements and attributes, unique IDs, and references between time slots, annotations, tiers, linguistic types, languages, and controlled vocabularies. All violations are listed with line numbers.")
+            .arg(Arg::new("eaf")
+                .help("One or more ELAN-files.")
+                .long("eaf")
+                .short('e')
+                .value_parser(clap::value_parser!(PathBuf))
+                .num_args(1..)
+                .required(true))
+            .arg(Arg::new("eaf-version")
+                .help("Also check that the ELAN-files can be read as specified EAF version, e.g. for archives that require EAF 2.8. Lists elements and attributes introduced in later versions.")
+                .long("eaf-version")
+                .value_parser(PossibleValuesParser::new(["2.7", "2.8", "3.0"])))
+        )
+
+        // Export telemetry to standalone files
+        .subcommand(Command::new("export")
+            .about("Export camera telemetry to standalone files.")
+            .long_about(r#"Export camera telemetry to standalone files.
+
+'--gpmf-raw' writes the raw GPMF track ('GoPro MET') of a GoPro MP4-file to a '.gpmf' file, together with a CSV index of sample byte offsets and timestamps. The telemetry can thus be preserved in re-encoding workflows that discard it, and the '.gpmf' file can be inspected via 'geoelan inspect --gpmf'.
+
+'--sensor' writes the full sensor stream (GoPro or VIRB) in physical units as CSV, one row per sample, with time relative to the start of the data in seconds as well as absolute date and time, e.g. 'GH010006_ACCELEROMETER.csv'. VIRB values are calibrated via the calibration messages in the FIT-file. GoPro values are scaled ('SCAL'), with axes in the order logged by the camera, i.e. the sensor orientation ('ORIN', 'MTRX') is not applied. Specify '--sensor' more than once to export several sensors.
+
+'--gpmf-salvage' is for damaged GoPro MP4-files that are otherwise discarded, e.g. via '--verify'. Each GPMF 'DEVC' block is validated separately. Damaged data is skipped up to the next 'DEVC', and the intact blocks are written to a '_SALVAGED.gpmf' file, which can be inspected via 'geoelan inspect --gpmf'. Skipped byte ranges are listed in a '_GPMF_DAMAGE.csv' report.
+
+'--gpmf-type' writes all values for a GPMF FourCC as CSV, one row per structure, e.g. 'GH010006_HUES.csv'. Complex structures (value type '?') are decoded according to the stream's 'TYPE' definition, including vendor extensions not otherwise supported. Values are not scaled. Specify '--gpmf-type' more than once to export several FourCCs."#)
+            .visible_alias("x")
+            .arg(Arg::new("video")
+                .help("GoPro MP4-file.")
+                .long("video")
+                .short('v')
+                .value_parser(clap::value_parser!(PathBuf))
+                .required_unless_present("fit")
+                .conflicts_with("fit"))
+            .arg(Arg::new("fit")
+                .help("VIRB FIT-file.")
+                .long("fit")
+                .short('f')
+                .value_parser(clap::value_parser!(PathBuf)))
+            .arg(Arg::new("gpmf-raw")
+                .help("Export the raw GPMF track and an index of sample timestamps.")
+                .long("gpmf-raw")
+                .requires("video")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("gpmf-salvage")
+                .help("Export intact GPMF data from a damaged GoPro MP4-file, skipping damaged data, together with a damage report.")
+                .long("gpmf-salvage")
+                .requires("video")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("gpmf-type")
+                .help("Export unscaled values for a GPMF FourCC as CSV, decoding complex structures via 'TYPE'. E.g. 'HUES'.")
+                .long("gpmf-type")
+                .requires("video")
+                .action(ArgAction::Append))
+            .arg(Arg::new("sensor")
+                .help("Export sensor data in physical units as CSV.")
+                .long("sensor")
+                .short('s')
+                .action(ArgAction::Append)
+                .value_parser(PossibleValuesParser::new([
+                    "acc", "accelerometer",
+                    "gyr", "gyroscope",
+                    "mag", "magnetometer",
+                    "grv", "gravity",
+                    "bar", "barometer",
+                ])))
+            .arg(Arg::new("output-directory")
+                .help("Output path for resulting files. Defaults to the directory of the input file.")
+                .long("outdir")
+                .short('o')
+                .value_parser(clap::value_parser!(PathBuf)))
+        )
+
+        // Overview map for all recording sessions in a directory
+        .subcommand(Command::new("overview")
+            .about("Generate a single KML and GeoJSON-file showing all GoPro and VIRB recording sessions in a directory.")
+            .long_about(r#"Generate a single KML and GeoJSON-file showing all GoPro and VIRB recording sessions in a directory.
+
+Each session is drawn as a coloured track, with a start marker listing session ID (MUID for GoPro, UUID for VIRB), start time, and links to the source files. Sessions without GPS data are skipped."#)
+            .visible_alias("o")
+            .arg(Arg::new("input-directory")
+                .help("Directory to search for recording sessions.")
+                .short('i')
+                .long("indir")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(true))
+            .arg(Arg::new("kind")
+                .help("Camera type to include. Defaults to both GoPro and VIRB.")
+                .short('k')
+                .long("kind")
+                .value_parser(PossibleValuesParser::new([
+                    "g", "gopro", // g short for gopro
+                    "v", "virb"   // v short for virb
+                ])))
+            .arg(Arg::new("downsample-factor")
+                .help("Downsample factor for coordinates. Must be a positive value.")
+                .long("downsample")
+                .short('d')
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("10"))
+            .arg(Arg::new("gpsfix")
+                .help("Min GPS fix threshold. 0 = No lock, 2 = 2D lock, 3 = 3D lock. GoPro only.")
+                .long("gpsfix")
+                .default_value("2")
+                .value_parser(clap::value_parser!(u32)))
+            .arg(Arg::new("output")
+                .help("Output path for resulting files. Defaults to 'overview.kml' and 'overview.json' in the input directory.")
+                .long("output")
+                .short('o')
+                .value_parser(clap::value_parser!(PathBuf)))
+        )
+
+        // Synchronise multiple cameras
+        .subcommand(Command::new("sync")
+            .about("Synchronise two or more cameras recording the same event, and generate an ELAN-file with all media linked.")
+            .long_about(r#"Synchronise two or more cameras recording the same event, e.g. GoPro + VIRB or two GoPros, and generate an ELAN-file with all media linked and per-media time offsets set.
+
+Offsets are determined via GPS datetime ('gps', default), audio cross-correlation ('audio'), or GPS refined by audio ('both'). GPS datetime is derived from the first logged point (GoPro) or the FIT-file (VIRB), with MP4 creation time as fallback for other cameras. Audio correlation requires FFmpeg.
+
+The first video is the reference. Since ELAN does not support negative time offsets, the ELAN timeline starts when the last camera started recording."#)
+            .arg(Arg::new("video")
+                .help("Videos to synchronise, e.g. an unedited clip or concatenated session per camera.")
+                .long("video")
+                .short('v')
+                .num_args(2..)
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(true))
+            .arg(Arg::new("method")
+                .help("Synchronisation method.")
+                .long("method")
+                .short('m')
+                .value_parser(PossibleValuesParser::new(["gps", "audio", "both"]))
+                .default_value("gps"))
+            .arg(Arg::new("input-directory")
+                .help("VIRB only. Directory containing the FIT-file. Defaults to the directory of the video.")
+                .long("indir")
+                .short('i')
+                .value_parser(clap::value_parser!(PathBuf)))
+            .arg(Arg::new("max-lag")
+                .help("Max offset in seconds to search for around the GPS offset (or zero) when correlating audio.")
+                .long("max-lag")
+                .value_parser(clap::value_parser!(f64))
+                .default_value("5.0"))
+            .arg(Arg::new("window")
+                .help("Length in seconds of the audio segment used for correlation.")
+                .long("window")
+                .value_parser(clap::value_parser!(f64))
+                .default_value("60.0"))
+            .arg(Arg::new("ffmpeg")
+                .help("Custom path to FFmpeg.")
+                .long("ffmpeg")
+                .value_parser(clap::value_parser!(PathBuf))
+                .default_value(if cfg!(windows) {"ffmpeg.exe"} else {"ffmpeg"}))
+            .arg(Arg::new("output")
+                .help("Output path for the ELAN-file. Defaults to '<first video>_sync.eaf'.")
+                .long("output")
+                .short('o')
+                .value_parser(clap::value_parser!(PathBuf)))
+        )
+
+        // Version report, check for new releases
+        .subcommand(Command::new("update")
+            .about("Print version and network access report, or check for a newer release.")
+            .long_about(r#"Print version and network access report, or check for a newer release.
+
+GeoELAN does not access the network, with two exceptions: '--check', which queries the latest release on GitHub via 'curl', and 'plot --osm', which generates plots that load OpenStreetMap tiles when viewed in a browser. Nothing is downloaded or installed. Use '--offline' with any command to disable all network access."#)
+            .arg(Arg::new("check")
+                .help("Compare the running version against the latest release. Requires network access.")
+                .long("check")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("doctor")
+                .help("Check that default behaviour is fully offline, i.e. that no network access is enabled via the config file. Fails otherwise.")
+                .long("doctor")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("curl")
+                .help("Custom path to curl.")
+                .long("curl")
+                .default_value(if cfg!(windows) {"curl.exe"} else {"curl"}))
         )
 
         // Print or save manual
@@ -593,13 +1459,60 @@ Geoshape options:
                 .help("Save the full manual as a PDF to current directory.")
                 .long("pdf")
                 .action(clap::ArgAction::SetTrue))
-        )
-        .get_matches();
+        );
+    // Default argument values from config file
+    let argv: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    if !argv.iter().any(|a| a == "--no-config") {
+        let explicit = argv.iter()
+            .position(|a| a == "--config")
+            .and_then(|i| argv.get(i + 1))
+            .map(PathBuf::from);
+        if let Some(path) = config::Config::locate(explicit.as_deref()) {
+            match config::Config::read(&path) {
+                Ok(cfg) => cli = cfg.apply(cli),
+                Err(err) => {
+                    eprintln!("{err}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+    }
+    let args = cli.clone().get_matches_from(argv);
+
+    // Overwrite policy for all written files
+    if let Some(policy) = args.get_one::<String>("overwrite") {
+        files::set_overwrite(policy.parse().unwrap_or(files::Overwrite::Ask));
+    }
+
+    // Log level and optional log file
+    let log_level = args.get_one::<String>("log-level") // clap: default 'info'
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(logging::Level::Info);
+    let quiet = *args.get_one::<bool>("quiet").unwrap();
+    if let Err(err) = logging::init(log_level, quiet, args.get_one::<PathBuf>("log-file").map(|p| p.as_path())) {
+        eprintln!("{err}");
+        return ExitCode::FAILURE;
+    }
+    logging::record(logging::Level::Info, &format!(
+        "GeoELAN {VERSION}: {}",
+        std::env::args().collect::<Vec<_>>().join(" ")
+    ));
+
+    // Only print planned outputs
+    files::set_dryrun(*args.get_one::<bool>("dryrun").unwrap());
+
+    // Progress output for long-running operations
+    progress::set_quiet(quiet);
+
+    // Language for prompts and messages
+    if let Some(lang) = args.get_one::<String>("lang") {
+        i18n::set_lang(lang.parse().unwrap_or(i18n::Lang::En));
+    }
 
     // VIEW, SAVE MANUAL
     if let Some(arg_matches) = args.subcommand_matches("manual") {
         if let Err(err) = manual::run(&arg_matches) {
-            eprintln!("{err}");
+            logging::error!("{err}");
             return ExitCode::FAILURE;
         }
     }
@@ -607,7 +1520,15 @@ Geoshape options:
     // ACTION CAMERA FOOTAGE TO EAF, GORP+VIRB
     if let Some(arg_matches) = args.subcommand_matches("cam2eaf") {
         if let Err(err) = cam2eaf::run(&arg_matches) {
-            eprintln!("{err}");
+            logging::error!("{err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // GPX/TCX TO EAF
+    if let Some(arg_matches) = args.subcommand_matches("gpx2eaf") {
+        if let Err(err) = gpx2eaf::run(&arg_matches) {
+            logging::error!("{err}");
             return ExitCode::FAILURE;
         }
     }
@@ -615,7 +1536,7 @@ Geoshape options:
     // EAF TO KML/GEOJSON
     if let Some(arg_matches) = args.subcommand_matches("eaf2geo") {
         if let Err(err) = eaf2geo::run(&arg_matches) {
-            eprintln!("{err}");
+            logging::error!("{err}");
             return ExitCode::FAILURE;
         }
     }
@@ -623,15 +1544,92 @@ Geoshape options:
     // INSPECT TELEMETRY, VIRB + GOPRO
     if let Some(arg_matches) = args.subcommand_matches("inspect") {
         if let Err(err) = inspect::run(&arg_matches) {
-            eprintln!("{err}");
+            logging::error!("{err}");
             return ExitCode::FAILURE;
         }
     }
 
     // PLOT TELEMETRY, VIRB + GOPRO
     if let Some(arg_matches) = args.subcommand_matches("plot") {
-        if let Err(err) = plot::run(&arg_matches) {
-            eprintln!("{err}");
+        // Regenerate plot from specification
+        let spec_matches = match arg_matches.get_one::<PathBuf>("from-spec") {
+            Some(path) => match plot::spec::read_spec(path) {
+                Ok(spec_args) => {
+                    let mut cli_args = vec!["geoelan".to_owned(), "plot".to_owned()];
+                    cli_args.extend(spec_args);
+                    match cli.clone().try_get_matches_from(cli_args) {
+                        Ok(m) => m.subcommand_matches("plot").cloned(),
+                        Err(err) => {
+                            logging::error!("(!) Invalid plot specification '{}': {err}", path.display());
+                            return ExitCode::FAILURE;
+                        }
+                    }
+                },
+                Err(err) => {
+                    logging::error!("{err}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            None => None,
+        };
+        if let Err(err) = plot::run(spec_matches.as_ref().unwrap_or(arg_matches)) {
+            logging::error!("{err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // ELAN-FILE UTILITIES
+    if let Some(arg_matches) = args.subcommand_matches("eaf") {
+        if let Err(err) = eaf::run(&arg_matches) {
+            logging::error!("{err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // CONVERT FILE FORMATS
+    if let Some(arg_matches) = args.subcommand_matches("convert") {
+        if let Err(err) = convert::run(&arg_matches) {
+            logging::error!("{err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // VALIDATE ELAN-FILES
+    if let Some(arg_matches) = args.subcommand_matches("validate") {
+        if let Err(err) = validate::run(&arg_matches) {
+            logging::error!("{err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // EXPORT TELEMETRY, GOPRO
+    if let Some(arg_matches) = args.subcommand_matches("export") {
+        if let Err(err) = export::run(&arg_matches) {
+            logging::error!("{err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // OVERVIEW MAP FOR ALL SESSIONS, VIRB + GOPRO
+    if let Some(arg_matches) = args.subcommand_matches("overview") {
+        if let Err(err) = overview::run(&arg_matches) {
+            logging::error!("{err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // SYNCHRONISE MULTIPLE CAMERAS
+    if let Some(arg_matches) = args.subcommand_matches("sync") {
+        if let Err(err) = sync::run(&arg_matches) {
+            logging::error!("{err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // VERSION REPORT, CHECK FOR NEW RELEASE
+    if let Some(arg_matches) = args.subcommand_matches("update") {
+        if let Err(err) = update::run(&arg_matches, &cli) {
+            logging::error!("{err}");
             return ExitCode::FAILURE;
         }
     }
@@ -639,7 +1637,7 @@ Geoshape options:
     // LOCATE AND MATCH FILES, VIRB + GOPRO
     if let Some(arg_matches) = args.subcommand_matches("locate") {
         if let Err(err) = locate::run(&arg_matches) {
-            eprintln!("{err}");
+            logging::error!("{err}");
             return ExitCode::FAILURE;
         }
     }
@@ -2,6 +2,8 @@
 
 use std::{io::ErrorKind, path::Path};
 
+use crate::files::WriteStatus;
+
 // MAIN MANUAL SUB-COMMAND
 pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     // let outpath: PathBuf;
@@ -18,8 +20,9 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
 
     // write selected file to disk, asks for confirmation if file exists
     match crate::files::writefile(&content, &outpath) {
-        Ok(true) => println!("Wrote {}", outpath.display()),
-        Ok(false) => println!("User aborted writing documentation."),
+        Ok(WriteStatus::Written) => println!("Wrote {}", outpath.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => println!("User aborted writing documentation."),
         Err(err) => {
             let msg = format!("(!) Failed to write '{}': {err}", outpath.display());
             return Err(std::io::Error::new(ErrorKind::Other, msg));
@@ -0,0 +1,846 @@
+//! Minimal, raw MP4 atom parsing for values not exposed by `mp4iter`,
+//! such as edit lists (`elst`), timecode (`tmcd`) samples,
+//! location (`©xyz`), creation time (`mvhd`), file type (`ftyp`),
+//! and media data (`mdat`) checksums.
+
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, Seek, SeekFrom, Take},
+    path::Path,
+};
+
+use time::{Date, Duration, Month, PrimitiveDateTime, Time};
+
+use crate::files::checksum::Sha256;
+
+/// Container atoms that are descended into
+/// when locating track atoms.
+const CONTAINERS: [&'static [u8; 4]; 6] = [b"moov", b"trak", b"mdia", b"minf", b"stbl", b"edts"];
+
+/// MP4 atom position and size.
+#[derive(Debug, Clone)]
+pub struct Atom {
+    /// FourCC.
+    pub name: [u8; 4],
+    /// Byte offset for the start of the atom, including header.
+    pub position: u64,
+    /// Total atom size, including header.
+    pub size: u64,
+    /// Header size, 8 or 16 bytes.
+    pub header_size: u64,
+}
+
+impl Atom {
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.name).to_string()
+    }
+
+    /// Payload size in bytes, excluding header.
+    pub fn payload_size(&self) -> std::io::Result<u64> {
+        match self.size.checked_sub(self.header_size) {
+            Some(n) => Ok(n),
+            None => {
+                let msg = format!(
+                    "(!) Invalid size for MP4 atom '{}' @{}",
+                    self.name(),
+                    self.position
+                );
+                Err(std::io::Error::new(ErrorKind::InvalidData, msg))
+            }
+        }
+    }
+
+    /// Returns a reader limited to the atom data, excluding header,
+    /// for streaming large atoms such as `mdat`.
+    pub fn reader<'a>(&self, file: &'a mut File) -> std::io::Result<Take<&'a mut File>> {
+        let size = self.payload_size()?;
+        file.seek(SeekFrom::Start(self.position + self.header_size))?;
+        Ok(file.take(size))
+    }
+
+    /// Reads atom data, excluding header.
+    pub fn data(&self, file: &mut File) -> std::io::Result<Vec<u8>> {
+        let mut buf: Vec<u8> = Vec::new();
+        let size = self.reader(file)?.read_to_end(&mut buf)?;
+        if (size as u64) < self.payload_size()? {
+            let msg = format!(
+                "(!) MP4 atom '{}' @{} is truncated",
+                self.name(),
+                self.position
+            );
+            return Err(std::io::Error::new(ErrorKind::UnexpectedEof, msg));
+        }
+        Ok(buf)
+    }
+}
+
+/// Returns the atoms between byte offset `start` and `end`,
+/// without descending into containers.
+pub fn atoms(file: &mut File, start: u64, end: u64) -> std::io::Result<Vec<Atom>> {
+    let mut atoms: Vec<Atom> = Vec::new();
+    let mut position = start;
+
+    while position + 8 <= end {
+        let mut header = [0_u8; 8];
+        file.seek(SeekFrom::Start(position))?;
+        file.read_exact(&mut header)?;
+
+        let mut size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        let name = [header[4], header[5], header[6], header[7]];
+        let mut header_size = 8;
+
+        match size {
+            // 64-bit size follows fourcc
+            1 => {
+                let mut largesize = [0_u8; 8];
+                file.read_exact(&mut largesize)?;
+                size = u64::from_be_bytes(largesize);
+                header_size = 16;
+            }
+            // Atom extends to end of parent/file
+            0 => size = end - position,
+            _ => (),
+        }
+
+        if size < header_size || position + size > end {
+            let msg = format!(
+                "(!) Invalid size for MP4 atom '{}' @{position}",
+                String::from_utf8_lossy(&name)
+            );
+            return Err(std::io::Error::new(ErrorKind::InvalidData, msg));
+        }
+
+        atoms.push(Atom {
+            name,
+            position,
+            size,
+            header_size,
+        });
+
+        position += size;
+    }
+
+    Ok(atoms)
+}
+
+/// Edit list entry (`elst`).
+#[derive(Debug, Clone)]
+pub struct EditListEntry {
+    /// Duration of this edit in movie time scale units (`mvhd`).
+    pub segment_duration: u64,
+    /// Start time within the media in media time scale units (`mdhd`).
+    /// `-1` denotes an empty edit, i.e. a delay.
+    pub media_time: i64,
+    /// Playback rate, usually 1.0.
+    pub media_rate: f64,
+}
+
+/// Track values not exposed by `mp4iter`.
+#[derive(Debug, Clone, Default)]
+pub struct TrackAtoms {
+    /// Track ID (`tkhd`).
+    pub id: u32,
+    /// Handler type (`hdlr`), e.g. `vide`, `soun`, `meta`, `tmcd`.
+    pub handler_type: String,
+    /// Handler name (`hdlr`), e.g. `GoPro MET`.
+    pub handler_name: String,
+    /// Media time scale (`mdhd`).
+    pub timescale: u32,
+    /// Format/codec fourcc of first sample description (`stsd`), e.g. `avc1`, `mp4a`.
+    pub codec: String,
+    /// First sample description (`stsd`), excluding size and format fourcc.
+    pub sample_description: Vec<u8>,
+    /// Edit list (`elst`).
+    pub edits: Vec<EditListEntry>,
+    /// Chunk offsets (`stco`/`co64`).
+    pub chunk_offsets: Vec<u64>,
+    /// Sample sizes (`stsz`).
+    pub sample_sizes: Vec<u32>,
+}
+
+impl TrackAtoms {
+    /// Returns start offset in seconds for track,
+    /// derived from the edit list.
+    /// Empty edits delay the start of the track and media time
+    /// of the first non-empty edit trims it.
+    pub fn edit_offset(&self, movie_timescale: u32) -> f64 {
+        let mut offset = 0.0;
+        for edit in self.edits.iter() {
+            if edit.media_time == -1 {
+                if movie_timescale > 0 {
+                    offset += edit.segment_duration as f64 / movie_timescale as f64;
+                }
+            } else {
+                if self.timescale > 0 {
+                    offset -= edit.media_time as f64 / self.timescale as f64;
+                }
+                break;
+            }
+        }
+        offset
+    }
+}
+
+/// Timecode (`tmcd`) for the start of the video.
+#[derive(Debug, Clone)]
+pub struct Timecode {
+    /// Frame number of first sample.
+    pub frame: u32,
+    /// Frames per second (rounded).
+    pub fps: u32,
+    /// Drop frame timecode.
+    pub drop_frame: bool,
+}
+
+impl Timecode {
+    /// Returns the frame count as labelled in the timecode. For drop frame
+    /// timecode (29.97 and 59.94 fps), frame labels 0 and 1 (0-3 for 59.94)
+    /// are skipped at the start of each minute, except every tenth minute.
+    fn frame_label(&self) -> u32 {
+        let fps = self.fps.max(1);
+        if !self.drop_frame || !fps.is_multiple_of(30) {
+            return self.frame;
+        }
+        let drop = fps / 15;
+        let per_minute = fps * 60 - drop;
+        let per_ten_minutes = fps * 600 - drop * 9;
+        let (tens, rest) = (self.frame / per_ten_minutes, self.frame % per_ten_minutes);
+        let minutes = match rest > drop {
+            true => (rest - drop) / per_minute,
+            false => 0,
+        };
+        self.frame + drop * (9 * tens + minutes)
+    }
+}
+
+impl std::fmt::Display for Timecode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let fps = self.fps.max(1);
+        let label = self.frame_label();
+        let frames = label % fps;
+        let seconds = label / fps;
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            seconds / 3600,
+            (seconds % 3600) / 60,
+            seconds % 60,
+            // Drop frame timecode uses ';' as separator
+            if self.drop_frame { ';' } else { ':' },
+            frames
+        )
+    }
+}
+
+/// Single location stored in the `©xyz` user data atom
+/// by phones, drones and some cameras.
+#[derive(Debug, Clone, Copy)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+impl Location {
+    /// Parses an ISO 6709 string in decimal degrees,
+    /// e.g. `+59.3293+018.0686+012.000/`.
+    pub fn from_iso6709(string: &str) -> Option<Self> {
+        let mut values: Vec<f64> = Vec::new();
+        let mut value = String::new();
+        for c in string.trim().trim_end_matches('/').chars() {
+            if (c == '+' || c == '-') && !value.is_empty() {
+                values.push(value.parse().ok()?);
+                value.clear();
+            }
+            value.push(c);
+        }
+        if !value.is_empty() {
+            values.push(value.parse().ok()?);
+        }
+
+        match values.as_slice() {
+            [latitude, longitude, rest @ ..] => Some(Self {
+                latitude: *latitude,
+                longitude: *longitude,
+                altitude: rest.first().copied(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "LAT:{:.6};LON:{:.6}", self.latitude, self.longitude)?;
+        if let Some(alt) = self.altitude {
+            write!(f, ";ALT:{alt:.1}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Encoder and software tags in `moov/udta`,
+/// either QuickTime style or in an iTunes style `meta/ilst`.
+const ENCODER_TAGS: [&'static [u8; 4]; 3] = [b"\xa9too", b"\xa9enc", b"\xa9swr"];
+
+/// Known encoders and handler names for editing software,
+/// used to determine if a file has been re-encoded.
+const EDITOR_SIGNATURES: [&'static str; 8] = [
+    "Lavf",
+    "VideoHandler", // FFmpeg default handler name
+    "SoundHandler", // FFmpeg default handler name
+    "HandBrake",
+    "Adobe",
+    "DaVinci",
+    "Final Cut",
+    "iMovie",
+];
+
+/// File type (`ftyp`) and encoder information,
+/// for determining the provenance of an MP4-file.
+#[derive(Debug, Clone, Default)]
+pub struct FileType {
+    /// Major brand, e.g. `mp41` (GoPro), `isom` (FFmpeg).
+    pub major_brand: String,
+    pub minor_version: u32,
+    pub compatible_brands: Vec<String>,
+    /// Encoder and software tags (`©too`, `©enc`, `©swr`), e.g. `Lavf60.3.100`.
+    pub encoders: Vec<String>,
+    /// Handler names for all tracks (`hdlr`), e.g. `GoPro AVC`.
+    pub handler_names: Vec<String>,
+}
+
+impl FileType {
+    /// Returns `true` if an encoder tag or handler name
+    /// matches known editing software, such as FFmpeg.
+    pub fn is_edited(&self) -> bool {
+        self.encoders
+            .iter()
+            .chain(self.handler_names.iter())
+            .any(|s| EDITOR_SIGNATURES.iter().any(|sig| s.contains(sig)))
+    }
+}
+
+impl std::fmt::Display for FileType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (version {}, compatible: {})",
+            self.major_brand,
+            self.minor_version,
+            self.compatible_brands.join(", ")
+        )?;
+        if !self.encoders.is_empty() {
+            write!(f, ", encoder: {}", self.encoders.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn be_u32(bytes: &[u8], idx: usize) -> Option<u32> {
+    bytes
+        .get(idx..idx + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn be_u64(bytes: &[u8], idx: usize) -> Option<u64> {
+    bytes.get(idx..idx + 8).map(|b| {
+        let mut arr = [0_u8; 8];
+        arr.copy_from_slice(b);
+        u64::from_be_bytes(arr)
+    })
+}
+
+fn be_i16(bytes: &[u8], idx: usize) -> Option<i16> {
+    bytes
+        .get(idx..idx + 2)
+        .map(|b| i16::from_be_bytes([b[0], b[1]]))
+}
+
+/// Returns time scale for full-box atoms `mvhd` and `mdhd`,
+/// which share the same layout up until time scale.
+fn timescale(data: &[u8]) -> Option<u32> {
+    match data.first() {
+        Some(1) => be_u32(data, 20),
+        _ => be_u32(data, 12),
+    }
+}
+
+fn parse_elst(data: &[u8]) -> Vec<EditListEntry> {
+    let version = data.first().cloned().unwrap_or(0);
+    let count = be_u32(data, 4).unwrap_or(0) as usize;
+    let entry_size = if version == 1 { 20 } else { 12 };
+
+    (0..count)
+        .filter_map(|i| {
+            let idx = 8 + i * entry_size;
+            let (segment_duration, media_time, rate_idx) = match version {
+                1 => (be_u64(data, idx)?, be_u64(data, idx + 8)? as i64, idx + 16),
+                _ => (
+                    be_u32(data, idx)? as u64,
+                    be_u32(data, idx + 4)? as i32 as i64,
+                    idx + 8,
+                ),
+            };
+            let rate_int = be_i16(data, rate_idx)?;
+            let rate_frac = be_i16(data, rate_idx + 2)?;
+            Some(EditListEntry {
+                segment_duration,
+                media_time,
+                media_rate: rate_int as f64 + rate_frac as f64 / 65536.0,
+            })
+        })
+        .collect()
+}
+
+fn parse_track(file: &mut File, trak: &Atom, track: &mut TrackAtoms) -> std::io::Result<()> {
+    let start = trak.position + trak.header_size;
+    let end = trak.position + trak.size;
+    for atom in atoms(file, start, end)? {
+        match &atom.name {
+            name if CONTAINERS.contains(&name) => parse_track(file, &atom, track)?,
+            b"tkhd" => {
+                let data = atom.data(file)?;
+                track.id = match data.first() {
+                    Some(1) => be_u32(&data, 20),
+                    _ => be_u32(&data, 12),
+                }
+                .unwrap_or(0);
+            }
+            b"mdhd" => track.timescale = timescale(&atom.data(file)?).unwrap_or(0),
+            b"hdlr" => {
+                let data = atom.data(file)?;
+                track.handler_type = data
+                    .get(8..12)
+                    .map(|b| String::from_utf8_lossy(b).to_string())
+                    .unwrap_or_default();
+                // Name may be null-terminated or, for QuickTime,
+                // a Pascal string with a leading length byte.
+                track.handler_name = data
+                    .get(24..)
+                    .map(|b| {
+                        String::from_utf8_lossy(b)
+                            .chars()
+                            .filter(|c| !c.is_control())
+                            .collect::<String>()
+                            .trim()
+                            .to_owned()
+                    })
+                    .unwrap_or_default();
+            }
+            b"elst" => track.edits = parse_elst(&atom.data(file)?),
+            b"stsd" => {
+                let data = atom.data(file)?;
+                // version + flags (4), entry count (4), then first entry:
+                // size (4), format (4), data
+                if let Some(size) = be_u32(&data, 8) {
+                    track.codec = data
+                        .get(12..16)
+                        .map(|b| String::from_utf8_lossy(b).to_string())
+                        .unwrap_or_default();
+                    track.sample_description = data
+                        .get(16..(8 + size as usize).min(data.len()))
+                        .map(|b| b.to_vec())
+                        .unwrap_or_default();
+                }
+            }
+            b"stco" => {
+                let data = atom.data(file)?;
+                let count = be_u32(&data, 4).unwrap_or(0) as usize;
+                track.chunk_offsets = (0..count)
+                    .filter_map(|i| be_u32(&data, 8 + i * 4).map(|o| o as u64))
+                    .collect();
+            }
+            b"co64" => {
+                let data = atom.data(file)?;
+                let count = be_u32(&data, 4).unwrap_or(0) as usize;
+                track.chunk_offsets = (0..count)
+                    .filter_map(|i| be_u64(&data, 8 + i * 8))
+                    .collect();
+            }
+            b"stsz" => {
+                let data = atom.data(file)?;
+                let sample_size = be_u32(&data, 4).unwrap_or(0);
+                let count = be_u32(&data, 8).unwrap_or(0) as usize;
+                track.sample_sizes = match sample_size {
+                    0 => (0..count)
+                        .filter_map(|i| be_u32(&data, 12 + i * 4))
+                        .collect(),
+                    s => vec![s; count],
+                };
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Container atoms that are descended into when searching
+/// for arbitrary atoms, in addition to `CONTAINERS`.
+const SEARCH_CONTAINERS: [&'static [u8; 4]; 6] =
+    [b"udta", b"dinf", b"mvex", b"moof", b"traf", b"mfra"];
+
+/// Returns all atoms named `name` at any depth, in file order.
+/// Only known container atoms are descended into.
+pub fn find(path: &Path, name: &[u8; 4]) -> std::io::Result<Vec<Atom>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut found: Vec<Atom> = Vec::new();
+    let mut stack = atoms(&mut file, 0, len)?;
+    stack.reverse();
+    while let Some(atom) = stack.pop() {
+        if &atom.name == name {
+            found.push(atom.clone());
+        }
+        if CONTAINERS.contains(&&atom.name) || SEARCH_CONTAINERS.contains(&&atom.name) {
+            let (start, end) = (atom.position + atom.header_size, atom.position + atom.size);
+            let mut children = atoms(&mut file, start, end)?;
+            children.reverse();
+            stack.extend(children);
+        }
+    }
+
+    Ok(found)
+}
+
+/// Returns the movie time scale (`mvhd`) and raw track values
+/// for all tracks in the MP4-file.
+pub fn tracks(path: &Path) -> std::io::Result<(u32, Vec<TrackAtoms>)> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let moov = match atoms(&mut file, 0, len)?
+        .into_iter()
+        .find(|a| &a.name == b"moov")
+    {
+        Some(a) => a,
+        None => {
+            let msg = format!("(!) No 'moov' atom in {}", path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    let mut movie_timescale = 0;
+    let mut tracks: Vec<TrackAtoms> = Vec::new();
+    let (start, end) = (moov.position + moov.header_size, moov.position + moov.size);
+    for atom in atoms(&mut file, start, end)? {
+        match &atom.name {
+            b"mvhd" => movie_timescale = timescale(&atom.data(&mut file)?).unwrap_or(0),
+            b"trak" => {
+                let mut track = TrackAtoms::default();
+                parse_track(&mut file, &atom, &mut track)?;
+                tracks.push(track);
+            }
+            _ => (),
+        }
+    }
+
+    Ok((movie_timescale, tracks))
+}
+
+/// Returns start timecode from the first `tmcd` track, if present.
+pub fn timecode(path: &Path) -> std::io::Result<Option<Timecode>> {
+    let (_, tracks) = tracks(path)?;
+
+    let Some(track) = tracks
+        .iter()
+        .find(|t| t.handler_type == "tmcd" || t.codec == "tmcd")
+    else {
+        return Ok(None);
+    };
+
+    // tmcd sample description, after reserved (6) + data reference index (2):
+    // reserved (4), flags (4), time scale (4), frame duration (4), number of frames (1)
+    let desc = &track.sample_description;
+    let flags = be_u32(desc, 12).unwrap_or(0);
+    let fps = match desc.get(24) {
+        Some(n) if *n > 0 => *n as u32,
+        _ => {
+            let timescale = be_u32(desc, 16).unwrap_or(0);
+            let frame_duration = be_u32(desc, 20).unwrap_or(0);
+            if frame_duration == 0 {
+                return Ok(None);
+            }
+            (timescale as f64 / frame_duration as f64).round() as u32
+        }
+    };
+
+    // First sample is a 32-bit frame number at first chunk offset
+    let Some(offset) = track.chunk_offsets.first() else {
+        return Ok(None);
+    };
+    let mut file = File::open(path)?;
+    let mut buf = [0_u8; 4];
+    file.seek(SeekFrom::Start(*offset))?;
+    file.read_exact(&mut buf)?;
+
+    Ok(Some(Timecode {
+        frame: u32::from_be_bytes(buf),
+        fps,
+        drop_frame: flags & 0x0001 != 0,
+    }))
+}
+
+/// Returns location from the `©xyz` atom in `moov/udta`, if present.
+pub fn location(path: &Path) -> std::io::Result<Option<Location>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let Some(moov) = atoms(&mut file, 0, len)?
+        .into_iter()
+        .find(|a| &a.name == b"moov")
+    else {
+        return Ok(None);
+    };
+    let (start, end) = (moov.position + moov.header_size, moov.position + moov.size);
+    let Some(udta) = atoms(&mut file, start, end)?
+        .into_iter()
+        .find(|a| &a.name == b"udta")
+    else {
+        return Ok(None);
+    };
+    let (start, end) = (udta.position + udta.header_size, udta.position + udta.size);
+    let Some(xyz) = atoms(&mut file, start, end)?
+        .into_iter()
+        .find(|a| &a.name == b"\xa9xyz")
+    else {
+        return Ok(None);
+    };
+
+    // String size (2), language code (2), ISO 6709 string
+    let data = xyz.data(&mut file)?;
+    let size = data
+        .get(0..2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+        .unwrap_or(0);
+    let string = data
+        .get(4..(4 + size).min(data.len()))
+        .map(|b| String::from_utf8_lossy(b).to_string())
+        .unwrap_or_default();
+
+    Ok(Location::from_iso6709(&string))
+}
+
+/// Returns creation time from the `mvhd` atom, if set.
+/// Cameras usually write local time, without time zone.
+pub fn creation_time(path: &Path) -> std::io::Result<Option<PrimitiveDateTime>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let Some(moov) = atoms(&mut file, 0, len)?
+        .into_iter()
+        .find(|a| &a.name == b"moov")
+    else {
+        return Ok(None);
+    };
+    let (start, end) = (moov.position + moov.header_size, moov.position + moov.size);
+    let Some(mvhd) = atoms(&mut file, start, end)?
+        .into_iter()
+        .find(|a| &a.name == b"mvhd")
+    else {
+        return Ok(None);
+    };
+
+    // Seconds since 1904-01-01, after version (1) + flags (3).
+    // 32-bit for version 0, 64-bit for version 1.
+    let data = mvhd.data(&mut file)?;
+    let seconds = match data.first() {
+        Some(1) => be_u64(&data, 4),
+        _ => be_u32(&data, 4).map(|n| n as u64),
+    };
+
+    let epoch = Date::from_calendar_date(1904, Month::January, 1)
+        .map(|d| PrimitiveDateTime::new(d, Time::MIDNIGHT))
+        .ok();
+
+    Ok(match (seconds, epoch) {
+        (Some(s), Some(t)) if s > 0 => Some(t + Duration::seconds(s as i64)),
+        _ => None,
+    })
+}
+
+/// Returns encoder strings in `udta`, either as QuickTime strings
+/// or as `data` atoms in `meta/ilst`.
+fn encoder_tags(file: &mut File, udta: &Atom) -> std::io::Result<Vec<String>> {
+    let mut tags: Vec<String> = Vec::new();
+    let (start, end) = (udta.position + udta.header_size, udta.position + udta.size);
+    for atom in atoms(file, start, end)? {
+        match &atom.name {
+            // String size (2), language code (2), string
+            name if ENCODER_TAGS.contains(&name) => {
+                let data = atom.data(file)?;
+                let size = data
+                    .get(0..2)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+                    .unwrap_or(0);
+                if let Some(b) = data.get(4..(4 + size).min(data.len())) {
+                    tags.push(String::from_utf8_lossy(b).trim().to_owned());
+                }
+            }
+            // ISO full box: version + flags (4) precede child atoms,
+            // QuickTime 'meta' has none.
+            b"meta" => {
+                let mut start = atom.position + atom.header_size;
+                let end = atom.position + atom.size;
+                if !atoms(file, start, end)?.iter().any(|a| &a.name == b"hdlr") {
+                    start += 4;
+                }
+                let Some(ilst) = atoms(file, start, end)?
+                    .into_iter()
+                    .find(|a| &a.name == b"ilst")
+                else {
+                    continue;
+                };
+                let (start, end) = (ilst.position + ilst.header_size, ilst.position + ilst.size);
+                for item in atoms(file, start, end)?
+                    .into_iter()
+                    .filter(|a| ENCODER_TAGS.contains(&&a.name))
+                {
+                    // 'data' atom: type (4), locale (4), value
+                    let data = item.data(file)?;
+                    if let Some(b) = data.get(16..) {
+                        tags.push(String::from_utf8_lossy(b).trim().to_owned());
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(tags)
+}
+
+/// Returns file type (`ftyp`) together with encoder tags
+/// and track handler names, if the file has an `ftyp` atom.
+pub fn file_type(path: &Path) -> std::io::Result<Option<FileType>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let top = atoms(&mut file, 0, len)?;
+
+    let Some(ftyp) = top.iter().find(|a| &a.name == b"ftyp") else {
+        return Ok(None);
+    };
+
+    // Major brand (4), minor version (4), compatible brands (4 each)
+    let data = ftyp.data(&mut file)?;
+    let brand = |b: &[u8]| String::from_utf8_lossy(b).trim().to_owned();
+    let mut file_type = FileType {
+        major_brand: data.get(0..4).map(brand).unwrap_or_default(),
+        minor_version: be_u32(&data, 4).unwrap_or(0),
+        compatible_brands: data
+            .get(8..)
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .map(brand)
+            .collect(),
+        ..FileType::default()
+    };
+
+    if let Some(moov) = top.iter().find(|a| &a.name == b"moov") {
+        let (start, end) = (moov.position + moov.header_size, moov.position + moov.size);
+        if let Some(udta) = atoms(&mut file, start, end)?
+            .into_iter()
+            .find(|a| &a.name == b"udta")
+        {
+            file_type.encoders = encoder_tags(&mut file, &udta)?;
+        }
+    }
+
+    file_type.handler_names = tracks(path)?
+        .1
+        .into_iter()
+        .map(|t| t.handler_name)
+        .filter(|n| !n.is_empty())
+        .collect();
+
+    Ok(Some(file_type))
+}
+
+/// Returns SHA-256 for the payload of top-level media data atoms (`mdat`),
+/// i.e. the audio, video, and telemetry samples, so that copies
+/// with differing container metadata can still be identified as the same clip.
+///
+/// Returns `None` if there is no `mdat` atom.
+pub fn mdat_sha256(path: &Path) -> std::io::Result<Option<String>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mdat: Vec<Atom> = atoms(&mut file, 0, len)?
+        .into_iter()
+        .filter(|a| &a.name == b"mdat")
+        .collect();
+    if mdat.is_empty() {
+        return Ok(None);
+    }
+
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0_u8; 1024 * 1024];
+    for atom in mdat.iter() {
+        let mut payload = atom.reader(&mut file)?;
+        loop {
+            match payload.read(&mut buf)? {
+                0 => break,
+                n => hasher.update(&buf[..n]),
+            }
+        }
+    }
+
+    Ok(Some(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timecode(frame: u32, fps: u32, drop_frame: bool) -> String {
+        Timecode {
+            frame,
+            fps,
+            drop_frame,
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn atom_payload() {
+        let path = std::env::temp_dir().join(format!("geoelan_atoms_{}", std::process::id()));
+        let mut bytes = 16_u32.to_be_bytes().to_vec();
+        bytes.extend(b"free01234567");
+        // Atom claims 64 bytes, but file ends after 16
+        bytes.extend(64_u32.to_be_bytes());
+        bytes.extend(b"udta0123");
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut file = File::open(&path).unwrap();
+        let atom = |position, size| Atom {
+            name: *b"test",
+            position,
+            size,
+            header_size: 8,
+        };
+        let free = atom(0, 16).data(&mut file);
+        let truncated = atom(16, 64).data(&mut file);
+        let invalid = atom(0, 4).data(&mut file);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(free.unwrap(), b"01234567");
+        assert_eq!(truncated.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(invalid.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn non_drop_frame() {
+        assert_eq!(timecode(1800, 30, false), "00:01:00:00");
+        assert_eq!(timecode(107892, 30, false), "00:59:56:12");
+    }
+
+    #[test]
+    fn drop_frame() {
+        assert_eq!(timecode(1799, 30, true), "00:00:59;29");
+        assert_eq!(timecode(1800, 30, true), "00:01:00;02");
+        assert_eq!(timecode(17982, 30, true), "00:10:00;00");
+        assert_eq!(timecode(107892, 30, true), "01:00:00;00");
+        assert_eq!(timecode(3600, 60, true), "00:01:00;04");
+        assert_eq!(timecode(35964, 60, true), "00:10:00;00");
+    }
+}
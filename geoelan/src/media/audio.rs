@@ -0,0 +1,85 @@
+//! Audio-based alignment of media files, via cross-correlation
+//! of audio envelopes extracted with FFmpeg, e.g. to compute
+//! sub-second offsets between an external recorder and camera audio.
+
+use std::{io::ErrorKind, path::Path};
+
+use super::Media;
+
+/// Sample rate for audio extraction.
+const PCM_RATE: u32 = 8000;
+/// Samples per audio envelope value, i.e. 2ms resolution at 8kHz.
+const ENVELOPE_BLOCK: usize = 16;
+
+/// Audio envelope (mean absolute amplitude per block),
+/// normalised to zero mean and unit variance.
+fn envelope(samples: &[i16]) -> Vec<f64> {
+    let env: Vec<f64> = samples
+        .chunks(ENVELOPE_BLOCK)
+        .map(|c| c.iter().map(|s| (*s as f64).abs()).sum::<f64>() / c.len() as f64)
+        .collect();
+    let mean = env.iter().sum::<f64>() / env.len().max(1) as f64;
+    let std = (env.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / env.len().max(1) as f64)
+        .sqrt()
+        .max(f64::EPSILON);
+    env.iter().map(|v| (v - mean) / std).collect()
+}
+
+/// Refines the offset in milliseconds of `other` relative to `reference`
+/// (positive if `other` started later) via cross-correlation of audio
+/// envelopes, searching `max_lag` seconds around `initial_ms`
+/// over a `window` seconds long segment where both files overlap.
+///
+/// Returns refined offset and correlation score (1.0 = identical envelopes).
+pub fn audio_offset(
+    reference: &Path,
+    other: &Path,
+    initial_ms: i64,
+    ffmpeg: &Path,
+    max_lag: f64,
+    window: f64,
+) -> std::io::Result<(i64, f64)> {
+    let initial = initial_ms as f64 / 1000.0;
+    // Reference segment start, such that the search range in 'other' starts at or after 0
+    let ref_start = initial.max(0.0) + max_lag;
+    let other_start = ref_start - initial - max_lag;
+
+    let ref_env = envelope(&Media::pcm(reference, ffmpeg, ref_start, window, PCM_RATE)?);
+    let other_env = envelope(&Media::pcm(
+        other,
+        ffmpeg,
+        other_start,
+        window + 2.0 * max_lag,
+        PCM_RATE,
+    )?);
+
+    let env_rate = (PCM_RATE as usize / ENVELOPE_BLOCK) as f64;
+    let max_k = (2.0 * max_lag * env_rate) as usize;
+    let mut best: Option<(usize, f64)> = None;
+    for k in 0..=max_k.min(other_env.len()) {
+        let n = ref_env.len().min(other_env.len() - k);
+        if n < env_rate as usize {
+            // Less than a second of overlap
+            break;
+        }
+        let score = (0..n).map(|i| ref_env[i] * other_env[i + k]).sum::<f64>() / n as f64;
+        if best.map(|(_, s)| score > s).unwrap_or(true) {
+            best = Some((k, score));
+        }
+    }
+
+    match best {
+        Some((k, score)) => {
+            let lag_ms = (k as f64 / env_rate * 1000.0).round() as i64;
+            Ok((initial_ms + (max_lag * 1000.0) as i64 - lag_ms, score))
+        }
+        None => {
+            let msg = format!(
+                "(!) Not enough overlapping audio in '{}' and '{}'.",
+                reference.display(),
+                other.display()
+            );
+            Err(std::io::Error::new(ErrorKind::Other, msg))
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Extract MP4 tracks as elementary streams.
+//! H.264/H.265 video is converted to Annex-B byte streams,
+//! and AAC audio is wrapped in ADTS headers, so that dumps
+//! are directly playable. Other tracks are extracted as is.
+
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use mp4iter::{track::Track, Mp4};
+
+use super::atoms::{find, tracks, TrackAtoms};
+use crate::files::{affix_file_name, writefile, writefile_from, WriteStatus};
+use crate::logging::info;
+
+const START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+/// Returns the data for the first box named `name`
+/// within a sample description, excluding box header.
+fn find_box<'a>(bytes: &'a [u8], name: &[u8; 4]) -> Option<&'a [u8]> {
+    let idx = bytes.windows(4).position(|w| w == name)?;
+    let start = idx.checked_sub(4)?;
+    let size = u32::from_be_bytes([
+        bytes[start],
+        bytes[start + 1],
+        bytes[start + 2],
+        bytes[start + 3],
+    ]) as usize;
+    bytes.get(idx + 4..(start + size).min(bytes.len()))
+}
+
+/// Returns NAL unit length prefix size, and parameter sets
+/// (SPS, PPS) from an `avcC` box.
+fn avcc(avcc: &[u8]) -> Option<(usize, Vec<Vec<u8>>)> {
+    let length_size = (*avcc.get(4)? & 0b11) as usize + 1;
+    let mut nals: Vec<Vec<u8>> = Vec::new();
+    let mut idx = 5;
+    // SPS count is in lower 5 bits, PPS count is a full byte
+    for mask in [0x1f_u8, 0xff] {
+        let count = *avcc.get(idx)? & mask;
+        idx += 1;
+        for _ in 0..count {
+            let len = u16::from_be_bytes([*avcc.get(idx)?, *avcc.get(idx + 1)?]) as usize;
+            nals.push(avcc.get(idx + 2..idx + 2 + len)?.to_vec());
+            idx += 2 + len;
+        }
+    }
+    Some((length_size, nals))
+}
+
+/// Returns NAL unit length prefix size, and parameter sets
+/// (VPS, SPS, PPS, SEI) from an `hvcC` box.
+fn hvcc(hvcc: &[u8]) -> Option<(usize, Vec<Vec<u8>>)> {
+    let length_size = (*hvcc.get(21)? & 0b11) as usize + 1;
+    let arrays = *hvcc.get(22)?;
+    let mut nals: Vec<Vec<u8>> = Vec::new();
+    let mut idx = 23;
+    for _ in 0..arrays {
+        // array completeness + NAL unit type (1)
+        let count = u16::from_be_bytes([*hvcc.get(idx + 1)?, *hvcc.get(idx + 2)?]);
+        idx += 3;
+        for _ in 0..count {
+            let len = u16::from_be_bytes([*hvcc.get(idx)?, *hvcc.get(idx + 1)?]) as usize;
+            nals.push(hvcc.get(idx + 2..idx + 2 + len)?.to_vec());
+            idx += 2 + len;
+        }
+    }
+    Some((length_size, nals))
+}
+
+/// Returns AAC object type, sampling frequency index, and channel configuration
+/// from the AudioSpecificConfig in an `esds` box.
+fn esds(esds: &[u8]) -> Option<(u8, u8, u8)> {
+    // Locate DecoderSpecificInfo (tag 0x05), skipping
+    // version + flags (4). Descriptor sizes may use 1-4 bytes.
+    let mut idx = 4;
+    while idx < esds.len() {
+        let tag = esds[idx];
+        idx += 1;
+        let mut size = 0_usize;
+        for _ in 0..4 {
+            let b = *esds.get(idx)?;
+            idx += 1;
+            size = (size << 7) | (b & 0x7f) as usize;
+            if b & 0x80 == 0 {
+                break;
+            }
+        }
+        match tag {
+            // ES_Descriptor: ES ID (2), flags (1)
+            0x03 => {
+                let flags = *esds.get(idx + 2)?;
+                idx += 3;
+                if flags & 0x80 != 0 {
+                    idx += 2 // dependsOn_ES_ID
+                }
+                if flags & 0x40 != 0 {
+                    idx += 1 + *esds.get(idx)? as usize // URL
+                }
+                if flags & 0x20 != 0 {
+                    idx += 2 // OCR_ES_Id
+                }
+            }
+            // DecoderConfigDescriptor: object type (1), stream type (1),
+            // buffer size (3), max bitrate (4), avg bitrate (4)
+            0x04 => idx += 13,
+            0x05 => {
+                let b0 = *esds.get(idx)?;
+                let b1 = *esds.get(idx + 1)?;
+                let object_type = b0 >> 3;
+                let freq_index = ((b0 & 0b111) << 1) | (b1 >> 7);
+                let channels = (b1 >> 3) & 0b1111;
+                return Some((object_type, freq_index, channels));
+            }
+            _ => idx += size,
+        }
+    }
+    None
+}
+
+/// Returns a 7 byte ADTS header for an AAC frame of `frame_size` bytes.
+fn adts_header(object_type: u8, freq_index: u8, channels: u8, frame_size: usize) -> [u8; 7] {
+    let len = frame_size + 7;
+    let profile = object_type.saturating_sub(1) & 0b11;
+    [
+        0xff,
+        0xf1, // MPEG-4, no CRC
+        (profile << 6) | ((freq_index & 0b1111) << 2) | ((channels >> 2) & 0b1),
+        ((channels & 0b11) << 6) | ((len >> 11) & 0b11) as u8,
+        ((len >> 3) & 0xff) as u8,
+        (((len & 0b111) << 5) as u8) | 0x1f,
+        0xfc,
+    ]
+}
+
+/// Converts length prefixed NAL units in a sample to Annex-B.
+fn annexb(sample: &[u8], length_size: usize, out: &mut Vec<u8>) {
+    let mut idx = 0;
+    while idx + length_size <= sample.len() {
+        let len = sample[idx..idx + length_size]
+            .iter()
+            .fold(0_usize, |acc, b| (acc << 8) | *b as usize);
+        idx += length_size;
+        let end = (idx + len).min(sample.len());
+        out.extend(START_CODE);
+        out.extend(&sample[idx..end]);
+        idx = end;
+    }
+}
+
+/// Extracts specified track as an elementary stream, and
+/// returns path to the resulting file. `track_id` is either a numerical
+/// track ID or a track name, as listed by `geoelan inspect --video`.
+/// Output is written next to `path`.
+pub fn dump_track(path: &Path, track_id: &str) -> std::io::Result<Option<PathBuf>> {
+    let mut mp4 = Mp4::new(path)?;
+    let track = match track_id.parse::<u32>() {
+        Ok(id) => Track::from_id(&mut mp4, id, false)?,
+        Err(_) => Track::from_name(&mut mp4, track_id, false)?,
+    };
+
+    let (_, atoms) = tracks(path)?;
+    let track_atoms = atoms
+        .into_iter()
+        .find(|t| t.id as u64 == track.id() as u64)
+        .unwrap_or(TrackAtoms::default());
+
+    let desc = track_atoms.sample_description.as_slice();
+
+    // Video: NAL unit length size and parameter sets, audio: ADTS values
+    let (extension, nal, adts) = match track_atoms.codec.as_str() {
+        "avc1" | "avc3" => ("h264", find_box(desc, b"avcC").and_then(avcc), None),
+        "hvc1" | "hev1" => ("h265", find_box(desc, b"hvcC").and_then(hvcc), None),
+        "mp4a" => ("aac", None, find_box(desc, b"esds").and_then(esds)),
+        "gpmd" => ("gpmf", None, None),
+        _ => ("bin", None, None),
+    };
+
+    let mut file = File::open(path)?;
+    let mut out: Vec<u8> = Vec::new();
+
+    if let Some((_, parameter_sets)) = &nal {
+        for nal in parameter_sets.iter() {
+            out.extend(START_CODE);
+            out.extend(nal);
+        }
+    }
+
+    for offset in track.offsets() {
+        let mut sample = vec![0_u8; offset.size as usize];
+        file.seek(SeekFrom::Start(offset.position as u64))?;
+        file.read_exact(&mut sample)?;
+
+        match (&nal, &adts) {
+            (Some((length_size, _)), _) => annexb(&sample, *length_size, &mut out),
+            (_, Some((object_type, freq_index, channels))) => {
+                out.extend(adts_header(
+                    *object_type,
+                    *freq_index,
+                    *channels,
+                    sample.len(),
+                ));
+                out.extend(sample);
+            }
+            _ => out.extend(sample),
+        }
+    }
+
+    if out.is_empty() {
+        let msg = format!("(!) No samples in track '{track_id}'");
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    let suffix = format!("_TRACK{}", track.id());
+    let out_path = affix_file_name(path, None, Some(&suffix), Some(extension));
+    match writefile(&out, &out_path)? {
+        WriteStatus::Written => Ok(Some(out_path)),
+        WriteStatus::Skipped | WriteStatus::Planned => Ok(None),
+    }
+}
+
+/// Converts a FourCC string to bytes, e.g. `udta`.
+/// Characters in the Latin-1 range are accepted, so that
+/// e.g. `©xyz` maps to `[0xa9, b'x', b'y', b'z']`.
+pub fn fourcc(name: &str) -> std::io::Result<[u8; 4]> {
+    let bytes: Vec<u8> = name
+        .chars()
+        .filter_map(|c| u8::try_from(c as u32).ok())
+        .collect();
+    match (name.chars().count(), <[u8; 4]>::try_from(bytes)) {
+        (4, Ok(fourcc)) => Ok(fourcc),
+        _ => {
+            let msg = format!("(!) Invalid FourCC '{name}', must be four characters, e.g. 'udta'");
+            Err(std::io::Error::new(ErrorKind::Other, msg))
+        }
+    }
+}
+
+/// Maximum number of bytes per atom printed as a hex view.
+const HEX_VIEW_LIMIT: u64 = 4096;
+
+/// Returns a hex view of `bytes`, 16 bytes per line, with byte offset,
+/// hexadecimal values, and printable ASCII characters.
+/// `offset` is the position of the first byte in the file.
+pub fn hex_view(bytes: &[u8], offset: u64) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02x}")).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|b| match b.is_ascii_graphic() || *b == b' ' {
+                    true => *b as char,
+                    false => '.',
+                })
+                .collect();
+            format!(
+                "{:010x}  {:<47}  {ascii}",
+                offset + i as u64 * 16,
+                hex.join(" ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Extracts the payload, excluding header, for all atoms named `name`,
+/// e.g. `udta`, `firm`, or vendor specific atoms.
+/// Each atom is either written next to `path` as `<STEM>_<FOURCC>.bin`
+/// (numbered if there are several), or printed as a hex view if `hex` is `true`.
+/// The hex view is limited to the first `HEX_VIEW_LIMIT` bytes of each atom.
+pub fn dump_atom(path: &Path, name: &str, hex: bool) -> std::io::Result<()> {
+    let fourcc = fourcc(name)?;
+    let found = find(path, &fourcc)?;
+    if found.is_empty() {
+        let msg = format!("(!) No atom '{name}' in {}", path.display());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    // Non-alphanumeric characters, e.g. '©', are not used in file names
+    let file_name: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect();
+
+    let mut file = File::open(path)?;
+    for (i, atom) in found.iter().enumerate() {
+        let payload_size = atom.payload_size()?;
+        if hex {
+            println!(
+                "[{}/{}] '{name}' @{} size: {} (payload: {payload_size} bytes)",
+                i + 1,
+                found.len(),
+                atom.position,
+                atom.size,
+            );
+            let mut data: Vec<u8> = Vec::new();
+            atom.reader(&mut file)?
+                .take(HEX_VIEW_LIMIT)
+                .read_to_end(&mut data)?;
+            println!("{}", hex_view(&data, atom.position + atom.header_size));
+            if payload_size > data.len() as u64 {
+                println!(
+                    "... {} more bytes, omit '--hex' to save the full payload.",
+                    payload_size - data.len() as u64
+                );
+            }
+            continue;
+        }
+        let suffix = match found.len() {
+            1 => format!("_{file_name}"),
+            _ => format!("_{file_name}_{}", i + 1),
+        };
+        let out_path = affix_file_name(path, None, Some(&suffix), Some("bin"));
+        match writefile_from(&mut atom.reader(&mut file)?, &out_path) {
+            Ok(WriteStatus::Written) => info!("Wrote {}", out_path.display()),
+            Ok(WriteStatus::Planned) => (),
+            Ok(WriteStatus::Skipped) => info!("User aborted writing {}", out_path.display()),
+            Err(err) => {
+                let msg = format!("(!) Failed to write '{}': {err}", out_path.display());
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,14 +1,24 @@
 //! Media processing, such as as concatenation and extracting audio from video.
 
 use std::{
-    io::{stdout, Write},
+    io::{BufRead, BufReader, Read},
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Stdio},
 };
 
 use eaf_rs::EafError;
+use mp4iter::{track::Track, Mp4};
 
-use crate::files::{affix_file_name, writefile};
+use crate::{
+    files::{affix_file_name, dryrun, gopro::GPMF_TRACK, may_write, writefile, WriteStatus},
+    logging::{info, warn},
+    progress::Progress,
+};
+
+pub mod atoms;
+pub mod audio;
+pub mod dump;
+pub mod timeline;
 
 pub struct Media;
 
@@ -16,11 +26,12 @@ impl Media {
     /// Extract WAV-file from video file.
     pub fn wav(video_path: &Path, ffmpeg_path: &Path) -> Result<PathBuf, EafError> {
         let wav = video_path.with_extension("wav");
-        if wav.exists() {
-            println!("      Audio target already exists.")
+        if dryrun() {
+            crate::files::plan(&wav);
+        } else if wav.exists() {
+            info!("      Audio target already exists.")
         } else {
-            print!("      Extracting wav to {}... ", wav.display());
-            stdout().flush()?;
+            info!("      Extracting wav to {}", wav.display());
             Command::new(&ffmpeg_path)
                 .args(&[
                     "-i",
@@ -29,7 +40,6 @@ impl Media {
                     &wav.display().to_string(),
                 ])
                 .output()?;
-            println!("Done");
         }
 
         Ok(wav)
@@ -38,42 +48,47 @@ impl Media {
     /// Concatenate video clips.
     /// Returns paths to resulting video and audio as
     /// a tuple `(video, audio)`.
+    ///
+    /// `name` replaces the file stem of the first clip
+    /// as base name for the resulting files.
+    ///
+    /// `metadata` is written to the resulting video as
+    /// custom MP4 metadata in the form `(key, value)`,
+    /// e.g. session UUID and processing provenance.
     pub fn concatenate(
         session: &[PathBuf],
         output_dir: &Path,
         extract_wav: bool,
+        name: Option<&str>,
         prefix: Option<&str>,
         suffix: Option<&str>,
         ffmpeg_path: &str,
+        metadata: &[(String, String)],
     ) -> std::io::Result<(Option<PathBuf>, Option<PathBuf>)> {
-        // NOTE 200324: Assumes output_dir exists
+        // NOTE 200324: Assumes output_dir exists, unless this is a dry run
         if session.is_empty() {
             return Err(std::io::ErrorKind::NotFound.into());
         } else {
             // SET UP PATHS
             let first_in_session = session[0].to_owned();
-            let filestem = first_in_session.file_stem().unwrap().to_os_string();
+            let filestem = match name {
+                Some(n) => n.into(),
+                None => first_in_session.file_stem().unwrap().to_os_string(),
+            };
 
-            let video_out = affix_file_name(
-                &output_dir.canonicalize()?.join(&filestem),
-                prefix,
-                suffix,
-                Some("mp4"),
-            );
+            let output_dir = match output_dir.exists() {
+                true => output_dir.canonicalize()?,
+                false => output_dir.to_owned(),
+            };
 
-            let audio_out = affix_file_name(
-                &output_dir.canonicalize()?.join(&filestem),
-                prefix,
-                suffix,
-                Some("wav"),
-            );
+            let video_out =
+                affix_file_name(&output_dir.join(&filestem), prefix, suffix, Some("mp4"));
 
-            let concatenation_list_path = affix_file_name(
-                &output_dir.canonicalize()?.join(&filestem),
-                prefix,
-                suffix,
-                Some("txt"),
-            );
+            let audio_out =
+                affix_file_name(&output_dir.join(&filestem), prefix, suffix, Some("wav"));
+
+            let concatenation_list_path =
+                affix_file_name(&output_dir.join(&filestem), prefix, suffix, Some("txt"));
 
             // concatenation_list_path.set_extension("txt");
 
@@ -87,15 +102,40 @@ impl Media {
 
             writefile(&concatenation_list.as_bytes(), &concatenation_list_path)?;
 
+            if dryrun() {
+                info!(
+                    "[dryrun] Would concatenate {} clip/s with FFmpeg",
+                    session.len()
+                );
+                crate::files::plan(&video_out);
+                if extract_wav {
+                    crate::files::plan(&audio_out);
+                }
+                return Ok((
+                    Some(video_out),
+                    if extract_wav { Some(audio_out) } else { None },
+                ));
+            }
+
+            // Total duration in microseconds for progress reporting
+            let duration_us = session
+                .iter()
+                .filter_map(|path| Self::duration(path).ok())
+                .map(|d| d.whole_microseconds().max(0) as u64)
+                .sum();
+
             // RUN FFMPEG
             // runs even for single-clip sessions to embed uuid, fit + fit checksum as metadata
             // copies original stream, no re-encoding, however since original is always
-            // copied into new container (remux), embedded data (VIRB UUID, GoPro GPMF) is lost.
+            // copied into new container (remux), embedded data (VIRB UUID) is lost.
+            // The GoPro GPMF track is explicitly mapped, see Media::gpmf_stream().
             Self::run(
                 &concatenation_list_path,
                 &video_out,
                 extract_wav,
                 ffmpeg_path,
+                metadata,
+                duration_us,
             )?;
 
             return Ok((
@@ -110,6 +150,8 @@ impl Media {
         output_path: &Path,
         extract_wav: bool,
         ffmpeg_cmd: &str,
+        metadata: &[(String, String)],
+        duration_us: u64,
     ) -> std::io::Result<()> {
         let concatenation_file_path_str = concatenation_file_path.display().to_string();
         let output_path_str = output_path.display().to_string();
@@ -118,36 +160,95 @@ impl Media {
             // don't want to return error here since wav extraction may still be needed...
             // perhaps restructure.
             // return Err(std::io::ErrorKind::AlreadyExists)
-            println!("      Video target already exists.")
+            info!("      Video target already exists.")
         } else {
-            print!("      Concatenating to {}... ", output_path.display());
-            stdout().flush()?;
+            info!("      Concatenating to {}", output_path.display());
 
-            let ffmpeg_args = vec![
+            let metadata_args = metadata
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>();
+
+            let mut ffmpeg_args = vec![
                 "-f",
                 "concat", // concatenate
                 "-safe",
                 "0", // ignore safety warning leading to exit
                 "-i",
                 &concatenation_file_path_str, // use file list as input
-                "-c:v",
-                "copy", // copy video data as is, no conversion
-                "-c:a",
-                "copy", // copy audio data as is, no conversion
-                &output_path_str,
+                "-movflags",
+                "use_metadata_tags", // write custom metadata keys
+                "-progress",
+                "pipe:1", // machine readable progress to stdout
+                "-nostats",
+            ];
+            for arg in metadata_args.iter() {
+                ffmpeg_args.extend(["-metadata", arg.as_str()]);
+            }
+
+            // Map the GoPro GPMF track, if present, by stream index.
+            // Other data tracks (timecode, 'fdsc' etc) are left out,
+            // since these can not be muxed as is.
+            let gpmf_map = Self::gpmf_stream(concatenation_file_path).map(|i| format!("0:{i}"));
+            let gpmf_args = gpmf_map.as_ref().map(|map| {
+                vec![
+                    "-map",
+                    "0:v", // video
+                    "-map",
+                    "0:a?", // audio, if present
+                    "-map",
+                    map.as_str(), // GPMF
+                    "-c",
+                    "copy", // copy all data as is, no conversion
+                    "-tag:d",
+                    "gpmd", // GPMF codec tag, required for the data track to be muxed
+                ]
+            });
+            let default_args = vec![
+                "-c:v", "copy", // copy video data as is, no conversion
+                "-c:a", "copy", // copy audio data as is, no conversion
             ];
 
-            Command::new(&ffmpeg_cmd).args(&ffmpeg_args).output()?;
-            println!("Done");
+            let mut result = Err(String::new());
+            if let Some(args) = gpmf_args {
+                result = Self::run_ffmpeg(
+                    ffmpeg_cmd,
+                    &[ffmpeg_args.as_slice(), &args, &[&output_path_str]].concat(),
+                    duration_us,
+                );
+                if let Err(err) = result.as_ref() {
+                    warn!("(!) FFmpeg failed to keep the GPMF track: {err}");
+                    warn!("(!) Concatenating without GPMF track.");
+                    if output_path.exists() {
+                        std::fs::remove_file(output_path)?;
+                    }
+                }
+            }
+            if result.is_err() {
+                result = Self::run_ffmpeg(
+                    ffmpeg_cmd,
+                    &[ffmpeg_args.as_slice(), &default_args, &[&output_path_str]].concat(),
+                    duration_us,
+                );
+            }
+
+            if let Err(err) = result {
+                // Remove partially written video to avoid
+                // truncated files being used in later runs.
+                if output_path.exists() {
+                    std::fs::remove_file(output_path)?;
+                }
+                let msg = format!("(!) FFmpeg failed to concatenate clips: {err}");
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
+            }
         }
 
         if extract_wav {
             let wav = output_path.with_extension("wav");
             if wav.exists() {
-                println!("      Audio target already exists.")
+                info!("      Audio target already exists.")
             } else {
-                print!("      Extracting wav to {}... ", wav.display());
-                stdout().flush()?;
+                info!("      Extracting wav to {}", wav.display());
                 Command::new(&ffmpeg_cmd)
                     .args(&[
                         "-i",
@@ -156,13 +257,168 @@ impl Media {
                         &wav.display().to_string(),
                     ])
                     .output()?;
-                println!("Done");
             }
         }
 
         Ok(())
     }
 
+    /// Runs FFmpeg with `args`, reporting progress
+    /// from `-progress pipe:1` output.
+    /// Returns the last line of FFmpeg's error output on failure.
+    fn run_ffmpeg(ffmpeg_cmd: &str, args: &[&str], duration_us: u64) -> Result<(), String> {
+        let mut child = Command::new(ffmpeg_cmd)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| err.to_string())?;
+
+        // Read stderr in a separate thread to avoid FFmpeg blocking on a full pipe
+        let mut child_stderr = child.stderr.take();
+        let stderr_reader = std::thread::spawn(move || {
+            let mut stderr = String::new();
+            if let Some(e) = child_stderr.as_mut() {
+                let _ = e.read_to_string(&mut stderr);
+            }
+            stderr
+        });
+
+        // Progress is reported as 'key=value' lines, e.g. 'out_time_us=2000000'
+        let progress = Progress::nested("      FFmpeg", duration_us);
+        if let Some(child_stdout) = child.stdout.take() {
+            for line in BufReader::new(child_stdout).lines().map_while(Result::ok) {
+                if let Some(Ok(us)) = line.strip_prefix("out_time_us=").map(|v| v.parse::<u64>()) {
+                    progress.set(us);
+                }
+            }
+        }
+
+        let status = child.wait().map_err(|err| err.to_string())?;
+        let stderr = stderr_reader.join().unwrap_or_default();
+        progress.finish();
+        match status.success() {
+            true => Ok(()),
+            false => Err(stderr.lines().last().unwrap_or("Unknown error").to_owned()),
+        }
+    }
+
+    /// Returns the stream index of the GoPro GPMF track (`GoPro MET`)
+    /// in the first clip listed in an FFmpeg concatenation list,
+    /// or `None` for non-GoPro clips.
+    fn gpmf_stream(concatenation_file_path: &Path) -> Option<usize> {
+        let list = std::fs::read_to_string(concatenation_file_path).ok()?;
+        let first = list
+            .lines()
+            .find_map(|l| l.strip_prefix("file '")?.strip_suffix('\''))?;
+        let (_, tracks) = atoms::tracks(Path::new(first)).ok()?;
+        tracks.iter().position(|t| t.handler_name == GPMF_TRACK)
+    }
+
+    /// Cuts a clip from `video_path`, starting at `start` seconds,
+    /// with duration `duration` seconds.
+    /// If `copy` is `true`, streams are copied without re-encoding,
+    /// which is faster, but the clip then starts at the closest
+    /// preceding keyframe.
+    /// An existing clip is overwritten according to the overwrite policy.
+    pub fn clip(
+        video_path: &Path,
+        ffmpeg_path: &Path,
+        start: f64,
+        duration: f64,
+        copy: bool,
+        clip_path: &Path,
+    ) -> std::io::Result<WriteStatus> {
+        if dryrun() {
+            crate::files::plan(clip_path);
+            return Ok(WriteStatus::Planned);
+        }
+        if !may_write(clip_path)? {
+            return Ok(WriteStatus::Skipped);
+        }
+
+        let mut args = vec![
+            "-y".to_owned(),
+            "-v".to_owned(),
+            "error".to_owned(),
+            "-ss".to_owned(),
+            format!("{start:.3}"),
+            "-t".to_owned(),
+            format!("{duration:.3}"),
+            "-i".to_owned(),
+            video_path.display().to_string(),
+        ];
+        if copy {
+            args.extend(["-c".to_owned(), "copy".to_owned()]);
+        }
+        args.push(clip_path.display().to_string());
+
+        let output = Command::new(ffmpeg_path).args(&args).output()?;
+        if !output.status.success() {
+            let msg = format!(
+                "(!) Failed to cut clip from '{}': {}",
+                video_path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
+        }
+
+        Ok(WriteStatus::Written)
+    }
+
+    /// Returns `true` if the MP4-file contains a track with specified name.
+    pub fn has_track(path: &Path, name: &str) -> bool {
+        match Mp4::new(path) {
+            Ok(mut mp4) => Track::from_name(&mut mp4, name, false).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Extracts mono audio as signed 16-bit samples at `sample_rate` Hz,
+    /// starting at `start` seconds, for at most `duration` seconds.
+    pub fn pcm(
+        path: &Path,
+        ffmpeg_path: &Path,
+        start: f64,
+        duration: f64,
+        sample_rate: u32,
+    ) -> std::io::Result<Vec<i16>> {
+        let output = Command::new(ffmpeg_path)
+            .args(&[
+                "-v",
+                "error",
+                "-ss",
+                &format!("{start:.3}"),
+                "-t",
+                &format!("{duration:.3}"),
+                "-i",
+                &path.display().to_string(),
+                "-vn",
+                "-ac",
+                "1",
+                "-ar",
+                &sample_rate.to_string(),
+                "-f",
+                "s16le",
+                "-",
+            ])
+            .output()?;
+        if !output.status.success() {
+            let msg = format!(
+                "(!) Failed to extract audio from '{}': {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
+        }
+
+        Ok(output
+            .stdout
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect())
+    }
+
     /// Returns duration for the longest track in an MP4-file.
     pub fn duration(path: &Path) -> std::io::Result<time::Duration> {
         let mut mp4 = mp4iter::Mp4::new(path)?;
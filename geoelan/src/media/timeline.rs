@@ -0,0 +1,158 @@
+//! Clip time spans within and between recording sessions,
+//! for detecting gaps, e.g. missing chapters.
+
+use std::path::{Path, PathBuf};
+
+use time::{Duration, PrimitiveDateTime};
+
+use super::{atoms, Media};
+
+/// Max difference between the end of a chapter and the start of the next
+/// that is still considered contiguous. MP4 creation times
+/// only have a resolution of one second.
+pub const CHAPTER_GAP_TOLERANCE: Duration = Duration::seconds(2);
+
+/// Returns chapter number for GoPro clip file names,
+/// e.g. `2` for `GX020123.MP4`, `GH020123.MP4` or `GL020123.LRV`.
+/// The first chapter for older models, `GOPR0123.MP4`, is `0`,
+/// followed by `GP010123.MP4`.
+pub fn gopro_chapter(path: &Path) -> Option<u32> {
+    let stem = path.file_stem()?.to_str()?.to_uppercase();
+    if stem.len() != 8 || !stem.starts_with('G') {
+        return None;
+    }
+    if stem.starts_with("GOPR") {
+        return Some(0);
+    }
+    stem.get(2..4)?.parse::<u32>().ok()
+}
+
+/// Single clip with start time and duration.
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub path: PathBuf,
+    /// MP4 creation time (`mvhd`).
+    pub start: Option<PrimitiveDateTime>,
+    pub duration: Duration,
+}
+
+impl Clip {
+    pub fn new(path: &Path) -> std::io::Result<Self> {
+        Ok(Self {
+            path: path.to_owned(),
+            start: atoms::creation_time(path)?,
+            duration: Media::duration(path)?,
+        })
+    }
+
+    pub fn end(&self) -> Option<PrimitiveDateTime> {
+        self.start.map(|t| t + self.duration)
+    }
+}
+
+/// Recording session with start and end time, and its clips.
+#[derive(Debug, Clone)]
+pub struct SessionSpan {
+    /// Session start, preferably derived from telemetry.
+    pub start: Option<PrimitiveDateTime>,
+    /// Session end, preferably derived from telemetry.
+    pub end: Option<PrimitiveDateTime>,
+    pub clips: Vec<Clip>,
+}
+
+impl SessionSpan {
+    /// New session span from clips, in recording order.
+    /// `start` and `end` fall back on clip creation times if `None`.
+    pub fn new(
+        clips: &[PathBuf],
+        start: Option<PrimitiveDateTime>,
+        end: Option<PrimitiveDateTime>,
+    ) -> Self {
+        let clips: Vec<Clip> = clips.iter().filter_map(|p| Clip::new(p).ok()).collect();
+        Self {
+            start: start.or_else(|| clips.first().and_then(|c| c.start)),
+            end: end.or_else(|| clips.last().and_then(|c| c.end())),
+            clips,
+        }
+    }
+
+    /// Returns the gaps between consecutive clips as
+    /// `(index of first clip, gap)`. Gaps are `None` if
+    /// start time could not be determined for either clip.
+    /// A negative gap means clips overlap.
+    pub fn chapter_gaps(&self) -> Vec<(usize, Option<Duration>)> {
+        self.clips
+            .windows(2)
+            .enumerate()
+            .map(|(i, w)| {
+                let gap = match (w[0].end(), w[1].start) {
+                    (Some(end), Some(start)) => Some(start - end),
+                    _ => None,
+                };
+                (i, gap)
+            })
+            .collect()
+    }
+
+    /// Returns gaps between consecutive clips that exceed
+    /// `CHAPTER_GAP_TOLERANCE`, e.g. a missing or corrupt chapter.
+    /// Overlapping clips (negative gaps) are not included.
+    pub fn missing_chapters(&self) -> Vec<(usize, Duration)> {
+        self.chapter_gaps()
+            .into_iter()
+            .filter_map(|(i, gap)| gap.map(|g| (i, g)))
+            .filter(|(_, g)| *g > CHAPTER_GAP_TOLERANCE)
+            .collect()
+    }
+
+    /// Returns chapter numbers missing between consecutive clips,
+    /// as `(index of first clip, missing chapter numbers)`,
+    /// judging from GoPro file names.
+    pub fn skipped_chapters(&self) -> Vec<(usize, Vec<u32>)> {
+        self.clips
+            .windows(2)
+            .enumerate()
+            .filter_map(|(i, w)| {
+                let (c1, c2) = (gopro_chapter(&w[0].path)?, gopro_chapter(&w[1].path)?);
+                match c2 > c1 + 1 {
+                    true => Some((i, (c1 + 1..c2).collect())),
+                    false => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns one annotation per clip, as `(value, start ms, end ms)`,
+    /// relative to the start of the concatenated clips.
+    /// Values are clip file names, with a note for clips that are preceded
+    /// by a gap in recording time or chapter numbering,
+    /// i.e. where a chapter is missing or corrupt.
+    pub fn chapter_spans(&self) -> Vec<(String, i64, i64)> {
+        let gaps = self.missing_chapters();
+        let skipped = self.skipped_chapters();
+        let mut start_ms = 0;
+        let mut spans = Vec::new();
+        for (i, clip) in self.clips.iter().enumerate() {
+            let mut value = clip
+                .path
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_default();
+            // Gaps are indexed by the first of the two clips
+            if let Some((_, gap)) = gaps.iter().find(|(g, _)| g + 1 == i) {
+                value.push_str(&format!(
+                    " (!) {:.1}s missing before clip",
+                    gap.as_seconds_f64()
+                ));
+            }
+            if let Some((_, chapters)) = skipped.iter().find(|(s, _)| s + 1 == i) {
+                let chapters: Vec<String> = chapters.iter().map(|c| format!("{c:02}")).collect();
+                value.push_str(&format!(" (!) chapter {} missing", chapters.join(", ")));
+            }
+            let end_ms = start_ms + clip.duration.whole_milliseconds() as i64;
+            spans.push((value, start_ms, end_ms));
+            start_ms = end_ms;
+        }
+        spans
+    }
+}
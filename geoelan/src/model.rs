@@ -2,8 +2,11 @@
 
 use std::{ffi::OsString, path::Path};
 
-use fit_rs::VirbFile;
-use gpmf_rs::DeviceName;
+use fit_rs::{VirbFile, VirbSession};
+use gpmf_rs::{DeviceName, GoProFile, GoProSession};
+use time::PrimitiveDateTime;
+
+use crate::files::checksum::Sha256;
 
 #[derive(Debug, Clone)]
 pub enum CameraModel {
@@ -15,6 +18,17 @@ pub enum CameraModel {
     Unknown,
 }
 
+impl CameraModel {
+    /// Camera name, e.g. `GoPro HERO11 Black`.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Virb(_) => "Garmin VIRB".to_owned(),
+            Self::GoPro(device) => format!("GoPro {}", device.to_str()).trim().to_owned(),
+            Self::Unknown => "Unknown".to_owned(),
+        }
+    }
+}
+
 impl From<&str> for CameraModel {
     fn from(kind: &str) -> Self {
         match kind {
@@ -35,6 +49,15 @@ impl From<&Path> for CameraModel {
             return CameraModel::GoPro(devname);
         }
 
+        // Newer models and firmware may use a device name
+        // that is not yet recognized. Fall back on the
+        // presence of a GoPro MUID/GUMI instead.
+        if let Ok(gopro) = GoProFile::new(path) {
+            if has_gopro_id(&gopro) {
+                return CameraModel::GoPro(DeviceName::default());
+            }
+        }
+
         return CameraModel::Unknown;
     }
 }
@@ -49,3 +72,132 @@ impl From<&OsString> for CameraModel {
         }
     }
 }
+
+/// Returns `true` if the GoPro file has a MUID or GUMI,
+/// i.e. the identifiers used to group clips into recording sessions.
+pub fn has_gopro_id(gopro: &GoProFile) -> bool {
+    gopro.muid.iter().any(|n| *n != 0) || gopro.gumi.iter().any(|n| *n != 0)
+}
+
+/// Returns GoPro model generation, e.g. `13` for "HERO13 Black",
+/// by extracting the first number in the device name.
+/// Returns `None` for unknown devices and models without
+/// a number in the name (e.g. "Camera" for Hero5 Black).
+pub fn hero_generation(device: &DeviceName) -> Option<u8> {
+    device
+        .to_str()
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse::<u8>()
+        .ok()
+}
+
+/// Returns `true` if the GoPro model logs GPS9
+/// (10Hz, individually timestamped points),
+/// currently Hero11 Black and Hero13 Black.
+/// Hero12 Black has no GPS module.
+/// Future models are assumed to log GPS9.
+pub fn logs_gps9(device: &DeviceName) -> bool {
+    match hero_generation(device) {
+        Some(12) => false,
+        Some(g) => g >= 11,
+        None => false,
+    }
+}
+
+/// Returns `false` for GoPro models without a GPS module
+/// (currently only Hero12 Black).
+pub fn has_gps(device: &DeviceName) -> bool {
+    hero_generation(device) != Some(12)
+}
+
+/// Returns GoPro MUID as a hexadecimal string,
+/// as used for recording session identifiers.
+pub fn muid_string(muid: &[u32]) -> String {
+    muid.iter().map(|n| format!("{n:08x}")).collect()
+}
+
+/// Returns recording session identifier for a clip,
+/// i.e. UUID (VIRB) or MUID as hex (GoPro).
+pub fn session_id(path: &Path) -> Option<String> {
+    if let Ok(uuid) = VirbFile::uuid_mp4(path) {
+        return Some(uuid);
+    }
+    GoProFile::new(path)
+        .ok()
+        .filter(has_gopro_id)
+        .map(|gopro| muid_string(&gopro.muid))
+}
+
+/// Returns a hardware independent identifier for a recording session:
+/// the first 16 hex characters of SHA-256 for the clip identifiers
+/// (UUID for VIRB, MUID as hex for GoPro) in recording order,
+/// and the start time of the first clip as Unix time in milliseconds.
+///
+/// Since it only depends on values embedded in the clips,
+/// files from the same session can be re-associated even after renaming.
+/// Use `stable_session_id_gopro()` or `stable_session_id_virb()`,
+/// so that the same clips are included regardless of command.
+pub fn stable_session_id(clip_ids: &[String], start: Option<PrimitiveDateTime>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(clip_ids.join(";").as_bytes());
+    if let Some(start) = start {
+        let ms = start.assume_utc().unix_timestamp_nanos() / 1_000_000;
+        hasher.update(format!("|{ms}").as_bytes());
+    }
+    hasher.finalize()[..16].to_owned()
+}
+
+/// Returns hardware independent session identifier for
+/// all clips in a GoPro recording session, including clips
+/// with only a low-resolution version. See `stable_session_id()`.
+pub fn stable_session_id_gopro(session: &GoProSession) -> String {
+    let ids: Vec<String> = session.iter().map(|f| muid_string(&f.muid)).collect();
+    stable_session_id(&ids, session.iter().next().map(|f| f.start()))
+}
+
+/// Returns hardware independent session identifier for
+/// all clips in a VIRB recording session, including clips
+/// with only a low-resolution version. See `stable_session_id()`.
+pub fn stable_session_id_virb(session: &VirbSession) -> String {
+    let ids: Vec<String> = session.virb.iter().map(|v| v.uuid.to_owned()).collect();
+    stable_session_id(&ids, session.virb.first().and_then(|v| v.created()))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Date, Month, PrimitiveDateTime, Time};
+
+    use super::*;
+
+    fn start(nanosecond: u32) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2023, Month::November, 14).unwrap(),
+            Time::from_hms_nano(22, 13, 20, nanosecond).unwrap(),
+        )
+    }
+
+    #[test]
+    fn stable_session_id_known_answer() {
+        // sha256("0a1b2c3d;0a1b2c3d|1700000000500")
+        let ids = vec!["0a1b2c3d".to_owned(), "0a1b2c3d".to_owned()];
+        assert_eq!(
+            stable_session_id(&ids, Some(start(500_000_000))),
+            "615c19fd8410a866"
+        );
+        assert_eq!(stable_session_id(&ids, None), "4981ee8fa1568f8b");
+    }
+
+    #[test]
+    fn stable_session_id_is_deterministic() {
+        let ids = vec![muid_string(&[1, 2, 3, 4]), muid_string(&[1, 2, 3, 4])];
+        let id = stable_session_id(&ids, Some(start(500_000_000)));
+        assert_eq!(id, stable_session_id(&ids, Some(start(500_000_000))));
+        // Only millisecond precision is hashed
+        assert_eq!(id, stable_session_id(&ids, Some(start(500_000_999))));
+        assert_ne!(id, stable_session_id(&ids, Some(start(501_000_000))));
+        assert_ne!(id, stable_session_id(&ids[..1], Some(start(500_000_000))));
+    }
+}
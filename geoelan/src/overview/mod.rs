@@ -0,0 +1,256 @@
+//! Project-level overview map for a fieldwork archive.
+//! Locates all GoPro and/or VIRB recording sessions in a directory
+//! and generates a single KML and GeoJSON-file, with one coloured track
+//! per session, and a start marker with session ID, start time
+//! and links to the source files.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use fit_rs::VirbSession;
+use geojson::Feature;
+use gpmf_rs::GoProSession;
+use kml::types::Placemark;
+
+use crate::{
+    files::{file_url, writefile, WriteStatus},
+    geo::{
+        geoshape::GeoShape,
+        json_gen::{geojson_from_features, geojson_linestring, geojson_point},
+        kml_gen::{
+            kml_extended_data, kml_from_placemarks, kml_linestring, kml_point, kml_style,
+            kml_to_string,
+        },
+        kml_styles::Rgba,
+        DocumentInfo, EafPoint, EafPointCluster,
+    },
+    logging::{info, warn},
+    model::{muid_string, stable_session_id_gopro, stable_session_id_virb},
+};
+
+/// Recording session track.
+struct SessionTrack {
+    /// Camera, e.g. `GoPro` or `VIRB`.
+    camera: &'static str,
+    /// MUID (GoPro) or UUID for first clip (VIRB).
+    session_id: String,
+    /// Hardware independent session identifier.
+    stable_session_id: String,
+    /// High-resolution clips and, for VIRB, the FIT-file.
+    files: Vec<PathBuf>,
+    points: Vec<EafPoint>,
+}
+
+impl SessionTrack {
+    /// Start marker description, with links to source files.
+    fn description(&self) -> String {
+        let mut lines = vec![
+            format!("Camera: {}", self.camera),
+            format!("Session: {}", self.session_id),
+            format!("Stable session ID: {}", self.stable_session_id),
+            format!(
+                "Start: {}",
+                self.points
+                    .first()
+                    .and_then(|p| p.datetime)
+                    .map(|dt| dt.to_string())
+                    .unwrap_or("Unknown".to_owned())
+            ),
+        ];
+        for path in self.files.iter() {
+            lines.push(format!(
+                "<a href=\"{}\">{}</a>",
+                file_url(path),
+                path.file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            ));
+        }
+        lines.join("<br/>")
+    }
+}
+
+/// GoPro session tracks. Sessions without GPS are skipped.
+fn gopro_tracks(indir: &Path, gpsfix: u32, downsample_factor: usize) -> Vec<SessionTrack> {
+    let sessions = match GoProSession::sessions_from_path(indir, None, false, true, true) {
+        Ok(s) => s,
+        Err(err) => {
+            warn!("(!) Failed to locate GoPro sessions: {err}");
+            return Vec::new();
+        }
+    };
+
+    sessions
+        .iter()
+        .filter_map(|session| {
+            let first = session.iter().next()?;
+            let session_id = muid_string(&first.muid);
+            let stable_session_id = stable_session_id_gopro(session);
+            let gpmf = session
+                .gpmf()
+                .map_err(|err| warn!("(!) Skipping GoPro session {session_id}: {err}"))
+                .ok()?;
+            let end = gpmf.duration().ok()?;
+            let gps = gpmf.gps().prune(gpsfix, None);
+            let cluster = EafPointCluster::from_gopro(&gps.0, None, &end, None)
+                .downsample(downsample_factor, Some(2));
+            Some(SessionTrack {
+                camera: "GoPro",
+                session_id,
+                stable_session_id,
+                files: session.iter().filter_map(|f| f.mp4.to_owned()).collect(),
+                points: cluster.points,
+            })
+        })
+        .collect()
+}
+
+/// VIRB session tracks. Sessions without GPS are skipped.
+fn virb_tracks(indir: &Path, downsample_factor: usize) -> Vec<SessionTrack> {
+    let mut sessions = VirbSession::sessions_from_path(indir, true);
+
+    sessions
+        .iter_mut()
+        .filter_map(|session| {
+            let session_id = session.virb.first()?.uuid.to_owned();
+            let stable_session_id = stable_session_id_virb(session);
+            if let Err(err) = session.process(0) {
+                warn!("(!) Skipping VIRB session {session_id}: {err}");
+                return None;
+            }
+            let gps = session.gps().ok()?;
+            let (t0, end) = (session.t0?, session.end?);
+            let cluster = EafPointCluster::from_virb(&gps, None, &t0, &end, None)
+                .downsample(downsample_factor, Some(2));
+            let mut files = vec![session.fit_path()];
+            files.extend(
+                session
+                    .virb
+                    .iter()
+                    .filter_map(|v| v.mp4().map(|p| p.to_path_buf())),
+            );
+            Some(SessionTrack {
+                camera: "VIRB",
+                session_id,
+                stable_session_id,
+                files,
+                points: cluster.points,
+            })
+        })
+        .collect()
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let indir = args.get_one::<PathBuf>("input-directory").unwrap(); // clap: required arg
+    let kind = args.get_one::<String>("kind").map(|s| s.as_str());
+    let gpsfix = *args.get_one::<u32>("gpsfix").unwrap(); // clap: default 2
+    let downsample_factor = *args.get_one::<u64>("downsample-factor").unwrap() as usize; // clap: default 10
+    let output = match args.get_one::<PathBuf>("output") {
+        Some(path) => path.to_owned(),
+        None => indir.join("overview.kml"),
+    };
+
+    info!("Locating recording sessions in {}...", indir.display());
+    let mut tracks: Vec<SessionTrack> = Vec::new();
+    if matches!(kind, None | Some("g" | "gopro")) {
+        tracks.extend(gopro_tracks(indir, gpsfix, downsample_factor.max(1)));
+    }
+    if matches!(kind, None | Some("v" | "virb")) {
+        tracks.extend(virb_tracks(indir, downsample_factor.max(1)));
+    }
+    tracks.retain(|t| !t.points.is_empty());
+    tracks.sort_by_key(|t| t.points.first().and_then(|p| p.datetime));
+
+    if tracks.is_empty() {
+        let msg = format!("(!) No sessions with GPS data in {}.", indir.display());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    let geoshape = GeoShape::LineAll { height: None };
+    let mut styles = Vec::new();
+    let mut placemarks: Vec<Placemark> = Vec::new();
+    let mut features: Vec<Feature> = Vec::new();
+    for (i, track) in tracks.iter().enumerate() {
+        info!(
+            "{:4}. {:5} {} {:6} points",
+            i + 1,
+            track.camera,
+            track.session_id,
+            track.points.len()
+        );
+
+        let style_id = format!("style{}", i + 1);
+        styles.push(kml_style(&style_id, &geoshape, &Rgba::random(None)));
+        let name = format!("{}. {} {}", i + 1, track.camera, track.session_id);
+        let session_data = kml_extended_data(&[
+            ("camera", track.camera),
+            ("session", &track.session_id),
+            ("stable_session", &track.stable_session_id),
+        ]);
+
+        let mut start = track.points[0].to_owned();
+        start.description = Some(track.description());
+        for mut placemark in [
+            kml_linestring(&track.points, Some(&name), None, false, Some(&style_id)),
+            kml_point(&start, Some(&name), None, false, None),
+        ] {
+            placemark.children.push(session_data.to_owned());
+            placemarks.push(placemark);
+        }
+
+        let files: Vec<String> = track
+            .files
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        for mut feature in [
+            geojson_linestring(&track.points, Some(i + 1)),
+            geojson_point(&track.points[0], Some(i + 1)),
+        ] {
+            feature.set_property("camera", track.camera);
+            feature.set_property("session", track.session_id.to_owned());
+            feature.set_property("stable_session", track.stable_session_id.to_owned());
+            feature.set_property("files", files.to_owned());
+            features.push(feature);
+        }
+    }
+
+    // Per track, so that the distance between sessions is not included
+    let points: Vec<&[EafPoint]> = tracks.iter().map(|t| t.points.as_slice()).collect();
+    let mut info = DocumentInfo::from_tracks(&points).with_filter("gpsfix", &gpsfix.to_string());
+    for track in tracks.iter() {
+        info = info
+            .with_session(&track.session_id)
+            .with_stable_session(&track.stable_session_id);
+    }
+
+    let mut kml = kml_from_placemarks(&placemarks, &styles);
+    info.add_to_kml(&mut kml);
+    let kml_path = output.with_extension("kml");
+    match writefile(kml_to_string(&kml).as_bytes(), &kml_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", kml_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing {}", kml_path.display()),
+        Err(err) => {
+            let msg = format!("(!) Failed to write '{}': {err}", kml_path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    }
+
+    let mut geojson = geojson_from_features(&features);
+    info.add_to_geojson(&mut geojson);
+    let json_path = output.with_extension("json");
+    match writefile(geojson.to_string().as_bytes(), &json_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", json_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing {}", json_path.display()),
+        Err(err) => {
+            let msg = format!("(!) Failed to write '{}': {err}", json_path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    }
+
+    Ok(())
+}
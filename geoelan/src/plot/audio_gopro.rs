@@ -0,0 +1,152 @@
+use std::io::ErrorKind;
+
+use gpmf_rs::GoProFile;
+use plotly::{common::Title, Scatter};
+
+use crate::files::gopro::{
+    audio::{audio_level, mic_wet, wind, AudioStream},
+    ExtractGpmf,
+};
+use crate::logging::info;
+
+use super::Source;
+
+/// Plot audio related GoPro streams for a single MP4-file:
+/// wind processing (`WNDM`), microphone wet detection (`MWET`),
+/// or audio levels (`AALP`). Decoded from the raw GPMF track,
+/// so an extracted GPMF-track or a session (`--session`) can not be used.
+pub(crate) fn audio2plot(
+    args: &clap::ArgMatches,
+    source: &Source,
+    y_axis: &str,
+) -> std::io::Result<(Title, Title, Title, Vec<Box<Scatter<f64, f64>>>)> {
+    let path = source.path; // verified to exist already
+    let x_axis = args.get_one::<String>("x-axis"); // optional, default to counts/index
+    let stream: AudioStream = y_axis.parse()?;
+
+    if !source.gopro {
+        let msg = format!("(!) '{y_axis}' is only logged by GoPro cameras (Hero8 and later).");
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+    if *args.get_one::<bool>("session").unwrap() {
+        let msg = format!(
+            "(!) '{y_axis}' can only be plotted for a single GoPro MP4-file, not for a session."
+        );
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    info!("Compiling data...");
+
+    let gopro = GoProFile::new(path).map_err(|err| {
+        let msg = format!("(!) Failed to read {} as GoPro MP4: {err}", path.display());
+        std::io::Error::new(ErrorKind::Other, msg)
+    })?;
+    let (gpmf, index) = gopro.extract_gpmf(false)?;
+
+    // Time, and one or more named value series for the stream
+    let (time, units, values): (Vec<f64>, &str, Vec<(&str, Vec<f64>)>) = match stream {
+        AudioStream::Wind => {
+            let values = wind(&gpmf, &index).map_err(decode_error)?;
+            (
+                values.iter().map(|v| v.time).collect(),
+                "0-100",
+                vec![
+                    ("level", values.iter().map(|v| v.level as f64).collect()),
+                    (
+                        "active",
+                        values.iter().map(|v| v.active as u8 as f64).collect(),
+                    ),
+                ],
+            )
+        }
+        AudioStream::Wet => {
+            let values = mic_wet(&gpmf, &index).map_err(decode_error)?;
+            (
+                values.iter().map(|v| v.time).collect(),
+                "0/1",
+                vec![
+                    (
+                        "some microphones",
+                        values.iter().map(|v| v.wet as u8 as f64).collect(),
+                    ),
+                    (
+                        "all microphones",
+                        values.iter().map(|v| v.all_wet as u8 as f64).collect(),
+                    ),
+                ],
+            )
+        }
+        AudioStream::Level => {
+            let values = audio_level(&gpmf, &index).map_err(decode_error)?;
+            (
+                values.iter().map(|v| v.time).collect(),
+                "dBFS",
+                vec![
+                    ("RMS", values.iter().map(|v| v.rms).collect()),
+                    ("peak", values.iter().map(|v| v.peak).collect()),
+                ],
+            )
+        }
+    };
+
+    if time.is_empty() {
+        let msg = format!(
+            "(!) No '{}' data in {}. Logged by Hero8 and later.",
+            String::from_utf8_lossy(stream.fourcc()),
+            path.display()
+        );
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    info!("Generating plot...");
+
+    let (x_axis_name, x_axis_units, x): (&str, &str, Vec<f64>) = match x_axis.map(|s| s.as_str()) {
+        Some("t" | "time") => (
+            "Time",
+            "seconds",
+            time.iter().map(|t| t + source.offset).collect(),
+        ),
+        Some("c" | "count") => (
+            "Sample count",
+            "",
+            (0..time.len()).map(|i| (i + 1) as f64).collect(),
+        ),
+        other => {
+            let msg = format!(
+                "(!) Invalid X-axis data type '{}'. Implemented values are 'time', 'count'.",
+                other.unwrap_or("NONE"),
+            );
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    let title_txt = format!(
+        "{stream} [{}]",
+        path.file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default()
+    );
+    let title = Title::from(title_txt);
+    let x_axis_label = Title::from(match x_axis_units {
+        "" => x_axis_name.to_owned(),
+        units => format!("{x_axis_name} ({units})"),
+    });
+    let y_axis_label = Title::from(format!("{stream} ({units})"));
+
+    let traces: Vec<Box<Scatter<f64, f64>>> = values
+        .into_iter()
+        .map(|(name, y)| {
+            let (x, y) = super::reduce(args, x.to_owned(), y);
+            Scatter::new(x, y)
+                .name(&source.name(&format!("{stream} {name}")))
+                .text(units)
+        })
+        .collect();
+
+    Ok((title, x_axis_label, y_axis_label, traces))
+}
+
+fn decode_error(err: String) -> std::io::Error {
+    let msg = format!("(!) Failed to decode GPMF: {err}");
+    std::io::Error::new(ErrorKind::Other, msg)
+}
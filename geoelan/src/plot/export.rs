@@ -0,0 +1,76 @@
+//! Export of plotted series as tidy data, one row per sample,
+//! e.g. for further analysis in R or pandas.
+
+use std::{io::ErrorKind, path::Path};
+
+use plotly::Plot;
+use serde_json::{json, Value};
+
+use crate::files::{csv_field, has_extension, writefile, WriteStatus};
+use crate::logging::info;
+
+/// Returns plotted series as rows `(x, value, variable)`,
+/// where `variable` is the trace name, e.g. `GH010026.MP4 Altitude`
+/// if several files are compared.
+fn rows(plot: &Plot) -> std::io::Result<Vec<(f64, f64, String)>> {
+    let json: Value = match serde_json::from_str(&plot.to_json()) {
+        Ok(v) => v,
+        Err(err) => {
+            let msg = format!("(!) Failed to serialize plot data: {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+    let mut rows = Vec::new();
+    for (i, trace) in json["data"].as_array().into_iter().flatten().enumerate() {
+        let variable = trace["name"]
+            .as_str()
+            .map(|s| s.to_owned())
+            .unwrap_or_else(|| format!("series{}", i + 1));
+        let x = trace["x"].as_array().into_iter().flatten();
+        let y = trace["y"].as_array().into_iter().flatten();
+        for (x, y) in x.zip(y) {
+            if let (Some(x), Some(y)) = (x.as_f64(), y.as_f64()) {
+                rows.push((x, y, variable.to_owned()));
+            }
+        }
+    }
+    Ok(rows)
+}
+
+/// Writes plotted series as tidy CSV, or as a JSON array of objects
+/// if `path` has the extension `json`.
+/// `x_name` is the column name for X-axis values, e.g. `time`.
+pub(crate) fn export_data(plot: &Plot, x_name: &str, path: &Path) -> std::io::Result<()> {
+    let rows = rows(plot)?;
+
+    let content = match has_extension(path, "json") {
+        true => {
+            let records: Vec<Value> = rows
+                .iter()
+                .map(
+                    |(x, value, variable)| json!({x_name: x, "value": value, "variable": variable}),
+                )
+                .collect();
+            serde_json::to_string_pretty(&records)?
+        }
+        false => {
+            let mut csv = format!("{x_name},value,variable\n");
+            for (x, value, variable) in rows.iter() {
+                csv.push_str(&format!("{x},{value},{}\n", csv_field(variable)));
+            }
+            csv
+        }
+    };
+
+    match writefile(content.as_bytes(), path) {
+        Ok(WriteStatus::Written) => info!("Wrote {} rows to {}", rows.len(), path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing {}", path.display()),
+        Err(err) => {
+            let msg = format!("(!) Failed to write '{}': {err}", path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::io::ErrorKind;
+
+use fit_rs::Fit;
+use plotly::{
+    common::{Fill, Title},
+    Scatter,
+};
+
+use crate::files::virb::{field_values, select_session};
+use crate::logging::info;
+
+use super::Source;
+
+/// Invalid value for FIT `uint8` fields,
+/// e.g. heart rate when no chest strap is paired.
+const INVALID_U8: f64 = 255.0;
+
+/// Plot heart rate or cadence, e.g. from a chest strap or
+/// cadence sensor paired with the VIRB, or GPS accuracy.
+///
+/// Heart rate is read from `record/20`, or from `hr/132`
+/// if the former contains no heart rate values.
+/// Cadence and GPS accuracy are read from `record/20`.
+pub(crate) fn fitness2plot(
+    args: &clap::ArgMatches,
+    source: &Source,
+    y_axis: &str,
+) -> std::io::Result<(Title, Title, Title, Vec<Box<Scatter<f64, f64>>>)> {
+    let path = source.path; // verified to exist already
+    let x_axis = args.get_one::<String>("x-axis"); // optional, default to counts/index
+    let fill = *args.get_one::<bool>("fill").unwrap();
+    let session = *args.get_one::<bool>("session").unwrap();
+
+    info!("Compiling data...");
+
+    let (mut fit, range) = match session {
+        true => {
+            let f = Fit::new(path)?;
+            let r = select_session(&f)?.range();
+            (f, Some(r))
+        }
+        false => (Fit::new(path)?, None),
+    };
+
+    // populate scale, offset for fields
+    fit.augment();
+
+    // record/20: timestamp (253), heart_rate (3), cadence (4), gps_accuracy (31)
+    let (y_axis_name, y_axis_units, field) = match y_axis {
+        "hr" | "heartrate" => ("Heart rate", "bpm", 3),
+        "gpsacc" | "gpsaccuracy" => ("GPS accuracy", "m", 31),
+        _ => ("Cadence", "rpm", 4),
+    };
+    let mut values: Vec<(f64, f64)> = fit
+        .filter(Some(20), range.as_ref())
+        .iter()
+        .filter_map(|record| {
+            let t = field_values(record, 253).first().copied()?;
+            let v = field_values(record, field).first().copied()?;
+            Some((t, v))
+        })
+        .filter(|(_, v)| *v != INVALID_U8)
+        .collect();
+
+    // hr/132: timestamp (253), filtered_bpm (6), event_timestamp (9)
+    // Each message contains up to 8 beats, timestamped relative to
+    // an unspecified origin (seconds).
+    if values.is_empty() && y_axis_name == "Heart rate" {
+        values = fit
+            .filter(Some(132), range.as_ref())
+            .iter()
+            .flat_map(|record| {
+                field_values(record, 9)
+                    .into_iter()
+                    .zip(field_values(record, 6).into_iter())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|(_, v)| *v != INVALID_U8)
+            .collect();
+    }
+
+    if values.is_empty() {
+        let msg = format!(
+            "(!) No {} values in {}.",
+            y_axis_name.to_lowercase(),
+            path.display()
+        );
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    info!("Generating plot...");
+
+    let t0 = values.first().map(|(t, _)| *t).unwrap_or_default();
+    let (x_axis_name, x_axis_units, x): (&str, &str, Vec<f64>) = match x_axis.map(|s| s.as_str()) {
+        Some("t" | "time") => (
+            "Time",
+            "seconds",
+            values.iter().map(|(t, _)| t - t0 + source.offset).collect(),
+        ),
+        Some("c" | "count") => (
+            "Sample count",
+            "",
+            (0..values.len()).map(|i| (i + 1) as f64).collect(),
+        ),
+        other => {
+            let msg = format!(
+                "(!) Invalid X-axis data type '{}'. Implemented values are 'time', 'count'.",
+                other.unwrap_or("NONE"),
+            );
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+    let y: Vec<f64> = values.iter().map(|(_, v)| *v).collect();
+    let (x, y) = super::reduce(args, x, y);
+
+    let title_txt = format!(
+        "{y_axis_name} [{}]",
+        path.file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap()
+    );
+    let title = Title::from(title_txt);
+    let x_axis_label = Title::from(match x_axis_units {
+        "" => x_axis_name.to_owned(),
+        units => format!("{x_axis_name} ({units})"),
+    });
+    let y_axis_label = Title::from(format!("{y_axis_name} ({y_axis_units})"));
+
+    let mut trace = Scatter::new(x, y)
+        .name(&source.name(y_axis_name))
+        .text(y_axis_units);
+    if fill {
+        trace = trace.fill(Fill::ToZeroY);
+    }
+
+    Ok((title, x_axis_label, y_axis_label, vec![trace]))
+}
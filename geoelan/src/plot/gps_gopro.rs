@@ -6,16 +6,20 @@ use std::{
 use gpmf_rs::{GoProSession, Gpmf, GpmfError};
 use plotly::{
     common::{Fill, Title},
-    Bar, Scatter, Trace,
+    Bar, Scatter,
 };
 
 use crate::geo::haversine;
+use crate::logging::info;
+
+use super::Source;
 
 pub(crate) fn gps2plot(
     args: &clap::ArgMatches,
-) -> std::io::Result<(Title, Title, Title, Vec<Box<dyn Trace>>)> {
-    let path = args.get_one::<PathBuf>("gpmf").unwrap(); // verified to exist already
-    let y_axis = args.get_one::<String>("y-axis").unwrap(); // sensor type, required arg
+    source: &Source,
+    y_axis: &str,
+) -> std::io::Result<(Title, Title, Title, Vec<Box<Scatter<f64, f64>>>)> {
+    let path = source.path; // verified to exist already
     let x_axis = args.get_one::<String>("x-axis"); // optional, default to counts/index
     let fill = *args.get_one::<bool>("fill").unwrap();
     let session = *args.get_one::<bool>("session").unwrap();
@@ -37,23 +41,21 @@ pub(crate) fn gps2plot(
         },
     };
 
-    println!("Compiling data...");
+    info!("Compiling data...");
 
     let gpmf = match session {
         true => GoProSession::from_path(&path, Some(&indir), false, true, true)?.gpmf()?,
         false => Gpmf::new(&path, false)?,
     };
 
-    // Gps5 may fail if not available. Currently, only Hero11 logs both
+    // Gps5 may fail if not available. Currently, only Hero11 and Hero13 log both
     // Removed filter/pruning on fix or dop
     let gps = match gps5 {
         true => gpmf.gps5(),
         false => gpmf.gps(),
     };
 
-    println!("Done");
-
-    println!("Generating plot...");
+    info!("Generating plot...");
 
     let x_axis_units: Option<&str>;
     let x_axis_name: &str;
@@ -61,7 +63,9 @@ pub(crate) fn gps2plot(
         Some("t" | "time") => {
             x_axis_units = Some("seconds");
             x_axis_name = "Time";
-            gps.iter().map(|g| g.time.as_seconds_f64()).collect()
+            gps.iter()
+                .map(|g| g.time.as_seconds_f64() + source.offset)
+                .collect()
         }
         Some("dst" | "distance") => {
             x_axis_units = Some("meters");
@@ -97,7 +101,7 @@ pub(crate) fn gps2plot(
 
     let y_axis_units: Option<&str>;
     let y_axis_name: &str;
-    let y: Vec<f64> = match y_axis.as_str() {
+    let y: Vec<f64> = match y_axis {
         "lat" | "latitude" => {
             y_axis_units = Some("deg");
             y_axis_name = "Latitude";
@@ -153,6 +157,7 @@ pub(crate) fn gps2plot(
     }
 
     assert_eq!(x.len(), y.len(), "(!) X and Y differ in size.");
+    let (x, y) = super::reduce(args, x, y);
 
     let title_txt = format!(
         "GPS [{}]",
@@ -172,13 +177,11 @@ pub(crate) fn gps2plot(
     );
     let y_axis_label = Title::from(y_axis_label_txt);
 
-    println!("Done");
-
-    let x_y_trace: Box<dyn Trace> = if fill {
+    let x_y_trace = if fill {
         // Fill, would be better to have an arbitrary Y value to give more height to data
         // let y_min = y.into_iter().reduce(&f64::min).expect("Failed to determine min value for Y-axis");
         // let y_min = y.iter().fold(f64::INFINITY, |acc, &val| acc.min(val)); // min for vec of floats without moving
-        // println!("Y MIN: {y_min}");
+        // info!("Y MIN: {y_min}");
         // Scatter::new(x, y).fill(Fill::ToZeroY).text(y_axis_units) //.y0(y_min)
 
         // match bar_plot {
@@ -188,6 +191,7 @@ pub(crate) fn gps2plot(
         //         .text(y_axis_units.unwrap_or_default()),
         // }
         Scatter::new(x, y)
+            .name(&source.name(y_axis_name))
             .fill(Fill::ToZeroY)
             .text(y_axis_units.unwrap_or_default())
     } else {
@@ -196,7 +200,9 @@ pub(crate) fn gps2plot(
         //     true => Bar::new(x, y).text(y_axis_units.unwrap_or_default()),
         //     false => Scatter::new(x, y).text(y_axis_units.unwrap_or_default()),
         // }
-        Scatter::new(x, y).text(y_axis_units.unwrap_or_default())
+        Scatter::new(x, y)
+            .name(&source.name(y_axis_name))
+            .text(y_axis_units.unwrap_or_default())
     };
 
     Ok((title, x_axis_label, y_axis_label, vec![x_y_trace]))
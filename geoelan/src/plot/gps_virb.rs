@@ -1,24 +1,27 @@
-use std::{io::ErrorKind, path::PathBuf};
+use std::io::ErrorKind;
 
 use fit_rs::{Fit, FitPoint};
 use plotly::{
     common::{Fill, Title},
-    Scatter, Trace,
+    Scatter,
 };
 
+use crate::logging::info;
 use crate::{files::virb::select_session, geo::haversine};
 
+use super::Source;
+
 pub(crate) fn gps2plot(
     args: &clap::ArgMatches,
-// ) -> std::io::Result<(Title, Title, Title, Vec<Box<Scatter<f64, f64>>>)> {
-) -> std::io::Result<(Title, Title, Title, Vec<Box<dyn Trace>>)> {
-    let path = args.get_one::<PathBuf>("fit").unwrap(); // verified to exist already
-    let y_axis = args.get_one::<String>("y-axis").unwrap(); // sensor type, required arg
+    source: &Source,
+    y_axis: &str,
+) -> std::io::Result<(Title, Title, Title, Vec<Box<Scatter<f64, f64>>>)> {
+    let path = source.path; // verified to exist already
     let x_axis = args.get_one::<String>("x-axis"); // optional, default to counts/index
     let fill = *args.get_one::<bool>("fill").unwrap();
     let session = *args.get_one::<bool>("session").unwrap();
 
-    println!("Compiling data...");
+    info!("Compiling data...");
 
     let (fit, range) = match session {
         true => {
@@ -36,9 +39,7 @@ pub(crate) fn gps2plot(
         .map(|g| g.to_point())
         .collect();
 
-    println!("Done");
-
-    println!("Generating plot...");
+    info!("Generating plot...");
 
     let x_axis_units: &str;
     let x_axis_name: &str;
@@ -46,7 +47,9 @@ pub(crate) fn gps2plot(
         Some("t" | "time") => {
             x_axis_units = "seconds";
             x_axis_name = "Time";
-            gps.iter().map(|g| g.time.as_seconds_f64()).collect()
+            gps.iter()
+                .map(|g| g.time.as_seconds_f64() + source.offset)
+                .collect()
         }
         Some("dst" | "distance") => {
             x_axis_units = "meters";
@@ -71,7 +74,7 @@ pub(crate) fn gps2plot(
 
     let y_axis_units: &str;
     let y_axis_name: &str;
-    let y: Vec<f64> = match y_axis.as_str() {
+    let y: Vec<f64> = match y_axis {
         "lat" | "latitude" => {
             y_axis_units = "deg";
             y_axis_name = "Latitude";
@@ -105,6 +108,8 @@ pub(crate) fn gps2plot(
         }
     };
 
+    let (x, y) = super::reduce(args, x, y);
+
     let title_txt = format!(
         "GPS [{}]",
         path.file_name()
@@ -119,12 +124,15 @@ pub(crate) fn gps2plot(
 
     let x_y_scatter = if fill {
         // Fill, would be better to have an arbitrary Y value to give more height to data
-        Scatter::new(x, y).fill(Fill::ToZeroY).text(y_axis_units)
+        Scatter::new(x, y)
+            .name(&source.name(y_axis_name))
+            .fill(Fill::ToZeroY)
+            .text(y_axis_units)
     } else {
-        Scatter::new(x, y).text(y_axis_units)
+        Scatter::new(x, y)
+            .name(&source.name(y_axis_name))
+            .text(y_axis_units)
     };
 
-    println!("Done");
-
     Ok((title, x_axis_label, y_axis_label, vec![x_y_scatter]))
 }
@@ -0,0 +1,166 @@
+//! Map view of the GPS track, color-coded by speed, altitude, or GPS fix.
+//! Optionally drawn over OpenStreetMap tiles.
+
+use std::{io::ErrorKind, path::PathBuf};
+
+use plotly::{
+    color::Rgb,
+    common::{ColorBar, ColorScale, ColorScalePalette, Line, Marker, Mode, Title},
+    layout::{Axis, Center, Mapbox, MapboxStyle},
+    Layout, Plot, Scatter, ScatterMapbox,
+};
+
+use crate::logging::info;
+use crate::{geo::EafPoint, telemetry};
+
+/// Returns color bar label and values for color-coding track points.
+fn color_values(points: &[EafPoint], color_by: &str) -> std::io::Result<(String, Vec<f64>)> {
+    match color_by {
+        "speed" => Ok((
+            "2D speed (m/s)".to_owned(),
+            points.iter().map(|p| p.speed2d).collect(),
+        )),
+        "altitude" => Ok((
+            "Altitude (m)".to_owned(),
+            points.iter().map(|p| p.altitude).collect(),
+        )),
+        // 0 = unknown, 1 = no lock, 2 = 2D lock, 3 = 3D lock
+        "fix" => Ok((
+            "Satellite lock level".to_owned(),
+            points.iter().map(|p| p.fix as u8 as f64).collect(),
+        )),
+        other => {
+            let msg = format!("(!) Can not color-code track by '{other}'.");
+            Err(std::io::Error::new(ErrorKind::Other, msg))
+        }
+    }
+}
+
+/// Map zoom level that fits the track's extent in degrees.
+fn zoom(points: &[EafPoint]) -> u8 {
+    let (lat, lon): (Vec<f64>, Vec<f64>) = points.iter().map(|p| (p.latitude, p.longitude)).unzip();
+    let extent = |v: &[f64]| {
+        v.iter().cloned().fold(f64::MIN, f64::max) - v.iter().cloned().fold(f64::MAX, f64::min)
+    };
+    let max_extent = extent(&lat).max(extent(&lon)).max(1e-6);
+    (360.0 / max_extent).log2().clamp(1.0, 18.0) as u8
+}
+
+/// GPS track as latitude over longitude for GoPro (`--gpmf`) or VIRB (`--fit`).
+pub(crate) fn map2plot(args: &clap::ArgMatches) -> std::io::Result<Plot> {
+    let gpmf = args.get_one::<PathBuf>("gpmf");
+    let fit = args.get_one::<PathBuf>("fit");
+    let session = *args.get_one::<bool>("session").unwrap();
+    let osm = *args.get_one::<bool>("osm").unwrap();
+    let color_by = args
+        .get_one::<String>("color-by")
+        .map(|s| s.as_str())
+        .unwrap_or("speed");
+
+    if osm && *args.get_one::<bool>("offline").unwrap() {
+        let msg = "(!) '--osm' requires network access, but '--offline' is set.";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    info!("Compiling data...");
+
+    let telemetry = telemetry::open(
+        gpmf.map(|p| p.as_path()),
+        fit.map(|p| p.as_path()),
+        args.get_one::<PathBuf>("input-directory")
+            .map(|p| p.as_path()),
+        false,
+        session,
+    )?;
+    // No filtering on satellite lock, so that the track can be color-coded by fix
+    let points = telemetry.points(0, None)?;
+    if points.is_empty() {
+        let msg = format!("(!) No GPS data in '{}'.", telemetry.path().display());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    info!("Generating plot...");
+
+    let (color_label, values) = color_values(&points, color_by)?;
+    let lat: Vec<f64> = points.iter().map(|p| p.latitude).collect();
+    let lon: Vec<f64> = points.iter().map(|p| p.longitude).collect();
+    // Hover text: datetime, value, satellite lock
+    let text: Vec<String> = points
+        .iter()
+        .zip(values.iter())
+        .map(|(p, v)| {
+            format!(
+                "{}<br>{color_label}: {v:.2}<br>{}",
+                p.datetime.map(|dt| dt.to_string()).unwrap_or_default(),
+                p.fix
+            )
+        })
+        .collect();
+
+    let marker = Marker::new()
+        .size(5)
+        .color_array(values)
+        .color_scale(ColorScale::Palette(ColorScalePalette::Viridis))
+        .show_scale(true)
+        .color_bar(ColorBar::new().title(Title::from(color_label.as_str())));
+    let line = Line::new().color(Rgb::new(150, 150, 150)).width(1.);
+
+    let title = Title::from(format!(
+        "GPS track [{}]",
+        telemetry
+            .path()
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default()
+    ));
+
+    let center_lat = lat.iter().sum::<f64>() / lat.len() as f64;
+    let center_lon = lon.iter().sum::<f64>() / lon.len() as f64;
+
+    let mut plot = Plot::new();
+    if osm {
+        let layout = Layout::new().height(600).title(title).mapbox(
+            Mapbox::new()
+                .style(MapboxStyle::OpenStreetMap)
+                .center(Center::new(center_lat, center_lon))
+                .zoom(zoom(&points)),
+        );
+        plot.set_layout(layout);
+        plot.add_trace(
+            ScatterMapbox::new(lat, lon)
+                .mode(Mode::LinesMarkers)
+                .marker(marker)
+                .line(line)
+                .text_array(text),
+        );
+    } else {
+        // Scale latitude relative to longitude, so that the track is not
+        // distorted away from the equator (a degree of longitude is shorter).
+        let layout = Layout::new()
+            .height(600)
+            .title(title)
+            .x_axis(
+                Axis::new()
+                    .title(Title::from("Longitude (deg)"))
+                    .grid_color(Rgb::new(255, 255, 255)),
+            )
+            .y_axis(
+                Axis::new()
+                    .title(Title::from("Latitude (deg)"))
+                    .scale_anchor("x")
+                    .scale_ratio(1.0 / center_lat.to_radians().cos().max(0.01))
+                    .grid_color(Rgb::new(255, 255, 255)),
+            )
+            .plot_background_color(Rgb::new(229, 229, 229));
+        plot.set_layout(layout);
+        plot.add_trace(
+            Scatter::new(lon, lat)
+                .mode(Mode::LinesMarkers)
+                .marker(marker)
+                .line(line)
+                .text_array(text),
+        );
+    }
+
+    Ok(plot)
+}
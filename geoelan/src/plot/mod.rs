@@ -3,22 +3,38 @@
 //! Export to CSV and import into ELAN as time series, e.g. to find sections with
 //! altitude changes as annotation targets.
 //!
-//! Currently only does a time series 2D plot, e.g. air pressure (VIRB) over time.
+//! Does a time series 2D plot, e.g. air pressure (VIRB) over time,
+//! or a map view of the GPS track via '--map'.
+//! Several files can be compared by specifying '--gpmf' and/or '--fit' more than once.
 
-use std::io::ErrorKind;
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
 
+mod audio_gopro;
+mod export;
+mod fitness_virb;
 mod gps_gopro;
 mod gps_virb;
-mod sensor_gopro;
-mod sensor_virb;
+mod map;
+mod overlay;
+mod sensor;
 mod sensors;
+pub mod spec;
 
 // https://lib.rs/crates/plotly
 use plotly::{
     color::Rgb,
     common::{HoverInfo, Label, Line, LineShape, Title},
-    layout::{Axis, HoverMode},
-    Layout, Plot, Scatter, Trace,
+    layout::{Axis, AxisSide, HoverMode},
+    Layout, Plot, Scatter,
+};
+
+use crate::{
+    files::{writefile, WriteStatus},
+    logging::{info, warn},
+    telemetry,
 };
 
 use self::sensors::print_table;
@@ -35,7 +51,20 @@ fn is_sensor(value: &str) -> bool {
     }
 }
 
+// Audio related GoPro streams, decoded from the raw GPMF track.
+fn is_audio(value: &str) -> bool {
+    matches!(
+        value,
+        "wnd" | "wind" | "wet" | "micwet" | "aud" | "audiolevel"
+    )
+}
+
 pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    // Map view of the GPS track instead of a time series
+    if *args.get_one::<bool>("map").unwrap() {
+        return write_plot(args, map::map2plot(args)?);
+    }
+
     // 'kind': what sensor data to plot:
     // - 'gyro' / 'gyroscope' (GP/VIRB)
     // - 'accl' / 'accelerometer' (GP/VIRB)
@@ -46,9 +75,18 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
     // - 'hdg' / 'heading' - GPS heading (VIRB - GP N/Y but possible via accelerometer)
     // - 'fix' / 'gpsfix' - GPS satellite lock/fix (GP - may exist in VIRB undocumented fields?)
     // - 'dop' / 'dilution' - GPS dilution of position (GP - may exist in VIRB undocumented fields?)
-    let y_axis = args.get_one::<String>("y-axis").unwrap(); // sensor type, required arg
-    let is_gopro = args.contains_id("gpmf");
-    let is_fit = args.contains_id("fit");
+    // - 'hr' / 'heartrate' - heart rate (VIRB, paired chest strap)
+    // - 'cad' / 'cadence' - cadence (VIRB, paired sensor)
+    // - 'gpsacc' / 'gpsaccuracy' - GPS accuracy in meters (FIT 'record/20', if logged)
+    // - 'wnd' / 'wind' - wind processing (GP Hero8 and later)
+    // - 'wet' / 'micwet' - microphone wet detection (GP Hero8 and later)
+    // - 'aud' / 'audiolevel' - RMS and peak audio level (GP Hero9 and later)
+    // Up to two data types, the second on a secondary Y-axis
+    let y_axes: Vec<&String> = args.get_many::<String>("y-axis").unwrap().collect(); // required arg
+    if y_axes.len() > 2 {
+        let msg = "(!) At most two Y-axis data types can be plotted together.";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
     // let print_sensor_table = *args.get_one::<bool>("sensor-table").unwrap();
 
     // if print_sensor_table {
@@ -57,40 +95,24 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
 
     // Data in tuples (DATA, SECONDS) as [(f64, f64), ...]
 
-    let title: Title;
-    let x_axis_label: Title;
-    let y_axis_label: Title;
-    // let traces: Vec<Box<Scatter<f64, f64>>>;
-    let traces: Vec<Box <dyn Trace>>;
-
-    // GoPro
-    if is_gopro {
-        (title, x_axis_label, y_axis_label, traces) = match y_axis.as_str() {
-            "acc" | "accelerometer"
-            | "gyr" | "gyroscope"
-            | "grv" | "gravity"
-            | "bar" | "barometer"
-            | "mag" | "magnetometer" => sensor_gopro::sensor2plot(args)?,
-            _ => gps_gopro::gps2plot(&args)?,
-        }
-    // FIT, VIRB
-    } else if is_fit {
-        (title, x_axis_label, y_axis_label, traces) = match y_axis.as_str() {
-            "acc" | "accelerometer"
-            | "gyr" | "gyroscope"
-            | "grv" | "gravity"
-            | "bar" | "barometer"
-            | "mag" | "magnetometer" => sensor_virb::sensor2plot(args)?,
-            _ => gps_virb::gps2plot(args)?,
-        };
-    } else {
-        let msg = "(!) No data file specified.";
+    // Annotation time spans are in milliseconds relative to start of media
+    let x_axis = args.get_one::<String>("x-axis").map(|s| s.as_str());
+    if args.contains_id("eaf") && !matches!(x_axis, Some("t" | "time")) {
+        let msg = "(!) '--eaf' requires '--x-axis time'.";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+    // Rolling mean window is in seconds
+    if args.contains_id("rolling-mean") && !matches!(x_axis, Some("t" | "time")) {
+        let msg = "(!) '--rolling-mean' requires '--x-axis time'.";
         return Err(std::io::Error::new(ErrorKind::Other, msg));
     }
 
+    let sources = sources(args)?;
+    let (title, x_axis_label, y_axis_label, traces) = series(args, &sources, y_axes[0])?;
+
     // Create plot canvas
     let mut plot = Plot::new();
-    let layout = Layout::new()
+    let mut layout = Layout::new()
         .height(600)
         .x_axis(
             Axis::new()
@@ -105,7 +127,6 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
         .plot_background_color(Rgb::new(229, 229, 229))
         .hover_mode(HoverMode::XUnified)
         .title(title);
-    plot.set_layout(layout);
 
     // Add traces to plot canvas
     for trace in traces.into_iter() {
@@ -113,7 +134,248 @@ pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
         plot.add_trace(trace)
     }
 
-    plot.show();
+    // Second data type on a secondary Y-axis to the right
+    if let Some(y_axis2) = y_axes.get(1) {
+        let (_, _, y_axis2_label, traces2) = series(args, &sources, y_axis2)?;
+        layout = layout.y_axis2(
+            Axis::new()
+                .title(y_axis2_label)
+                .overlaying("y")
+                .side(AxisSide::Right)
+                .show_grid(false),
+        );
+        for trace in traces2.into_iter() {
+            plot.add_trace(trace.y_axis("y2"))
+        }
+    }
+
+    // Shaded annotation time spans from ELAN-file
+    if let Some(path) = args.get_one::<PathBuf>("eaf") {
+        let tier_id = args.get_one::<String>("tier").unwrap(); // clap: required with '--eaf'
+        // 'sources' is never empty, the ELAN-file is aligned with the first file
+        layout = overlay::annotation_overlay(layout, path, tier_id, &sources[0])?;
+    }
+    plot.set_layout(layout);
+
+    // Plotted series as tidy CSV/JSON
+    if let Some(path) = args.get_one::<PathBuf>("export-data") {
+        let x_name = match x_axis {
+            Some("t" | "time") => "time",
+            Some("dst" | "distance") => "distance",
+            _ => "count",
+        };
+        export::export_data(&plot, x_name, path)?;
+    }
+
+    write_plot(args, plot)
+}
+
+/// Input file for a data series.
+pub(crate) struct Source<'a> {
+    pub path: &'a Path,
+    /// GoPro MP4 or GPMF-track if `true`, otherwise FIT-file.
+    pub gopro: bool,
+    /// Seconds added to time values, for aligning several files
+    /// on recording date and time.
+    pub offset: f64,
+    /// File name, if several files are compared.
+    pub label: Option<String>,
+}
+
+impl Source<'_> {
+    /// Trace name, prefixed with file name if several files are compared.
+    pub fn name(&self, name: &str) -> String {
+        match &self.label {
+            Some(label) => format!("{label} {name}"),
+            None => name.to_owned(),
+        }
+    }
+}
+
+/// Returns input files in the order GoPro, FIT.
+/// If several files are compared with '--align absolute',
+/// time offsets are set relative to the earliest recording start.
+fn sources(args: &clap::ArgMatches) -> std::io::Result<Vec<Source>> {
+    let session = *args.get_one::<bool>("session").unwrap();
+    let indir = args.get_one::<PathBuf>("input-directory");
+    let inputs: Vec<(&Path, bool)> = args
+        .get_many::<PathBuf>("gpmf")
+        .into_iter()
+        .flatten()
+        .map(|p| (p.as_path(), true))
+        .chain(
+            args.get_many::<PathBuf>("fit")
+                .into_iter()
+                .flatten()
+                .map(|p| (p.as_path(), false)),
+        )
+        .collect();
+
+    if inputs.is_empty() {
+        let msg = "(!) No data file specified.";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    let compare = inputs.len() > 1;
+    let absolute =
+        compare && args.get_one::<String>("align").map(|s| s.as_str()) == Some("absolute");
+
+    // Recording start for each file. Start of FIT-file for VIRB,
+    // since GPS time values are relative to the start of the FIT-file.
+    let mut starts = Vec::new();
+    if absolute {
+        for (path, gopro) in inputs.iter() {
+            let telemetry = telemetry::open(
+                gopro.then_some(*path),
+                (!gopro).then_some(*path),
+                indir.map(|p| p.as_path()),
+                false,
+                *gopro && session,
+            )?;
+            match telemetry.start_time() {
+                Some(start) => starts.push(start),
+                None => {
+                    let msg = format!(
+                        "(!) Failed to determine start time for '{}'.",
+                        path.display()
+                    );
+                    return Err(std::io::Error::new(ErrorKind::Other, msg));
+                }
+            }
+        }
+    }
+    let t0 = starts.iter().min().copied();
+
+    Ok(inputs
+        .iter()
+        .enumerate()
+        .map(|(i, (path, gopro))| Source {
+            path: *path,
+            gopro: *gopro,
+            offset: match (starts.get(i), t0) {
+                (Some(start), Some(t0)) => (*start - t0).as_seconds_f64(),
+                _ => 0.,
+            },
+            label: compare.then(|| {
+                path.file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            }),
+        })
+        .collect())
+}
+
+/// Returns title, axis labels, and traces for a single Y-axis data type.
+/// Traces for all input files are combined, and the title lists all files.
+fn series(
+    args: &clap::ArgMatches,
+    sources: &[Source],
+    y_axis: &str,
+) -> std::io::Result<(Title, Title, Title, Vec<Box<Scatter<f64, f64>>>)> {
+    let mut series: Option<(Title, Title, Title)> = None;
+    let mut traces: Vec<Box<Scatter<f64, f64>>> = Vec::new();
+    for source in sources.iter() {
+        let (title, x_axis_label, y_axis_label, t) = source_series(args, source, y_axis)?;
+        series.get_or_insert((title, x_axis_label, y_axis_label));
+        traces.extend(t);
+    }
+
+    // 'sources' is never empty
+    let (mut title, x_axis_label, y_axis_label) = series.unwrap();
+    if sources.len() > 1 {
+        let labels: Vec<&str> = sources.iter().filter_map(|s| s.label.as_deref()).collect();
+        title = Title::from(format!("Comparison [{}]", labels.join(", ")));
+    }
+
+    Ok((title, x_axis_label, y_axis_label, traces))
+}
+
+/// Returns title, axis labels, and traces for a single Y-axis data type
+/// and a single input file.
+fn source_series(
+    args: &clap::ArgMatches,
+    source: &Source,
+    y_axis: &str,
+) -> std::io::Result<(Title, Title, Title, Vec<Box<Scatter<f64, f64>>>)> {
+    // Sensor data is read via the Telemetry trait for both GoPro and VIRB
+    if is_sensor(y_axis) {
+        return sensor::sensor2plot(args, source, y_axis);
+    }
+    if is_audio(y_axis) {
+        return audio_gopro::audio2plot(args, source, y_axis);
+    }
+    match (source.gopro, y_axis) {
+        (true, _) => gps_gopro::gps2plot(args, source, y_axis),
+        (false, "hr" | "heartrate" | "cad" | "cadence" | "gpsacc" | "gpsaccuracy") => {
+            fitness_virb::fitness2plot(args, source, y_axis)
+        }
+        (false, _) => gps_virb::gps2plot(args, source, y_axis),
+    }
+}
+
+/// Centered rolling mean of `y` over a window of `window` seconds,
+/// with `x` as time values in ascending order.
+fn rolling_mean(x: &[f64], y: &[f64], window: f64) -> Vec<f64> {
+    let half = window / 2.;
+    let (mut lo, mut hi, mut sum) = (0, 0, 0.);
+    x.iter()
+        .map(|t| {
+            while hi < x.len() && x[hi] <= t + half {
+                sum += y[hi];
+                hi += 1;
+            }
+            while x[lo] < t - half {
+                sum -= y[lo];
+                lo += 1;
+            }
+            sum / (hi - lo) as f64
+        })
+        .collect()
+}
+
+/// Applies '--rolling-mean' and '--downsample' to a data series,
+/// in that order, so that downsampled values are smoothed.
+pub(crate) fn reduce(args: &clap::ArgMatches, x: Vec<f64>, y: Vec<f64>) -> (Vec<f64>, Vec<f64>) {
+    let y = match args.get_one::<f64>("rolling-mean") {
+        Some(window) if *window > 0. => rolling_mean(&x, &y, *window),
+        _ => y,
+    };
+    match args.get_one::<u64>("downsample") {
+        Some(n) if *n > 1 => (
+            x.into_iter().step_by(*n as usize).collect(),
+            y.into_iter().step_by(*n as usize).collect(),
+        ),
+        _ => (x, y),
+    }
+}
+
+/// Writes plot as HTML if an output path is set, otherwise
+/// opens it in a browser. Optionally writes the plot specification.
+fn write_plot(args: &clap::ArgMatches, plot: Plot) -> std::io::Result<()> {
+    // Write HTML if an output path is set, otherwise open in browser
+    match args.get_one::<PathBuf>("output") {
+        Some(path) => match writefile(plot.to_html().as_bytes(), path) {
+            Ok(WriteStatus::Written) => info!("Wrote {}", path.display()),
+            Ok(WriteStatus::Planned) => (),
+            Ok(WriteStatus::Skipped) => info!("User aborted writing {}", path.display()),
+            Err(err) => {
+                let msg = format!("(!) Failed to write '{}': {err}", path.display());
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        },
+        None if crate::files::dryrun() => info!("[dryrun] Would open plot in browser"),
+        None => plot.show(),
+    }
+
+    // Write plot specification for regenerating the figure
+    if let Some(path) = args.get_one::<PathBuf>("spec") {
+        match spec::write_spec(args, path) {
+            Ok(WriteStatus::Written) => info!("Wrote {}", path.display()),
+            Ok(WriteStatus::Planned) => (),
+            Ok(WriteStatus::Skipped) => info!("User aborted writing plot specification"),
+            Err(err) => warn!("(!) Failed to write '{}': {err}", path.display()),
+        }
+    }
 
     Ok(())
 }
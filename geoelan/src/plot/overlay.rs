@@ -0,0 +1,121 @@
+//! Annotation overlay from an ELAN-file, drawn as shaded regions
+//! for annotation time spans, for verifying annotation/telemetry alignment.
+
+use std::{io::ErrorKind, path::Path};
+
+use eaf_rs::Eaf;
+use plotly::{
+    color::Rgb,
+    common::Anchor,
+    layout::{Annotation, Shape, ShapeLayer, ShapeLine, ShapeType},
+    Layout,
+};
+
+use crate::{
+    cam2eaf::cam2eaf::FIT_OFFSET_PROPERTY,
+    elan::xml::Document,
+    i18n::{trf, Msg},
+    logging::{info, warn},
+};
+
+use super::Source;
+
+/// Returns annotations in tier as (value, start seconds, end seconds).
+/// Annotations without time values are ignored.
+fn tier_spans(path: &Path, tier_id: &str) -> std::io::Result<Vec<(String, f64, f64)>> {
+    let eaf = Eaf::read(path)?;
+    let Some(tier) = eaf.tiers.iter().find(|t| t.tier_id == tier_id) else {
+        let msg = format!("(!) {}", trf(Msg::NoTierWithId, tier_id));
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    };
+    Ok(tier
+        .annotations
+        .iter()
+        .filter_map(|a| match a.ts_val() {
+            (Some(start), Some(end)) => Some((
+                a.value().to_string(),
+                start as f64 / 1000.,
+                end as f64 / 1000.,
+            )),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Returns start of the media in seconds relative to the start of the FIT-file,
+/// as set by `cam2eaf` for VIRB recordings. Defaults to 0 if not set.
+fn fit_offset(path: &Path) -> std::io::Result<f64> {
+    let doc = Document::read(path)?;
+    match doc
+        .property(FIT_OFFSET_PROPERTY)
+        .and_then(|ms| ms.parse::<i64>().ok())
+    {
+        Some(ms) => Ok(ms as f64 / 1000.),
+        None => {
+            warn!(
+                "(!) No FIT offset in {}. Annotations are aligned with the start of the FIT-file.",
+                path.display()
+            );
+            Ok(0.)
+        }
+    }
+}
+
+/// Adds a shaded region spanning the full plot height for each annotation
+/// in `tier_id`, labelled with the annotation value.
+/// The X-axis must be time in seconds, relative to the start of the data in `source`.
+/// Annotation times are relative to the start of the media, and are shifted by
+/// the start of the media in the FIT-file (VIRB) and the offset for `source`.
+pub(crate) fn annotation_overlay(
+    layout: Layout,
+    path: &Path,
+    tier_id: &str,
+    source: &Source,
+) -> std::io::Result<Layout> {
+    let offset = match source.gopro {
+        true => source.offset,
+        false => source.offset + fit_offset(path)?,
+    };
+    let spans: Vec<(String, f64, f64)> = tier_spans(path, tier_id)?
+        .into_iter()
+        .map(|(value, start, end)| (value, start + offset, end + offset))
+        .collect();
+    info!(
+        "Adding {} annotations from tier '{tier_id}' in {}",
+        spans.len(),
+        path.display()
+    );
+
+    let shapes: Vec<Shape> = spans
+        .iter()
+        .map(|(_, start, end)| {
+            Shape::new()
+                .shape_type(ShapeType::Rect)
+                .x_ref("x")
+                .y_ref("paper")
+                .x0(*start)
+                .x1(*end)
+                .y0(0)
+                .y1(1)
+                .fill_color(Rgb::new(255, 165, 0))
+                .opacity(0.25)
+                .layer(ShapeLayer::Below)
+                .line(ShapeLine::new().width(0.))
+        })
+        .collect();
+    let annotations: Vec<Annotation> = spans
+        .iter()
+        .map(|(value, start, end)| {
+            Annotation::new()
+                .text(value)
+                .x_ref("x")
+                .y_ref("paper")
+                .x((start + end) / 2.)
+                .y(1)
+                .y_anchor(Anchor::Bottom)
+                .show_arrow(false)
+        })
+        .collect();
+
+    Ok(layout.shapes(shapes).annotations(annotations))
+}
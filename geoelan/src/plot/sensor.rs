@@ -0,0 +1,87 @@
+use std::{io::ErrorKind, path::PathBuf};
+
+use plotly::{common::Title, Scatter};
+
+use crate::logging::info;
+use crate::telemetry;
+
+use super::Source;
+
+/// Sensor data for GoPro (`--gpmf`) or VIRB (`--fit`).
+pub(crate) fn sensor2plot(
+    args: &clap::ArgMatches,
+    source: &Source,
+    y_axis: &str,
+) -> std::io::Result<(Title, Title, Title, Vec<Box<Scatter<f64, f64>>>)> {
+    let x_axis = args.get_one::<String>("x-axis"); // optional, default to counts/index
+    let session = *args.get_one::<bool>("session").unwrap();
+    let average = *args.get_one::<bool>("average").unwrap();
+
+    info!("Compiling data...");
+
+    let telemetry = telemetry::open(
+        source.gopro.then_some(source.path),
+        (!source.gopro).then_some(source.path),
+        args.get_one::<PathBuf>("input-directory")
+            .map(|p| p.as_path()),
+        false,
+        session,
+    )?;
+    let series = telemetry.sensors(y_axis, average)?;
+
+    info!("Generating plot...");
+
+    // x-axis values
+    let x_axis_name: &str;
+    let x_axis_units: &str;
+    let x_axis: Vec<f64> = match x_axis.map(|s| s.as_str()) {
+        Some("t" | "time") => {
+            x_axis_units = " (seconds)";
+            x_axis_name = "Time";
+            series.time.iter().map(|t| t + source.offset).collect()
+        }
+        Some("c" | "count") => {
+            x_axis_units = "";
+            x_axis_name = "Sample count";
+            (0..series.len()).map(|i| (i + 1) as f64).collect()
+        }
+        other => {
+            let msg = format!("(!) Invalid X-axis data type '{}'. Implemented values are 'time', 'count'. Run 'geoelan inspect --gpmf {}' for a summary.",
+                other.unwrap_or("NONE"),
+                telemetry.path().display()
+            );
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    // Plot title: DATA [FILENAME]
+    let title_txt = format!(
+        "{} [{}]",
+        series.name,
+        telemetry
+            .path()
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default()
+    );
+    let title = Title::from(title_txt);
+    let x_axis_label_txt = format!("{x_axis_name}{x_axis_units}");
+    let x_axis_label = Title::from(x_axis_label_txt);
+    let y_axis_label_txt = format!("{} ({})", series.quantifier, series.units);
+    let y_axis_label = Title::from(y_axis_label_txt);
+
+    // One-dimensional sensors, e.g. barometer, only have x values
+    let traces: Vec<Box<Scatter<f64, f64>>> =
+        [("x", &series.x), ("y", &series.y), ("z", &series.z)]
+            .into_iter()
+            .filter(|(_, values)| !values.is_empty())
+            .map(|(name, values)| {
+                let (x, y) = super::reduce(args, x_axis.to_owned(), values.to_owned());
+                Scatter::new(x, y)
+                    .name(&source.name(&format!("{} {name}", series.name)))
+                    .text(&series.units)
+            })
+            .collect();
+
+    Ok((title, x_axis_label, y_axis_label, traces))
+}
@@ -13,6 +13,7 @@ pub fn print_table() -> std::io::Result<()> {
     Hero 10 Black | Yes*  | Accelerometer | Gyroscope | Gravity |     N/A      |
     Hero 11 Black | Yes** | Accelerometer | Gyroscope | Gravity |     N/A      |
     Hero 12 Black | No*** | Accelerometer | Gyroscope | Gravity |     N/A      |
+    Hero 13 Black | Yes** | Accelerometer | Gyroscope | Gravity |     N/A      |
 
     *   18Hz GPS, individual points not timestamped, only 1-second cluster (GPS5).
     **  10Hz GPS, individual points timestamped (GPS9).
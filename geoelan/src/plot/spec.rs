@@ -0,0 +1,149 @@
+//! Plot specification sidecar, capturing inputs and options
+//! for a figure, so that it can be regenerated via '--from-spec'.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    files::{writefile, WriteStatus},
+    logging::warn,
+    VERSION,
+};
+
+/// Path arguments as `(id, flag)`.
+const PATHS: [(&'static str, &'static str); 4] = [
+    ("gpmf", "--gpmf"),
+    ("fit", "--fit"),
+    ("input-directory", "--indir"),
+    ("eaf", "--eaf"),
+];
+/// Value arguments as `(id, flag)`.
+const VALUES: [(&'static str, &'static str); 7] = [
+    ("y-axis", "--y-axis"),
+    ("x-axis", "--x-axis"),
+    ("color-by", "--color-by"),
+    ("align", "--align"),
+    ("tier", "--tier"),
+    ("downsample", "--downsample"),
+    ("rolling-mean", "--rolling-mean"),
+];
+/// Boolean arguments as `(id, flag)`.
+const FLAGS: [(&'static str, &'static str); 6] = [
+    ("session", "--session"),
+    ("gps5", "--gps5"),
+    ("fill", "--fill"),
+    ("average", "--average"),
+    ("map", "--map"),
+    ("osm", "--osm"),
+];
+
+/// Inserts a single value as is, and several values as an array,
+/// e.g. two Y-axis data types, or several input files to compare.
+fn insert(map: &mut Map<String, Value>, id: &str, mut values: Vec<Value>) {
+    match values.len() {
+        0 => (),
+        1 => {
+            map.insert(id.to_owned(), values.remove(0));
+        }
+        _ => {
+            map.insert(id.to_owned(), Value::Array(values));
+        }
+    }
+}
+
+/// Returns `flag` followed by the value, for each string value
+/// in `value` (a single string or an array of strings).
+fn flag_values(flag: &str, value: &Value) -> Vec<String> {
+    let values = match value {
+        Value::Array(values) => values.iter().collect(),
+        value => vec![value],
+    };
+    values
+        .into_iter()
+        .filter_map(|v| v.as_str())
+        .flat_map(|v| [flag.to_owned(), v.to_owned()])
+        .collect()
+}
+
+/// Writes a plot specification to `path`:
+/// GeoELAN version, creation date, input files (absolute paths),
+/// axes, filters (session, GPS5), smoothing (average, rolling mean,
+/// downsampling) and map view.
+pub fn write_spec(args: &clap::ArgMatches, path: &Path) -> std::io::Result<WriteStatus> {
+    let mut inputs = Map::new();
+    for (id, _) in PATHS {
+        let paths = args
+            .get_many::<PathBuf>(id)
+            .into_iter()
+            .flatten()
+            .map(|p| json!(p.canonicalize().unwrap_or(p.to_owned())))
+            .collect();
+        insert(&mut inputs, id, paths);
+    }
+    let mut values = Map::new();
+    for (id, _) in VALUES {
+        // Raw values, since not all value arguments are strings
+        let v = args
+            .get_raw(id)
+            .into_iter()
+            .flatten()
+            .map(|v| json!(v.to_string_lossy()))
+            .collect();
+        insert(&mut values, id, v);
+    }
+    let mut options = Map::new();
+    for (id, _) in FLAGS {
+        options.insert(id.to_owned(), json!(*args.get_one::<bool>(id).unwrap()));
+    }
+
+    let spec = json!({
+        "geoelan_version": VERSION,
+        "created": time::OffsetDateTime::now_utc().to_string(),
+        "inputs": inputs,
+        "axes": values,
+        "options": options,
+    });
+
+    let string = serde_json::to_string_pretty(&spec)?;
+    writefile(string.as_bytes(), path)
+}
+
+/// Reads a plot specification and returns the corresponding
+/// arguments for the 'plot' subcommand.
+pub fn read_spec(path: &Path) -> std::io::Result<Vec<String>> {
+    let string = std::fs::read_to_string(path)?;
+    let spec: Value = match serde_json::from_str(&string) {
+        Ok(v) => v,
+        Err(err) => {
+            let msg = format!("(!) Failed to parse '{}': {err}", path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    match spec["geoelan_version"].as_str() {
+        Some(VERSION) => (),
+        Some(v) => warn!(
+            "(!) Plot specification was created with GeoELAN {v}, current version is {VERSION}."
+        ),
+        None => warn!("(!) Plot specification has no GeoELAN version."),
+    }
+
+    let mut arguments: Vec<String> = Vec::new();
+    for (id, flag) in PATHS {
+        arguments.extend(flag_values(flag, &spec["inputs"][id]));
+    }
+    for (id, flag) in VALUES {
+        arguments.extend(flag_values(flag, &spec["axes"][id]));
+    }
+    for (id, flag) in FLAGS {
+        if spec["options"][id].as_bool() == Some(true) {
+            arguments.push(flag.to_owned());
+        }
+    }
+
+    Ok(arguments)
+}
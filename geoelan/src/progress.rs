@@ -0,0 +1,144 @@
+//! Progress reporting for long-running operations, such as directory scans,
+//! batch processing, and FFmpeg concatenation.
+//!
+//! Progress is printed to stderr, so that it does not mix with
+//! structured output on stdout (e.g. `--format json`), and only if
+//! stderr is a terminal. Disabled for the rest of the run via `--quiet`.
+
+use std::{
+    io::{IsTerminal, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::Instant,
+};
+
+/// Progress bar width in characters.
+const BAR_WIDTH: usize = 30;
+
+static QUIET: OnceLock<bool> = OnceLock::new();
+
+/// Disables progress output for the rest of the run.
+/// Only the first call has an effect.
+pub fn set_quiet(quiet: bool) {
+    let _ = QUIET.set(quiet);
+}
+
+/// Returns `true` if progress output is disabled.
+pub fn quiet() -> bool {
+    QUIET.get().copied().unwrap_or(false)
+}
+
+static NESTED: OnceLock<bool> = OnceLock::new();
+
+/// Disables progress output for steps within a session, e.g. FFmpeg,
+/// for the rest of the run. Used for concurrent batch jobs,
+/// where several progress bars would overwrite each other.
+/// Only the first call has an effect.
+pub fn set_nested(nested: bool) {
+    let _ = NESTED.set(nested);
+}
+
+/// Returns `true` if progress for steps within a session is shown.
+fn nested() -> bool {
+    NESTED.get().copied().unwrap_or(true)
+}
+
+/// Formats seconds as `MM:SS`, or `HH:MM:SS` if an hour or longer.
+fn hms(seconds: u64) -> String {
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match h {
+        0 => format!("{m:02}:{s:02}"),
+        _ => format!("{h:02}:{m:02}:{s:02}"),
+    }
+}
+
+/// Progress bar with estimated time remaining, e.g.
+/// `Scanning [=========>          ] 12/40 (30%) ETA 00:42`.
+/// Safe to update from several threads.
+pub struct Progress {
+    label: String,
+    total: u64,
+    position: AtomicU64,
+    start: Instant,
+    enabled: bool,
+}
+
+impl Progress {
+    /// New progress bar for `total` steps, e.g. number of sessions,
+    /// or microseconds of video for FFmpeg.
+    pub fn new(label: &str, total: u64) -> Self {
+        Self::with_enabled(label, total, true)
+    }
+
+    /// New progress bar for a step within a session,
+    /// e.g. FFmpeg or checksums. Disabled via `set_nested(false)`.
+    pub fn nested(label: &str, total: u64) -> Self {
+        Self::with_enabled(label, total, nested())
+    }
+
+    fn with_enabled(label: &str, total: u64, enabled: bool) -> Self {
+        let progress = Self {
+            label: label.to_owned(),
+            total,
+            position: AtomicU64::new(0),
+            start: Instant::now(),
+            enabled: enabled && !quiet() && std::io::stderr().is_terminal(),
+        };
+        progress.draw(0);
+        progress
+    }
+
+    /// Advances progress by `steps`.
+    pub fn inc(&self, steps: u64) {
+        let position = self.position.fetch_add(steps, Ordering::SeqCst) + steps;
+        self.draw(position);
+    }
+
+    /// Sets absolute progress.
+    pub fn set(&self, position: u64) {
+        self.position.store(position, Ordering::SeqCst);
+        self.draw(position);
+    }
+
+    fn draw(&self, position: u64) {
+        if !self.enabled {
+            return;
+        }
+        let position = position.min(self.total);
+        let ratio = match self.total {
+            0 => 1.0,
+            t => position as f64 / t as f64,
+        };
+        let filled = (ratio * BAR_WIDTH as f64) as usize;
+        let bar = match filled {
+            0 => " ".repeat(BAR_WIDTH),
+            n if n >= BAR_WIDTH => "=".repeat(BAR_WIDTH),
+            n => format!("{}>{}", "=".repeat(n - 1), " ".repeat(BAR_WIDTH - n)),
+        };
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let eta = match position {
+            0 => "--:--".to_owned(),
+            p => hms((elapsed * (self.total - p) as f64 / p as f64) as u64),
+        };
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r{} [{bar}] {position}/{} ({:.0}%) ETA {eta} ",
+            self.label,
+            self.total,
+            ratio * 100.0
+        );
+        let _ = stderr.flush();
+    }
+
+    /// Completes progress bar and prints elapsed time.
+    pub fn finish(&self) {
+        if !self.enabled {
+            return;
+        }
+        self.draw(self.total);
+        eprintln!("elapsed {}", hms(self.start.elapsed().as_secs()));
+    }
+}
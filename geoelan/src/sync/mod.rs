@@ -0,0 +1,172 @@
+//! Synchronise media from multiple cameras recording the same event,
+//! e.g. GoPro + VIRB or two GoPros, and generate an ELAN-file with all
+//! media linked and per-media time offsets (`TIME_ORIGIN`) set in the header.
+//!
+//! Offsets are derived from GPS datetime (GoPro: first logged point,
+//! VIRB: FIT session start), MP4 creation time as a fallback,
+//! and/or refined via audio cross-correlation (see `media::audio`).
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use eaf_rs::Eaf;
+use fit_rs::VirbSession;
+use gpmf_rs::GoProSession;
+use time::PrimitiveDateTime;
+
+use crate::{
+    elan::xml::Document,
+    files::{writefile, WriteStatus},
+    geo::EafPoint,
+    logging::{info, warn},
+    media::{atoms, audio::audio_offset},
+    model::CameraModel,
+};
+
+/// Returns recording start for a video as `(datetime, source)`,
+/// from GPS if possible, otherwise MP4 creation time.
+fn recording_start(path: &Path, indir: Option<&Path>) -> Option<(PrimitiveDateTime, &'static str)> {
+    let gps_start = match CameraModel::from(path) {
+        CameraModel::GoPro(_) => GoProSession::single(path).ok().and_then(|session| {
+            let gps = session.gpmf().ok()?.gps().prune(2, None);
+            let point = EafPoint::from(gps.iter().next()?);
+            Some(point.datetime? - point.timestamp?)
+        }),
+        CameraModel::Virb(_) => {
+            let indir = indir.or(path.parent())?;
+            let mut session = VirbSession::from_mp4(path, indir, true)?;
+            session.process(0).ok()?;
+            Some(session.t0? + session.start?)
+        }
+        CameraModel::Unknown => None,
+    };
+
+    match gps_start {
+        Some(dt) => Some((dt, "GPS")),
+        None => atoms::creation_time(path)
+            .ok()
+            .flatten()
+            .map(|dt| (dt, "MP4 creation time")),
+    }
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let videos: Vec<PathBuf> = args
+        .get_many::<PathBuf>("video")
+        .unwrap() // clap: required arg
+        .map(|p| p.canonicalize())
+        .collect::<std::io::Result<_>>()?;
+    let indir = args.get_one::<PathBuf>("input-directory");
+    let method = args.get_one::<String>("method").unwrap().as_str(); // clap: default 'gps'
+    let ffmpeg = args.get_one::<PathBuf>("ffmpeg").unwrap(); // clap: default value
+    let max_lag = *args.get_one::<f64>("max-lag").unwrap(); // clap: default 5.0
+    let window = *args.get_one::<f64>("window").unwrap(); // clap: default 60.0
+    let eaf_path = match args.get_one::<PathBuf>("output") {
+        Some(p) => p.with_extension("eaf"),
+        None => {
+            let stem = videos[0].file_stem().unwrap_or_default().to_string_lossy();
+            videos[0].with_file_name(format!("{stem}_sync.eaf"))
+        }
+    };
+
+    // Offsets in milliseconds relative to first video
+    let mut offsets: Vec<i64> = vec![0; videos.len()];
+    let mut sources: Vec<String> = vec!["reference".to_owned(); videos.len()];
+
+    if method != "audio" {
+        let starts: Vec<_> = videos
+            .iter()
+            .map(|p| recording_start(p, indir.map(|p| p.as_path())))
+            .collect();
+        let Some((ref_start, _)) = starts[0] else {
+            let msg = format!(
+                "(!) Failed to determine recording start for '{}'. Use '--method audio'.",
+                videos[0].display()
+            );
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        };
+        for (i, start) in starts.iter().enumerate().skip(1) {
+            match start {
+                Some((dt, source)) => {
+                    offsets[i] = (*dt - ref_start).whole_milliseconds() as i64;
+                    sources[i] = source.to_string();
+                }
+                None if method == "both" => sources[i] = "none".to_owned(),
+                None => {
+                    let msg = format!(
+                        "(!) Failed to determine recording start for '{}'. Use '--method audio' or '--method both'.",
+                        videos[i].display()
+                    );
+                    return Err(std::io::Error::new(ErrorKind::Other, msg));
+                }
+            }
+        }
+    }
+
+    if method != "gps" {
+        for i in 1..videos.len() {
+            info!("Correlating audio for {}...", videos[i].display());
+            let (offset, score) =
+                audio_offset(&videos[0], &videos[i], offsets[i], ffmpeg, max_lag, window)?;
+            info!("Correlation score {score:.2}");
+            sources[i] = match sources[i].as_str() {
+                "reference" | "none" => "audio".to_owned(),
+                source => format!("{source} + audio"),
+            };
+            offsets[i] = offset;
+        }
+    }
+
+    // ELAN time origins can not be negative, so the timeline
+    // starts when the last camera started recording.
+    let latest = offsets.iter().max().copied().unwrap_or(0);
+    let origins: Vec<i64> = offsets.iter().map(|o| latest - o).collect();
+
+    info!("Offsets relative to {}:", videos[0].display());
+    for (i, video) in videos.iter().enumerate() {
+        info!(
+            "  {:2}. {:+10.3}s  (ELAN time origin {:8.3}s, {})  {}",
+            i + 1,
+            offsets[i] as f64 / 1000.0,
+            origins[i] as f64 / 1000.0,
+            sources[i],
+            video.display()
+        );
+    }
+    if latest > 0 {
+        warn!(
+            "(!) ELAN timeline starts when the last camera started recording. Earlier media is offset accordingly."
+        );
+    }
+
+    let mut eaf = Eaf::default();
+    eaf.with_media_mut(&videos);
+    eaf.index();
+    if let Err(err) = eaf.derive() {
+        let msg = format!("(!) Failed to generate EAF: {err}");
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+    let mut doc = match eaf.to_string(Some(4)) {
+        Ok(s) => Document::parse(&s)?,
+        Err(err) => {
+            let msg = format!("(!) Failed to generate EAF: {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+    doc.set_time_origins(&origins);
+    let xml = doc.to_string();
+
+    match writefile(xml.as_bytes(), &eaf_path) {
+        Ok(WriteStatus::Written) => info!("Wrote {}", eaf_path.display()),
+        Ok(WriteStatus::Planned) => (),
+        Ok(WriteStatus::Skipped) => info!("User aborted writing {}", eaf_path.display()),
+        Err(err) => {
+            let msg = format!("(!) Failed to write '{}': {err}", eaf_path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    }
+
+    Ok(())
+}
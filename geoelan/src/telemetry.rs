@@ -0,0 +1,507 @@
+//! Brand independent access to camera telemetry, so that subcommands
+//! do not have to special-case GoPro (GPMF) and Garmin VIRB (FIT).
+//! Support for other formats is added by implementing `Telemetry`.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use fit_rs::{Fit, FitSession, FitSessions};
+use gpmf_rs::{DeviceName, GoProFile, GoProSession, Gpmf};
+use time::{Duration, PrimitiveDateTime};
+
+use crate::{
+    files::virb::select_session,
+    geo::EafPoint,
+    logging::warn,
+    model::{logs_gps9, muid_string, session_id, CameraModel},
+};
+
+/// Recording session.
+#[derive(Debug, Clone, Default)]
+pub struct TelemetrySession {
+    /// Session identifier, i.e. MUID (GoPro) or UUID for first clip (VIRB).
+    pub id: String,
+    pub start: Option<PrimitiveDateTime>,
+    pub end: Option<PrimitiveDateTime>,
+    /// Clip identifiers, i.e. MUID for each clip (GoPro)
+    /// or UUIDs (VIRB), in recording order.
+    pub clip_ids: Vec<String>,
+    /// Clips in session. Empty if these can not be determined
+    /// from the telemetry alone, e.g. for FIT-files.
+    pub files: Vec<PathBuf>,
+}
+
+/// Sensor time series, e.g. accelerometer.
+#[derive(Debug, Clone, Default)]
+pub struct SensorSeries {
+    /// Sensor name, e.g. `Accelerometer`.
+    pub name: String,
+    /// Quantity, e.g. `Acceleration`.
+    pub quantifier: String,
+    /// Units, e.g. `m/s²`.
+    pub units: String,
+    /// Seconds relative to start of data.
+    pub time: Vec<f64>,
+    pub x: Vec<f64>,
+    /// Empty for one-dimensional sensors, e.g. barometer.
+    pub y: Vec<f64>,
+    /// Empty for one-dimensional sensors, e.g. barometer.
+    pub z: Vec<f64>,
+}
+
+impl SensorSeries {
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+}
+
+/// Camera telemetry, e.g. GoPro GPMF or Garmin FIT.
+pub trait Telemetry {
+    /// Telemetry source, e.g. MP4 (GoPro) or FIT-file (VIRB).
+    fn path(&self) -> &Path;
+
+    fn model(&self) -> CameraModel;
+
+    /// Absolute start time for data.
+    fn start_time(&self) -> Option<PrimitiveDateTime>;
+
+    /// Recording sessions in data.
+    fn sessions(&self) -> std::io::Result<Vec<TelemetrySession>>;
+
+    /// GPS log with timestamps relative to start of data.
+    /// Points with a satellite lock below `min_fix`
+    /// or dilution of precision above `max_dop` are pruned, if logged.
+    fn points(&self, min_fix: u32, max_dop: Option<f64>) -> std::io::Result<Vec<EafPoint>>;
+
+    /// Sensor data for `sensor`, e.g. `acc` or `accelerometer`.
+    /// If `average` is `true`, a single value is returned
+    /// for each logged cluster of samples.
+    fn sensors(&self, sensor: &str, average: bool) -> std::io::Result<SensorSeries>;
+}
+
+/// Opens telemetry for either a GoPro MP4 (`gpmf`) or a FIT-file (`fit`).
+/// If `session` is `true`, GoPro clips are merged into a recording session,
+/// and a recording session in the FIT-file is selected interactively.
+pub fn open(
+    gpmf: Option<&Path>,
+    fit: Option<&Path>,
+    indir: Option<&Path>,
+    verify: bool,
+    session: bool,
+) -> std::io::Result<Box<dyn Telemetry>> {
+    match (gpmf, fit) {
+        (Some(path), None) => Ok(Box::new(GoProTelemetry::new(path, indir, verify, session)?)),
+        (None, Some(path)) => Ok(Box::new(VirbTelemetry::new(path, session)?)),
+        _ => {
+            let msg = "(!) Specify either a GoPro MP4 or a FIT-file.";
+            Err(std::io::Error::new(ErrorKind::Other, msg))
+        }
+    }
+}
+
+/// GoPro GPMF telemetry for a single clip or a recording session.
+pub struct GoProTelemetry {
+    path: PathBuf,
+    device: Option<DeviceName>,
+    session: Option<GoProSession>,
+    gpmf: Gpmf,
+}
+
+impl GoProTelemetry {
+    pub fn new(
+        path: &Path,
+        indir: Option<&Path>,
+        verify: bool,
+        session: bool,
+    ) -> std::io::Result<Self> {
+        match session {
+            true => {
+                let gopro_session = GoProSession::from_path(path, indir, verify, true, true)?;
+                Ok(Self {
+                    path: path.to_owned(),
+                    device: gopro_session.device().cloned(),
+                    gpmf: gopro_session.gpmf()?,
+                    session: Some(gopro_session),
+                })
+            }
+            false => Ok(Self {
+                path: path.to_owned(),
+                device: DeviceName::from_path(path).ok(),
+                gpmf: Gpmf::new(path, false)?,
+                session: None,
+            }),
+        }
+    }
+
+    /// As `new()`, for already parsed or merged GPMF,
+    /// e.g. for a recording session. `path` is the first clip.
+    pub fn from_gpmf(path: &Path, device: Option<DeviceName>, gpmf: Gpmf) -> Self {
+        Self {
+            path: path.to_owned(),
+            device,
+            session: None,
+            gpmf,
+        }
+    }
+
+    /// Recording session, if merged.
+    pub fn session(&self) -> Option<&GoProSession> {
+        self.session.as_ref()
+    }
+
+    /// Parsed GPMF, merged for all clips if a recording session.
+    pub fn gpmf(&self) -> &Gpmf {
+        &self.gpmf
+    }
+}
+
+impl Telemetry for GoProTelemetry {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn model(&self) -> CameraModel {
+        CameraModel::GoPro(self.device.to_owned().unwrap_or_default())
+    }
+
+    fn start_time(&self) -> Option<PrimitiveDateTime> {
+        match &self.session {
+            Some(session) => session.start(),
+            None => GoProFile::new(&self.path).ok().map(|gp| gp.start()),
+        }
+    }
+
+    fn sessions(&self) -> std::io::Result<Vec<TelemetrySession>> {
+        let session = match &self.session {
+            Some(session) => {
+                let clip_ids: Vec<String> =
+                    session.iter().map(|gp| muid_string(&gp.muid)).collect();
+                TelemetrySession {
+                    id: clip_ids.first().cloned().unwrap_or_default(),
+                    start: session.start(),
+                    end: session.end(),
+                    clip_ids,
+                    files: session
+                        .iter()
+                        .filter_map(|gp| gp.mp4.to_owned().or(gp.lrv.to_owned()))
+                        .collect(),
+                }
+            }
+            None => {
+                let start = self.start_time();
+                let id = session_id(&self.path).unwrap_or_default();
+                TelemetrySession {
+                    start,
+                    end: GoProFile::new(&self.path)
+                        .ok()
+                        .and_then(|gp| start.map(|t| t + gp.duration())),
+                    clip_ids: vec![id.to_owned()],
+                    id,
+                    files: vec![self.path.to_owned()],
+                }
+            }
+        };
+        Ok(vec![session])
+    }
+
+    fn points(&self, min_fix: u32, max_dop: Option<f64>) -> std::io::Result<Vec<EafPoint>> {
+        let gps = match self.device.as_ref().map(logs_gps9).unwrap_or(false) {
+            true => self.gpmf.gps9(),
+            false => self.gpmf.gps5(),
+        };
+        Ok(gps
+            .prune(min_fix, max_dop)
+            .iter()
+            .map(EafPoint::from)
+            .collect())
+    }
+
+    fn sensors(&self, sensor: &str, average: bool) -> std::io::Result<SensorSeries> {
+        let sensor_type = gpmf_rs::SensorType::from(sensor);
+        let sensor_data = self.gpmf.sensor(&sensor_type);
+
+        if sensor_data.is_empty() {
+            let device = self
+                .gpmf
+                .device_name()
+                .first()
+                .cloned()
+                .unwrap_or(String::from("Unknown model"));
+            let msg = format!("(!) No '{}' data found. Either it is not supported by {device} or not yet implemented. Run 'geoelan inspect --gpmf {}' for a summary.",
+                sensor_type.to_string(),
+                self.path.display()
+            );
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+
+        // Use units logged in the GPMF stream (UNIT/SIUN) if present,
+        // and only fall back on default units for sensor type if not.
+        let units = sensor_data
+            .iter()
+            .find_map(|s| s.units.to_owned())
+            .unwrap_or(sensor_type.units().to_string());
+
+        let series = match average {
+            false => {
+                // Samples are evenly distributed over total duration
+                let (total, duration) = sensor_data
+                    .last()
+                    .map(|s| {
+                        (
+                            s.total,
+                            s.timestamp.unwrap_or_default() + s.duration.unwrap_or_default(),
+                        )
+                    })
+                    .unwrap_or_default();
+                let t_incr = duration.as_seconds_f64() / total as f64;
+                SensorSeries {
+                    time: (0..total).map(|i| i as f64 * t_incr).collect(),
+                    x: sensor_data.iter().flat_map(|s| s.x()).collect(),
+                    y: sensor_data.iter().flat_map(|s| s.y()).collect(),
+                    z: sensor_data.iter().flat_map(|s| s.z()).collect(),
+                    ..SensorSeries::default()
+                }
+            }
+            // A single average value for each sensor cluster
+            true => SensorSeries {
+                time: sensor_data
+                    .iter()
+                    .map(|s| s.timestamp.unwrap_or_default().as_seconds_f64())
+                    .collect(),
+                x: sensor_data.iter().map(|s| s.x_avg()).collect(),
+                y: sensor_data.iter().map(|s| s.y_avg()).collect(),
+                z: sensor_data.iter().map(|s| s.z_avg()).collect(),
+                ..SensorSeries::default()
+            },
+        };
+
+        Ok(SensorSeries {
+            name: sensor_type.to_string(),
+            quantifier: sensor_type.quantifier().to_string(),
+            units,
+            ..series
+        })
+    }
+}
+
+/// Garmin VIRB FIT telemetry, optionally limited to a single recording session.
+pub struct VirbTelemetry {
+    path: PathBuf,
+    fit: Fit,
+    session: Option<FitSession>,
+}
+
+impl VirbTelemetry {
+    /// If `session` is `true`, a recording session
+    /// present in the FIT-file is selected interactively.
+    pub fn new(path: &Path, session: bool) -> std::io::Result<Self> {
+        Self::from_fit(path, Fit::new(path)?, session)
+    }
+
+    /// As `new()`, for an already parsed FIT-file at `path`.
+    pub fn from_fit(path: &Path, fit: Fit, session: bool) -> std::io::Result<Self> {
+        let session = match session {
+            true => Some(select_session(&fit)?),
+            false => None,
+        };
+        Ok(Self {
+            path: path.to_owned(),
+            fit,
+            session,
+        })
+    }
+
+    /// As `new()`, but selects the recording session
+    /// that contains the clip with `uuid`, without user input.
+    pub fn from_uuid(path: &Path, uuid: &str) -> std::io::Result<Self> {
+        let fit = Fit::new(path)?;
+        let session = FitSessions::from_fit(&fit)?
+            .iter()
+            .find(|s| s.iter().any(|u| u.to_string() == uuid))
+            .cloned();
+        if session.is_none() {
+            let msg = format!("(!) No session with UUID '{uuid}' in {}", path.display());
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            fit,
+            session,
+        })
+    }
+
+    /// Parsed FIT-file.
+    pub fn fit(&self) -> &Fit {
+        &self.fit
+    }
+
+    /// Start of selected session relative to start of FIT-file.
+    fn session_offset(&self) -> Duration {
+        match &self.session {
+            Some(session) => match session.timespan_rel() {
+                Some((start, _)) => start,
+                None => {
+                    warn!("(!) Unable to determine start time for session.");
+                    warn!("    Setting start time to 0.");
+                    Duration::ZERO
+                }
+            },
+            None => Duration::ZERO,
+        }
+    }
+}
+
+impl Telemetry for VirbTelemetry {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn model(&self) -> CameraModel {
+        let uuid = self
+            .session
+            .as_ref()
+            .and_then(|s| s.iter().next().map(|u| u.to_string()))
+            .unwrap_or_default();
+        CameraModel::Virb(uuid)
+    }
+
+    fn start_time(&self) -> Option<PrimitiveDateTime> {
+        self.fit
+            .t0(0, false)
+            .ok()
+            .map(|t0| t0 + self.session_offset())
+    }
+
+    fn sessions(&self) -> std::io::Result<Vec<TelemetrySession>> {
+        let sessions = FitSessions::from_fit(&self.fit)?
+            .iter()
+            .map(|s| {
+                let mut session = s.clone();
+                let span = match session.derive() {
+                    Ok(_) => session.timespan_abs(None, true).ok(),
+                    Err(_) => None,
+                };
+                TelemetrySession {
+                    id: session
+                        .iter()
+                        .next()
+                        .map(|u| u.to_string())
+                        .unwrap_or_default(),
+                    start: span.map(|(start, _)| start),
+                    end: span.map(|(_, end)| end),
+                    clip_ids: session.iter().map(|u| u.to_string()).collect(),
+                    files: Vec::new(),
+                }
+            })
+            .collect();
+        Ok(sessions)
+    }
+
+    /// FIT-files do not log satellite lock, `min_fix` and `max_dop` are ignored.
+    fn points(&self, _min_fix: u32, _max_dop: Option<f64>) -> std::io::Result<Vec<EafPoint>> {
+        let range = self.session.as_ref().map(|s| s.range());
+
+        // Ignore custom time offset here, since done in eaf2geo during point conversion.
+        let t0 = self.fit.t0(0, false)?;
+        let start_time = self.session_offset();
+
+        // Subtract relative start time for session
+        // as logged in the FIT-file to generate timeline
+        // where 0 seconds reflects start of session.
+        let points = self.fit.gps(range.as_ref()).map(|gps| {
+            gps.iter()
+                .map(|p_in| {
+                    let mut p_out = EafPoint::from_fit(&p_in, Some(t0));
+                    p_out.timestamp = p_out
+                        .timestamp
+                        .map(|t| (t - start_time).max(Duration::ZERO));
+                    p_out
+                })
+                .collect::<Vec<_>>()
+        })?;
+
+        Ok(points)
+    }
+
+    fn sensors(&self, sensor: &str, average: bool) -> std::io::Result<SensorSeries> {
+        let sensor_type = match fit_rs::SensorType::from_str(sensor) {
+            Some(s) => s,
+            None => {
+                let msg = format!("(!) '{sensor}' is not supported by the FIT format or not yet implemented. Run 'geoelan inspect --fit {}' for a summary.", self.path.display());
+                return Err(std::io::Error::new(ErrorKind::Other, msg));
+            }
+        };
+
+        let range = self.session.as_ref().map(|s| s.range());
+        let sensor_data = self.fit.sensor(&sensor_type, range.as_ref())?;
+
+        let mean = |values: &[f64]| match values.is_empty() {
+            true => 0.,
+            false => values.iter().sum::<f64>() / values.len() as f64,
+        };
+        let is_3d = !matches!(sensor_type, fit_rs::SensorType::Barometer);
+
+        let series = match average {
+            false => SensorSeries {
+                time: sensor_data
+                    .iter()
+                    .flat_map(|s| {
+                        // add millisecond offset for each sample to record timestamp
+                        s.sample_time_offset.iter().map(|o| {
+                            *o as f64 / 1000. + s.timestamp as f64 + s.timestamp_ms as f64 / 1000.
+                        })
+                    })
+                    .collect(),
+                x: sensor_data
+                    .iter()
+                    .flat_map(|s| s.calibrated_x.iter().copied())
+                    .collect(),
+                y: match is_3d {
+                    true => sensor_data
+                        .iter()
+                        .flat_map(|s| s.calibrated_y.iter().copied())
+                        .collect(),
+                    false => Vec::new(),
+                },
+                z: match is_3d {
+                    true => sensor_data
+                        .iter()
+                        .flat_map(|s| s.calibrated_z.iter().copied())
+                        .collect(),
+                    false => Vec::new(),
+                },
+                ..SensorSeries::default()
+            },
+            // A single average value for each FIT record
+            true => SensorSeries {
+                time: sensor_data
+                    .iter()
+                    .map(|s| s.timestamp as f64 + s.timestamp_ms as f64 / 1000.)
+                    .collect(),
+                x: sensor_data.iter().map(|s| mean(&s.calibrated_x)).collect(),
+                y: match is_3d {
+                    true => sensor_data.iter().map(|s| mean(&s.calibrated_y)).collect(),
+                    false => Vec::new(),
+                },
+                z: match is_3d {
+                    true => sensor_data.iter().map(|s| mean(&s.calibrated_z)).collect(),
+                    false => Vec::new(),
+                },
+                ..SensorSeries::default()
+            },
+        };
+
+        Ok(SensorSeries {
+            name: sensor_type.to_string(),
+            quantifier: sensor_type.quantifier().to_string(),
+            units: sensor_type.units().to_string(),
+            ..series
+        })
+    }
+}
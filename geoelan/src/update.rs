@@ -0,0 +1,186 @@
+//! Version report and opt-in check for newer releases.
+//!
+//! GeoELAN never accesses the network, with the exception of
+//! `geoelan update --check`, which queries the latest release on GitHub
+//! via `curl`, and `geoelan plot --osm`, which generates plots that load
+//! map tiles in the browser. Nothing is downloaded or installed.
+//! `--offline` disables both, so that no command can access the network.
+
+use std::{io::ErrorKind, process::Command};
+
+use clap::builder::OsStr;
+use serde_json::Value;
+
+use crate::{
+    logging::{info, warn},
+    REPO, VERSION,
+};
+
+/// GitHub API endpoint for latest release.
+const RELEASES_LATEST: &'static str =
+    "https://api.github.com/repos/jenslar/geoelan/releases/latest";
+
+/// Arguments that enable network access, as `(subcommand, argument ID)`.
+const NETWORK_ARGS: [(&'static str, &'static str); 2] = [("plot", "osm"), ("update", "check")];
+
+/// Returns arguments in `cli` that enable network access by default,
+/// e.g. `plot --osm` set to `true` in the config file, as `plot --osm`.
+/// Empty if `--offline` is set by default.
+pub fn network_defaults(cli: &clap::Command) -> Vec<String> {
+    let is_set = |cmd: &clap::Command, id: &str| {
+        cmd.get_arguments()
+            .find(|a| a.get_id() == id)
+            .is_some_and(|a| a.get_default_values() == [OsStr::from("true")])
+    };
+    if is_set(cli, "offline") {
+        return Vec::new();
+    }
+    NETWORK_ARGS
+        .iter()
+        .filter(|(name, id)| {
+            cli.find_subcommand(name)
+                .is_some_and(|sub| is_set(sub, id) && !is_set(sub, "offline"))
+        })
+        .map(|(name, id)| format!("{name} --{id}"))
+        .collect()
+}
+
+/// Parses version string, e.g. `v2.7.0` or `2.7`, as `[major, minor, patch]`.
+fn parse_version(version: &str) -> Option<[u32; 3]> {
+    let mut numbers = version
+        .trim()
+        .trim_start_matches(['v', 'V'])
+        .split(['.', '-', '+'])
+        .map(|n| n.parse::<u32>());
+    Some([
+        numbers.next()?.ok()?,
+        numbers.next().and_then(|n| n.ok()).unwrap_or(0),
+        numbers.next().and_then(|n| n.ok()).unwrap_or(0),
+    ])
+}
+
+/// Returns tag for latest release, e.g. `v2.8.0`.
+fn latest_release(curl: &str) -> std::io::Result<String> {
+    let output = match Command::new(curl)
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--location",
+            "--max-time",
+            "10",
+            "--header",
+            "Accept: application/vnd.github+json",
+            RELEASES_LATEST,
+        ])
+        .output()
+    {
+        Ok(o) => o,
+        Err(err) => {
+            let msg = format!("(!) Failed to run '{curl}': {err}");
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+    };
+
+    if !output.status.success() {
+        let msg = format!(
+            "(!) Failed to query latest release: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    let json: Value = serde_json::from_slice(&output.stdout)?;
+    match json["tag_name"].as_str() {
+        Some(tag) => Ok(tag.to_owned()),
+        None => {
+            let msg = "(!) No release tag in response.";
+            Err(std::io::Error::new(ErrorKind::Other, msg))
+        }
+    }
+}
+
+/// `cli` is the command line interface with config file defaults applied,
+/// used for the offline check.
+pub fn run(args: &clap::ArgMatches, cli: &clap::Command) -> std::io::Result<()> {
+    let check = *args.get_one::<bool>("check").unwrap();
+    let doctor = *args.get_one::<bool>("doctor").unwrap();
+    let offline = *args.get_one::<bool>("offline").unwrap();
+    let curl = args.get_one::<String>("curl").unwrap(); // clap: default value
+
+    let network = network_defaults(cli);
+
+    println!("GeoELAN {VERSION}");
+    println!("  Source:          {REPO}");
+    println!(
+        "  Network access:  {}",
+        match (offline, network.is_empty()) {
+            (true, _) => "disabled ('--offline')".to_owned(),
+            (false, true) => {
+                "none, except 'geoelan update --check' and 'geoelan plot --osm'".to_owned()
+            }
+            (false, false) => format!("enabled by default via config file: {}", network.join(", ")),
+        }
+    );
+    println!("  Self-update:     never, releases must be installed manually");
+
+    if doctor {
+        if !network.is_empty() {
+            let msg = format!(
+                "(!) Default behaviour is not offline. Network access is enabled by default for: {}",
+                network.join(", ")
+            );
+            return Err(std::io::Error::new(ErrorKind::Other, msg));
+        }
+        println!("OK: default behaviour is fully offline.");
+    }
+
+    if !check {
+        return Ok(());
+    }
+
+    if offline {
+        let msg = "(!) '--check' requires network access, but '--offline' is set.";
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    info!("Checking latest release...");
+    let tag = latest_release(curl)?;
+    info!("Latest release: {tag}");
+
+    match (parse_version(&tag), parse_version(VERSION)) {
+        (Some(latest), Some(current)) if latest > current => {
+            println!("New version available: {tag}. See {REPO}/releases")
+        }
+        (Some(_), Some(_)) => println!("GeoELAN {VERSION} is up to date."),
+        _ => warn!("(!) Failed to compare versions '{tag}' and '{VERSION}'."),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{Arg, ArgAction};
+
+    fn cli(osm: &str, offline: &str) -> clap::Command {
+        let flag = |id: &'static str, default: &str| {
+            Arg::new(id)
+                .long(id)
+                .action(ArgAction::SetTrue)
+                .default_value(default.to_owned())
+        };
+        clap::Command::new("geoelan")
+            .arg(flag("offline", offline).global(true))
+            .subcommand(clap::Command::new("plot").arg(flag("osm", osm)))
+            .subcommand(clap::Command::new("update").arg(flag("check", "false")))
+    }
+
+    #[test]
+    fn offline_by_default() {
+        assert!(network_defaults(&cli("false", "false")).is_empty());
+        assert_eq!(network_defaults(&cli("true", "false")), ["plot --osm"]);
+        assert!(network_defaults(&cli("true", "true")).is_empty());
+    }
+}
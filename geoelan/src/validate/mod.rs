@@ -0,0 +1,380 @@
+//! Validate ELAN-files against the structural constraints in the EAF schema
+//! (required attributes, unique IDs, and references between time slots,
+//! annotations, tiers, linguistic types, languages, and controlled vocabularies),
+//! so that invalid files are discovered before ELAN refuses to open them.
+//!
+//! Validation is done on the element tree from `elan::xml`, which keeps
+//! the position of each element to report line numbers, and reports
+//! all violations rather than failing on the first one.
+//! XML that is not well-formed is reported as a single violation. Element content models (e.g. the order
+//! of child elements) are not checked, since that requires the EAF XSD.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::ErrorKind,
+    path::PathBuf,
+};
+
+use crate::elan::xml::{Document, Element};
+
+/// Elements and attributes introduced in later EAF versions,
+/// as `(version, element, attribute)`. `None` for the element itself.
+const VERSION_FEATURES: [(&'static str, &'static str, Option<&'static str>); 5] = [
+    ("2.8", "LANGUAGE", None),
+    ("2.8", "LICENSE", None),
+    ("2.8", "CV_ENTRY_ML", None),
+    ("2.8", "TIER", Some("LANG_REF")),
+    ("3.0", "REF_LINK_SET", None),
+];
+
+/// Parses EAF version, e.g. `2.8`, as `(major, minor)`.
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let (major, minor) = version.trim().split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// A single schema violation.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    /// Line number, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {:5}: {}", self.line, self.message)
+    }
+}
+
+/// Validates ELAN-file XML text. Returns all violations,
+/// sorted by line number.
+pub fn validate(xml: &str) -> Vec<Violation> {
+    let mut violations: Vec<Violation> = Vec::new();
+    let line = |offset: usize| xml[..offset].matches('\n').count() + 1;
+    let mut violation = |offset: usize, message: String| {
+        violations.push(Violation {
+            line: line(offset),
+            message,
+        })
+    };
+    let doc = match Document::try_parse(xml) {
+        Ok(doc) => doc,
+        Err((offset, err)) => {
+            violation(offset, format!("Invalid XML: {err}"));
+            return violations;
+        }
+    };
+    let elements = |tag: &str| doc.elements(tag);
+
+    // Required attributes
+    let required: [(&str, &[&str]); 6] = [
+        ("ANNOTATION_DOCUMENT", &["AUTHOR", "DATE", "VERSION"]),
+        ("HEADER", &["MEDIA_FILE"]),
+        ("TIME_SLOT", &["TIME_SLOT_ID"]),
+        ("TIER", &["TIER_ID", "LINGUISTIC_TYPE_REF"]),
+        ("LINGUISTIC_TYPE", &["LINGUISTIC_TYPE_ID"]),
+        (
+            "ALIGNABLE_ANNOTATION",
+            &["ANNOTATION_ID", "TIME_SLOT_REF1", "TIME_SLOT_REF2"],
+        ),
+    ];
+    for (tag, attributes) in required {
+        let elems = elements(tag);
+        if elems.is_empty() && ["ANNOTATION_DOCUMENT", "HEADER"].contains(&tag) {
+            violation(0, format!("Missing required element '{tag}'"));
+        }
+        for elem in elems.iter() {
+            for attr in attributes.iter().filter(|a| elem.attribute(a).is_none()) {
+                violation(
+                    elem.offset,
+                    format!("'{tag}' is missing required attribute '{attr}'"),
+                );
+            }
+        }
+    }
+
+    // Unique IDs (xsd:key)
+    let mut unique = |tag: &str, attribute: &str| -> HashSet<String> {
+        let mut ids: HashSet<String> = HashSet::new();
+        for elem in elements(tag).iter() {
+            if let Some(id) = elem.attribute(attribute) {
+                if !ids.insert(id.to_owned()) {
+                    violation(elem.offset, format!("Duplicate {attribute} '{id}'"));
+                }
+            }
+        }
+        ids
+    };
+    let time_slot_ids = unique("TIME_SLOT", "TIME_SLOT_ID");
+    let tier_ids = unique("TIER", "TIER_ID");
+    let type_ids = unique("LINGUISTIC_TYPE", "LINGUISTIC_TYPE_ID");
+    let lang_ids = unique("LANGUAGE", "LANG_ID");
+    let cv_ids = unique("CONTROLLED_VOCABULARY", "CV_ID");
+    // Annotation IDs are unique across both annotation types
+    let mut annotation_ids: HashSet<String> = HashSet::new();
+    let mut annotations: Vec<&Element> = elements("ALIGNABLE_ANNOTATION");
+    annotations.extend(elements("REF_ANNOTATION"));
+    annotations.sort_by_key(|e| e.offset);
+    for elem in annotations.iter() {
+        if let Some(id) = elem.attribute("ANNOTATION_ID") {
+            if !annotation_ids.insert(id.to_owned()) {
+                violation(elem.offset, format!("Duplicate ANNOTATION_ID '{id}'"));
+            }
+        }
+    }
+
+    // References (xsd:keyref)
+    let mut reference = |tag: &str, attribute: &str, ids: &HashSet<String>, target: &str| {
+        for elem in elements(tag).iter() {
+            if let Some(id) = elem.attribute(attribute) {
+                if !ids.contains(id) {
+                    violation(
+                        elem.offset,
+                        format!("{attribute} '{id}' does not refer to an existing {target}"),
+                    );
+                }
+            }
+        }
+    };
+    reference(
+        "ALIGNABLE_ANNOTATION",
+        "TIME_SLOT_REF1",
+        &time_slot_ids,
+        "time slot",
+    );
+    reference(
+        "ALIGNABLE_ANNOTATION",
+        "TIME_SLOT_REF2",
+        &time_slot_ids,
+        "time slot",
+    );
+    reference(
+        "REF_ANNOTATION",
+        "ANNOTATION_REF",
+        &annotation_ids,
+        "annotation",
+    );
+    reference("TIER", "PARENT_REF", &tier_ids, "tier");
+    reference("TIER", "LINGUISTIC_TYPE_REF", &type_ids, "linguistic type");
+    reference("TIER", "LANG_REF", &lang_ids, "language");
+    reference(
+        "LINGUISTIC_TYPE",
+        "CONTROLLED_VOCABULARY_REF",
+        &cv_ids,
+        "controlled vocabulary",
+    );
+
+    // Annotation time order, for time slots with values
+    let time_values: HashMap<&str, i64> = elements("TIME_SLOT")
+        .iter()
+        .filter_map(|e| {
+            Some((
+                e.attribute("TIME_SLOT_ID")?,
+                e.attribute("TIME_VALUE")?.parse().ok()?,
+            ))
+        })
+        .collect();
+    for elem in elements("ALIGNABLE_ANNOTATION").iter() {
+        let (Some(ts1), Some(ts2)) = (
+            elem.attribute("TIME_SLOT_REF1")
+                .and_then(|r| time_values.get(r)),
+            elem.attribute("TIME_SLOT_REF2")
+                .and_then(|r| time_values.get(r)),
+        ) else {
+            continue;
+        };
+        if ts1 > ts2 {
+            violation(
+                elem.offset,
+                format!(
+                    "Annotation '{}' ends before it starts ({ts1}ms > {ts2}ms)",
+                    elem.attribute("ANNOTATION_ID").unwrap_or_default()
+                ),
+            );
+        }
+    }
+
+    violations.sort_by_key(|v| v.line);
+    violations
+}
+
+/// Checks that ELAN-file XML text can be read as EAF version `target`,
+/// e.g. `2.8` for archives that do not accept later versions.
+/// Reports a newer document version, and each element or attribute
+/// not available in `target`. XML that is not well-formed
+/// is reported by `validate()`.
+pub fn validate_version(xml: &str, target: &str) -> Vec<Violation> {
+    let mut violations: Vec<Violation> = Vec::new();
+    let (Some(target_version), Ok(doc)) = (parse_version(target), Document::try_parse(xml)) else {
+        return violations;
+    };
+    let line = |offset: usize| xml[..offset].matches('\n').count() + 1;
+
+    if let Some(root) = doc.elements("ANNOTATION_DOCUMENT").first() {
+        if let Some(version) = root
+            .attribute("VERSION")
+            .filter(|v| parse_version(v).is_some_and(|v| v > target_version))
+        {
+            violations.push(Violation {
+                line: line(root.offset),
+                message: format!("Document is EAF {version}, target is EAF {target}"),
+            });
+        }
+    }
+
+    for (version, tag, attribute) in VERSION_FEATURES {
+        if parse_version(version).is_some_and(|v| v <= target_version) {
+            continue;
+        }
+        for elem in doc.elements(tag).iter() {
+            let message = match attribute {
+                Some(attr) if elem.attribute(attr).is_some() => {
+                    format!("'{tag}' attribute '{attr}' requires EAF {version}")
+                }
+                Some(_) => continue,
+                None => format!("'{tag}' requires EAF {version}"),
+            };
+            violations.push(Violation {
+                line: line(elem.offset),
+                message,
+            });
+        }
+    }
+
+    violations.sort_by_key(|v| v.line);
+    violations
+}
+
+pub fn run(args: &clap::ArgMatches) -> std::io::Result<()> {
+    let paths: Vec<&PathBuf> = args.get_many::<PathBuf>("eaf").unwrap().collect(); // clap: required arg
+    let target = args.get_one::<String>("eaf-version");
+
+    let mut invalid = 0;
+    for path in paths.iter() {
+        let xml = std::fs::read_to_string(path)?;
+        let version = Document::try_parse(&xml)
+            .ok()
+            .and_then(|d| d.root.attribute("VERSION").map(String::from))
+            .unwrap_or("unknown".to_owned());
+        let mut violations = validate(&xml);
+        if let Some(target) = target {
+            violations.extend(validate_version(&xml, target));
+            violations.sort_by_key(|v| v.line);
+        }
+
+        if violations.is_empty() {
+            println!("[{}] OK (EAF {version})", path.display());
+            continue;
+        }
+
+        invalid += 1;
+        println!(
+            "[{}] {} violations (EAF {version})",
+            path.display(),
+            violations.len()
+        );
+        for v in violations.iter() {
+            println!("  {v}");
+        }
+    }
+
+    if invalid > 0 {
+        let msg = format!("(!) {invalid} of {} files failed validation.", paths.len());
+        return Err(std::io::Error::new(ErrorKind::Other, msg));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_target() {
+        let xml = r#"<ANNOTATION_DOCUMENT VERSION="3.0">
+    <TIER LANG_REF="swe" LINGUISTIC_TYPE_REF="lt" TIER_ID="A"/>
+    <TIER LINGUISTIC_TYPE_REF="lt" TIER_ID="B"/>
+    <LANGUAGE LANG_ID="swe"/>
+    <REF_LINK_SET LINK_SET_ID="rs1"/>
+</ANNOTATION_DOCUMENT>"#;
+        let messages = |target: &str| -> Vec<String> {
+            validate_version(xml, target)
+                .into_iter()
+                .map(|v| format!("{}: {}", v.line, v.message))
+                .collect()
+        };
+        assert!(messages("3.0").is_empty());
+        assert_eq!(
+            messages("2.8"),
+            [
+                "1: Document is EAF 3.0, target is EAF 2.8",
+                "5: 'REF_LINK_SET' requires EAF 3.0"
+            ]
+        );
+        assert_eq!(
+            messages("2.7"),
+            [
+                "1: Document is EAF 3.0, target is EAF 2.7",
+                "2: 'TIER' attribute 'LANG_REF' requires EAF 2.8",
+                "4: 'LANGUAGE' requires EAF 2.8",
+                "5: 'REF_LINK_SET' requires EAF 3.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn violations_with_line_numbers() {
+        let xml = r#"<ANNOTATION_DOCUMENT AUTHOR="" DATE="2024-01-01T00:00:00+00:00" VERSION="3.0">
+    <HEADER MEDIA_FILE="" TIME_UNITS="milliseconds"/>
+    <TIME_ORDER>
+        <TIME_SLOT TIME_SLOT_ID="ts1" TIME_VALUE="2000"/>
+        <TIME_SLOT TIME_SLOT_ID="ts1" TIME_VALUE="1000"/>
+    </TIME_ORDER>
+    <TIER
+        LINGUISTIC_TYPE_REF="default-lt" TIER_ID="A">
+        <ANNOTATION>
+            <ALIGNABLE_ANNOTATION ANNOTATION_ID="a1" TIME_SLOT_REF1="ts1" TIME_SLOT_REF2="ts2">
+                <ANNOTATION_VALUE>one</ANNOTATION_VALUE>
+            </ALIGNABLE_ANNOTATION>
+        </ANNOTATION>
+    </TIER>
+    <TIER LINGUISTIC_TYPE_REF="missing" PARENT_REF="A"/>
+    <LINGUISTIC_TYPE LINGUISTIC_TYPE_ID="default-lt" TIME_ALIGNABLE="true"/>
+</ANNOTATION_DOCUMENT>
+"#;
+        let violations: Vec<(usize, String)> = validate(xml)
+            .into_iter()
+            .map(|v| (v.line, v.message))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                (5, "Duplicate TIME_SLOT_ID 'ts1'".to_owned()),
+                (
+                    10,
+                    "TIME_SLOT_REF2 'ts2' does not refer to an existing time slot".to_owned()
+                ),
+                (
+                    15,
+                    "'TIER' is missing required attribute 'TIER_ID'".to_owned()
+                ),
+                (
+                    15,
+                    "LINGUISTIC_TYPE_REF 'missing' does not refer to an existing linguistic type"
+                        .to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_well_formed() {
+        let xml = "<ANNOTATION_DOCUMENT>\n    <HEADER>\n</ANNOTATION_DOCUMENT>\n";
+        let violations = validate(xml);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].line, 3);
+        assert!(violations[0].message.starts_with("Invalid XML"));
+    }
+}
@@ -0,0 +1,523 @@
+//! Miniature synthesized camera files: GoPro MP4 with a GPMF track,
+//! Garmin VIRB MP4 with an embedded UUID, and a corresponding VIRB FIT-file.
+//!
+//! The files only contain what GeoELAN reads: MP4 headers and identifiers,
+//! GPS telemetry, and for FIT the messages describing a recording session.
+//! There is no audio or video data, since FFmpeg is mocked in the tests.
+
+use std::path::{Path, PathBuf};
+
+/// Recording start, 2023-06-01T10:00:00Z, as Unix time.
+pub const START_UNIX: i64 = 1_685_613_600;
+/// Seconds between 1904-01-01 (MP4 epoch) and 1970-01-01.
+const MP4_EPOCH_OFFSET: i64 = 2_082_844_800;
+/// Seconds between 1989-12-31 (FIT epoch) and 1970-01-01.
+const FIT_EPOCH_OFFSET: i64 = 631_065_600;
+
+/// Start coordinate (latitude, longitude).
+pub const ORIGIN: (f64, f64) = (57.7000, 11.9700);
+/// GPS points per second (GoPro).
+pub const GOPRO_HZ: usize = 10;
+
+/// Synthesized GPS point.
+#[derive(Debug, Clone, Copy)]
+pub struct Point {
+    /// Seconds since recording start.
+    pub time: f64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+    /// Meters per second.
+    pub speed: f64,
+}
+
+/// Track heading north. Moves at 5 m/s for the first half,
+/// then stands still, so that motion tiers get two segments.
+pub fn track(seconds: u32, hz: usize) -> Vec<Point> {
+    let half = seconds as f64 / 2.0;
+    (0..seconds as usize * hz)
+        .map(|i| {
+            let time = i as f64 / hz as f64;
+            let (distance, speed) = match time < half {
+                true => (time * 5.0, 5.0),
+                false => (half * 5.0, 0.0),
+            };
+            Point {
+                time,
+                latitude: ORIGIN.0 + distance / 111_320.0,
+                longitude: ORIGIN.1,
+                altitude: 50.0 + time / 10.0,
+                speed,
+            }
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// MP4
+// ---------------------------------------------------------------------------
+
+fn atom(name: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut bytes = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+    bytes.extend(name);
+    bytes.extend(payload);
+    bytes
+}
+
+fn full_atom(name: &[u8; 4], flags: u32, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = (flags & 0x00ff_ffff).to_be_bytes().to_vec(); // version 0
+    bytes.extend(payload);
+    atom(name, &bytes)
+}
+
+fn be32(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+/// Unity matrix for `mvhd`, `tkhd`.
+fn matrix() -> Vec<u8> {
+    be32(&[0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000])
+}
+
+fn mvhd(created: u32, timescale: u32, duration: u32) -> Vec<u8> {
+    let mut p = be32(&[created, created, timescale, duration, 0x0001_0000]);
+    p.extend(0x0100u16.to_be_bytes()); // volume
+    p.extend([0; 10]); // reserved
+    p.extend(matrix());
+    p.extend([0; 24]); // pre-defined
+    p.extend(2u32.to_be_bytes()); // next track ID
+    full_atom(b"mvhd", 0, &p)
+}
+
+/// Timed metadata track (GoPro GPMF) with one sample per second,
+/// stored as a single chunk at `chunk_offset`.
+fn gpmf_trak(created: u32, sizes: &[u32], chunk_offset: u32) -> Vec<u8> {
+    let timescale = 1000;
+    let duration = sizes.len() as u32 * timescale;
+
+    let mut tkhd = be32(&[created, created, 1, 0, duration]);
+    tkhd.extend([0; 8]); // reserved
+    tkhd.extend([0; 8]); // layer, alternate group, volume, reserved
+    tkhd.extend(matrix());
+    tkhd.extend(be32(&[0, 0])); // width, height
+
+    let mut mdhd = be32(&[created, created, timescale, duration]);
+    mdhd.extend(0x55c4u16.to_be_bytes()); // 'und'
+    mdhd.extend([0; 2]);
+
+    let mut hdlr = be32(&[0]);
+    hdlr.extend(b"meta");
+    hdlr.extend([0; 12]);
+    hdlr.extend(b"GoPro MET\0");
+
+    let mut gpmd = vec![0; 6]; // reserved
+    gpmd.extend(1u16.to_be_bytes()); // data reference index
+    gpmd.extend([0; 4]);
+    let mut stsd = be32(&[1]);
+    stsd.extend(atom(b"gpmd", &gpmd));
+
+    let mut stsz = be32(&[0, sizes.len() as u32]);
+    stsz.extend(be32(sizes));
+
+    let stbl = [
+        full_atom(b"stsd", 0, &stsd),
+        full_atom(b"stts", 0, &be32(&[1, sizes.len() as u32, timescale])),
+        full_atom(b"stsc", 0, &be32(&[1, 1, sizes.len() as u32, 1])),
+        full_atom(b"stsz", 0, &stsz),
+        full_atom(b"stco", 0, &be32(&[1, chunk_offset])),
+    ]
+    .concat();
+
+    let dref = full_atom(
+        b"dref",
+        0,
+        &[be32(&[1]), full_atom(b"url ", 1, &[])].concat(),
+    );
+    let minf = [
+        full_atom(b"nmhd", 0, &[]),
+        atom(b"dinf", &dref),
+        atom(b"stbl", &stbl),
+    ]
+    .concat();
+
+    let mdia = [
+        full_atom(b"mdhd", 0, &mdhd),
+        full_atom(b"hdlr", 0, &hdlr),
+        atom(b"minf", &minf),
+    ]
+    .concat();
+
+    atom(
+        b"trak",
+        &[full_atom(b"tkhd", 3, &tkhd), atom(b"mdia", &mdia)].concat(),
+    )
+}
+
+/// MP4 with `mdat` before `moov`, as written by cameras.
+/// `samples` are stored in `mdat` and indexed as a GPMF track if not empty.
+fn mp4(seconds: u32, samples: &[Vec<u8>], udta: &[u8]) -> Vec<u8> {
+    let created = (START_UNIX + MP4_EPOCH_OFFSET) as u32;
+    let mut ftyp = b"mp41".to_vec();
+    ftyp.extend(be32(&[0x2013_0904]));
+    ftyp.extend(b"mp41isom");
+    let ftyp = atom(b"ftyp", &ftyp);
+
+    let mdat = atom(b"mdat", &samples.concat());
+    let chunk_offset = (ftyp.len() + 8) as u32;
+
+    let mut moov = mvhd(created, 1000, seconds * 1000);
+    if !samples.is_empty() {
+        let sizes: Vec<u32> = samples.iter().map(|s| s.len() as u32).collect();
+        moov.extend(gpmf_trak(created, &sizes, chunk_offset));
+    }
+    moov.extend(atom(b"udta", udta));
+
+    [ftyp, mdat, atom(b"moov", &moov)].concat()
+}
+
+// ---------------------------------------------------------------------------
+// GoPro
+// ---------------------------------------------------------------------------
+
+/// GPMF KLV entry. Data is padded to 32-bit alignment.
+fn klv(key: &[u8; 4], kind: u8, size: u8, repeat: u16, data: &[u8]) -> Vec<u8> {
+    let mut bytes = key.to_vec();
+    bytes.extend([kind, size]);
+    bytes.extend(repeat.to_be_bytes());
+    bytes.extend(data);
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(0);
+    }
+    bytes
+}
+
+fn klv_str(key: &[u8; 4], value: &str) -> Vec<u8> {
+    klv(key, b'c', value.len() as u8, 1, value.as_bytes())
+}
+
+fn klv_nested(key: &[u8; 4], children: &[Vec<u8>]) -> Vec<u8> {
+    let data = children.concat();
+    klv(key, 0, 1, data.len() as u16, &data)
+}
+
+/// GPMF sample (one second) with a GPS5 stream, as logged by Hero5-10.
+fn gpmf_sample(device: &str, second: u32, points: &[Point]) -> Vec<u8> {
+    let scale = [10_000_000i32, 10_000_000, 1000, 1000, 100];
+    let gps5: Vec<u8> = points
+        .iter()
+        .flat_map(|p| {
+            [p.latitude, p.longitude, p.altitude, p.speed, p.speed]
+                .iter()
+                .zip(scale)
+                .flat_map(|(v, s)| ((v * s as f64).round() as i32).to_be_bytes())
+                .collect::<Vec<u8>>()
+        })
+        .collect();
+
+    let datetime = time::OffsetDateTime::from_unix_timestamp(START_UNIX + second as i64)
+        .expect("Invalid timestamp");
+    let gpsu = format!(
+        "{:02}{:02}{:02}{:02}{:02}{:02}.000",
+        datetime.year() % 100,
+        datetime.month() as u8,
+        datetime.day(),
+        datetime.hour(),
+        datetime.minute(),
+        datetime.second()
+    );
+
+    let strm = klv_nested(
+        b"STRM",
+        &[
+            klv(
+                b"STMP",
+                b'J',
+                8,
+                1,
+                &(second as u64 * 1_000_000).to_be_bytes(),
+            ),
+            klv(
+                b"TSMP",
+                b'L',
+                4,
+                1,
+                &((second + 1) * points.len() as u32).to_be_bytes(),
+            ),
+            klv_str(b"STNM", "GPS (Lat., Long., Alt., 2D speed, 3D speed)"),
+            klv(b"GPSF", b'L', 4, 1, &3u32.to_be_bytes()),
+            klv(b"GPSU", b'U', 16, 1, gpsu.as_bytes()),
+            klv(b"GPSP", b'S', 2, 1, &150u16.to_be_bytes()),
+            klv(
+                b"SCAL",
+                b'l',
+                4,
+                5,
+                &scale
+                    .iter()
+                    .flat_map(|s| s.to_be_bytes())
+                    .collect::<Vec<u8>>(),
+            ),
+            klv(b"GPS5", b'l', 20, points.len() as u16, &gps5),
+        ],
+    );
+
+    klv_nested(
+        b"DEVC",
+        &[
+            klv(b"DVID", b'L', 4, 1, &1u32.to_be_bytes()),
+            klv_str(b"DVNM", device),
+            strm,
+        ],
+    )
+}
+
+/// `udta` for a GoPro clip: firmware, MUID, and GUMI (in `udta/GPMF`).
+fn gopro_udta(muid: &[u32; 8], gumi: &[u8; 16]) -> Vec<u8> {
+    [
+        atom(b"FIRM", b"HD9.01.01.60.00"),
+        atom(b"MUID", &be32(muid)),
+        atom(b"GPMF", &klv(b"GUMI", b'B', 1, 16, gumi)),
+    ]
+    .concat()
+}
+
+/// Writes a GoPro Hero9 recording session to `dir`, split into `chapters`
+/// clips of `seconds` each: `GH0<n><id>.MP4` and the corresponding
+/// low-resolution `GL0<n><id>.LRV`. Returns the high-resolution clips.
+#[allow(dead_code)]
+pub fn gopro_session(dir: &Path, id: u16, chapters: u8, seconds: u32) -> Vec<PathBuf> {
+    let points = track(seconds * chapters as u32, GOPRO_HZ);
+    let muid = [id as u32, 0x1234_5678, 0x9abc_def0, 1, 2, 3, 4, 5];
+    let mut gumi = [0u8; 16];
+    gumi[..2].copy_from_slice(&id.to_be_bytes());
+
+    (0..chapters)
+        .map(|chapter| {
+            let samples: Vec<Vec<u8>> = (0..seconds)
+                .map(|s| {
+                    let second = chapter as u32 * seconds + s;
+                    let start = second as usize * GOPRO_HZ;
+                    gpmf_sample("HERO9 Black", second, &points[start..start + GOPRO_HZ])
+                })
+                .collect();
+            let bytes = mp4(seconds, &samples, &gopro_udta(&muid, &gumi));
+            let mp4_path = dir.join(format!("GH{:02}{id:04}.MP4", chapter + 1));
+            let lrv_path = dir.join(format!("GL{:02}{id:04}.LRV", chapter + 1));
+            std::fs::write(&mp4_path, &bytes).expect("Failed to write GoPro MP4");
+            std::fs::write(&lrv_path, &bytes).expect("Failed to write GoPro LRV");
+            mp4_path
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Garmin VIRB
+// ---------------------------------------------------------------------------
+
+/// UUID for a synthesized VIRB clip, in the format used by VIRB Ultra 30.
+#[allow(dead_code)]
+pub fn virb_uuid(id: u16) -> String {
+    format!("VIRBactioncameraULTRA30_Expansion_1_0_2023_06_01_10_00_00_{id:04}")
+}
+
+/// FIT base types.
+const ENUM: u8 = 0x00;
+const SINT16: u8 = 0x83;
+const UINT16: u8 = 0x84;
+const SINT32: u8 = 0x85;
+const UINT32: u8 = 0x86;
+const STRING: u8 = 0x07;
+
+/// FIT field definition as `(field number, size, base type)`.
+type FieldDef = (u8, u8, u8);
+
+/// Minimal FIT writer. Each message type gets its own local message number.
+struct FitWriter {
+    records: Vec<u8>,
+    defined: Vec<u16>,
+}
+
+impl FitWriter {
+    fn new() -> Self {
+        Self {
+            records: Vec::new(),
+            defined: Vec::new(),
+        }
+    }
+
+    /// Writes a definition (first time only) and a data message.
+    /// `fields` and `values` must be in the same order.
+    fn message(&mut self, global: u16, fields: &[FieldDef], values: &[Vec<u8>]) {
+        let local = match self.defined.iter().position(|g| *g == global) {
+            Some(i) => i as u8,
+            None => {
+                let local = self.defined.len() as u8;
+                self.records.push(0x40 | local);
+                self.records.extend([0, 0]); // reserved, little endian
+                self.records.extend(global.to_le_bytes());
+                self.records.push(fields.len() as u8);
+                for (number, size, base) in fields.iter() {
+                    self.records.extend([*number, *size, *base]);
+                }
+                self.defined.push(global);
+                local
+            }
+        };
+        self.records.push(local);
+        for ((_, size, _), value) in fields.iter().zip(values) {
+            let mut value = value.to_owned();
+            value.resize(*size as usize, 0);
+            self.records.extend(value);
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut header = vec![14, 0x20];
+        header.extend(2132u16.to_le_bytes()); // profile version
+        header.extend((self.records.len() as u32).to_le_bytes());
+        header.extend(b".FIT");
+        header.extend(crc(&header).to_le_bytes());
+
+        let mut bytes = [header, self.records].concat();
+        bytes.extend(crc(&bytes).to_le_bytes());
+        bytes
+    }
+}
+
+/// FIT CRC-16.
+fn crc(bytes: &[u8]) -> u16 {
+    const TABLE: [u16; 16] = [
+        0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800,
+        0xB401, 0x5000, 0x9C01, 0x8801, 0x4400,
+    ];
+    bytes.iter().fold(0, |mut crc, byte| {
+        let tmp = TABLE[(crc & 0xF) as usize];
+        crc = (crc >> 4) & 0x0FFF;
+        crc = crc ^ tmp ^ TABLE[(byte & 0xF) as usize];
+        let tmp = TABLE[(crc & 0xF) as usize];
+        crc = (crc >> 4) & 0x0FFF;
+        crc ^ tmp ^ TABLE[((byte >> 4) & 0xF) as usize]
+    })
+}
+
+fn semicircles(degrees: f64) -> i32 {
+    (degrees * (2f64.powi(31) / 180.0)).round() as i32
+}
+
+/// FIT-file for a single-clip VIRB recording session:
+/// `file_id`, `timestamp_correlation` (162), `camera_event` (161)
+/// start/end with the clip UUID, and `gps_metadata` (160) at 1 Hz.
+fn virb_fit(uuid: &str, seconds: u32) -> Vec<u8> {
+    // System time (seconds since camera was turned on) at recording start
+    let system_start = 100u32;
+    let fit_start = (START_UNIX - FIT_EPOCH_OFFSET) as u32;
+    let mut fit = FitWriter::new();
+
+    fit.message(
+        0,
+        &[(0, 1, ENUM), (1, 2, UINT16), (2, 2, UINT16), (4, 4, UINT32)],
+        &[
+            vec![4],                        // activity
+            1u16.to_le_bytes().to_vec(),    // garmin
+            2713u16.to_le_bytes().to_vec(), // VIRB Ultra 30
+            fit_start.to_le_bytes().to_vec(),
+        ],
+    );
+
+    fit.message(
+        162,
+        &[
+            (253, 4, UINT32),
+            (0, 2, UINT16),
+            (1, 4, UINT32),
+            (2, 2, UINT16),
+            (4, 2, UINT16),
+            (5, 2, UINT16),
+        ],
+        &[
+            fit_start.to_le_bytes().to_vec(),
+            0u16.to_le_bytes().to_vec(),
+            system_start.to_le_bytes().to_vec(),
+            0u16.to_le_bytes().to_vec(),
+            0u16.to_le_bytes().to_vec(),
+            0u16.to_le_bytes().to_vec(),
+        ],
+    );
+
+    let uuid_size = uuid.len() as u8 + 1; // null terminated
+    let camera_event = |fit: &mut FitWriter, system_time: u32, event: u8| {
+        fit.message(
+            161,
+            &[
+                (253, 4, UINT32),
+                (0, 2, UINT16),
+                (1, 1, ENUM),
+                (2, uuid_size, STRING),
+            ],
+            &[
+                system_time.to_le_bytes().to_vec(),
+                0u16.to_le_bytes().to_vec(),
+                vec![event],
+                uuid.as_bytes().to_vec(),
+            ],
+        )
+    };
+
+    camera_event(&mut fit, system_start, 0); // video_start
+    for point in track(seconds, 1).iter() {
+        let system_time = system_start + point.time as u32;
+        fit.message(
+            160,
+            &[
+                (253, 4, UINT32),
+                (0, 2, UINT16),
+                (1, 4, SINT32),
+                (2, 4, SINT32),
+                (3, 4, UINT32),
+                (4, 4, UINT32),
+                (5, 2, UINT16),
+                (6, 4, UINT32),
+                (7, 6, SINT16),
+            ],
+            &[
+                system_time.to_le_bytes().to_vec(),
+                0u16.to_le_bytes().to_vec(),
+                semicircles(point.latitude).to_le_bytes().to_vec(),
+                semicircles(point.longitude).to_le_bytes().to_vec(),
+                (((point.altitude + 500.0) * 5.0) as u32)
+                    .to_le_bytes()
+                    .to_vec(),
+                ((point.speed * 1000.0) as u32).to_le_bytes().to_vec(),
+                0u16.to_le_bytes().to_vec(), // heading north
+                (fit_start + point.time as u32).to_le_bytes().to_vec(),
+                [0i16, (point.speed * 100.0) as i16, 0]
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect(),
+            ],
+        );
+    }
+    camera_event(&mut fit, system_start + seconds, 2); // video_end
+
+    fit.finish()
+}
+
+/// Writes a single-clip VIRB recording session to `dir`:
+/// `V0<id>.MP4`, low-resolution `V0<id>.GLV`, and `<date>.fit`.
+/// Returns paths to the MP4 and the FIT-file.
+#[allow(dead_code)]
+pub fn virb_session(dir: &Path, id: u16, seconds: u32) -> (PathBuf, PathBuf) {
+    let uuid = virb_uuid(id);
+    let mut udta_uuid = uuid.as_bytes().to_vec();
+    udta_uuid.push(0);
+    let bytes = mp4(seconds, &[], &atom(b"uuid", &udta_uuid));
+
+    let mp4_path = dir.join(format!("V0{id:04}.MP4"));
+    let glv_path = dir.join(format!("V0{id:04}.GLV"));
+    let fit_path = dir.join("2023-06-01-10-00-00.fit");
+    std::fs::write(&mp4_path, &bytes).expect("Failed to write VIRB MP4");
+    std::fs::write(&glv_path, &bytes).expect("Failed to write VIRB GLV");
+    std::fs::write(&fit_path, virb_fit(&uuid, seconds)).expect("Failed to write FIT");
+
+    (mp4_path, fit_path)
+}
@@ -0,0 +1,186 @@
+//! Shared helpers for the end-to-end tests: running the `geoelan` binary,
+//! temporary directories, a mock FFmpeg, and golden output comparison.
+//!
+//! Golden files live in `tests/golden`. Run with `GEOELAN_BLESS=1` to
+//! (re)generate them, e.g. after an intentional change in output.
+//! Missing golden files fail the test, unless blessing.
+//!
+//! Each test crate compiles this module separately, so helpers
+//! that only some of them use are marked `#[allow(dead_code)]`.
+
+pub mod fixtures;
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Temporary directory, removed on drop
+/// unless `GEOELAN_KEEP_TMP` is set.
+pub struct TempDir(PathBuf);
+
+static TEMP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "geoelan-test-{name}-{}-{}",
+            std::process::id(),
+            TEMP_COUNT.fetch_add(1, Ordering::SeqCst)
+        ));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).expect("Failed to remove old temp dir");
+        }
+        std::fs::create_dir_all(&dir).expect("Failed to create temp dir");
+        Self(dir.canonicalize().expect("Failed to canonicalize temp dir"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, path: &str) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if std::env::var_os("GEOELAN_KEEP_TMP").is_none() {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+}
+
+/// Runs `geoelan` with specified arguments. Existing files
+/// are always overwritten, so that no command prompts for input,
+/// and config files are ignored.
+pub fn geoelan(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_geoelan"))
+        .args(args)
+        .args(["--overwrite", "always", "--no-config"])
+        .output()
+        .expect("Failed to run geoelan")
+}
+
+/// Runs `geoelan` and panics with stdout and stderr on failure.
+/// Returns stdout.
+pub fn geoelan_ok(args: &[&str]) -> String {
+    let output = geoelan(args);
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(
+        output.status.success(),
+        "geoelan {} failed\n--- stdout\n{stdout}\n--- stderr\n{}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    stdout
+}
+
+/// Writes a mock FFmpeg to `dir` and returns its path.
+///
+/// Instead of remuxing, the mock copies the first clip in
+/// a concatenation list (`-f concat -i LIST`), or the first input
+/// file (`-i INPUT`), to the output path (last argument).
+/// Audio (`.wav`) outputs are created as empty files.
+#[cfg(unix)]
+#[allow(dead_code)]
+pub fn mock_ffmpeg(dir: &Path) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let script = r#"#!/bin/sh
+out=""
+in=""
+concat=0
+prev=""
+for arg in "$@"; do
+    [ "$prev" = "-f" ] && [ "$arg" = "concat" ] && concat=1
+    [ "$prev" = "-i" ] && [ -z "$in" ] && in="$arg"
+    prev="$arg"
+    out="$arg"
+done
+case "$out" in
+    *.wav|*.WAV) : > "$out"; exit 0 ;;
+esac
+if [ "$concat" = "1" ]; then
+    in=$(sed -n "s/^file '\(.*\)'$/\1/p" "$in" | head -n 1)
+fi
+cp "$in" "$out"
+"#;
+    let path = dir.join("ffmpeg");
+    std::fs::write(&path, script).expect("Failed to write mock FFmpeg");
+    let mut permissions = std::fs::metadata(&path)
+        .expect("Failed to read mock FFmpeg metadata")
+        .permissions();
+    permissions.set_mode(0o755);
+    std::fs::set_permissions(&path, permissions).expect("Failed to set mock FFmpeg permissions");
+    path
+}
+
+/// Replaces temporary paths and volatile values (e.g. creation dates)
+/// so that output can be compared between runs.
+#[allow(dead_code)]
+pub fn normalize(text: &str, tmp: &Path) -> String {
+    let date =
+        regex::Regex::new(r"\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:\d{2})?")
+            .expect("Invalid regex");
+    let text = text.replace(&tmp.display().to_string(), "<TMP>");
+    let text = date.replace_all(&text, "<DATETIME>");
+    text.replace("\r\n", "\n")
+}
+
+/// Compares `actual` with `tests/golden/<name>`.
+/// Writes the golden file if `GEOELAN_BLESS` is set.
+#[allow(dead_code)]
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(name);
+    if std::env::var_os("GEOELAN_BLESS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).expect("Failed to create golden dir");
+        std::fs::write(&path, actual).expect("Failed to write golden file");
+        return;
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(expected) => assert_eq!(
+            expected,
+            actual,
+            "Output differs from {}. Run with GEOELAN_BLESS=1 if the change is intended.",
+            path.display()
+        ),
+        Err(_) => panic!(
+            "No golden file {}. Run with GEOELAN_BLESS=1 to create it.",
+            path.display()
+        ),
+    }
+}
+
+/// Returns the only file with specified extension in `dir`, recursively.
+#[allow(dead_code)]
+pub fn find_file(dir: &Path, ext: &str) -> PathBuf {
+    let mut found: Vec<PathBuf> = walk(dir)
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case(ext)))
+        .collect();
+    assert_eq!(
+        found.len(),
+        1,
+        "Expected a single '.{ext}' file in {}, found {found:?}",
+        dir.display()
+    );
+    found.remove(0)
+}
+
+fn walk(dir: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        match path.is_dir() {
+            true => paths.extend(walk(&path)),
+            false => paths.push(path),
+        }
+    }
+    paths
+}
@@ -0,0 +1,69 @@
+//! `locate --duplicates` for a synthesized two-chapter GoPro recording
+//! session. All chapters share the MUID, but are not copies of each other.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use common::{fixtures, geoelan_ok, TempDir};
+use serde_json::Value;
+
+/// Chapter duration in seconds.
+const CHAPTER: u32 = 5;
+
+fn duplicates(indir: &Path) -> Vec<Value> {
+    let stdout = geoelan_ok(&[
+        "locate",
+        "--indir",
+        indir.to_str().unwrap(),
+        "--duplicates",
+        "--format",
+        "json",
+    ]);
+    let json: Value = serde_json::from_str(&stdout).expect("Invalid duplicates JSON");
+    json.as_array().cloned().expect("Expected JSON array")
+}
+
+#[test]
+fn chapters_are_not_duplicates() {
+    let tmp = TempDir::new("duplicates-chapters");
+    let clips = fixtures::gopro_session(tmp.path(), 42, 2, CHAPTER);
+    assert_eq!(clips.len(), 2);
+
+    let groups = duplicates(tmp.path());
+    assert!(
+        groups.is_empty(),
+        "Chapters reported as duplicates: {groups:?}"
+    );
+}
+
+#[test]
+fn copied_chapter_is_duplicate() {
+    let tmp = TempDir::new("duplicates-copy");
+    let clips = fixtures::gopro_session(tmp.path(), 42, 2, CHAPTER);
+    let backup = tmp.join("backup");
+    std::fs::create_dir_all(&backup).unwrap();
+    let copy = backup.join(clips[1].file_name().unwrap());
+    std::fs::copy(&clips[1], &copy).unwrap();
+    // Original is kept, since it has the oldest modification time
+    std::fs::File::options()
+        .write(true)
+        .open(&copy)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+
+    let groups = duplicates(tmp.path());
+    assert_eq!(groups.len(), 1, "Expected a single group: {groups:?}");
+    assert_eq!(groups[0]["reason"], "id");
+    assert_eq!(groups[0]["keep"], clips[1].display().to_string());
+    assert_eq!(
+        groups[0]["redundant"],
+        serde_json::json!([copy.display().to_string()])
+    );
+}
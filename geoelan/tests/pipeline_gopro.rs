@@ -0,0 +1,90 @@
+//! End-to-end: locate -> cam2eaf -> eaf2geo -> plot for a synthesized
+//! two-chapter GoPro recording session. FFmpeg is mocked.
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{assert_golden, find_file, fixtures, geoelan_ok, mock_ffmpeg, normalize, TempDir};
+use serde_json::Value;
+
+/// Chapter duration in seconds.
+const CHAPTER: u32 = 20;
+
+#[test]
+fn gopro_pipeline() {
+    let tmp = TempDir::new("gopro");
+    let clips = fixtures::gopro_session(tmp.path(), 42, 2, CHAPTER);
+    let ffmpeg = mock_ffmpeg(tmp.path());
+    let outdir = tmp.join("out");
+    let indir = tmp.path().to_str().unwrap();
+
+    // LOCATE
+    let stdout = geoelan_ok(&[
+        "locate", "--indir", indir, "--kind", "gopro", "--format", "json",
+    ]);
+    let sessions: Value = serde_json::from_str(&stdout).expect("Invalid locate JSON");
+    assert_eq!(sessions.as_array().map(|s| s.len()), Some(1));
+    assert_eq!(sessions[0]["clips"].as_array().map(|c| c.len()), Some(2));
+    assert_golden("gopro_locate.json", &normalize(&stdout, tmp.path()));
+
+    // CAM2EAF
+    geoelan_ok(&[
+        "cam2eaf",
+        "--video",
+        clips[0].to_str().unwrap(),
+        "--ffmpeg",
+        ffmpeg.to_str().unwrap(),
+        "--outdir",
+        outdir.to_str().unwrap(),
+        "--geotier",
+        "--motion-tier",
+    ]);
+    let eaf_path = find_file(&outdir, "eaf");
+    let eaf = std::fs::read_to_string(&eaf_path).unwrap();
+    assert!(
+        eaf.contains(r#"TIER_ID="geo""#),
+        "No geotier in {}",
+        eaf_path.display()
+    );
+    assert!(eaf.contains(r#"TIER_ID="geo-motion""#));
+    assert!(eaf.contains("moving") && eaf.contains("stationary"));
+    assert_golden("gopro_cam2eaf.eaf", &normalize(&eaf, tmp.path()));
+
+    // EAF2GEO
+    let eaf_str = eaf_path.to_str().unwrap();
+    geoelan_ok(&[
+        "eaf2geo",
+        "--eaf",
+        eaf_str,
+        "--geo-tier",
+        "geo",
+        "--tier",
+        "geo-motion",
+        "--name",
+        "track_{shape}",
+    ]);
+    let geojson = std::fs::read_to_string(eaf_path.with_file_name("track_point-all.json"))
+        .expect("No GeoJSON written");
+    let features: Value = serde_json::from_str(&geojson).expect("Invalid GeoJSON");
+    assert!(features["features"]
+        .as_array()
+        .is_some_and(|f| !f.is_empty()));
+    assert_golden("gopro_eaf2geo.geojson", &normalize(&geojson, tmp.path()));
+
+    // PLOT
+    let html = tmp.join("plot.html");
+    geoelan_ok(&[
+        "plot",
+        "--gpmf",
+        clips[0].to_str().unwrap(),
+        "--y-axis",
+        "altitude",
+        "--x-axis",
+        "time",
+        "--session",
+        "--output",
+        html.to_str().unwrap(),
+    ]);
+    assert!(std::fs::read_to_string(&html).is_ok_and(|s| s.contains("plotly")));
+}
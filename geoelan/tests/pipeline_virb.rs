@@ -0,0 +1,89 @@
+//! End-to-end: locate -> cam2eaf -> eaf2geo -> plot for a synthesized
+//! single-clip Garmin VIRB recording session with FIT-file. FFmpeg is mocked.
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{assert_golden, find_file, fixtures, geoelan_ok, mock_ffmpeg, normalize, TempDir};
+
+/// Clip duration in seconds.
+const DURATION: u32 = 30;
+
+#[test]
+fn virb_pipeline() {
+    let tmp = TempDir::new("virb");
+    let (_mp4, fit) = fixtures::virb_session(tmp.path(), 42, DURATION);
+    let ffmpeg = mock_ffmpeg(tmp.path());
+    let outdir = tmp.join("out");
+    let indir = tmp.path().to_str().unwrap();
+    let fit = fit.to_str().unwrap();
+
+    // LOCATE
+    let stdout = geoelan_ok(&["locate", "--indir", indir, "--fit", fit, "--format", "csv"]);
+    assert!(
+        stdout.starts_with("camera,"),
+        "Not only CSV on stdout: {stdout}"
+    );
+    assert_eq!(
+        stdout.lines().count(),
+        2,
+        "Expected header and a single clip"
+    );
+    assert!(stdout.contains(&fixtures::virb_uuid(42)));
+    assert_golden("virb_locate.csv", &normalize(&stdout, tmp.path()));
+
+    // CAM2EAF
+    geoelan_ok(&[
+        "cam2eaf",
+        "--fit",
+        fit,
+        "--indir",
+        indir,
+        "--ffmpeg",
+        ffmpeg.to_str().unwrap(),
+        "--outdir",
+        outdir.to_str().unwrap(),
+        "--geotier",
+    ]);
+    let eaf_path = find_file(&outdir, "eaf");
+    let eaf = std::fs::read_to_string(&eaf_path).unwrap();
+    assert!(
+        eaf.contains(r#"TIER_ID="geo""#),
+        "No geotier in {}",
+        eaf_path.display()
+    );
+    assert_golden("virb_cam2eaf.eaf", &normalize(&eaf, tmp.path()));
+
+    // EAF2GEO, with the FIT-file as coordinate source
+    geoelan_ok(&[
+        "eaf2geo",
+        "--eaf",
+        eaf_path.to_str().unwrap(),
+        "--fit",
+        fit,
+        "--tier",
+        "geo",
+        "--geoshape",
+        "line-all",
+        "--name",
+        "track_{shape}",
+    ]);
+    let kml = std::fs::read_to_string(eaf_path.with_file_name("track_line-all.kml"))
+        .expect("No KML written");
+    assert!(kml.contains("<LineString>"));
+    assert_golden("virb_eaf2geo.kml", &normalize(&kml, tmp.path()));
+
+    // PLOT
+    let html = tmp.join("plot.html");
+    geoelan_ok(&[
+        "plot",
+        "--fit",
+        fit,
+        "--y-axis",
+        "speed2d",
+        "--output",
+        html.to_str().unwrap(),
+    ]);
+    assert!(std::fs::read_to_string(&html).is_ok_and(|s| s.contains("plotly")));
+}